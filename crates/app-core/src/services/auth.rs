@@ -1,10 +1,119 @@
+use app_domain::entities::AuthEventEntity;
 use async_trait::async_trait;
+use thiserror::Error;
 
 #[cfg(test)]
 use mockall::{predicate::*, *};
-use std::error::Error;
+use std::{net::IpAddr, time::Duration};
+
+use super::RepositoryError;
+
+/// Role granting access to the admin routes
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Role granting to change facts, as admins may too
+pub const EDITOR_ROLE: &str = "editor";
+
+/// Roles starting with it bind a principal to one tenant, e.g. `tenant:acme`
+pub const TENANT_ROLE_PREFIX: &str = "tenant:";
+
+/// Who is making a request, as established by an `AuthService`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub username: String,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn new(username: String, roles: Vec<String>) -> Self {
+        Principal { username, roles }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Whether the principal may change facts
+    pub fn is_editor(&self) -> bool {
+        self.has_role(EDITOR_ROLE) || self.has_role(ADMIN_ROLE)
+    }
+
+    /// The tenant the principal belongs to, if bound to one
+    pub fn tenant(&self) -> Option<&str> {
+        self.roles
+            .iter()
+            .find_map(|r| r.strip_prefix(TENANT_ROLE_PREFIX))
+    }
+}
+
+/// An opaque bearer token and the principal it stands for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthToken {
+    pub token: String,
+    pub principal: Principal,
+}
+
+/// An interface of any authentication backend
+///
+/// `login` checks credentials, `issue_token` hands out a bearer token for
+/// the principal it returned, which `verify_token` resolves until `logout`
+/// revokes it or it expires.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait AuthService: Send + Sync {
+    async fn login(
+        &self,
+        username: &str,
+        password: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<Principal, AuthError>;
+
+    async fn issue_token(&self, principal: &Principal) -> Result<AuthToken, AuthError>;
+
+    async fn verify_token(&self, token: &str) -> Result<Principal, AuthError>;
+
+    async fn logout(&self, token: &str) -> Result<(), AuthError>;
+
+    /// Lift any lockout on an account, for services that lock accounts at all
+    async fn unlock(&self, _username: &str) -> Result<(), AuthError> {
+        Ok(())
+    }
+
+    /// Revoke every token issued to a user, e.g. once their password
+    /// changed, for services whose tokens outlive a password at all
+    async fn revoke_tokens(&self, _username: &str) -> Result<(), AuthError> {
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Too many failed login attempts, retry in {} seconds", .retry_after.as_secs().max(1))]
+    Throttled { retry_after: Duration },
+    #[error("Authentication backend error: {0}")]
+    Backend(String),
+}
+
+/// An append-only trail of authentication events, to audit attacks
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait AuthAuditLog: Send + Sync {
+    async fn record(&self, event: AuthEventEntity) -> Result<(), RepositoryError>;
+}
+
+/// One-way hashing of passwords, slow on purpose
+///
+/// Being slow, implementations should keep the work off the async
+/// executor, e.g. on a blocking thread pool.
 #[cfg_attr(test, automock)]
-#[async_trait(?Send)]
-pub trait AuthService {
-    async fn login(&self, username: &str, password: &str) -> Result<(), Box<dyn Error>>;
+#[async_trait]
+pub trait PasswordHasher: Send + Sync {
+    /// A salted hash, carrying whatever `verify` needs to check it
+    async fn hash(&self, password: &str) -> Result<String, AuthError>;
+    /// Whether `password` hashes to `password_hash`, in constant time
+    async fn verify(&self, password: &str, password_hash: &str) -> bool;
 }
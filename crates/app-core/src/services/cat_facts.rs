@@ -0,0 +1,37 @@
+use app_domain::entities::CatFactEntity;
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// One page of an upstream fact listing
+#[derive(Debug, Clone)]
+pub struct FactsPage<T> {
+    pub facts: Vec<T>,
+    pub current_page: i32,
+    pub last_page: i32,
+}
+
+impl<T> FactsPage<T> {
+    pub fn is_last(&self) -> bool {
+        self.facts.is_empty() || self.current_page >= self.last_page
+    }
+}
+
+/// An interface of any live source of cat facts
+///
+/// Pages are numbered from 1, as upstream APIs do.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait CatFactsService: Send + Sync {
+    async fn get_random_cat_fact(&self) -> Result<CatFactEntity, UpstreamError>;
+    async fn get_cat_facts_page(
+        &self,
+        page: i32,
+    ) -> Result<FactsPage<CatFactEntity>, UpstreamError>;
+}
+
+#[derive(Error, Debug)]
+#[error("Upstream error: {0}")]
+pub struct UpstreamError(pub String);
@@ -0,0 +1,19 @@
+use app_domain::entities::DogFactEntity;
+use async_trait::async_trait;
+
+use super::{FactsPage, UpstreamError};
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// An interface of any live source of dog facts
+///
+/// Pages are numbered from 1, as upstream APIs do.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait DogFactsService: Send + Sync {
+    async fn get_dog_facts_page(
+        &self,
+        page: i32,
+    ) -> Result<FactsPage<DogFactEntity>, UpstreamError>;
+}
@@ -0,0 +1,28 @@
+use app_domain::events::DomainEvent;
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// Where use cases tell what they did, once it is committed
+///
+/// Use cases don't know who reacts to their events, nor hear about their
+/// reactions failing.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, event: DomainEvent);
+}
+
+/// A reaction to domain events
+#[async_trait]
+pub trait EventSubscriber: Send + Sync {
+    /// To tell subscribers apart in logs
+    fn name(&self) -> &str;
+    async fn handle(&self, event: &DomainEvent) -> Result<(), EventError>;
+}
+
+#[derive(Error, Debug)]
+#[error("Event error: {0}")]
+pub struct EventError(pub String);
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// A plain text mail to one recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// An interface of any way of mailing users
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), MailError>;
+}
+
+#[derive(Error, Debug)]
+#[error("Mail error: {0}")]
+pub struct MailError(pub String);
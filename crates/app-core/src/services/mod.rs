@@ -1,5 +1,15 @@
 mod auth;
+mod cat_facts;
+mod dog_facts;
+mod events;
+mod mailer;
 mod persistence;
+mod sso;
 
 pub use auth::*;
+pub use cat_facts::*;
+pub use dog_facts::*;
+pub use events::*;
+pub use mailer::*;
 pub use persistence::*;
+pub use sso::*;
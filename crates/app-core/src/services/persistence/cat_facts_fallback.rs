@@ -0,0 +1,673 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use app_domain::{
+    entities::{CatFactEntity, TenantEntity},
+    events::{DomainEvent, FactRef, Species},
+};
+use async_trait::async_trait;
+
+use super::{CatRepo, Persistence, RepositoryError, Transaction, TransactionOptions};
+use crate::services::{CatFactsService, EventPublisher};
+
+/// Where a random fact comes from when the catalogue can't give one,
+/// because it is empty or the database is unreachable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CatFactsFallback {
+    /// Fail like the catalogue did
+    #[default]
+    Disabled,
+    /// Serve a fact from the upstream source
+    Upstream,
+    /// Serve a fact from the upstream source, and keep it in the catalogue
+    WriteThrough,
+}
+
+/// A persistence whose transactions carry the upstream source, for
+/// `FallbackCatRepo` to stand in for the catalogue
+///
+/// Transactions are those of `P`, reads only reading. When the database
+/// can't be reached, a tenant transaction is still given, which only
+/// reaches upstream.
+pub struct FallbackPersistence<P> {
+    inner: Arc<P>,
+    service: Arc<dyn CatFactsService>,
+    fallback: CatFactsFallback,
+    events: Option<Arc<dyn EventPublisher>>,
+}
+
+impl<P> Clone for FallbackPersistence<P> {
+    fn clone(&self) -> Self {
+        FallbackPersistence {
+            inner: self.inner.clone(),
+            service: self.service.clone(),
+            fallback: self.fallback,
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<P> FallbackPersistence<P>
+where
+    P: Persistence,
+{
+    pub fn new(inner: P, service: Arc<dyn CatFactsService>, fallback: CatFactsFallback) -> Self {
+        FallbackPersistence {
+            inner: Arc::new(inner),
+            service,
+            fallback,
+            events: None,
+        }
+    }
+
+    /// Tell `events` about the facts kept when writing through, once committed
+    pub fn with_events(mut self, events: Arc<dyn EventPublisher>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn wrap(
+        &self,
+        tenant: Option<&TenantEntity>,
+        inner: Result<P::Transaction, RepositoryError>,
+    ) -> Result<FallbackTransaction<P>, RepositoryError> {
+        let inner = match inner {
+            Ok(tx) => Ok(tx),
+            Err(e) if tenant.is_some() && self.fallback != CatFactsFallback::Disabled => {
+                Err(e.message)
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(FallbackTransaction {
+            inner,
+            persistence: self.inner.clone(),
+            tenant: tenant.cloned(),
+            service: self.service.clone(),
+            fallback: self.fallback,
+            events: self.events.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl<P> Persistence for FallbackPersistence<P>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+{
+    type Transaction = FallbackTransaction<P>;
+
+    async fn get_transaction(&self) -> Result<Self::Transaction, RepositoryError> {
+        self.wrap(None, self.inner.get_transaction().await)
+    }
+
+    async fn get_tenant_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<Self::Transaction, RepositoryError> {
+        self.wrap(
+            Some(tenant),
+            self.inner.get_tenant_transaction(tenant).await,
+        )
+    }
+
+    async fn get_transaction_with(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<Self::Transaction, RepositoryError> {
+        self.wrap(None, self.inner.get_transaction_with(options).await)
+    }
+
+    async fn get_tenant_transaction_with(
+        &self,
+        tenant: &TenantEntity,
+        options: TransactionOptions,
+    ) -> Result<Self::Transaction, RepositoryError> {
+        let tx = self
+            .inner
+            .get_tenant_transaction_with(tenant, options)
+            .await;
+        self.wrap(Some(tenant), tx)
+    }
+
+    async fn get_read_transaction(&self) -> Result<Self::Transaction, RepositoryError> {
+        self.wrap(None, self.inner.get_read_transaction().await)
+    }
+
+    async fn get_tenant_read_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<Self::Transaction, RepositoryError> {
+        let tx = self.inner.get_tenant_read_transaction(tenant).await;
+        self.wrap(Some(tenant), tx)
+    }
+}
+
+pub struct FallbackTransaction<P: Persistence> {
+    /// The reason the database couldn't be reached otherwise
+    inner: Result<P::Transaction, String>,
+    /// Where facts are kept when writing through, in a transaction of their
+    /// own as `inner` may only read, e.g. from a replica
+    persistence: Arc<P>,
+    tenant: Option<TenantEntity>,
+    service: Arc<dyn CatFactsService>,
+    fallback: CatFactsFallback,
+    events: Option<Arc<dyn EventPublisher>>,
+}
+
+impl<P: Persistence> FallbackTransaction<P> {
+    fn inner(&mut self) -> Result<&mut P::Transaction, RepositoryError> {
+        self.inner
+            .as_mut()
+            .map_err(|message| RepositoryError::new(message.clone()))
+    }
+}
+
+#[async_trait]
+impl<P> Transaction for FallbackTransaction<P>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+{
+    /// Nothing was written without a database, there is nothing to commit
+    async fn commit(self) -> Result<(), RepositoryError> {
+        match self.inner {
+            Ok(tx) => tx.commit().await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    async fn rollback(self) -> Result<(), RepositoryError> {
+        match self.inner {
+            Ok(tx) => tx.rollback().await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    async fn savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.inner()?.savepoint(name).await
+    }
+
+    async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.inner()?.rollback_to_savepoint(name).await
+    }
+
+    async fn release_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.inner()?.release_savepoint(name).await
+    }
+
+    async fn add_event(&mut self, event: &DomainEvent) -> Result<(), RepositoryError> {
+        self.inner()?.add_event(event).await
+    }
+}
+
+/// The facts of `R`, random ones coming from upstream when `R` has none to
+/// give, and kept in `R` when writing through
+pub struct FallbackCatRepo<R> {
+    repo: PhantomData<R>,
+}
+
+impl<R> FallbackCatRepo<R> {
+    /// Keep an upstream fact, unless the catalogue holds it already, in a
+    /// transaction of its own, returning it as stored
+    async fn keep<P>(
+        tx: &FallbackTransaction<P>,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError>
+    where
+        P: Persistence,
+        <P as Persistence>::Transaction: Transaction,
+        R: CatRepo<P>,
+    {
+        let tenant = tx
+            .tenant
+            .as_ref()
+            .ok_or_else(|| RepositoryError::new("No tenant to keep the fact for".into()))?;
+
+        // dropped without a commit when failing, nothing is kept then
+        let mut writer = tx.persistence.get_tenant_transaction(tenant).await?;
+        let (kept, added) = R::upsert_cat_fact(&mut writer, fact).await?;
+        let event = added.then(|| DomainEvent::FactCreated {
+            fact: FactRef::new(Species::Cat, tenant.tenant_id.clone(), kept.fact_id),
+            text: kept.fact_txt.clone(),
+        });
+        if let Some(event) = &event {
+            writer.add_event(event).await?;
+        }
+        writer.commit().await?;
+
+        if let (Some(event), Some(events)) = (event, &tx.events) {
+            events.publish(event).await;
+        }
+        Ok(kept)
+    }
+}
+
+#[async_trait]
+impl<P, R> CatRepo<FallbackPersistence<P>> for FallbackCatRepo<R>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    R: CatRepo<P>,
+{
+    async fn get_all_cat_facts(
+        tx: &mut FallbackTransaction<P>,
+    ) -> Result<Vec<CatFactEntity>, RepositoryError> {
+        R::get_all_cat_facts(tx.inner()?).await
+    }
+
+    async fn get_cat_fact_by_id(
+        tx: &mut FallbackTransaction<P>,
+        fact_id: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        R::get_cat_fact_by_id(tx.inner()?, fact_id).await
+    }
+
+    async fn get_random_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let error = match tx.inner() {
+            Ok(inner) => match R::get_random_cat_fact(inner).await {
+                Ok(fact) => return Ok(fact),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+        if tx.fallback == CatFactsFallback::Disabled {
+            return Err(error);
+        }
+
+        // the catalogue is what failed in the first place
+        let fact = match tx.service.get_random_cat_fact().await {
+            Ok(fact) => fact,
+            Err(e) => {
+                log::error!("Can't fall back on an upstream cat fact: {}", e);
+                return Err(error);
+            }
+        };
+        log::warn!(
+            "Serving an upstream cat fact, none from the catalogue: {}",
+            error
+        );
+
+        if tx.fallback == CatFactsFallback::WriteThrough {
+            // the fact is served even when it can't be kept, without an id
+            match Self::keep::<P>(tx, fact.fact_txt.clone()).await {
+                Ok(kept) => return Ok(kept),
+                Err(e) => log::error!("Can't keep the upstream cat fact: {}", e),
+            }
+        }
+        Ok(fact)
+    }
+
+    async fn insert_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        R::insert_cat_fact(tx.inner()?, fact).await
+    }
+
+    async fn upsert_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        fact: String,
+    ) -> Result<(CatFactEntity, bool), RepositoryError> {
+        R::upsert_cat_fact(tx.inner()?, fact).await
+    }
+
+    async fn get_upstream_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        upstream_index: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        R::get_upstream_cat_fact(tx.inner()?, upstream_index).await
+    }
+
+    async fn insert_upstream_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        R::insert_upstream_cat_fact(tx.inner()?, upstream_index, fact).await
+    }
+
+    async fn flag_changed_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        R::flag_changed_cat_fact(tx.inner()?, fact_id).await
+    }
+
+    async fn update_cat_fact(
+        tx: &mut FallbackTransaction<P>,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        R::update_cat_fact(tx.inner()?, fact_id, fact, version).await
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use mockall::predicate::{always, eq};
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        MockCatFactsService, MockCatRepo, MockEventPublisher, MockPersistence, MockTransaction,
+        UpstreamError,
+    };
+    use app_domain::entities::UNSTORED_FACT_ID;
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
+    type MockRepo = MockCatRepo<MockPersistence>;
+    type Repo = FallbackCatRepo<MockRepo>;
+
+    fn upstream_fact() -> Arc<dyn CatFactsService> {
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_random_cat_fact()
+            .returning(|| {
+                Ok(CatFactEntity::new(
+                    String::from("upstream fact"),
+                    UNSTORED_FACT_ID,
+                ))
+            });
+        Arc::new(cat_facts_service)
+    }
+
+    fn transaction() -> MockTransaction {
+        let mut tx = MockTransaction::new();
+        tx.expect_commit().returning(|| Ok(()));
+        tx
+    }
+
+    /// Reads only read, facts are kept in a transaction of their own
+    fn empty_catalogue(kept_events: usize) -> MockPersistence {
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Ok(transaction()));
+        persistence
+            .expect_get_tenant_transaction()
+            .returning(move |_tenant| {
+                let mut tx = transaction();
+                tx.expect_add_event()
+                    .times(kept_events)
+                    .returning(|_event| Ok(()));
+                Ok(tx)
+            });
+        persistence
+    }
+
+    fn no_random_fact() -> impl Drop {
+        let repo_ctx = MockRepo::get_random_cat_fact_context();
+        repo_ctx
+            .expect()
+            .returning(|_tx| Err(RepositoryError::new("no rows returned".into())));
+        repo_ctx
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fall_back_to_upstream() {
+        let _m = get_lock(&MTX);
+
+        // given an empty catalogue, and an upstream serving facts
+        let _repo_ctx = no_random_fact();
+        let upsert_ctx = MockRepo::upsert_cat_fact_context();
+        upsert_ctx.expect().times(0);
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Ok(transaction()));
+        let persistence =
+            FallbackPersistence::new(inner, upstream_fact(), CatFactsFallback::Upstream);
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await.unwrap();
+
+        // then the upstream fact is served, without being kept nor given an id
+        assert_eq!(data.fact_txt, "upstream fact");
+        assert_eq!(data.fact_id, UNSTORED_FACT_ID);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_keep_upstream_fact_when_writing_through() {
+        let _m = get_lock(&MTX);
+
+        // given an empty catalogue, and an upstream serving facts
+        let _repo_ctx = no_random_fact();
+        let upsert_ctx = MockRepo::upsert_cat_fact_context();
+        upsert_ctx
+            .expect()
+            .with(always(), eq(String::from("upstream fact")))
+            .times(1)
+            .returning(|_tx, fact| Ok((CatFactEntity::new(fact, 11), true)));
+        let mut events = MockEventPublisher::new();
+        events
+            .expect_publish()
+            .withf(|event| {
+                matches!(event, DomainEvent::FactCreated { fact, text }
+                    if fact.species == Species::Cat && fact.fact_id == 11 && text == "upstream fact")
+            })
+            .times(1)
+            .return_const(());
+        let persistence = FallbackPersistence::new(
+            empty_catalogue(1),
+            upstream_fact(),
+            CatFactsFallback::WriteThrough,
+        )
+        .with_events(Arc::new(events));
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // then the fact is served as kept in the catalogue
+        assert_eq!(data.fact_txt, "upstream fact");
+        assert_eq!(data.fact_id, 11);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_keep_upstream_fact_twice() {
+        let _m = get_lock(&MTX);
+
+        // given a catalogue failing to pick a fact, yet already holding the
+        // one upstream serves
+        let _repo_ctx = no_random_fact();
+        let upsert_ctx = MockRepo::upsert_cat_fact_context();
+        upsert_ctx.expect().times(1).returning(|_tx, fact| {
+            Ok((
+                CatFactEntity {
+                    fact_txt: fact,
+                    fact_id: 11,
+                    version: 2,
+                },
+                false,
+            ))
+        });
+        let mut events = MockEventPublisher::new();
+        events.expect_publish().times(0);
+        let persistence = FallbackPersistence::new(
+            empty_catalogue(0),
+            upstream_fact(),
+            CatFactsFallback::WriteThrough,
+        )
+        .with_events(Arc::new(events));
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await.unwrap();
+
+        // then the fact already kept is served, and not told about again
+        assert_eq!(data.fact_id, 11);
+        assert_eq!(data.version, 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_serve_upstream_fact_when_it_cant_be_kept() {
+        let _m = get_lock(&MTX);
+
+        // given an empty catalogue on a replica, and an unreachable primary
+        let _repo_ctx = no_random_fact();
+        let upsert_ctx = MockRepo::upsert_cat_fact_context();
+        upsert_ctx.expect().times(0);
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Ok(transaction()));
+        inner
+            .expect_get_tenant_transaction()
+            .times(1)
+            .returning(|_tenant| Err(RepositoryError::new("connection refused".into())));
+        let persistence =
+            FallbackPersistence::new(inner, upstream_fact(), CatFactsFallback::WriteThrough);
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await.unwrap();
+
+        // then the upstream fact is served, without an id
+        assert_eq!(data.fact_txt, "upstream fact");
+        assert_eq!(data.fact_id, UNSTORED_FACT_ID);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_only_read_other_facts_when_writing_through() {
+        let _m = get_lock(&MTX);
+
+        // given a catalogue writing upstream facts through
+        let all_ctx = MockRepo::get_all_cat_facts_context();
+        all_ctx
+            .expect()
+            .returning(|_tx| Ok(vec![CatFactEntity::new("fact".into(), 1)]));
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .times(1)
+            .returning(|_tenant| Ok(transaction()));
+        inner.expect_get_tenant_transaction().times(0);
+        let persistence =
+            FallbackPersistence::new(inner, upstream_fact(), CatFactsFallback::WriteThrough);
+
+        // when listing the facts
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_all_cat_facts(&mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // then they are only read, e.g. from a replica
+        assert_eq!(data.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fall_back_when_database_is_unreachable() {
+        let _m = get_lock(&MTX);
+
+        // given an unreachable database, and an upstream serving facts
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Err(RepositoryError::new("connection refused".into())));
+        let persistence =
+            FallbackPersistence::new(inner, upstream_fact(), CatFactsFallback::Upstream);
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        // then the upstream fact is served
+        assert_eq!(data.fact_txt, "upstream fact");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_report_catalogue_error_when_upstream_fails_too() {
+        let _m = get_lock(&MTX);
+
+        // given an unreachable database, and a failing upstream
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Err(RepositoryError::new("connection refused".into())));
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_random_cat_fact()
+            .times(1)
+            .returning(|| Err(UpstreamError("503".into())));
+        let persistence = FallbackPersistence::new(
+            inner,
+            Arc::new(cat_facts_service),
+            CatFactsFallback::WriteThrough,
+        );
+
+        // when getting a random fact
+        let mut tx = persistence
+            .get_tenant_read_transaction(&acme())
+            .await
+            .unwrap();
+        let data = Repo::get_random_cat_fact(&mut tx).await;
+
+        // then the database error is reported
+        assert_eq!(
+            data.unwrap_err().to_string(),
+            "Repository error: connection refused"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_hide_unreachable_database_without_fallback() {
+        // given an unreachable database, and no fallback
+        let mut inner = MockPersistence::new();
+        inner
+            .expect_get_tenant_read_transaction()
+            .returning(|_tenant| Err(RepositoryError::new("connection refused".into())));
+        let persistence =
+            FallbackPersistence::new(inner, upstream_fact(), CatFactsFallback::Disabled);
+
+        // when getting a transaction
+        let tx = persistence.get_tenant_read_transaction(&acme()).await;
+
+        // then the database error is reported
+        assert!(tx.is_err());
+    }
+}
@@ -15,6 +15,45 @@ where
     async fn get_all_cat_facts(
         tx: &mut P::Transaction,
     ) -> Result<Vec<CatFactEntity>, RepositoryError>;
+    async fn get_cat_fact_by_id(
+        tx: &mut P::Transaction,
+        fact_id: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError>;
     async fn get_random_cat_fact(tx: &mut P::Transaction)
         -> Result<CatFactEntity, RepositoryError>;
+    /// Add a fact to the catalogue, returning it with its id
+    async fn insert_cat_fact(
+        tx: &mut P::Transaction,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError>;
+    /// Add a fact to the catalogue unless it holds it already, apart from
+    /// synchronised ones, returning it as stored and whether it was added
+    async fn upsert_cat_fact(
+        tx: &mut P::Transaction,
+        fact: String,
+    ) -> Result<(CatFactEntity, bool), RepositoryError>;
+    /// The fact synchronised from the given position of the upstream listing
+    async fn get_upstream_cat_fact(
+        tx: &mut P::Transaction,
+        upstream_index: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError>;
+    /// Returning it with its id
+    async fn insert_upstream_cat_fact(
+        tx: &mut P::Transaction,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError>;
+    /// Mark a fact as no longer matching upstream, for an editor to review
+    async fn flag_changed_cat_fact(
+        tx: &mut P::Transaction,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError>;
+    /// Replace the text of a fact still at `version`, returning it at the
+    /// next one, `None` when it is missing or at another version
+    async fn update_cat_fact(
+        tx: &mut P::Transaction,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError>;
 }
@@ -19,4 +19,28 @@ where
         tx: &mut P::Transaction,
         fact_id: i32,
     ) -> Result<Option<DogFactEntity>, RepositoryError>;
+    /// The fact synchronised from the given position of the upstream listing
+    async fn get_upstream_dog_fact(
+        tx: &mut P::Transaction,
+        upstream_index: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError>;
+    /// Returning it with its id
+    async fn insert_upstream_dog_fact(
+        tx: &mut P::Transaction,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<DogFactEntity, RepositoryError>;
+    /// Mark a fact as no longer matching upstream, for an editor to review
+    async fn flag_changed_dog_fact(
+        tx: &mut P::Transaction,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError>;
+    /// Replace the text of a fact still at `version`, returning it at the
+    /// next one, `None` when it is missing or at another version
+    async fn update_dog_fact(
+        tx: &mut P::Transaction,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError>;
 }
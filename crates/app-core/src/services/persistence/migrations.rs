@@ -0,0 +1,16 @@
+use app_domain::entities::MigrationEntity;
+use async_trait::async_trait;
+
+use super::RepositoryError;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// The schema migrations a backend ships with, and which of them its
+/// database has
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SchemaMigrations: Send + Sync {
+    /// Ordered by version
+    async fn get_migrations(&self) -> Result<Vec<MigrationEntity>, RepositoryError>;
+}
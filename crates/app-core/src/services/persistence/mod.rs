@@ -1,11 +1,24 @@
+use app_domain::{entities::TenantEntity, events::DomainEvent};
 use async_trait::async_trait;
 use thiserror::Error;
 
+mod cat_facts_fallback;
 mod cat_repo;
 mod dog_repo;
+mod migrations;
+mod outbox;
+mod session_repo;
+mod sync_run_repo;
+mod user_repo;
 
+pub use cat_facts_fallback::*;
 pub use cat_repo::*;
 pub use dog_repo::*;
+pub use migrations::*;
+pub use outbox::*;
+pub use session_repo::*;
+pub use sync_run_repo::*;
+pub use user_repo::*;
 
 #[cfg(test)]
 use mockall::{predicate::*, *};
@@ -21,6 +34,71 @@ pub trait Persistence: 'static + Send + Sync {
     type Transaction;
     /// Get a connection to persistence as
     async fn get_transaction(&self) -> Result<Self::Transaction, RepositoryError>;
+    /// Get a connection only seeing, and only writing, the data of one tenant
+    async fn get_tenant_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<Self::Transaction, RepositoryError>;
+    /// Get a connection as `options` ask, backends that can't keep to them
+    /// fail rather than give a looser one
+    async fn get_transaction_with(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<Self::Transaction, RepositoryError>;
+    /// Get a connection of one tenant as `options` ask
+    async fn get_tenant_transaction_with(
+        &self,
+        tenant: &TenantEntity,
+        options: TransactionOptions,
+    ) -> Result<Self::Transaction, RepositoryError>;
+    /// Get a connection only reading, possibly of a replica lagging a little
+    /// behind, backends without replicas read where they write
+    async fn get_read_transaction(&self) -> Result<Self::Transaction, RepositoryError> {
+        self.get_transaction_with(TransactionOptions::read_only())
+            .await
+    }
+    /// Get a connection only reading the data of one tenant
+    async fn get_tenant_read_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<Self::Transaction, RepositoryError> {
+        self.get_tenant_transaction_with(tenant, TransactionOptions::read_only())
+            .await
+    }
+}
+
+/// How strictly a transaction is kept apart from concurrent ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    #[default]
+    ReadCommitted,
+    RepeatableRead,
+    /// As if transactions ran one after the other, some fail with a
+    /// transient error instead, to be run again
+    Serializable,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    pub isolation: IsolationLevel,
+    /// Writing fails
+    pub read_only: bool,
+}
+
+impl TransactionOptions {
+    pub fn serializable() -> Self {
+        TransactionOptions {
+            isolation: IsolationLevel::Serializable,
+            read_only: false,
+        }
+    }
+
+    pub fn read_only() -> Self {
+        TransactionOptions {
+            isolation: IsolationLevel::ReadCommitted,
+            read_only: true,
+        }
+    }
 }
 
 #[cfg_attr(test, automock)]
@@ -28,8 +106,42 @@ pub trait Persistence: 'static + Send + Sync {
 pub trait Transaction: Send + Sync {
     async fn commit(self) -> Result<(), RepositoryError>;
     async fn rollback(self) -> Result<(), RepositoryError>;
+    /// Mark a point the transaction can go back to, without ending it
+    async fn savepoint(&mut self, name: &str) -> Result<(), RepositoryError>;
+    /// Undo what was done since the savepoint, which is kept
+    async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), RepositoryError>;
+    /// Keep what was done since the savepoint, which is forgotten
+    async fn release_savepoint(&mut self, name: &str) -> Result<(), RepositoryError>;
+    /// Keep `event` with the changes of the transaction, to be relayed once
+    /// committed; backends without an outbox keep nothing, their events are
+    /// only published
+    async fn add_event(&mut self, _event: &DomainEvent) -> Result<(), RepositoryError> {
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
-#[error("Repository error: {0}")]
-pub struct RepositoryError(pub String);
+#[error("Repository error: {message}")]
+pub struct RepositoryError {
+    pub message: String,
+    /// The backend gave up on the transaction to keep concurrent ones
+    /// consistent, a serialization failure or a deadlock, so running it
+    /// again may well succeed
+    pub transient: bool,
+}
+
+impl RepositoryError {
+    pub fn new(message: String) -> Self {
+        RepositoryError {
+            message,
+            transient: false,
+        }
+    }
+
+    pub fn transient(message: String) -> Self {
+        RepositoryError {
+            message,
+            transient: true,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use app_domain::entities::OutboxEventEntity;
+use async_trait::async_trait;
+
+use super::RepositoryError;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// The events kept by transactions, as delivered by the relay
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    /// Claim the due events of up to `limit` facts for `lease`, counting an
+    /// attempt for each
+    ///
+    /// Of a fact, only the oldest pending event is claimed, once no other
+    /// relay holds a claim on it, for subscribers to get them in order.
+    async fn claim_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<OutboxEventEntity>, RepositoryError>;
+    /// Every subscriber handled it
+    async fn mark_delivered(&self, event_id: i64) -> Result<(), RepositoryError>;
+    /// Try again after `delay` for the subscribers not in `delivered_to`
+    async fn retry_event(
+        &self,
+        event: &OutboxEventEntity,
+        error: &str,
+        delay: Duration,
+    ) -> Result<(), RepositoryError>;
+    /// Give up on it, keeping it with its error, no longer holding back the
+    /// next events of its fact
+    async fn dead_letter_event(
+        &self,
+        event: &OutboxEventEntity,
+        error: &str,
+    ) -> Result<(), RepositoryError>;
+    /// Forget the events delivered more than `retention` ago, returning how
+    /// many, dead-lettered ones are kept for someone to look into
+    async fn purge_delivered(&self, retention: Duration) -> Result<u64, RepositoryError>;
+}
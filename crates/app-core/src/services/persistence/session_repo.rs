@@ -0,0 +1,44 @@
+use app_domain::entities::SessionEntity;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::{Persistence, RepositoryError, Transaction};
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// Sessions are stored and looked up by the SHA-256 of their id, the id
+/// itself only ever lives in the client's cookie
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SessionRepo<P: Persistence>: 'static
+where
+    <P as Persistence>::Transaction: Transaction,
+{
+    async fn create_session(
+        tx: &mut P::Transaction,
+        session: SessionEntity,
+    ) -> Result<(), RepositoryError>;
+    async fn get_session(
+        tx: &mut P::Transaction,
+        id_hash: String,
+    ) -> Result<Option<SessionEntity>, RepositoryError>;
+    async fn renew_session(
+        tx: &mut P::Transaction,
+        id_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+    async fn delete_session(
+        tx: &mut P::Transaction,
+        id_hash: String,
+    ) -> Result<(), RepositoryError>;
+    /// End every session of a user, e.g. once their password changed
+    async fn delete_user_sessions(
+        tx: &mut P::Transaction,
+        username: String,
+    ) -> Result<(), RepositoryError>;
+    async fn delete_expired_sessions(
+        tx: &mut P::Transaction,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+}
@@ -0,0 +1,29 @@
+use app_domain::entities::SyncRunEntity;
+use async_trait::async_trait;
+
+use super::{Persistence, RepositoryError, Transaction};
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SyncRunRepo<P: Persistence>: 'static
+where
+    <P as Persistence>::Transaction: Transaction,
+{
+    /// Store a started run, returns its id
+    async fn create_sync_run(
+        tx: &mut P::Transaction,
+        run: SyncRunEntity,
+    ) -> Result<i64, RepositoryError>;
+    async fn finish_sync_run(
+        tx: &mut P::Transaction,
+        run: SyncRunEntity,
+    ) -> Result<(), RepositoryError>;
+    /// The latest runs first
+    async fn get_sync_runs(
+        tx: &mut P::Transaction,
+        limit: i64,
+    ) -> Result<Vec<SyncRunEntity>, RepositoryError>;
+}
@@ -0,0 +1,42 @@
+use app_domain::entities::{PasswordResetEntity, UserEntity};
+use async_trait::async_trait;
+
+use super::{Persistence, RepositoryError, Transaction};
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait UserRepo<P: Persistence>: 'static
+where
+    <P as Persistence>::Transaction: Transaction,
+{
+    async fn create_user(tx: &mut P::Transaction, user: UserEntity) -> Result<(), RepositoryError>;
+    async fn get_user(
+        tx: &mut P::Transaction,
+        username: String,
+    ) -> Result<Option<UserEntity>, RepositoryError>;
+    async fn get_user_by_email(
+        tx: &mut P::Transaction,
+        email: String,
+    ) -> Result<Option<UserEntity>, RepositoryError>;
+    async fn update_password_hash(
+        tx: &mut P::Transaction,
+        username: String,
+        password_hash: String,
+    ) -> Result<(), RepositoryError>;
+    async fn create_password_reset(
+        tx: &mut P::Transaction,
+        reset: PasswordResetEntity,
+    ) -> Result<(), RepositoryError>;
+    /// Remove the reset of that token and return it, for it to be used once
+    async fn take_password_reset(
+        tx: &mut P::Transaction,
+        token_hash: String,
+    ) -> Result<Option<PasswordResetEntity>, RepositoryError>;
+    async fn delete_password_resets(
+        tx: &mut P::Transaction,
+        username: String,
+    ) -> Result<(), RepositoryError>;
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use thiserror::Error;
+
+#[cfg(test)]
+use mockall::{predicate::*, *};
+
+/// How long a user has to come back from the identity provider
+pub const SSO_PENDING_LOGIN_TTL: Duration = Duration::from_secs(600);
+
+/// Where to send the user to authenticate against the identity provider
+#[derive(Debug, Clone)]
+pub struct SsoRedirect {
+    pub url: String,
+    pub state: String,
+    /// The login waiting for the provider, sealed so that only the service
+    /// can open it, to be kept in the user's browser until the callback
+    pub pending: String,
+}
+
+/// The identity of a user as asserted by the identity provider
+/// and mapped to local roles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsoIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub roles: Vec<String>,
+}
+
+impl SsoIdentity {
+    /// The name of the user here, namespaced by provider so that no subject
+    /// can pass for a local user, nor for one of another provider
+    pub fn principal_name(&self) -> String {
+        format!("oidc:{}:{}", self.issuer, self.subject)
+    }
+}
+
+/// An interface of any single sign-on provider
+///
+/// Implementations run an authorization-code flow: `begin_login` gives the
+/// URL to redirect the user to, `complete_login` exchanges the code the
+/// provider sends back for a verified identity, given the sealed pending
+/// login the browser kept since `begin_login`.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SsoService: Send + Sync {
+    async fn begin_login(&self) -> Result<SsoRedirect, SsoError>;
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        pending: &str,
+    ) -> Result<SsoIdentity, SsoError>;
+}
+
+#[derive(Error, Debug)]
+pub enum SsoError {
+    #[error("Identity provider error: {0}")]
+    Provider(String),
+    #[error("Unknown or expired login state")]
+    InvalidState,
+    #[error("Invalid ID token: {0}")]
+    InvalidToken(String),
+}
@@ -0,0 +1,71 @@
+use crate::services::{SsoRedirect, SsoService};
+
+use super::UseCaseError;
+
+pub struct BeginSsoLoginUseCase<'a, S: ?Sized> {
+    sso_service: &'a S,
+}
+
+impl<'a, S: ?Sized> BeginSsoLoginUseCase<'a, S> {
+    pub fn new(sso_service: &'a S) -> Self {
+        BeginSsoLoginUseCase { sso_service }
+    }
+}
+
+impl<'a, S> BeginSsoLoginUseCase<'a, S>
+where
+    S: SsoService + ?Sized,
+{
+    pub async fn execute(&self) -> Result<SsoRedirect, UseCaseError> {
+        let redirect = self.sso_service.begin_login().await?;
+
+        Ok(redirect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{MockSsoService, SsoError};
+
+    #[actix_rt::test]
+    async fn test_should_return_upstream_error_when_provider_unreachable() {
+        // given an identity provider that can't be reached
+        let mut sso_service = MockSsoService::new();
+        sso_service
+            .expect_begin_login()
+            .times(1)
+            .returning(|| Err(SsoError::Provider("connection refused".into())));
+
+        // when calling usecase
+        let begin_sso_login_usecase = BeginSsoLoginUseCase::new(&sso_service);
+        let data = begin_sso_login_usecase.execute().await;
+
+        // then exception
+        assert!(data.is_err());
+        let result = data.unwrap_err();
+        assert_eq!("Upstream error: connection refused", result.to_string());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_redirect() {
+        // given an identity provider building the authorization url
+        let mut sso_service = MockSsoService::new();
+        sso_service.expect_begin_login().times(1).returning(|| {
+            Ok(SsoRedirect {
+                url: String::from("https://idp.example/authorize?state=abc"),
+                state: String::from("abc"),
+                pending: String::from("sealed"),
+            })
+        });
+
+        // when calling usecase
+        let begin_sso_login_usecase = BeginSsoLoginUseCase::new(&sso_service);
+        let data = begin_sso_login_usecase.execute().await.unwrap();
+
+        // then assert the result is the provider redirect
+        assert_eq!(data.url, "https://idp.example/authorize?state=abc");
+        assert_eq!(data.state, "abc");
+    }
+}
@@ -0,0 +1,212 @@
+use std::marker::PhantomData;
+
+use crate::services::{
+    AuthError, AuthService, PasswordHasher, Persistence, SessionRepo, Transaction, UserRepo,
+};
+
+use super::{
+    credentials::{check_new_password, replace_password},
+    UseCaseError,
+};
+
+/// Let a user logged in change their password, given the current one,
+/// ending the sessions and tokens of the old one
+pub struct ChangePasswordUseCase<'a, P, UR, SR, H: ?Sized, A: ?Sized> {
+    persistance: P,
+    password_hasher: &'a H,
+    auth_service: &'a A,
+    user_repository: PhantomData<UR>,
+    session_repository: PhantomData<SR>,
+}
+
+impl<'a, P, UR, SR, H: ?Sized, A: ?Sized> ChangePasswordUseCase<'a, P, UR, SR, H, A> {
+    pub fn new(persistance: P, password_hasher: &'a H, auth_service: &'a A) -> Self {
+        ChangePasswordUseCase {
+            persistance,
+            password_hasher,
+            auth_service,
+            user_repository: PhantomData::<UR>,
+            session_repository: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<'a, P, UR, SR, H, A> ChangePasswordUseCase<'a, P, UR, SR, H, A>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    UR: UserRepo<P>,
+    SR: SessionRepo<P>,
+    H: PasswordHasher + ?Sized,
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(
+        &self,
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), UseCaseError> {
+        check_new_password(new_password)?;
+
+        let mut tx = self.persistance.get_transaction().await?;
+        let verified = match UR::get_user(&mut tx, username.into()).await? {
+            Some(user) => {
+                self.password_hasher
+                    .verify(current_password, &user.password_hash)
+                    .await
+            }
+            None => false,
+        };
+        if !verified {
+            return Err(AuthError::InvalidCredentials.into());
+        }
+        let password_hash = self.password_hasher.hash(new_password).await?;
+        replace_password::<P, UR, SR>(&mut tx, username, password_hash).await?;
+        tx.commit().await?;
+
+        self.auth_service.revoke_tokens(username).await?;
+        Ok(())
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        MockAuthService, MockPasswordHasher, MockPersistence, MockSessionRepo, MockTransaction,
+        MockUserRepo,
+    };
+    use app_domain::entities::UserEntity;
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockUsers = MockUserRepo<MockPersistence>;
+    type MockSessions = MockSessionRepo<MockPersistence>;
+    type MockUseCase<'a> = ChangePasswordUseCase<
+        'a,
+        MockPersistence,
+        MockUsers,
+        MockSessions,
+        MockPasswordHasher,
+        MockAuthService,
+    >;
+
+    fn password_hasher() -> MockPasswordHasher {
+        let mut password_hasher = MockPasswordHasher::new();
+        password_hasher
+            .expect_verify()
+            .returning(|password, hash| hash == format!("hashed {}", password));
+        password_hasher
+            .expect_hash()
+            .returning(|password| Ok(format!("hashed {}", password)));
+        password_hasher
+    }
+
+    fn jane() -> Option<UserEntity> {
+        Some(UserEntity::new(
+            "jane".into(),
+            "jane@example.com".into(),
+            "hashed old secret".into(),
+        ))
+    }
+
+    #[actix_rt::test]
+    async fn test_should_refuse_wrong_current_password() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .times(1)
+            .returning(|| Ok(MockTransaction::new()));
+
+        // given a user with another password
+        let get_ctx = MockUsers::get_user_context();
+        get_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(jane()));
+        let update_ctx = MockUsers::update_password_hash_context();
+        update_ctx.expect().times(0);
+        let mut auth_service = MockAuthService::new();
+        auth_service.expect_revoke_tokens().times(0);
+
+        // when calling usecase
+        let password_hasher = password_hasher();
+        let usecase = MockUseCase::new(persistence, &password_hasher, &auth_service);
+        let data = usecase
+            .execute("jane", "wrong secret", "correct horse")
+            .await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_change_password_and_end_sessions() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_transaction().times(1).returning(|| {
+            let mut tx = MockTransaction::new();
+            tx.expect_commit().times(1).returning(|| Ok(()));
+            Ok(tx)
+        });
+
+        // given a user with that password
+        let get_ctx = MockUsers::get_user_context();
+        get_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(jane()));
+
+        // then the password is replaced, ending the resets, the sessions and
+        // the tokens
+        let update_ctx = MockUsers::update_password_hash_context();
+        update_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, username, hash| username == "jane" && hash == "hashed correct horse")
+            .returning(|_tx, _username, _hash| Ok(()));
+        let resets_ctx = MockUsers::delete_password_resets_context();
+        resets_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(()));
+        let sessions_ctx = MockSessions::delete_user_sessions_context();
+        sessions_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(()));
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_revoke_tokens()
+            .times(1)
+            .returning(|_username| Ok(()));
+
+        // when calling usecase
+        let password_hasher = password_hasher();
+        let usecase = MockUseCase::new(persistence, &password_hasher, &auth_service);
+        let data = usecase.execute("jane", "old secret", "correct horse").await;
+
+        assert!(data.is_ok());
+    }
+}
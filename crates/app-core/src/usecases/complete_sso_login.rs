@@ -0,0 +1,133 @@
+use crate::services::{SsoIdentity, SsoService};
+
+use super::UseCaseError;
+
+pub struct CompleteSsoLoginUseCase<'a, S: ?Sized> {
+    sso_service: &'a S,
+}
+
+impl<'a, S: ?Sized> CompleteSsoLoginUseCase<'a, S> {
+    pub fn new(sso_service: &'a S) -> Self {
+        CompleteSsoLoginUseCase { sso_service }
+    }
+}
+
+impl<'a, S> CompleteSsoLoginUseCase<'a, S>
+where
+    S: SsoService + ?Sized,
+{
+    pub async fn execute(
+        &self,
+        code: &str,
+        state: &str,
+        pending: &str,
+    ) -> Result<SsoIdentity, UseCaseError> {
+        if code.is_empty() || state.is_empty() {
+            return Err(UseCaseError::Unauthorized(
+                "Missing authorization code or state".into(),
+            ));
+        }
+        // a callback the browser didn't start, e.g. forged by another site
+        if pending.is_empty() {
+            return Err(UseCaseError::Unauthorized(
+                "No login pending in this browser".into(),
+            ));
+        }
+
+        let identity = self
+            .sso_service
+            .complete_login(code, state, pending)
+            .await?;
+
+        Ok(identity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{MockSsoService, SsoError};
+    use mockall::predicate::eq;
+
+    #[actix_rt::test]
+    async fn test_should_not_call_provider_without_code() {
+        // given an identity provider that must not be called
+        let mut sso_service = MockSsoService::new();
+        sso_service.expect_complete_login().times(0);
+
+        // when calling usecase without an authorization code
+        let complete_sso_login_usecase = CompleteSsoLoginUseCase::new(&sso_service);
+        let data = complete_sso_login_usecase
+            .execute("", "state", "pending")
+            .await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_call_provider_without_pending_login() {
+        // given an identity provider that must not be called
+        let mut sso_service = MockSsoService::new();
+        sso_service.expect_complete_login().times(0);
+
+        // when calling usecase from a browser that never started a login
+        let complete_sso_login_usecase = CompleteSsoLoginUseCase::new(&sso_service);
+        let data = complete_sso_login_usecase
+            .execute("code", "state", "")
+            .await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_unauthorized_when_state_unknown() {
+        // given an identity provider rejecting the state
+        let mut sso_service = MockSsoService::new();
+        sso_service
+            .expect_complete_login()
+            .times(1)
+            .returning(|_code, _state, _pending| Err(SsoError::InvalidState));
+
+        // when calling usecase
+        let complete_sso_login_usecase = CompleteSsoLoginUseCase::new(&sso_service);
+        let data = complete_sso_login_usecase
+            .execute("code", "state", "pending")
+            .await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_identity() {
+        // given an identity provider accepting the code
+        let mut sso_service = MockSsoService::new();
+        sso_service
+            .expect_complete_login()
+            .with(eq("code"), eq("state"), eq("pending"))
+            .times(1)
+            .returning(|_code, _state, _pending| {
+                Ok(SsoIdentity {
+                    issuer: String::from("https://idp.example.com"),
+                    subject: String::from("user-1"),
+                    email: Some(String::from("jane@example.com")),
+                    name: None,
+                    roles: vec![String::from("admin")],
+                })
+            });
+
+        // when calling usecase
+        let complete_sso_login_usecase = CompleteSsoLoginUseCase::new(&sso_service);
+        let data = complete_sso_login_usecase
+            .execute("code", "state", "pending")
+            .await
+            .unwrap();
+
+        // then assert the result is the mapped identity
+        assert_eq!(data.principal_name(), "oidc:https://idp.example.com:user-1");
+        assert_eq!(data.roles, vec!["admin"]);
+    }
+}
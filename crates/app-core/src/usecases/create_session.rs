@@ -0,0 +1,238 @@
+use std::{marker::PhantomData, net::IpAddr};
+
+use chrono::{Duration, Utc};
+
+use crate::services::{AuthService, Persistence, Principal, SessionRepo, Transaction};
+use app_domain::entities::SessionEntity;
+
+use super::{
+    credentials::{hash_token, random_token},
+    UseCaseError,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SessionPolicy {
+    /// A session expires after this long without any request
+    pub idle_timeout: Duration,
+    /// A session expires this long after login, however active it is
+    pub absolute_timeout: Duration,
+}
+
+pub struct CreateSessionUseCase<'a, P, R, A: ?Sized> {
+    persistance: P,
+    auth_service: &'a A,
+    policy: SessionPolicy,
+    repo: PhantomData<R>,
+}
+
+impl<'a, P, SR, A: ?Sized> CreateSessionUseCase<'a, P, SR, A> {
+    pub fn new(persistance: P, auth_service: &'a A, policy: SessionPolicy) -> Self {
+        CreateSessionUseCase {
+            persistance,
+            auth_service,
+            policy,
+            repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<'a, P, SR, A> CreateSessionUseCase<'a, P, SR, A>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SessionRepo<P>,
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(
+        &self,
+        username: &str,
+        password: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<SessionEntity, UseCaseError> {
+        let principal = self
+            .auth_service
+            .login(username, password, client_ip)
+            .await?;
+
+        self.execute_for(principal).await
+    }
+
+    /// Open a session for a principal authenticated elsewhere, e.g. by the
+    /// identity provider of a single sign-on
+    pub async fn execute_for(&self, principal: Principal) -> Result<SessionEntity, UseCaseError> {
+        let now = Utc::now();
+        let session_id = random_token();
+        let session = SessionEntity::new(
+            hash_token(&session_id),
+            principal.username,
+            principal.roles,
+            random_token(),
+            now,
+            now + self.policy.idle_timeout.min(self.policy.absolute_timeout),
+        );
+
+        let mut tx = self.persistance.get_transaction().await?;
+        SR::delete_expired_sessions(&mut tx, now).await?;
+        SR::create_session(&mut tx, session.clone()).await?;
+        tx.commit().await?;
+
+        // only its hash is stored, the client alone holds the id
+        Ok(SessionEntity {
+            session_id,
+            ..session
+        })
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        AuthError, MockAuthService, MockPersistence, MockSessionRepo, MockTransaction,
+    };
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+        static ref STORED_ID: Mutex<String> = Mutex::new(String::new());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockSessionRepo<MockPersistence>;
+    type MockUseCase<'a> = CreateSessionUseCase<'a, MockPersistence, MockRepo, MockAuthService>;
+
+    const POLICY: SessionPolicy = SessionPolicy {
+        idle_timeout: Duration::minutes(30),
+        absolute_timeout: Duration::hours(12),
+    };
+
+    #[actix_rt::test]
+    async fn test_should_return_unauthorized_when_login_fails() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_transaction().times(0);
+
+        // given an auth service rejecting the credentials
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_login()
+            .times(1)
+            .returning(|_username, _password, _client_ip| Err(AuthError::InvalidCredentials));
+
+        // when calling usecase
+        let create_session_usecase = MockUseCase::new(persistence, &auth_service, POLICY);
+        let data = create_session_usecase.execute("jane", "wrong", None).await;
+
+        // then unauthorized, without any session stored
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_store_new_session() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given an auth service accepting the credentials
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_login()
+            .times(1)
+            .returning(|username, _password, _client_ip| {
+                Ok(Principal::new(username.into(), vec!["admin".into()]))
+            });
+
+        // and a repo storing the session
+        let expired_ctx = MockRepo::delete_expired_sessions_context();
+        expired_ctx.expect().times(1).returning(|_tx, _now| Ok(()));
+        let repo_ctx = MockRepo::create_session_context();
+        repo_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, session| session.username == "jane" && session.roles == ["admin"])
+            .returning(|_tx, session| {
+                STORED_ID.lock().unwrap().clone_from(&session.session_id);
+                Ok(())
+            });
+
+        // when calling usecase
+        let create_session_usecase = MockUseCase::new(persistence, &auth_service, POLICY);
+        let data = create_session_usecase
+            .execute("jane", "secret", None)
+            .await
+            .unwrap();
+
+        // then assert the session is random, stored by hash only,
+        // and expires after the idle timeout
+        assert_eq!(data.username, "jane");
+        assert_eq!(*STORED_ID.lock().unwrap(), hash_token(&data.session_id));
+        assert_eq!(data.session_id.len(), 43);
+        assert_ne!(data.session_id, data.csrf_token);
+        assert_eq!(data.expires_at - data.created_at, Duration::minutes(30));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_store_session_of_principal_authenticated_elsewhere() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given an auth service that must not check any password
+        let mut auth_service = MockAuthService::new();
+        auth_service.expect_login().times(0);
+
+        // and a repo storing the session
+        let expired_ctx = MockRepo::delete_expired_sessions_context();
+        expired_ctx.expect().times(1).returning(|_tx, _now| Ok(()));
+        let repo_ctx = MockRepo::create_session_context();
+        repo_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, session| session.username == "jane" && session.roles == ["admin"])
+            .returning(|_tx, _session| Ok(()));
+
+        // when calling usecase with a principal from the identity provider
+        let create_session_usecase = MockUseCase::new(persistence, &auth_service, POLICY);
+        let data = create_session_usecase
+            .execute_for(Principal::new("jane".into(), vec!["admin".into()]))
+            .await
+            .unwrap();
+
+        // then assert the session is the principal's
+        assert_eq!(data.username, "jane");
+        assert_eq!(data.roles, vec!["admin"]);
+    }
+}
@@ -0,0 +1,105 @@
+use std::net::IpAddr;
+
+use crate::services::{AuthService, AuthToken, Principal};
+
+use super::UseCaseError;
+
+pub struct CreateTokenUseCase<'a, A: ?Sized> {
+    auth_service: &'a A,
+}
+
+impl<'a, A: ?Sized> CreateTokenUseCase<'a, A> {
+    pub fn new(auth_service: &'a A) -> Self {
+        CreateTokenUseCase { auth_service }
+    }
+}
+
+impl<'a, A> CreateTokenUseCase<'a, A>
+where
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(
+        &self,
+        username: &str,
+        password: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<AuthToken, UseCaseError> {
+        let principal = self
+            .auth_service
+            .login(username, password, client_ip)
+            .await?;
+
+        self.execute_for(&principal).await
+    }
+
+    /// Issue a token to a principal authenticated elsewhere, e.g. by the
+    /// identity provider of a single sign-on
+    pub async fn execute_for(&self, principal: &Principal) -> Result<AuthToken, UseCaseError> {
+        Ok(self.auth_service.issue_token(principal).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::services::{AuthError, MockAuthService};
+
+    #[actix_rt::test]
+    async fn test_should_return_too_many_requests_when_throttled() {
+        // given an auth service throttling the account
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_login()
+            .times(1)
+            .returning(|_username, _password, _client_ip| {
+                Err(AuthError::Throttled {
+                    retry_after: Duration::from_secs(30),
+                })
+            });
+        auth_service.expect_issue_token().times(0);
+
+        // when calling usecase
+        let create_token_usecase = CreateTokenUseCase::new(&auth_service);
+        let data = create_token_usecase.execute("jane", "secret", None).await;
+
+        // then too many requests, without any token issued
+        assert!(matches!(
+            data,
+            Err(UseCaseError::TooManyRequests { retry_after }) if retry_after == Duration::from_secs(30)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_issue_token_for_principal() {
+        // given an auth service accepting the credentials
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_login()
+            .times(1)
+            .returning(|username, _password, _client_ip| {
+                Ok(Principal::new(username.into(), vec![]))
+            });
+        auth_service
+            .expect_issue_token()
+            .times(1)
+            .returning(|principal| {
+                Ok(AuthToken {
+                    token: String::from("token"),
+                    principal: principal.clone(),
+                })
+            });
+
+        // when calling usecase
+        let create_token_usecase = CreateTokenUseCase::new(&auth_service);
+        let data = create_token_usecase
+            .execute("jane", "secret", None)
+            .await
+            .unwrap();
+
+        // then assert the token is issued to the logged in principal
+        assert_eq!(data.token, "token");
+        assert_eq!(data.principal.username, "jane");
+    }
+}
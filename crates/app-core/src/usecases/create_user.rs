@@ -0,0 +1,163 @@
+use std::marker::PhantomData;
+
+use crate::services::{PasswordHasher, Persistence, Transaction, UserRepo};
+use app_domain::entities::UserEntity;
+
+use super::{
+    credentials::{check_new_password, normalize_email},
+    UseCaseError,
+};
+
+pub struct CreateUserUseCase<'a, P, R, H: ?Sized> {
+    persistance: P,
+    password_hasher: &'a H,
+    repo: PhantomData<R>,
+}
+
+impl<'a, P, UR, H: ?Sized> CreateUserUseCase<'a, P, UR, H> {
+    pub fn new(persistance: P, password_hasher: &'a H) -> Self {
+        CreateUserUseCase {
+            persistance,
+            password_hasher,
+            repo: PhantomData::<UR>,
+        }
+    }
+}
+
+impl<'a, P, UR, H> CreateUserUseCase<'a, P, UR, H>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    UR: UserRepo<P>,
+    H: PasswordHasher + ?Sized,
+{
+    pub async fn execute(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<UserEntity, UseCaseError> {
+        let username = username.trim();
+        let email = normalize_email(email);
+        if username.is_empty() || !email.contains('@') {
+            return Err(UseCaseError::Business(
+                "A user needs a username and an email".into(),
+            ));
+        }
+        check_new_password(password)?;
+        let password_hash = self.password_hasher.hash(password).await?;
+
+        let mut tx = self.persistance.get_transaction().await?;
+        if UR::get_user(&mut tx, username.into()).await?.is_some()
+            || UR::get_user_by_email(&mut tx, email.clone())
+                .await?
+                .is_some()
+        {
+            return Err(UseCaseError::Business(
+                "Username or email already taken".into(),
+            ));
+        }
+        let user = UserEntity::new(username.into(), email, password_hash);
+        UR::create_user(&mut tx, user.clone()).await?;
+        tx.commit().await?;
+
+        Ok(user)
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{MockPasswordHasher, MockPersistence, MockTransaction, MockUserRepo};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockUserRepo<MockPersistence>;
+    type MockUseCase<'a> = CreateUserUseCase<'a, MockPersistence, MockRepo, MockPasswordHasher>;
+
+    #[actix_rt::test]
+    async fn test_should_refuse_short_password() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_transaction().times(0);
+        let mut password_hasher = MockPasswordHasher::new();
+        password_hasher.expect_hash().times(0);
+
+        // when calling usecase with a password too short
+        let create_user_usecase = MockUseCase::new(persistence, &password_hasher);
+        let data = create_user_usecase
+            .execute("jane", "jane@example.com", "short")
+            .await;
+
+        // then a business error, without anything stored
+        assert!(matches!(data, Err(UseCaseError::Business(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_store_hashed_password() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_transaction().times(1).returning(|| {
+            let mut tx = MockTransaction::new();
+            tx.expect_commit().times(1).returning(|| Ok(()));
+            Ok(tx)
+        });
+
+        // given a hasher
+        let mut password_hasher = MockPasswordHasher::new();
+        password_hasher
+            .expect_hash()
+            .times(1)
+            .returning(|password| Ok(format!("hashed {}", password)));
+
+        // and a repo without that user yet
+        let get_ctx = MockRepo::get_user_context();
+        get_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(None));
+        let get_by_email_ctx = MockRepo::get_user_by_email_context();
+        get_by_email_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, email| email == "jane@example.com")
+            .returning(|_tx, _email| Ok(None));
+        let create_ctx = MockRepo::create_user_context();
+        create_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, user| user.password_hash == "hashed correct horse")
+            .returning(|_tx, _user| Ok(()));
+
+        // when calling usecase
+        let create_user_usecase = MockUseCase::new(persistence, &password_hasher);
+        let data = create_user_usecase
+            .execute("jane", " Jane@Example.com", "correct horse")
+            .await
+            .unwrap();
+
+        // then assert the email is normalised
+        assert_eq!(data.username, "jane");
+        assert_eq!(data.email, "jane@example.com");
+    }
+}
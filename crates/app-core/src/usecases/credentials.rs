@@ -0,0 +1,60 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use super::UseCaseError;
+use crate::services::{Persistence, SessionRepo, Transaction, UserRepo};
+
+/// Shorter passwords are refused
+pub(crate) const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// 43 alphanumeric characters, over 256 bits of randomness
+pub(crate) fn random_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(43)
+        .map(char::from)
+        .collect()
+}
+
+/// How tokens handed out to users are kept, for a leaked table not to be
+/// usable as is; they are random enough not to need a salt
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub(crate) fn check_new_password(password: &str) -> Result<(), UseCaseError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(UseCaseError::Business(format!(
+            "Passwords must be at least {} characters long",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Emails are compared case-insensitively
+pub(crate) fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Swap the password hash of a user, ending what the old password gave
+/// access to: pending resets and sessions
+pub(crate) async fn replace_password<P, UR, SR>(
+    tx: &mut P::Transaction,
+    username: &str,
+    password_hash: String,
+) -> Result<(), UseCaseError>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    UR: UserRepo<P>,
+    SR: SessionRepo<P>,
+{
+    UR::update_password_hash(tx, username.into(), password_hash).await?;
+    UR::delete_password_resets(tx, username.into()).await?;
+    SR::delete_user_sessions(tx, username.into()).await?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+
+use crate::services::{Persistence, SessionRepo, Transaction};
+
+use super::{credentials::hash_token, UseCaseError};
+
+pub struct DeleteSessionUseCase<P, R> {
+    persistance: P,
+    repo: PhantomData<R>,
+}
+
+impl<P, SR> DeleteSessionUseCase<P, SR> {
+    pub fn new(persistance: P) -> Self {
+        DeleteSessionUseCase {
+            persistance,
+            repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<P, SR> DeleteSessionUseCase<P, SR>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SessionRepo<P>,
+{
+    pub async fn execute(&self, session_id: &str) -> Result<(), UseCaseError> {
+        let mut tx = self.persistance.get_transaction().await?;
+        SR::delete_session(&mut tx, hash_token(session_id)).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{MockPersistence, MockSessionRepo, MockTransaction};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockSessionRepo<MockPersistence>;
+    type MockUseCase = DeleteSessionUseCase<MockPersistence, MockRepo>;
+
+    #[actix_rt::test]
+    async fn test_should_delete_session() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given a repo deleting the session
+        let repo_ctx = MockRepo::delete_session_context();
+        repo_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, id| *id == hash_token("session"))
+            .returning(|_tx, _id| Ok(()));
+
+        // when calling usecase
+        let delete_session_usecase = MockUseCase::new(persistence);
+        let data = delete_session_usecase.execute("session").await;
+
+        // then assert it succeeded
+        assert!(data.is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::services::{CatRepo, Persistence, Transaction};
-use app_domain::entities::CatFactEntity;
+use app_domain::entities::{CatFactEntity, TenantEntity};
 
 use super::UseCaseError;
 
@@ -25,9 +25,9 @@ where
     <P as Persistence>::Transaction: Transaction,
     CR: CatRepo<P>,
 {
-    pub async fn execute(&self) -> Result<Vec<CatFactEntity>, UseCaseError> {
+    pub async fn execute(&self, tenant: &TenantEntity) -> Result<Vec<CatFactEntity>, UseCaseError> {
         let cat_facts = {
-            let mut tx = self.persistance.get_transaction().await?;
+            let mut tx = self.persistance.get_tenant_read_transaction(tenant).await?;
             let facts = CR::get_all_cat_facts(&mut tx).await?;
             tx.commit().await?;
             facts
@@ -42,6 +42,7 @@ where
 mod tests {
     use super::*;
     use lazy_static::lazy_static;
+    use mockall::predicate::eq;
     use std::sync::{Mutex, MutexGuard};
 
     use crate::services::{MockCatRepo, MockPersistence, MockTransaction};
@@ -62,6 +63,10 @@ mod tests {
         }
     }
 
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
     type MockRepo = MockCatRepo<MockPersistence>;
     type MockUseCase = GetAllCatFactsUseCase<MockPersistence, MockRepo>;
 
@@ -71,20 +76,20 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| Ok(MockTransaction::new()));
+            .returning(|_tenant| Ok(MockTransaction::new()));
 
         // given the "all cat facts" usecase repo with an unexpected error
         let repo_ctx = MockRepo::get_all_cat_facts_context();
         repo_ctx
             .expect()
-            .returning(|_tx| Err(crate::services::RepositoryError("Oh no!".into())));
+            .returning(|_tx| Err(crate::services::RepositoryError::new("Oh no!".into())));
 
         // when calling usecase
         let get_all_cat_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_cat_facts_usecase.execute().await;
+        let data = get_all_cat_facts_usecase.execute(&acme()).await;
 
         // then exception
         assert!(data.is_err());
@@ -98,10 +103,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -115,7 +120,7 @@ mod tests {
 
         // when calling usecase
         let get_all_cat_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_cat_facts_usecase.execute().await.unwrap();
+        let data = get_all_cat_facts_usecase.execute(&acme()).await.unwrap();
 
         // then assert the result is an empty list
         assert_eq!(data.len(), 0);
@@ -127,10 +132,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -143,17 +148,19 @@ mod tests {
                 CatFactEntity {
                     fact_txt: String::from("fact1"),
                     fact_id: 1,
+                    version: 1,
                 },
                 CatFactEntity {
                     fact_txt: String::from("fact2"),
                     fact_id: 2,
+                    version: 1,
                 },
             ])
         });
 
         // when calling usecase
         let get_all_cat_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_cat_facts_usecase.execute().await.unwrap();
+        let data = get_all_cat_facts_usecase.execute(&acme()).await.unwrap();
 
         // then assert the result is an empty list
         assert_eq!(data.len(), 2);
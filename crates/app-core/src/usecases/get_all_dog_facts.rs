@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::services::{DogRepo, Persistence, Transaction};
-use app_domain::entities::DogFactEntity;
+use app_domain::entities::{DogFactEntity, TenantEntity};
 
 use super::UseCaseError;
 
@@ -25,9 +25,9 @@ where
     <P as Persistence>::Transaction: Transaction,
     DR: DogRepo<P>,
 {
-    pub async fn execute(&self) -> Result<Vec<DogFactEntity>, UseCaseError> {
+    pub async fn execute(&self, tenant: &TenantEntity) -> Result<Vec<DogFactEntity>, UseCaseError> {
         let dog_facts = {
-            let mut tx = self.persistance.get_transaction().await?;
+            let mut tx = self.persistance.get_tenant_read_transaction(tenant).await?;
             let facts = DR::get_all_dog_facts(&mut tx).await?;
             // transaction is dropped if repo gets out of scope without commit
             tx.commit().await?;
@@ -43,6 +43,7 @@ where
 mod tests {
     use super::*;
     use lazy_static::lazy_static;
+    use mockall::predicate::eq;
     use std::sync::{Mutex, MutexGuard};
 
     use crate::services::{MockDogRepo, MockPersistence, MockTransaction};
@@ -63,6 +64,10 @@ mod tests {
         }
     }
 
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
     type MockRepo = MockDogRepo<MockPersistence>;
     type MockUseCase = GetAllDogFactsUseCase<MockPersistence, MockRepo>;
 
@@ -72,20 +77,20 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| Ok(MockTransaction::new()));
+            .returning(|_tenant| Ok(MockTransaction::new()));
 
         // given the "all dog facts" usecase repo with an unexpected random error
         let repo_ctx = MockRepo::get_all_dog_facts_context();
         repo_ctx
             .expect()
-            .returning(|_tx| Err(crate::services::RepositoryError("Oh no!".into())));
+            .returning(|_tx| Err(crate::services::RepositoryError::new("Oh no!".into())));
 
         // when calling usecase
         let get_all_dog_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_dog_facts_usecase.execute().await;
+        let data = get_all_dog_facts_usecase.execute(&acme()).await;
 
         // then exception
         assert!(data.is_err());
@@ -99,10 +104,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -116,7 +121,7 @@ mod tests {
 
         // when calling usecase
         let get_all_dog_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_dog_facts_usecase.execute().await.unwrap();
+        let data = get_all_dog_facts_usecase.execute(&acme()).await.unwrap();
 
         // then assert the result is an empty list
         assert_eq!(data.len(), 0);
@@ -128,10 +133,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -144,17 +149,19 @@ mod tests {
                 DogFactEntity {
                     fact_id: 1,
                     fact: String::from("fact1"),
+                    version: 1,
                 },
                 DogFactEntity {
                     fact_id: 2,
                     fact: String::from("fact2"),
+                    version: 1,
                 },
             ])
         });
 
         // when calling usecase
         let get_all_dog_facts_usecase = MockUseCase::new(persistence);
-        let data = get_all_dog_facts_usecase.execute().await.unwrap();
+        let data = get_all_dog_facts_usecase.execute(&acme()).await.unwrap();
 
         // then assert the result is an empty list
         assert_eq!(data.len(), 2);
@@ -0,0 +1,150 @@
+use crate::services::CatFactsService;
+use app_domain::entities::CatFactEntity;
+
+use super::{sync_facts::get_all_pages, UseCaseError};
+
+pub struct GetAllUpstreamCatFactsUseCase<'a, S: ?Sized> {
+    cat_facts_service: &'a S,
+}
+
+impl<'a, S: ?Sized> GetAllUpstreamCatFactsUseCase<'a, S> {
+    pub fn new(cat_facts_service: &'a S) -> Self {
+        GetAllUpstreamCatFactsUseCase { cat_facts_service }
+    }
+}
+
+impl<'a, S> GetAllUpstreamCatFactsUseCase<'a, S>
+where
+    S: CatFactsService + ?Sized,
+{
+    /// Walk the listing page by page, until the last one
+    pub async fn execute(&self) -> Result<Vec<CatFactEntity>, UseCaseError> {
+        let cat_facts =
+            get_all_pages(|page| self.cat_facts_service.get_cat_facts_page(page)).await?;
+        Ok(cat_facts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{FactsPage, MockCatFactsService, UpstreamError};
+    use crate::usecases::sync_facts::MAX_PAGES;
+    use app_domain::entities::UNSTORED_FACT_ID;
+    use mockall::predicate::eq;
+
+    fn page(current_page: i32, last_page: i32, facts: &[&str]) -> FactsPage<CatFactEntity> {
+        FactsPage {
+            facts: facts
+                .iter()
+                .map(|fact| CatFactEntity::new(fact.to_string(), UNSTORED_FACT_ID))
+                .collect(),
+            current_page,
+            last_page,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_walk_every_page() {
+        // given a listing of two pages
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(page(1, 2, &["fact1", "fact2"])));
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(2))
+            .times(1)
+            .returning(|_| Ok(page(2, 2, &["fact3"])));
+
+        // when calling usecase
+        let usecase = GetAllUpstreamCatFactsUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await.unwrap();
+
+        // then assert the facts of both pages are returned in order
+        let facts: Vec<_> = data.iter().map(|f| f.fact_txt.as_str()).collect();
+        assert_eq!(facts, vec!["fact1", "fact2", "fact3"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_stop_on_empty_page() {
+        // given a listing claiming more pages than it has
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(page(1, 10, &[])));
+
+        // when calling usecase
+        let usecase = GetAllUpstreamCatFactsUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await.unwrap();
+
+        // then assert no more pages are requested
+        assert!(data.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_upstream_error_when_a_page_fails() {
+        // given a listing failing on its second page
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(1))
+            .returning(|_| Ok(page(1, 2, &["fact1"])));
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(2))
+            .returning(|_| Err(UpstreamError("bad gateway".into())));
+
+        // when calling usecase
+        let usecase = GetAllUpstreamCatFactsUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await;
+
+        // then exception
+        assert_eq!("Upstream error: bad gateway", data.unwrap_err().to_string());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fail_when_listing_does_not_advance() {
+        // given a listing answering the first page whatever the page asked
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .times(2)
+            .returning(|_| Ok(page(1, 3, &["fact1"])));
+
+        // when calling usecase
+        let usecase = GetAllUpstreamCatFactsUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await;
+
+        // then exception, rather than walking it forever
+        assert_eq!(
+            "Upstream error: asked for page 2, got page 1",
+            data.unwrap_err().to_string()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fail_beyond_max_pages() {
+        // given a listing that never ends
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .times(MAX_PAGES as usize)
+            .returning(|current_page| Ok(page(current_page, i32::MAX, &["fact"])));
+
+        // when calling usecase
+        let usecase = GetAllUpstreamCatFactsUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await;
+
+        // then exception once the cap is reached
+        assert_eq!(
+            format!("Upstream error: more than {} pages", MAX_PAGES),
+            data.unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use app_domain::entities::MigrationEntity;
+
+use crate::services::SchemaMigrations;
+
+use super::UseCaseError;
+
+pub struct GetMigrationsUseCase<'a, M: ?Sized> {
+    schema_migrations: &'a M,
+}
+
+impl<'a, M: ?Sized> GetMigrationsUseCase<'a, M> {
+    pub fn new(schema_migrations: &'a M) -> Self {
+        GetMigrationsUseCase { schema_migrations }
+    }
+}
+
+impl<'a, M> GetMigrationsUseCase<'a, M>
+where
+    M: SchemaMigrations + ?Sized,
+{
+    pub async fn execute(&self) -> Result<Vec<MigrationEntity>, UseCaseError> {
+        Ok(self.schema_migrations.get_migrations().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{MockSchemaMigrations, RepositoryError};
+
+    #[actix_rt::test]
+    async fn test_should_list_applied_and_pending_migrations() {
+        // given a database missing the last migration
+        let mut schema_migrations = MockSchemaMigrations::new();
+        schema_migrations
+            .expect_get_migrations()
+            .times(1)
+            .returning(|| {
+                Ok(vec![
+                    MigrationEntity {
+                        version: 1,
+                        description: String::from("init"),
+                        applied: true,
+                    },
+                    MigrationEntity {
+                        version: 2,
+                        description: String::from("sessions"),
+                        applied: false,
+                    },
+                ])
+            });
+
+        // when calling usecase
+        let get_migrations_usecase = GetMigrationsUseCase::new(&schema_migrations);
+        let data = get_migrations_usecase.execute().await.unwrap();
+
+        // then both are listed, only the first one applied
+        assert_eq!(
+            data.iter()
+                .map(|m| (m.version, m.applied))
+                .collect::<Vec<_>>(),
+            vec![(1, true), (2, false)]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_error_when_database_unreachable() {
+        // given a database refusing connections
+        let mut schema_migrations = MockSchemaMigrations::new();
+        schema_migrations
+            .expect_get_migrations()
+            .returning(|| Err(RepositoryError::new("connection refused".into())));
+
+        // when calling usecase
+        let get_migrations_usecase = GetMigrationsUseCase::new(&schema_migrations);
+        let data = get_migrations_usecase.execute().await;
+
+        // then exception
+        assert_eq!(
+            data.unwrap_err().to_string(),
+            "Repository error: connection refused"
+        );
+    }
+}
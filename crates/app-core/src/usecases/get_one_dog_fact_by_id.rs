@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::services::{DogRepo, Persistence, Transaction};
-use app_domain::entities::DogFactEntity;
+use app_domain::entities::{DogFactEntity, TenantEntity};
 
 use super::UseCaseError;
 
@@ -25,9 +25,13 @@ where
     <P as Persistence>::Transaction: Transaction,
     DR: DogRepo<P>,
 {
-    pub async fn execute(&self, dog_fact_id: &i32) -> Result<DogFactEntity, UseCaseError> {
+    pub async fn execute(
+        &self,
+        tenant: &TenantEntity,
+        dog_fact_id: &i32,
+    ) -> Result<DogFactEntity, UseCaseError> {
         let dog_fact = {
-            let mut tx = self.persistance.get_transaction().await?;
+            let mut tx = self.persistance.get_tenant_read_transaction(tenant).await?;
             let fact = DR::get_dog_fact_by_id(&mut tx, *dog_fact_id).await?;
             // transaction is dropped if repo gets out of scope without commit
             tx.commit().await?;
@@ -43,6 +47,7 @@ where
 mod tests {
     use super::*;
     use lazy_static::lazy_static;
+    use mockall::predicate::eq;
     use std::sync::{Mutex, MutexGuard};
 
     use crate::services::{MockDogRepo, MockPersistence, MockTransaction};
@@ -63,6 +68,10 @@ mod tests {
         }
     }
 
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
     type MockRepo = MockDogRepo<MockPersistence>;
     type MockUseCase = GetOneDogFactByIdUseCase<MockPersistence, MockRepo>;
 
@@ -72,21 +81,21 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| Ok(MockTransaction::new()));
+            .returning(|_tenant| Ok(MockTransaction::new()));
 
         // given the "all dog facts" usecase repo with an unexpected random error
         let repo_ctx = MockRepo::get_dog_fact_by_id_context();
         repo_ctx
             .expect()
             .times(1)
-            .returning(|_tx, _id| Err(crate::services::RepositoryError("Oh no!".into())));
+            .returning(|_tx, _id| Err(crate::services::RepositoryError::new("Oh no!".into())));
 
         // when calling usecase
         let get_one_dog_fact_by_id_usecase = MockUseCase::new(persistence);
-        let data = get_one_dog_fact_by_id_usecase.execute(&1).await;
+        let data = get_one_dog_fact_by_id_usecase.execute(&acme(), &1).await;
 
         // then exception
         assert!(data.is_err());
@@ -100,10 +109,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -115,12 +124,16 @@ mod tests {
             Ok(Some(DogFactEntity {
                 fact_id: 1,
                 fact: String::from("fact1"),
+                version: 1,
             }))
         });
 
         // when calling usecase
         let get_one_dog_fact_by_id_usecase = MockUseCase::new(persistence);
-        let data = get_one_dog_fact_by_id_usecase.execute(&1).await.unwrap();
+        let data = get_one_dog_fact_by_id_usecase
+            .execute(&acme(), &1)
+            .await
+            .unwrap();
 
         // then assert the result is the expected entity
         assert_eq!(data.fact_id, 1);
@@ -1,10 +1,12 @@
 use std::marker::PhantomData;
 
 use crate::services::{CatRepo, Persistence, Transaction};
-use app_domain::entities::CatFactEntity;
+use app_domain::entities::{CatFactEntity, TenantEntity};
 
 use super::UseCaseError;
 
+/// Standing in for an empty or unreachable catalogue is up to the
+/// repository, see `FallbackCatRepo`
 pub struct GetOneRandomCatFactUseCase<P, R> {
     persistance: P,
     repo: PhantomData<R>,
@@ -25,16 +27,12 @@ where
     <P as Persistence>::Transaction: Transaction,
     CR: CatRepo<P>,
 {
-    pub async fn execute(&self) -> Result<CatFactEntity, UseCaseError> {
-        let cat_fact = {
-            let mut tx = self.persistance.get_transaction().await?;
-            let fact = CR::get_random_cat_fact(&mut tx).await?;
-            // transaction is dropped if repo gets out of scope without commit
-            tx.commit().await?;
-            fact
-        };
-
-        Ok(cat_fact)
+    pub async fn execute(&self, tenant: &TenantEntity) -> Result<CatFactEntity, UseCaseError> {
+        let mut tx = self.persistance.get_tenant_read_transaction(tenant).await?;
+        let fact = CR::get_random_cat_fact(&mut tx).await?;
+        // transaction is dropped if repo gets out of scope without commit
+        tx.commit().await?;
+        Ok(fact)
     }
 }
 
@@ -43,6 +41,7 @@ where
 mod tests {
     use super::*;
     use lazy_static::lazy_static;
+    use mockall::predicate::eq;
     use std::sync::{Mutex, MutexGuard};
 
     use crate::services::{MockCatRepo, MockPersistence, MockTransaction};
@@ -63,6 +62,10 @@ mod tests {
         }
     }
 
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
     type MockRepo = MockCatRepo<MockPersistence>;
     type MockUseCase = GetOneRandomCatFactUseCase<MockPersistence, MockRepo>;
 
@@ -72,20 +75,20 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| Ok(MockTransaction::new()));
+            .returning(|_tenant| Ok(MockTransaction::new()));
 
         // given the "all cat facts" usecase repo with an unexpected error
         let repo_ctx = MockRepo::get_random_cat_fact_context();
         repo_ctx
             .expect()
-            .returning(|_tx| Err(crate::services::RepositoryError("Oh no!".into())));
+            .returning(|_tx| Err(crate::services::RepositoryError::new("Oh no!".into())));
 
         // when calling usecase
         let get_one_random_cat_fact_usecase = MockUseCase::new(persistence);
-        let data = get_one_random_cat_fact_usecase.execute().await;
+        let data = get_one_random_cat_fact_usecase.execute(&acme()).await;
 
         // then exception
         assert!(data.is_err());
@@ -99,10 +102,10 @@ mod tests {
 
         let mut persistence = MockPersistence::new();
         persistence
-            .expect_get_transaction()
-            .with()
+            .expect_get_tenant_read_transaction()
+            .with(eq(acme()))
             .times(1)
-            .returning(|| {
+            .returning(|_tenant| {
                 let mut tx = MockTransaction::new();
                 tx.expect_commit().times(1).returning(|| Ok(()));
                 Ok(tx)
@@ -114,12 +117,16 @@ mod tests {
             Ok(CatFactEntity {
                 fact_txt: String::from("fact1"),
                 fact_id: 1,
+                version: 1,
             })
         });
 
         // when calling usecase
         let get_one_random_cat_fact_usecase = MockUseCase::new(persistence);
-        let data = get_one_random_cat_fact_usecase.execute().await.unwrap();
+        let data = get_one_random_cat_fact_usecase
+            .execute(&acme())
+            .await
+            .unwrap();
 
         // then assert the result is the expected entity
         assert_eq!(data.fact_txt, "fact1");
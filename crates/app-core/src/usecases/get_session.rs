@@ -0,0 +1,236 @@
+use std::marker::PhantomData;
+
+use chrono::Utc;
+
+use crate::services::{Persistence, SessionRepo, Transaction};
+use app_domain::entities::SessionEntity;
+
+use super::{create_session::SessionPolicy, credentials::hash_token, UseCaseError};
+
+pub struct GetSessionUseCase<P, R> {
+    persistance: P,
+    policy: SessionPolicy,
+    repo: PhantomData<R>,
+}
+
+impl<P, SR> GetSessionUseCase<P, SR> {
+    pub fn new(persistance: P, policy: SessionPolicy) -> Self {
+        GetSessionUseCase {
+            persistance,
+            policy,
+            repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<P, SR> GetSessionUseCase<P, SR>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SessionRepo<P>,
+{
+    /// Get a live session, sliding its expiry forward on activity
+    pub async fn execute(&self, session_id: &str) -> Result<SessionEntity, UseCaseError> {
+        let now = Utc::now();
+
+        let id_hash = hash_token(session_id);
+
+        let mut tx = self.persistance.get_transaction().await?;
+        let mut session = SR::get_session(&mut tx, id_hash.clone())
+            .await?
+            .filter(|session| session.expires_at > now)
+            // transaction is dropped if repo gets out of scope without commit
+            .ok_or_else(|| UseCaseError::Unauthorized("Session expired".into()))?;
+
+        // only written back once half of the idle timeout is spent, not on every request
+        let expires_at =
+            (now + self.policy.idle_timeout).min(session.created_at + self.policy.absolute_timeout);
+        if session.expires_at - now < self.policy.idle_timeout / 2
+            && expires_at > session.expires_at
+        {
+            SR::renew_session(&mut tx, id_hash, expires_at).await?;
+            session.expires_at = expires_at;
+        }
+        tx.commit().await?;
+
+        session.session_id = session_id.into();
+        Ok(session)
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{MockPersistence, MockSessionRepo, MockTransaction};
+    use mockall::predicate::{always, eq};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockSessionRepo<MockPersistence>;
+    type MockUseCase = GetSessionUseCase<MockPersistence, MockRepo>;
+
+    const POLICY: SessionPolicy = SessionPolicy {
+        idle_timeout: Duration::minutes(30),
+        absolute_timeout: Duration::hours(12),
+    };
+
+    fn session(created_ago: Duration, expires_in: Duration) -> SessionEntity {
+        let now = Utc::now();
+        SessionEntity::new(
+            String::from("session"),
+            String::from("jane"),
+            vec![],
+            String::from("csrf"),
+            now - created_ago,
+            now + expires_in,
+        )
+    }
+
+    fn committing_persistence() -> MockPersistence {
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+        persistence
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_unauthorized_when_session_unknown() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| Ok(MockTransaction::new()));
+
+        // given no stored session
+        let repo_ctx = MockRepo::get_session_context();
+        repo_ctx.expect().returning(|_tx, _id| Ok(None));
+
+        // when calling usecase
+        let get_session_usecase = MockUseCase::new(persistence, POLICY);
+        let data = get_session_usecase.execute("session").await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_unauthorized_when_session_expired() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .with()
+            .times(1)
+            .returning(|| Ok(MockTransaction::new()));
+
+        // given a session that expired a minute ago
+        let repo_ctx = MockRepo::get_session_context();
+        repo_ctx
+            .expect()
+            .returning(|_tx, _id| Ok(Some(session(Duration::hours(1), Duration::minutes(-1)))));
+
+        // when calling usecase
+        let get_session_usecase = MockUseCase::new(persistence, POLICY);
+        let data = get_session_usecase.execute("session").await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_renew_fresh_session() {
+        let _m = get_lock(&MTX);
+
+        // given a session with most of its idle timeout left, stored by hash
+        let repo_ctx = MockRepo::get_session_context();
+        repo_ctx
+            .expect()
+            .with(always(), eq(hash_token("session")))
+            .returning(|_tx, _id| Ok(Some(session(Duration::minutes(5), Duration::minutes(25)))));
+        let renew_ctx = MockRepo::renew_session_context();
+        renew_ctx.expect().times(0);
+
+        // when calling usecase
+        let get_session_usecase = MockUseCase::new(committing_persistence(), POLICY);
+        let data = get_session_usecase.execute("session").await.unwrap();
+
+        // then assert the session is returned as is
+        assert_eq!(data.username, "jane");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_renew_session_close_to_expiry() {
+        let _m = get_lock(&MTX);
+
+        // given a session about to expire
+        let repo_ctx = MockRepo::get_session_context();
+        repo_ctx
+            .expect()
+            .returning(|_tx, _id| Ok(Some(session(Duration::minutes(25), Duration::minutes(5)))));
+        let renew_ctx = MockRepo::renew_session_context();
+        renew_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _id, _expires_at| Ok(()));
+
+        // when calling usecase
+        let get_session_usecase = MockUseCase::new(committing_persistence(), POLICY);
+        let data = get_session_usecase.execute("session").await.unwrap();
+
+        // then assert the session gets a full idle timeout again
+        assert!(data.expires_at - Utc::now() > Duration::minutes(29));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_renew_past_absolute_timeout() {
+        let _m = get_lock(&MTX);
+
+        // given a session about to reach its absolute timeout
+        let repo_ctx = MockRepo::get_session_context();
+        repo_ctx.expect().returning(|_tx, _id| {
+            Ok(Some(session(
+                Duration::hours(12) - Duration::minutes(5),
+                Duration::minutes(5),
+            )))
+        });
+        let renew_ctx = MockRepo::renew_session_context();
+        renew_ctx.expect().times(0);
+
+        // when calling usecase
+        let get_session_usecase = MockUseCase::new(committing_persistence(), POLICY);
+        let data = get_session_usecase.execute("session").await.unwrap();
+
+        // then assert the expiry did not move
+        assert!(data.expires_at - Utc::now() <= Duration::minutes(5));
+    }
+}
@@ -0,0 +1,124 @@
+use std::marker::PhantomData;
+
+use crate::services::{Persistence, SyncRunRepo, Transaction};
+use app_domain::entities::SyncRunEntity;
+
+use super::UseCaseError;
+
+pub struct GetSyncRunsUseCase<P, R> {
+    persistance: P,
+    repo: PhantomData<R>,
+}
+
+impl<P, SR> GetSyncRunsUseCase<P, SR> {
+    pub fn new(persistance: P) -> Self {
+        GetSyncRunsUseCase {
+            persistance,
+            repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<P, SR> GetSyncRunsUseCase<P, SR>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SyncRunRepo<P>,
+{
+    /// The latest runs of every tenant, most recent first
+    pub async fn execute(&self, limit: i64) -> Result<Vec<SyncRunEntity>, UseCaseError> {
+        if limit < 1 {
+            return Err(UseCaseError::Business("limit must be positive".into()));
+        }
+
+        let runs = {
+            let mut tx = self.persistance.get_read_transaction().await?;
+            let runs = SR::get_sync_runs(&mut tx, limit).await?;
+            // transaction is dropped if repo gets out of scope without commit
+            tx.commit().await?;
+            runs
+        };
+
+        Ok(runs)
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{MockPersistence, MockSyncRunRepo, MockTransaction};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockSyncRunRepo<MockPersistence>;
+    type MockUseCase = GetSyncRunsUseCase<MockPersistence, MockRepo>;
+
+    #[actix_rt::test]
+    async fn test_should_reject_non_positive_limit() {
+        let _m = get_lock(&MTX);
+
+        // given a persistence that must not be used
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_read_transaction().times(0);
+
+        // when asking for no runs at all
+        let get_sync_runs_usecase = MockUseCase::new(persistence);
+        let data = get_sync_runs_usecase.execute(0).await;
+
+        // then exception
+        assert!(matches!(data, Err(UseCaseError::Business(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_runs() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_read_transaction()
+            .times(1)
+            .returning(|| {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given the repo returning one run
+        let repo_ctx = MockRepo::get_sync_runs_context();
+        repo_ctx
+            .expect()
+            .withf(|_tx, limit| *limit == 20)
+            .returning(|_tx, _limit| {
+                Ok(vec![SyncRunEntity::start(
+                    "cat_facts".into(),
+                    "acme".into(),
+                )])
+            });
+
+        // when calling usecase
+        let get_sync_runs_usecase = MockUseCase::new(persistence);
+        let data = get_sync_runs_usecase.execute(20).await.unwrap();
+
+        // then assert the run is returned
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].tenant_id, "acme");
+    }
+}
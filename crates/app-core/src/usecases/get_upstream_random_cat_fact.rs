@@ -0,0 +1,66 @@
+use crate::services::CatFactsService;
+use app_domain::entities::CatFactEntity;
+
+use super::UseCaseError;
+
+pub struct GetUpstreamRandomCatFactUseCase<'a, S: ?Sized> {
+    cat_facts_service: &'a S,
+}
+
+impl<'a, S: ?Sized> GetUpstreamRandomCatFactUseCase<'a, S> {
+    pub fn new(cat_facts_service: &'a S) -> Self {
+        GetUpstreamRandomCatFactUseCase { cat_facts_service }
+    }
+}
+
+impl<'a, S> GetUpstreamRandomCatFactUseCase<'a, S>
+where
+    S: CatFactsService + ?Sized,
+{
+    pub async fn execute(&self) -> Result<CatFactEntity, UseCaseError> {
+        Ok(self.cat_facts_service.get_random_cat_fact().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{MockCatFactsService, UpstreamError};
+
+    #[actix_rt::test]
+    async fn test_should_return_upstream_error_when_source_unreachable() {
+        // given a source that can't be reached
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_random_cat_fact()
+            .times(1)
+            .returning(|| Err(UpstreamError("connection refused".into())));
+
+        // when calling usecase
+        let usecase = GetUpstreamRandomCatFactUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await;
+
+        // then exception
+        assert!(data.is_err());
+        let result = data.unwrap_err();
+        assert_eq!("Upstream error: connection refused", result.to_string());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_one_result() {
+        // given a source returning a fact
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_random_cat_fact()
+            .times(1)
+            .returning(|| Ok(CatFactEntity::new(String::from("fact1"), 5)));
+
+        // when calling usecase
+        let usecase = GetUpstreamRandomCatFactUseCase::new(&cat_facts_service);
+        let data = usecase.execute().await.unwrap();
+
+        // then assert the result is the upstream fact
+        assert_eq!(data.fact_txt, "fact1");
+    }
+}
@@ -1,26 +1,94 @@
+pub mod begin_sso_login;
+pub mod change_password;
+pub mod complete_sso_login;
+pub mod create_session;
+pub mod create_token;
+pub mod create_user;
+mod credentials;
+pub mod delete_session;
 pub mod get_all_cat_facts;
 pub mod get_all_dog_facts;
+pub mod get_all_upstream_cat_facts;
+pub mod get_migrations;
 pub mod get_one_dog_fact_by_id;
 pub mod get_one_random_cat_fact;
+pub mod get_session;
+pub mod get_sync_runs;
+pub mod get_upstream_random_cat_fact;
+pub mod request_password_reset;
+pub mod reset_password;
+pub mod retry;
+pub mod revoke_token;
+pub mod sync_cat_facts;
+pub mod sync_dog_facts;
+mod sync_facts;
+pub mod unit_of_work;
+pub mod unlock_account;
+pub mod update_cat_fact;
+pub mod update_dog_fact;
+pub mod verify_token;
 
+use std::time::Duration;
 use thiserror::Error;
 
-use crate::services::RepositoryError;
+use crate::services::{AuthError, RepositoryError, SsoError, UpstreamError};
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum UseCaseError {
     #[error("Repository error: {0}")]
     Repository(String),
+    /// Given up on by the backend, in favour of concurrent transactions
+    #[error("Repository error: {0}")]
+    Transient(String),
     #[error("Business error: {0}")]
     Business(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
     #[error("Error: not authenticated or token expired")]
     Unauthorized(String),
     #[error("Error: resource not allowed")]
     Forbidden(String),
+    #[error("Upstream error: {0}")]
+    Upstream(String),
+    /// Asked to wait before trying again
+    #[error("Error: too many requests, retry in {} seconds", .retry_after.as_secs().max(1))]
+    TooManyRequests { retry_after: Duration },
+    /// Changed by someone else since it was read
+    #[error("Conflict: changed since read, now at version {current_version}")]
+    Conflict { current_version: i32 },
 }
 
 impl From<RepositoryError> for UseCaseError {
     fn from(value: RepositoryError) -> Self {
-        Self::Repository(value.0)
+        if value.transient {
+            Self::Transient(value.message)
+        } else {
+            Self::Repository(value.message)
+        }
+    }
+}
+
+impl From<UpstreamError> for UseCaseError {
+    fn from(value: UpstreamError) -> Self {
+        Self::Upstream(value.0)
+    }
+}
+
+impl From<SsoError> for UseCaseError {
+    fn from(value: SsoError) -> Self {
+        match value {
+            SsoError::Provider(e) => Self::Upstream(e),
+            e => Self::Unauthorized(e.to_string()),
+        }
+    }
+}
+
+impl From<AuthError> for UseCaseError {
+    fn from(value: AuthError) -> Self {
+        match value {
+            AuthError::Backend(e) => Self::Upstream(e),
+            AuthError::Throttled { retry_after } => Self::TooManyRequests { retry_after },
+            e => Self::Unauthorized(e.to_string()),
+        }
     }
 }
@@ -0,0 +1,218 @@
+use std::marker::PhantomData;
+
+use chrono::{Duration, Utc};
+
+use crate::services::{Mail, Mailer, Persistence, Transaction, UserRepo};
+use app_domain::entities::PasswordResetEntity;
+
+use super::{
+    credentials::{hash_token, normalize_email, random_token},
+    UseCaseError,
+};
+
+#[derive(Debug, Clone)]
+pub struct PasswordResetPolicy {
+    /// A reset token can't be used any more this long after being mailed
+    pub token_ttl: Duration,
+    /// Where users choose their new password, the token is appended to it
+    pub reset_url: String,
+}
+
+/// Mail a single-use reset token to the user of an email, if any
+///
+/// Whether there is one is never told, neither by the result nor by an
+/// error of the mailer, which is only logged.
+pub struct RequestPasswordResetUseCase<'a, P, R, M: ?Sized> {
+    persistance: P,
+    mailer: &'a M,
+    policy: PasswordResetPolicy,
+    repo: PhantomData<R>,
+}
+
+impl<'a, P, UR, M: ?Sized> RequestPasswordResetUseCase<'a, P, UR, M> {
+    pub fn new(persistance: P, mailer: &'a M, policy: PasswordResetPolicy) -> Self {
+        RequestPasswordResetUseCase {
+            persistance,
+            mailer,
+            policy,
+            repo: PhantomData::<UR>,
+        }
+    }
+}
+
+impl<'a, P, UR, M> RequestPasswordResetUseCase<'a, P, UR, M>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    UR: UserRepo<P>,
+    M: Mailer + ?Sized,
+{
+    pub async fn execute(&self, email: &str) -> Result<(), UseCaseError> {
+        let mut tx = self.persistance.get_transaction().await?;
+        let user = match UR::get_user_by_email(&mut tx, normalize_email(email)).await? {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        // only the latest token mailed works
+        let token = random_token();
+        let reset = PasswordResetEntity::new(
+            hash_token(&token),
+            user.username.clone(),
+            Utc::now() + self.policy.token_ttl,
+        );
+        UR::delete_password_resets(&mut tx, user.username.clone()).await?;
+        UR::create_password_reset(&mut tx, reset).await?;
+        tx.commit().await?;
+
+        let mail = Mail {
+            to: user.email,
+            subject: String::from("Reset your password"),
+            body: format!(
+                "A password reset was asked for your account {}.\n\n\
+                 Choose a new password at {}{} within {} minutes, or ignore \
+                 this mail to keep your current one.\n",
+                user.username,
+                self.policy.reset_url,
+                token,
+                self.policy.token_ttl.num_minutes()
+            ),
+        };
+        if let Err(e) = self.mailer.send(mail).await {
+            log::error!("Can't mail a password reset to {}: {}", user.username, e);
+        }
+
+        Ok(())
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    use crate::services::{MailError, MockMailer, MockPersistence, MockTransaction, MockUserRepo};
+    use app_domain::entities::UserEntity;
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockRepo = MockUserRepo<MockPersistence>;
+    type MockUseCase<'a> = RequestPasswordResetUseCase<'a, MockPersistence, MockRepo, MockMailer>;
+
+    fn policy() -> PasswordResetPolicy {
+        PasswordResetPolicy {
+            token_ttl: Duration::minutes(30),
+            reset_url: String::from("https://example.com/reset?token="),
+        }
+    }
+
+    fn persistence(commits: usize) -> MockPersistence {
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .times(1)
+            .returning(move || {
+                let mut tx = MockTransaction::new();
+                tx.expect_commit().times(commits).returning(|| Ok(()));
+                Ok(tx)
+            });
+        persistence
+    }
+
+    #[actix_rt::test]
+    async fn test_should_mail_nothing_for_unknown_email() {
+        let _m = get_lock(&MTX);
+
+        // given no user with that email
+        let get_ctx = MockRepo::get_user_by_email_context();
+        get_ctx.expect().times(1).returning(|_tx, _email| Ok(None));
+        let create_ctx = MockRepo::create_password_reset_context();
+        create_ctx.expect().times(0);
+        let mut mailer = MockMailer::new();
+        mailer.expect_send().times(0);
+
+        // when calling usecase
+        let usecase = MockUseCase::new(persistence(0), &mailer, policy());
+        let data = usecase.execute("nobody@example.com").await;
+
+        // then it succeeds all the same
+        assert!(data.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_mail_token_stored_hashed() {
+        let _m = get_lock(&MTX);
+
+        // given a user with that email
+        let get_ctx = MockRepo::get_user_by_email_context();
+        get_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, email| email == "jane@example.com")
+            .returning(|_tx, email| Ok(Some(UserEntity::new("jane".into(), email, "hash".into()))));
+        let delete_ctx = MockRepo::delete_password_resets_context();
+        delete_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(()));
+        let stored = Arc::new(Mutex::new(None));
+        let create_ctx = MockRepo::create_password_reset_context();
+        {
+            let stored = stored.clone();
+            create_ctx.expect().times(1).returning(move |_tx, reset| {
+                *stored.lock().unwrap() = Some(reset);
+                Ok(())
+            });
+        }
+
+        // and a mailer failing
+        let mailed = Arc::new(Mutex::new(None));
+        let mut mailer = MockMailer::new();
+        {
+            let mailed = mailed.clone();
+            mailer.expect_send().times(1).returning(move |mail| {
+                *mailed.lock().unwrap() = Some(mail);
+                Err(MailError("unreachable".into()))
+            });
+        }
+
+        // when calling usecase
+        let usecase = MockUseCase::new(persistence(1), &mailer, policy());
+        let data = usecase.execute("Jane@example.com").await;
+
+        // then it succeeds without telling the mail failed
+        assert!(data.is_ok());
+
+        // and the token mailed is only stored hashed
+        let reset = stored.lock().unwrap().clone().unwrap();
+        let mail = mailed.lock().unwrap().clone().unwrap();
+        let token = mail
+            .body
+            .split("https://example.com/reset?token=")
+            .nth(1)
+            .unwrap()
+            .split_whitespace()
+            .next()
+            .unwrap();
+        assert_eq!(mail.to, "jane@example.com");
+        assert_eq!(reset.username, "jane");
+        assert_eq!(reset.token_hash, hash_token(token));
+        assert!(!reset.token_hash.contains(token));
+    }
+}
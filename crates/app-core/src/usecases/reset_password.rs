@@ -0,0 +1,230 @@
+use std::marker::PhantomData;
+
+use chrono::Utc;
+
+use crate::services::{
+    AuthService, PasswordHasher, Persistence, SessionRepo, Transaction, UserRepo,
+};
+
+use super::{
+    credentials::{check_new_password, hash_token, replace_password},
+    UseCaseError,
+};
+
+/// Set a new password with a token mailed by `RequestPasswordResetUseCase`,
+/// ending the sessions and tokens of the old one
+pub struct ResetPasswordUseCase<'a, P, UR, SR, H: ?Sized, A: ?Sized> {
+    persistance: P,
+    password_hasher: &'a H,
+    auth_service: &'a A,
+    user_repository: PhantomData<UR>,
+    session_repository: PhantomData<SR>,
+}
+
+impl<'a, P, UR, SR, H: ?Sized, A: ?Sized> ResetPasswordUseCase<'a, P, UR, SR, H, A> {
+    pub fn new(persistance: P, password_hasher: &'a H, auth_service: &'a A) -> Self {
+        ResetPasswordUseCase {
+            persistance,
+            password_hasher,
+            auth_service,
+            user_repository: PhantomData::<UR>,
+            session_repository: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<'a, P, UR, SR, H, A> ResetPasswordUseCase<'a, P, UR, SR, H, A>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    UR: UserRepo<P>,
+    SR: SessionRepo<P>,
+    H: PasswordHasher + ?Sized,
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(&self, token: &str, password: &str) -> Result<(), UseCaseError> {
+        check_new_password(password)?;
+
+        let mut tx = self.persistance.get_transaction().await?;
+        // gone once taken, used or not
+        let reset = UR::take_password_reset(&mut tx, hash_token(token)).await?;
+        let reset = match reset {
+            Some(reset) if reset.expires_at > Utc::now() => reset,
+            _ => {
+                tx.commit().await?;
+                return Err(UseCaseError::Business(
+                    "Invalid or expired password reset token".into(),
+                ));
+            }
+        };
+        // only hashed for a valid token, not to slow down the server for anyone
+        let password_hash = self.password_hasher.hash(password).await?;
+        replace_password::<P, UR, SR>(&mut tx, &reset.username, password_hash).await?;
+        tx.commit().await?;
+
+        self.auth_service.revoke_tokens(&reset.username).await?;
+        Ok(())
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use lazy_static::lazy_static;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        MockAuthService, MockPasswordHasher, MockPersistence, MockSessionRepo, MockTransaction,
+        MockUserRepo,
+    };
+    use app_domain::entities::PasswordResetEntity;
+    use mockall::predicate::eq;
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    type MockUsers = MockUserRepo<MockPersistence>;
+    type MockSessions = MockSessionRepo<MockPersistence>;
+    type MockUseCase<'a> = ResetPasswordUseCase<
+        'a,
+        MockPersistence,
+        MockUsers,
+        MockSessions,
+        MockPasswordHasher,
+        MockAuthService,
+    >;
+
+    fn persistence() -> MockPersistence {
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_transaction().times(1).returning(|| {
+            let mut tx = MockTransaction::new();
+            tx.expect_commit().times(1).returning(|| Ok(()));
+            Ok(tx)
+        });
+        persistence
+    }
+
+    fn password_hasher() -> MockPasswordHasher {
+        let mut password_hasher = MockPasswordHasher::new();
+        password_hasher
+            .expect_hash()
+            .returning(|password| Ok(format!("hashed {}", password)));
+        password_hasher
+    }
+
+    #[actix_rt::test]
+    async fn test_should_refuse_expired_token() {
+        let _m = get_lock(&MTX);
+
+        // given a reset that expired
+        let take_ctx = MockUsers::take_password_reset_context();
+        take_ctx.expect().times(1).returning(|_tx, token_hash| {
+            Ok(Some(PasswordResetEntity::new(
+                token_hash,
+                "jane".into(),
+                Utc::now() - Duration::minutes(1),
+            )))
+        });
+        let update_ctx = MockUsers::update_password_hash_context();
+        update_ctx.expect().times(0);
+        let mut auth_service = MockAuthService::new();
+        auth_service.expect_revoke_tokens().times(0);
+
+        // when calling usecase
+        let password_hasher = password_hasher();
+        let usecase = MockUseCase::new(persistence(), &password_hasher, &auth_service);
+        let data = usecase.execute("token", "correct horse").await;
+
+        // then a business error, the password unchanged
+        assert!(matches!(data, Err(UseCaseError::Business(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_hash_for_unknown_token() {
+        let _m = get_lock(&MTX);
+
+        // given no reset of that token
+        let take_ctx = MockUsers::take_password_reset_context();
+        take_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _token_hash| Ok(None));
+        let mut password_hasher = MockPasswordHasher::new();
+        password_hasher.expect_hash().times(0);
+        let auth_service = MockAuthService::new();
+
+        // when calling usecase
+        let usecase = MockUseCase::new(persistence(), &password_hasher, &auth_service);
+        let data = usecase.execute("token", "correct horse").await;
+
+        // then a business error, before any costly hashing
+        assert!(matches!(data, Err(UseCaseError::Business(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_replace_password_and_end_sessions() {
+        let _m = get_lock(&MTX);
+
+        // given a reset of that token
+        let take_ctx = MockUsers::take_password_reset_context();
+        take_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, token_hash| *token_hash == hash_token("token"))
+            .returning(|_tx, token_hash| {
+                Ok(Some(PasswordResetEntity::new(
+                    token_hash,
+                    "jane".into(),
+                    Utc::now() + Duration::minutes(10),
+                )))
+            });
+
+        // then the password is replaced, ending the other resets, the
+        // sessions and the tokens
+        let update_ctx = MockUsers::update_password_hash_context();
+        update_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, username, hash| username == "jane" && hash == "hashed correct horse")
+            .returning(|_tx, _username, _hash| Ok(()));
+        let resets_ctx = MockUsers::delete_password_resets_context();
+        resets_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _username| Ok(()));
+        let sessions_ctx = MockSessions::delete_user_sessions_context();
+        sessions_ctx
+            .expect()
+            .times(1)
+            .withf(|_tx, username| username == "jane")
+            .returning(|_tx, _username| Ok(()));
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_revoke_tokens()
+            .with(eq("jane"))
+            .times(1)
+            .returning(|_username| Ok(()));
+
+        // when calling usecase
+        let password_hasher = password_hasher();
+        let usecase = MockUseCase::new(persistence(), &password_hasher, &auth_service);
+        let data = usecase.execute("token", "correct horse").await;
+
+        assert!(data.is_ok());
+    }
+}
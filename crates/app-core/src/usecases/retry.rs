@@ -0,0 +1,131 @@
+use std::{future::Future, time::Duration};
+
+use rand::Rng;
+
+use super::UseCaseError;
+
+/// How often a use case body is run when the backend keeps giving up on
+/// its transactions
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Runs in all, the first one included
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Full jitter, anywhere between nothing and the exponential ceiling,
+    /// so that the transactions which collided don't collide again
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+/// Run `body` again, from its own transaction, as long as it fails with a
+/// serialization failure or a deadlock and attempts are left
+pub async fn retry_transient<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut body: F,
+) -> Result<T, UseCaseError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, UseCaseError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match body().await {
+            Err(UseCaseError::Transient(_)) if attempt + 1 < policy.max_attempts => {
+                tokio::time::sleep(policy.backoff(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    fn no_wait() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_rerun_body_until_it_succeeds() {
+        // given a body failing with a serialization failure once
+        let runs = Cell::new(0);
+        let body = || async {
+            runs.set(runs.get() + 1);
+            if runs.get() == 1 {
+                Err(UseCaseError::Transient("could not serialize access".into()))
+            } else {
+                Ok(runs.get())
+            }
+        };
+
+        // when running it
+        let data = retry_transient(&no_wait(), body).await;
+
+        // then the second run's result is returned
+        assert_eq!(data.unwrap(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_give_up_after_max_attempts() {
+        // given a body always deadlocking
+        let runs = Cell::new(0);
+        let body = || async {
+            runs.set(runs.get() + 1);
+            Err::<(), _>(UseCaseError::Transient("deadlock detected".into()))
+        };
+
+        // when running it
+        let data = retry_transient(&no_wait(), body).await;
+
+        // then it ran as many times as allowed, and the last error is returned
+        assert_eq!(runs.get(), 3);
+        assert_eq!(
+            data.unwrap_err().to_string(),
+            "Repository error: deadlock detected"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_rerun_body_on_other_errors() {
+        // given a body failing for good
+        let runs = Cell::new(0);
+        let body = || async {
+            runs.set(runs.get() + 1);
+            Err::<(), _>(UseCaseError::Repository("relation does not exist".into()))
+        };
+
+        // when running it
+        let data = retry_transient(&no_wait(), body).await;
+
+        // then it ran once
+        assert_eq!(runs.get(), 1);
+        assert!(data.is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use crate::services::AuthService;
+
+use super::UseCaseError;
+
+pub struct RevokeTokenUseCase<'a, A: ?Sized> {
+    auth_service: &'a A,
+}
+
+impl<'a, A: ?Sized> RevokeTokenUseCase<'a, A> {
+    pub fn new(auth_service: &'a A) -> Self {
+        RevokeTokenUseCase { auth_service }
+    }
+}
+
+impl<'a, A> RevokeTokenUseCase<'a, A>
+where
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(&self, token: &str) -> Result<(), UseCaseError> {
+        Ok(self.auth_service.logout(token).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::MockAuthService;
+    use mockall::predicate::eq;
+
+    #[actix_rt::test]
+    async fn test_should_logout_token() {
+        // given an auth service revoking tokens
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_logout()
+            .with(eq("token"))
+            .times(1)
+            .returning(|_token| Ok(()));
+
+        // when calling usecase
+        let revoke_token_usecase = RevokeTokenUseCase::new(&auth_service);
+        let data = revoke_token_usecase.execute("token").await;
+
+        // then assert it succeeded
+        assert!(data.is_ok());
+    }
+}
@@ -0,0 +1,383 @@
+use std::marker::PhantomData;
+
+use crate::services::{
+    CatFactsService, CatRepo, EventPublisher, Persistence, SyncRunRepo, Transaction,
+    TransactionOptions,
+};
+use app_domain::{
+    entities::{CatFactEntity, SyncRunEntity, TenantEntity},
+    events::{DomainEvent, FactRef, Species},
+};
+
+use super::{
+    get_all_upstream_cat_facts::GetAllUpstreamCatFactsUseCase,
+    retry::{retry_transient, RetryPolicy},
+    sync_facts::{record_sync_run, SyncCounts},
+    unit_of_work::UnitOfWork,
+    UseCaseError,
+};
+
+/// Source recorded on the runs of this use case
+pub const CAT_FACTS_SOURCE: &str = "cat_facts";
+
+pub struct SyncCatFactsUseCase<'a, P, CR, SR, S: ?Sized> {
+    persistance: P,
+    cat_facts_service: &'a S,
+    events: &'a dyn EventPublisher,
+    cat_repo: PhantomData<CR>,
+    sync_run_repo: PhantomData<SR>,
+}
+
+impl<'a, P, CR, SR, S: ?Sized> SyncCatFactsUseCase<'a, P, CR, SR, S> {
+    pub fn new(persistance: P, cat_facts_service: &'a S, events: &'a dyn EventPublisher) -> Self {
+        SyncCatFactsUseCase {
+            persistance,
+            cat_facts_service,
+            events,
+            cat_repo: PhantomData::<CR>,
+            sync_run_repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<'a, P, CR, SR, S> SyncCatFactsUseCase<'a, P, CR, SR, S>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    CR: CatRepo<P>,
+    SR: SyncRunRepo<P>,
+    S: CatFactsService + ?Sized,
+{
+    /// Bring the tenant's cat facts in step with upstream
+    ///
+    /// Facts are matched on their position in the upstream listing. New
+    /// ones are inserted, stored ones that upstream changed are flagged
+    /// rather than overwritten. The run is recorded whatever the outcome.
+    pub async fn execute(&self, tenant: &TenantEntity) -> Result<SyncRunEntity, UseCaseError> {
+        record_sync_run::<P, SR>(
+            &self.persistance,
+            CAT_FACTS_SOURCE,
+            tenant,
+            self.synchronise(tenant),
+        )
+        .await
+    }
+
+    /// Nothing is stored when it fails
+    async fn synchronise(&self, tenant: &TenantEntity) -> Result<SyncCounts, UseCaseError> {
+        let facts = GetAllUpstreamCatFactsUseCase::new(self.cat_facts_service)
+            .execute()
+            .await?;
+
+        // a concurrent run of the same tenant makes one of them start over
+        let (counts, created) =
+            retry_transient(&RetryPolicy::default(), || self.store(tenant, &facts)).await?;
+        for event in created {
+            self.events.publish(event).await;
+        }
+        Ok(counts)
+    }
+
+    async fn store(
+        &self,
+        tenant: &TenantEntity,
+        facts: &[CatFactEntity],
+    ) -> Result<(SyncCounts, Vec<DomainEvent>), UseCaseError> {
+        let tx = self
+            .persistance
+            .get_tenant_transaction_with(tenant, TransactionOptions::serializable())
+            .await?;
+        UnitOfWork::new(tx)
+            .run(|uow| {
+                Box::pin(async move {
+                    let (mut inserted, mut unchanged, mut changed) = (0, 0, 0);
+                    let mut created = vec![];
+                    for (upstream_index, fact) in (1..).zip(facts) {
+                        match CR::get_upstream_cat_fact(uow.tx(), upstream_index).await? {
+                            None => {
+                                let kept = CR::insert_upstream_cat_fact(
+                                    uow.tx(),
+                                    upstream_index,
+                                    fact.fact_txt.clone(),
+                                )
+                                .await?;
+                                let event = DomainEvent::FactCreated {
+                                    fact: FactRef::new(
+                                        Species::Cat,
+                                        tenant.tenant_id.clone(),
+                                        kept.fact_id,
+                                    ),
+                                    text: kept.fact_txt,
+                                };
+                                uow.tx().add_event(&event).await?;
+                                created.push(event);
+                                inserted += 1;
+                            }
+                            Some(stored) if stored.fact_txt == fact.fact_txt => unchanged += 1,
+                            Some(stored) => {
+                                CR::flag_changed_cat_fact(uow.tx(), stored.fact_id).await?;
+                                changed += 1;
+                            }
+                        }
+                    }
+                    Ok(((inserted, unchanged, changed), created))
+                })
+            })
+            .await
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use mockall::predicate::eq;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        FactsPage, MockCatFactsService, MockCatRepo, MockEventPublisher, MockPersistence,
+        MockSyncRunRepo, MockTransaction, RepositoryError, UpstreamError,
+    };
+    use app_domain::entities::{CatFactEntity, UNSTORED_FACT_ID};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
+    fn committed_transaction() -> MockTransaction {
+        let mut tx = MockTransaction::new();
+        tx.expect_add_event().returning(|_event| Ok(()));
+        tx.expect_commit().times(1).returning(|| Ok(()));
+        tx
+    }
+
+    type MockCatRepository = MockCatRepo<MockPersistence>;
+    type MockSyncRunRepository = MockSyncRunRepo<MockPersistence>;
+    type MockUseCase<'a> = SyncCatFactsUseCase<
+        'a,
+        MockPersistence,
+        MockCatRepository,
+        MockSyncRunRepository,
+        MockCatFactsService,
+    >;
+
+    #[actix_rt::test]
+    async fn test_should_insert_new_and_flag_changed_facts() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .times(2)
+            .returning(|| Ok(committed_transaction()));
+        persistence
+            .expect_get_tenant_transaction_with()
+            .with(eq(acme()), eq(TransactionOptions::serializable()))
+            .times(1)
+            .returning(|_tenant, _options| Ok(committed_transaction()));
+
+        // given an upstream listing of three facts, matched by position as
+        // they have no id
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .with(eq(1))
+            .returning(|_| {
+                Ok(FactsPage {
+                    facts: ["fact1", "fact2 edited", "fact3"]
+                        .iter()
+                        .map(|f| CatFactEntity::new(f.to_string(), UNSTORED_FACT_ID))
+                        .collect(),
+                    current_page: 1,
+                    last_page: 1,
+                })
+            });
+
+        // and the first two already stored
+        let get_ctx = MockCatRepository::get_upstream_cat_fact_context();
+        get_ctx.expect().returning(|_tx, index| {
+            Ok(match index {
+                1 => Some(CatFactEntity::new("fact1".into(), 10)),
+                2 => Some(CatFactEntity::new("fact2".into(), 20)),
+                _ => None,
+            })
+        });
+        let insert_ctx = MockCatRepository::insert_upstream_cat_fact_context();
+        insert_ctx
+            .expect()
+            .withf(|_tx, index, fact| *index == 3 && fact == "fact3")
+            .times(1)
+            .returning(|_tx, _index, fact| Ok(CatFactEntity::new(fact, 30)));
+        let flag_ctx = MockCatRepository::flag_changed_cat_fact_context();
+        flag_ctx
+            .expect()
+            .withf(|_tx, fact_id| *fact_id == 20)
+            .times(1)
+            .returning(|_tx, _fact_id| Ok(()));
+
+        let create_ctx = MockSyncRunRepository::create_sync_run_context();
+        create_ctx.expect().times(1).returning(|_tx, _run| Ok(7));
+        let finish_ctx = MockSyncRunRepository::finish_sync_run_context();
+        finish_ctx
+            .expect()
+            .withf(|_tx, run| run.run_id == 7 && run.finished_at.is_some() && run.error.is_none())
+            .times(1)
+            .returning(|_tx, _run| Ok(()));
+
+        let mut events = MockEventPublisher::new();
+        events
+            .expect_publish()
+            .withf(|event| {
+                matches!(event, DomainEvent::FactCreated { fact, .. }
+                    if fact.species == Species::Cat && fact.tenant_id == "acme" && fact.fact_id == 30)
+            })
+            .times(1)
+            .return_const(());
+
+        // when calling usecase
+        let sync_cat_facts_usecase = MockUseCase::new(persistence, &cat_facts_service, &events);
+        let run = sync_cat_facts_usecase.execute(&acme()).await.unwrap();
+
+        // then assert every fact is accounted for
+        assert_eq!(run.tenant_id, "acme");
+        assert_eq!((run.inserted, run.unchanged, run.changed), (1, 1, 1));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_record_failed_run_when_upstream_fails() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .times(2)
+            .returning(|| Ok(committed_transaction()));
+        persistence.expect_get_tenant_transaction_with().times(0);
+
+        // given an unreachable upstream
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .returning(|_| Err(UpstreamError("connection refused".into())));
+
+        let create_ctx = MockSyncRunRepository::create_sync_run_context();
+        create_ctx.expect().times(1).returning(|_tx, _run| Ok(8));
+        let finish_ctx = MockSyncRunRepository::finish_sync_run_context();
+        finish_ctx
+            .expect()
+            .withf(|_tx, run| {
+                run.run_id == 8
+                    && run.error.as_deref() == Some("Upstream error: connection refused")
+            })
+            .times(1)
+            .returning(|_tx, _run| Ok(()));
+
+        let mut events = MockEventPublisher::new();
+        events.expect_publish().times(0);
+
+        // when calling usecase
+        let sync_cat_facts_usecase = MockUseCase::new(persistence, &cat_facts_service, &events);
+        let result = sync_cat_facts_usecase.execute(&acme()).await;
+
+        // then exception, after the run was recorded as failed
+        assert!(matches!(result, Err(UseCaseError::Upstream(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_start_over_when_transaction_conflicts() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_transaction()
+            .times(2)
+            .returning(|| Ok(committed_transaction()));
+        // the first transaction is rolled back as soon as it fails
+        let mut attempts = 0;
+        persistence
+            .expect_get_tenant_transaction_with()
+            .times(2)
+            .returning(move |_tenant, _options| {
+                attempts += 1;
+                if attempts == 1 {
+                    let mut tx = MockTransaction::new();
+                    tx.expect_rollback().times(1).returning(|| Ok(()));
+                    Ok(tx)
+                } else {
+                    Ok(committed_transaction())
+                }
+            });
+
+        // given an upstream listing of one fact
+        let mut cat_facts_service = MockCatFactsService::new();
+        cat_facts_service
+            .expect_get_cat_facts_page()
+            .times(1)
+            .returning(|_| {
+                Ok(FactsPage {
+                    facts: vec![CatFactEntity::new("fact1".into(), 5)],
+                    current_page: 1,
+                    last_page: 1,
+                })
+            });
+
+        // and a concurrent run making the first insert fail
+        let get_ctx = MockCatRepository::get_upstream_cat_fact_context();
+        get_ctx.expect().returning(|_tx, _index| Ok(None));
+        let mut inserts = 0;
+        let insert_ctx = MockCatRepository::insert_upstream_cat_fact_context();
+        insert_ctx
+            .expect()
+            .times(2)
+            .returning(move |_tx, _index, fact| {
+                inserts += 1;
+                if inserts == 1 {
+                    Err(RepositoryError::transient(
+                        "could not serialize access".into(),
+                    ))
+                } else {
+                    Ok(CatFactEntity::new(fact, 30))
+                }
+            });
+
+        let create_ctx = MockSyncRunRepository::create_sync_run_context();
+        create_ctx.expect().times(1).returning(|_tx, _run| Ok(9));
+        let finish_ctx = MockSyncRunRepository::finish_sync_run_context();
+        finish_ctx.expect().times(1).returning(|_tx, _run| Ok(()));
+
+        let mut events = MockEventPublisher::new();
+        events
+            .expect_publish()
+            .withf(|event| {
+                matches!(event, DomainEvent::FactCreated { fact, .. }
+                    if fact.species == Species::Cat && fact.tenant_id == "acme" && fact.fact_id == 30)
+            })
+            .times(1)
+            .return_const(());
+
+        // when calling usecase
+        let sync_cat_facts_usecase = MockUseCase::new(persistence, &cat_facts_service, &events);
+        let run = sync_cat_facts_usecase.execute(&acme()).await.unwrap();
+
+        // then the second attempt is recorded, upstream was only asked once
+        assert_eq!(run.error, None);
+        assert_eq!(run.inserted, 1);
+    }
+}
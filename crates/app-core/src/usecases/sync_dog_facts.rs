@@ -0,0 +1,275 @@
+use std::marker::PhantomData;
+
+use crate::services::{
+    DogFactsService, DogRepo, EventPublisher, Persistence, SyncRunRepo, Transaction,
+    TransactionOptions,
+};
+use app_domain::{
+    entities::{DogFactEntity, SyncRunEntity, TenantEntity},
+    events::{DomainEvent, FactRef, Species},
+};
+
+use super::{
+    retry::{retry_transient, RetryPolicy},
+    sync_facts::{get_all_pages, record_sync_run, SyncCounts},
+    unit_of_work::UnitOfWork,
+    UseCaseError,
+};
+
+/// Source recorded on the runs of this use case
+pub const DOG_FACTS_SOURCE: &str = "dog_facts";
+
+pub struct SyncDogFactsUseCase<'a, P, DR, SR, S: ?Sized> {
+    persistance: P,
+    dog_facts_service: &'a S,
+    events: &'a dyn EventPublisher,
+    dog_repo: PhantomData<DR>,
+    sync_run_repo: PhantomData<SR>,
+}
+
+impl<'a, P, DR, SR, S: ?Sized> SyncDogFactsUseCase<'a, P, DR, SR, S> {
+    pub fn new(persistance: P, dog_facts_service: &'a S, events: &'a dyn EventPublisher) -> Self {
+        SyncDogFactsUseCase {
+            persistance,
+            dog_facts_service,
+            events,
+            dog_repo: PhantomData::<DR>,
+            sync_run_repo: PhantomData::<SR>,
+        }
+    }
+}
+
+impl<'a, P, DR, SR, S> SyncDogFactsUseCase<'a, P, DR, SR, S>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    DR: DogRepo<P>,
+    SR: SyncRunRepo<P>,
+    S: DogFactsService + ?Sized,
+{
+    /// Bring the tenant's dog facts in step with upstream, as cat facts are
+    pub async fn execute(&self, tenant: &TenantEntity) -> Result<SyncRunEntity, UseCaseError> {
+        record_sync_run::<P, SR>(
+            &self.persistance,
+            DOG_FACTS_SOURCE,
+            tenant,
+            self.synchronise(tenant),
+        )
+        .await
+    }
+
+    /// Nothing is stored when it fails
+    async fn synchronise(&self, tenant: &TenantEntity) -> Result<SyncCounts, UseCaseError> {
+        let facts = get_all_pages(|page| self.dog_facts_service.get_dog_facts_page(page)).await?;
+
+        // a concurrent run of the same tenant makes one of them start over
+        let (counts, created) =
+            retry_transient(&RetryPolicy::default(), || self.store(tenant, &facts)).await?;
+        for event in created {
+            self.events.publish(event).await;
+        }
+        Ok(counts)
+    }
+
+    async fn store(
+        &self,
+        tenant: &TenantEntity,
+        facts: &[DogFactEntity],
+    ) -> Result<(SyncCounts, Vec<DomainEvent>), UseCaseError> {
+        let tx = self
+            .persistance
+            .get_tenant_transaction_with(tenant, TransactionOptions::serializable())
+            .await?;
+        UnitOfWork::new(tx)
+            .run(|uow| {
+                Box::pin(async move {
+                    let (mut inserted, mut unchanged, mut changed) = (0, 0, 0);
+                    let mut created = vec![];
+                    for (upstream_index, fact) in (1..).zip(facts) {
+                        match DR::get_upstream_dog_fact(uow.tx(), upstream_index).await? {
+                            None => {
+                                let kept = DR::insert_upstream_dog_fact(
+                                    uow.tx(),
+                                    upstream_index,
+                                    fact.fact.clone(),
+                                )
+                                .await?;
+                                let event = DomainEvent::FactCreated {
+                                    fact: FactRef::new(
+                                        Species::Dog,
+                                        tenant.tenant_id.clone(),
+                                        kept.fact_id,
+                                    ),
+                                    text: kept.fact,
+                                };
+                                uow.tx().add_event(&event).await?;
+                                created.push(event);
+                                inserted += 1;
+                            }
+                            Some(stored) if stored.fact == fact.fact => unchanged += 1,
+                            Some(stored) => {
+                                DR::flag_changed_dog_fact(uow.tx(), stored.fact_id).await?;
+                                changed += 1;
+                            }
+                        }
+                    }
+                    Ok(((inserted, unchanged, changed), created))
+                })
+            })
+            .await
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use mockall::predicate::eq;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{
+        FactsPage, MockDogFactsService, MockDogRepo, MockEventPublisher, MockPersistence,
+        MockSyncRunRepo, MockTransaction, UpstreamError,
+    };
+    use app_domain::entities::{DogFactEntity, UNSTORED_FACT_ID};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
+    type MockDogRepository = MockDogRepo<MockPersistence>;
+    // runs are recorded as for cat facts, only the synchronisation is tested
+    // here, not to share the sync run mocks with those tests
+    type MockUseCase<'a> = SyncDogFactsUseCase<
+        'a,
+        MockPersistence,
+        MockDogRepository,
+        MockSyncRunRepo<MockPersistence>,
+        MockDogFactsService,
+    >;
+
+    #[actix_rt::test]
+    async fn test_should_insert_new_and_flag_changed_facts() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_tenant_transaction_with()
+            .with(eq(acme()), eq(TransactionOptions::serializable()))
+            .times(1)
+            .returning(|_tenant, _options| {
+                let mut tx = MockTransaction::new();
+                // kept with the fact inserted
+                tx.expect_add_event().times(1).returning(|_event| Ok(()));
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given an upstream listing of three facts over two pages
+        let mut dog_facts_service = MockDogFactsService::new();
+        dog_facts_service
+            .expect_get_dog_facts_page()
+            .returning(|page| {
+                let facts: &[&str] = if page == 1 {
+                    &["fact1", "fact2 edited"]
+                } else {
+                    &["fact3"]
+                };
+                Ok(FactsPage {
+                    facts: facts
+                        .iter()
+                        .map(|f| DogFactEntity::new(UNSTORED_FACT_ID, f.to_string()))
+                        .collect(),
+                    current_page: page,
+                    last_page: 2,
+                })
+            });
+
+        // and the first two already stored
+        let get_ctx = MockDogRepository::get_upstream_dog_fact_context();
+        get_ctx.expect().returning(|_tx, index| {
+            Ok(match index {
+                1 => Some(DogFactEntity::new(10, "fact1".into())),
+                2 => Some(DogFactEntity::new(20, "fact2".into())),
+                _ => None,
+            })
+        });
+        let insert_ctx = MockDogRepository::insert_upstream_dog_fact_context();
+        insert_ctx
+            .expect()
+            .withf(|_tx, index, fact| *index == 3 && fact == "fact3")
+            .times(1)
+            .returning(|_tx, _index, fact| Ok(DogFactEntity::new(30, fact)));
+        let flag_ctx = MockDogRepository::flag_changed_dog_fact_context();
+        flag_ctx
+            .expect()
+            .withf(|_tx, fact_id| *fact_id == 20)
+            .times(1)
+            .returning(|_tx, _fact_id| Ok(()));
+
+        let mut events = MockEventPublisher::new();
+        events
+            .expect_publish()
+            .withf(|event| {
+                matches!(event, DomainEvent::FactCreated { fact, .. }
+                    if fact.species == Species::Dog && fact.tenant_id == "acme" && fact.fact_id == 30)
+            })
+            .times(1)
+            .return_const(());
+
+        // when synchronising
+        let sync_dog_facts_usecase = MockUseCase::new(persistence, &dog_facts_service, &events);
+        let counts = sync_dog_facts_usecase.synchronise(&acme()).await.unwrap();
+
+        // then assert every fact is accounted for
+        assert_eq!(counts, (1, 1, 1));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_store_nothing_when_upstream_fails() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence.expect_get_tenant_transaction_with().times(0);
+
+        // given an upstream failing on its second page
+        let mut dog_facts_service = MockDogFactsService::new();
+        dog_facts_service
+            .expect_get_dog_facts_page()
+            .returning(|page| match page {
+                1 => Ok(FactsPage {
+                    facts: vec![DogFactEntity::new(UNSTORED_FACT_ID, "fact1".into())],
+                    current_page: 1,
+                    last_page: 2,
+                }),
+                _ => Err(UpstreamError("connection refused".into())),
+            });
+
+        let mut events = MockEventPublisher::new();
+        events.expect_publish().times(0);
+
+        // when synchronising
+        let sync_dog_facts_usecase = MockUseCase::new(persistence, &dog_facts_service, &events);
+        let result = sync_dog_facts_usecase.synchronise(&acme()).await;
+
+        // then exception, before touching the catalogue
+        assert!(matches!(result, Err(UseCaseError::Upstream(_))));
+    }
+}
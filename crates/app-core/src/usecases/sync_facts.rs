@@ -0,0 +1,78 @@
+use std::future::Future;
+
+use crate::services::{FactsPage, Persistence, SyncRunRepo, Transaction, UpstreamError};
+use app_domain::entities::{SyncRunEntity, TenantEntity};
+
+use super::UseCaseError;
+
+/// Facts inserted, unchanged and changed by a run
+pub(crate) type SyncCounts = (i32, i32, i32);
+
+/// Pages walked at most, for a listing that never ends not to run forever
+pub(crate) const MAX_PAGES: i32 = 1000;
+
+/// Walk an upstream listing page by page, until the last one
+///
+/// A listing not answering with the page asked for, e.g. ignoring the page
+/// number, or longer than `MAX_PAGES` is an upstream error.
+pub(crate) async fn get_all_pages<T, F, Fut>(get_page: F) -> Result<Vec<T>, UpstreamError>
+where
+    F: Fn(i32) -> Fut,
+    Fut: Future<Output = Result<FactsPage<T>, UpstreamError>>,
+{
+    let mut facts = vec![];
+    let mut page = 1;
+    loop {
+        let facts_page = get_page(page).await?;
+        if facts_page.current_page != page {
+            return Err(UpstreamError(format!(
+                "asked for page {}, got page {}",
+                page, facts_page.current_page
+            )));
+        }
+        let is_last = facts_page.is_last();
+        facts.extend(facts_page.facts);
+        if is_last {
+            return Ok(facts);
+        }
+        if page >= MAX_PAGES {
+            return Err(UpstreamError(format!("more than {} pages", MAX_PAGES)));
+        }
+        page += 1;
+    }
+}
+
+/// Record a run of `synchronise`, whatever its outcome
+pub(crate) async fn record_sync_run<P, SR>(
+    persistance: &P,
+    source: &str,
+    tenant: &TenantEntity,
+    synchronise: impl Future<Output = Result<SyncCounts, UseCaseError>>,
+) -> Result<SyncRunEntity, UseCaseError>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SyncRunRepo<P>,
+{
+    let mut run = SyncRunEntity::start(source.into(), tenant.tenant_id.clone());
+    let mut tx = persistance.get_transaction().await?;
+    run.run_id = SR::create_sync_run(&mut tx, run.clone()).await?;
+    tx.commit().await?;
+
+    let result = synchronise.await;
+    match &result {
+        Ok((inserted, unchanged, changed)) => {
+            run.inserted = *inserted;
+            run.unchanged = *unchanged;
+            run.changed = *changed;
+            run.finish(None);
+        }
+        Err(e) => run.finish(Some(e.to_string())),
+    }
+
+    let mut tx = persistance.get_transaction().await?;
+    SR::finish_sync_run(&mut tx, run.clone()).await?;
+    tx.commit().await?;
+
+    result.map(|_| run)
+}
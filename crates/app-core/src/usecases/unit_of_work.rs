@@ -0,0 +1,170 @@
+use std::{future::Future, marker::PhantomData, pin::Pin};
+
+use crate::services::Transaction;
+
+use super::UseCaseError;
+
+/// What the body of a unit of work gives back, borrowing the unit for `'u`
+pub type UnitFuture<'u, R> = Pin<Box<dyn Future<Output = Result<R, UseCaseError>> + Send + 'u>>;
+
+/// One transaction shared by every repository a use case calls
+///
+/// The work is committed when the body succeeds and rolled back as soon
+/// as it fails, rather than whenever the transaction happens to be
+/// dropped. Parts of it may be nested, each undone on its own. `'a` is
+/// how long the body may borrow from the use case.
+pub struct UnitOfWork<'a, T> {
+    tx: T,
+    /// Savepoints taken so far, for their names to stay unique
+    savepoints: u32,
+    env: PhantomData<&'a ()>,
+}
+
+impl<'a, T: Transaction> UnitOfWork<'a, T> {
+    pub fn new(tx: T) -> Self {
+        UnitOfWork {
+            tx,
+            savepoints: 0,
+            env: PhantomData,
+        }
+    }
+
+    /// The transaction to hand to repositories
+    pub fn tx(&mut self) -> &mut T {
+        &mut self.tx
+    }
+
+    /// Run `body`, then commit, or roll back and return its error
+    pub async fn run<R, F>(mut self, body: F) -> Result<R, UseCaseError>
+    where
+        F: for<'u> FnOnce(&'u mut UnitOfWork<'a, T>) -> UnitFuture<'u, R>,
+    {
+        match body(&mut self).await {
+            Ok(value) => {
+                self.tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                // the body's error is the one worth reporting
+                let _ = self.tx.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Run `body` from a savepoint, what it did is undone when it fails
+    /// while the rest of the unit carries on
+    pub async fn nested<R, F>(&mut self, body: F) -> Result<R, UseCaseError>
+    where
+        F: for<'u> FnOnce(&'u mut UnitOfWork<'a, T>) -> UnitFuture<'u, R>,
+    {
+        self.savepoints += 1;
+        let name = format!("unit_of_work_{}", self.savepoints);
+        self.tx.savepoint(&name).await?;
+
+        let result = body(self).await;
+        if result.is_err() {
+            self.tx.rollback_to_savepoint(&name).await?;
+        }
+        self.tx.release_savepoint(&name).await?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::{predicate::eq, Sequence};
+
+    use crate::services::{MockTransaction, RepositoryError};
+
+    #[actix_rt::test]
+    async fn test_should_commit_when_body_succeeds() {
+        // given a transaction expected to be committed
+        let mut tx = MockTransaction::new();
+        tx.expect_commit().times(1).returning(|| Ok(()));
+        tx.expect_rollback().times(0);
+
+        // when running a succeeding body
+        let data = UnitOfWork::new(tx)
+            .run(|_uow| Box::pin(async { Ok(42) }))
+            .await;
+
+        // then its result is returned
+        assert_eq!(data.unwrap(), 42);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_roll_back_when_body_fails() {
+        // given a transaction expected to be rolled back, failing to
+        let mut tx = MockTransaction::new();
+        tx.expect_commit().times(0);
+        tx.expect_rollback()
+            .times(1)
+            .returning(|| Err(RepositoryError::new("connection closed".into())));
+
+        // when running a body returning early
+        let data = UnitOfWork::new(tx)
+            .run(|_uow| Box::pin(async { Err::<(), _>(UseCaseError::Business("no".into())) }))
+            .await;
+
+        // then the body's error is returned
+        assert_eq!(data.unwrap_err().to_string(), "Business error: no");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_undo_failed_nested_work_only() {
+        // given a transaction expecting a savepoint undone, then one kept
+        let mut seq = Sequence::new();
+        let mut tx = MockTransaction::new();
+        tx.expect_savepoint()
+            .with(eq("unit_of_work_1"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        tx.expect_rollback_to_savepoint()
+            .with(eq("unit_of_work_1"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        tx.expect_release_savepoint()
+            .with(eq("unit_of_work_1"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        tx.expect_savepoint()
+            .with(eq("unit_of_work_2"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        tx.expect_release_savepoint()
+            .with(eq("unit_of_work_2"))
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(()));
+        tx.expect_commit()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(()));
+
+        // when a first nested part fails and a second one succeeds
+        let data = UnitOfWork::new(tx)
+            .run(|uow| {
+                Box::pin(async move {
+                    let failed = uow
+                        .nested(|_uow| {
+                            Box::pin(async {
+                                Err::<(), _>(UseCaseError::Repository("duplicate key".into()))
+                            })
+                        })
+                        .await;
+                    let kept = uow.nested(|_uow| Box::pin(async { Ok(2) })).await?;
+                    Ok((failed.is_err(), kept))
+                })
+            })
+            .await;
+
+        // then the unit still commits, with the second part's result
+        assert_eq!(data.unwrap(), (true, 2));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::services::{AuthError, AuthService};
+
+use super::UseCaseError;
+
+pub struct UnlockAccountUseCase<'a, A: ?Sized> {
+    auth_service: &'a A,
+}
+
+impl<'a, A: ?Sized> UnlockAccountUseCase<'a, A> {
+    pub fn new(auth_service: &'a A) -> Self {
+        UnlockAccountUseCase { auth_service }
+    }
+}
+
+impl<'a, A> UnlockAccountUseCase<'a, A>
+where
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(&self, username: &str) -> Result<(), UseCaseError> {
+        self.auth_service
+            .unlock(username)
+            .await
+            .map_err(|e| match e {
+                AuthError::Backend(e) => UseCaseError::Upstream(e),
+                e => UseCaseError::Business(e.to_string()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::MockAuthService;
+    use mockall::predicate::eq;
+
+    #[actix_rt::test]
+    async fn test_should_unlock_account() {
+        // given an auth service locking accounts
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_unlock()
+            .with(eq("jane"))
+            .times(1)
+            .returning(|_username| Ok(()));
+
+        // when calling usecase
+        let unlock_account_usecase = UnlockAccountUseCase::new(&auth_service);
+        let data = unlock_account_usecase.execute("jane").await;
+
+        // then assert it succeeded
+        assert!(data.is_ok());
+    }
+}
@@ -0,0 +1,248 @@
+use std::marker::PhantomData;
+
+use crate::services::{DogRepo, EventPublisher, Persistence, Transaction};
+use app_domain::{
+    entities::{DogFactEntity, TenantEntity},
+    events::{DomainEvent, FactRef, Species},
+};
+
+use super::{unit_of_work::UnitOfWork, UseCaseError};
+
+pub struct UpdateDogFactUseCase<'a, P, R> {
+    persistance: P,
+    events: &'a dyn EventPublisher,
+    repo: PhantomData<R>,
+}
+
+impl<'a, P, DR> UpdateDogFactUseCase<'a, P, DR> {
+    pub fn new(persistance: P, events: &'a dyn EventPublisher) -> Self {
+        UpdateDogFactUseCase {
+            persistance,
+            events,
+            repo: PhantomData::<DR>,
+        }
+    }
+}
+
+impl<'a, P, DR> UpdateDogFactUseCase<'a, P, DR>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    DR: DogRepo<P>,
+{
+    /// Replace the text of a fact, provided it is still at the `version`
+    /// it was read at, returning it at the next one
+    pub async fn execute(
+        &self,
+        tenant: &TenantEntity,
+        dog_fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<DogFactEntity, UseCaseError> {
+        let tx = self.persistance.get_tenant_transaction(tenant).await?;
+        let (updated, event) = UnitOfWork::new(tx)
+            .run(|uow| {
+                Box::pin(async move {
+                    if let Some(updated) =
+                        DR::update_dog_fact(uow.tx(), dog_fact_id, fact, version).await?
+                    {
+                        let event = DomainEvent::FactUpdated {
+                            fact: FactRef::new(
+                                Species::Dog,
+                                tenant.tenant_id.clone(),
+                                updated.fact_id,
+                            ),
+                            text: updated.fact.clone(),
+                            version: updated.version,
+                        };
+                        uow.tx().add_event(&event).await?;
+                        return Ok((updated, event));
+                    }
+                    match DR::get_dog_fact_by_id(uow.tx(), dog_fact_id).await? {
+                        Some(current) => Err(UseCaseError::Conflict {
+                            current_version: current.version,
+                        }),
+                        None => Err(UseCaseError::NotFound("No dog fact found".into())),
+                    }
+                })
+            })
+            .await?;
+
+        self.events.publish(event).await;
+        Ok(updated)
+    }
+}
+
+#[allow(clippy::await_holding_lock)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lazy_static::lazy_static;
+    use mockall::predicate::eq;
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::services::{MockDogRepo, MockEventPublisher, MockPersistence, MockTransaction};
+
+    lazy_static! {
+        static ref MTX: Mutex<()> = Mutex::new(());
+    }
+
+    // When a test panics, it will poison the Mutex. Since we don't actually
+    // care about the state of the data we ignore that it is poisoned and grab
+    // the lock regardless.  If you just do `let _m = &MTX.lock().unwrap()`, one
+    // test panicking will cause all other tests that try and acquire a lock on
+    // that Mutex to also panic.
+    fn get_lock(m: &'static Mutex<()>) -> MutexGuard<'static, ()> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
+    type MockRepo = MockDogRepo<MockPersistence>;
+    type MockUseCase<'a> = UpdateDogFactUseCase<'a, MockPersistence, MockRepo>;
+
+    #[actix_rt::test]
+    async fn test_should_update_fact_at_expected_version() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_tenant_transaction()
+            .with(eq(acme()))
+            .times(1)
+            .returning(|_tenant| {
+                let mut tx = MockTransaction::new();
+                tx.expect_add_event()
+                    .withf(|event| matches!(event, DomainEvent::FactUpdated { version: 4, .. }))
+                    .times(1)
+                    .returning(|_event| Ok(()));
+                tx.expect_commit().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given a fact at version 3
+        let update_ctx = MockRepo::update_dog_fact_context();
+        update_ctx
+            .expect()
+            .withf(|_tx, id, fact, version| *id == 1 && fact == "fact1 edited" && *version == 3)
+            .times(1)
+            .returning(|_tx, id, fact, version| {
+                Ok(Some(DogFactEntity {
+                    fact_id: id,
+                    fact,
+                    version: version + 1,
+                }))
+            });
+
+        // and subscribers to tell
+        let mut events = MockEventPublisher::new();
+        events
+            .expect_publish()
+            .withf(|event| {
+                matches!(event, DomainEvent::FactUpdated { fact, version: 4, .. }
+                    if fact.species == Species::Dog && fact.tenant_id == "acme" && fact.fact_id == 1)
+            })
+            .times(1)
+            .return_const(());
+
+        // when updating it from version 3
+        let update_dog_fact_usecase = MockUseCase::new(persistence, &events);
+        let data = update_dog_fact_usecase
+            .execute(&acme(), 1, "fact1 edited".into(), 3)
+            .await
+            .unwrap();
+
+        // then it is at version 4
+        assert_eq!(data.fact, "fact1 edited");
+        assert_eq!(data.version, 4);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_conflict_with_current_version() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_tenant_transaction()
+            .times(1)
+            .returning(|_tenant| {
+                let mut tx = MockTransaction::new();
+                tx.expect_rollback().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given a fact updated by someone else, now at version 5
+        let update_ctx = MockRepo::update_dog_fact_context();
+        update_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _id, _fact, _version| Ok(None));
+        let get_ctx = MockRepo::get_dog_fact_by_id_context();
+        get_ctx.expect().times(1).returning(|_tx, id| {
+            Ok(Some(DogFactEntity {
+                fact_id: id,
+                fact: "fact1 edited elsewhere".into(),
+                version: 5,
+            }))
+        });
+
+        let mut events = MockEventPublisher::new();
+        events.expect_publish().times(0);
+
+        // when updating it from version 3
+        let update_dog_fact_usecase = MockUseCase::new(persistence, &events);
+        let data = update_dog_fact_usecase
+            .execute(&acme(), 1, "fact1 edited".into(), 3)
+            .await;
+
+        // then conflict, with the version to start over from
+        assert!(matches!(
+            data,
+            Err(UseCaseError::Conflict { current_version: 5 })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_return_error_when_fact_missing() {
+        let _m = get_lock(&MTX);
+
+        let mut persistence = MockPersistence::new();
+        persistence
+            .expect_get_tenant_transaction()
+            .times(1)
+            .returning(|_tenant| {
+                let mut tx = MockTransaction::new();
+                tx.expect_rollback().times(1).returning(|| Ok(()));
+                Ok(tx)
+            });
+
+        // given no such fact
+        let update_ctx = MockRepo::update_dog_fact_context();
+        update_ctx
+            .expect()
+            .times(1)
+            .returning(|_tx, _id, _fact, _version| Ok(None));
+        let get_ctx = MockRepo::get_dog_fact_by_id_context();
+        get_ctx.expect().times(1).returning(|_tx, _id| Ok(None));
+
+        let mut events = MockEventPublisher::new();
+        events.expect_publish().times(0);
+
+        // when updating it
+        let update_dog_fact_usecase = MockUseCase::new(persistence, &events);
+        let data = update_dog_fact_usecase
+            .execute(&acme(), 1, "fact1 edited".into(), 1)
+            .await;
+
+        // then exception
+        assert_eq!(
+            data.unwrap_err().to_string(),
+            "Not found: No dog fact found"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use crate::services::{AuthService, Principal};
+
+use super::UseCaseError;
+
+pub struct VerifyTokenUseCase<'a, A: ?Sized> {
+    auth_service: &'a A,
+}
+
+impl<'a, A: ?Sized> VerifyTokenUseCase<'a, A> {
+    pub fn new(auth_service: &'a A) -> Self {
+        VerifyTokenUseCase { auth_service }
+    }
+}
+
+impl<'a, A> VerifyTokenUseCase<'a, A>
+where
+    A: AuthService + ?Sized,
+{
+    pub async fn execute(&self, token: &str) -> Result<Principal, UseCaseError> {
+        Ok(self.auth_service.verify_token(token).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::services::{AuthError, MockAuthService};
+    use mockall::predicate::eq;
+
+    #[actix_rt::test]
+    async fn test_should_return_unauthorized_for_unknown_token() {
+        // given an auth service not knowing the token
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_verify_token()
+            .with(eq("unknown"))
+            .times(1)
+            .returning(|_token| Err(AuthError::InvalidToken));
+
+        // when calling usecase
+        let verify_token_usecase = VerifyTokenUseCase::new(&auth_service);
+        let data = verify_token_usecase.execute("unknown").await;
+
+        // then unauthorized
+        assert!(matches!(data, Err(UseCaseError::Unauthorized(_))));
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthEventKind {
+    LoginSucceeded,
+    LoginFailed,
+    LoginThrottled,
+    AccountLocked,
+    AccountUnlocked,
+}
+
+impl fmt::Display for AuthEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            AuthEventKind::LoginSucceeded => "login_succeeded",
+            AuthEventKind::LoginFailed => "login_failed",
+            AuthEventKind::LoginThrottled => "login_throttled",
+            AuthEventKind::AccountLocked => "account_locked",
+            AuthEventKind::AccountUnlocked => "account_unlocked",
+        };
+        f.write_str(kind)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthEventEntity {
+    pub kind: AuthEventKind,
+    pub username: String,
+    pub client_ip: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl AuthEventEntity {
+    pub fn new(kind: AuthEventKind, username: String, client_ip: Option<String>) -> Self {
+        AuthEventEntity {
+            kind,
+            username,
+            client_ip,
+            occurred_at: Utc::now(),
+        }
+    }
+}
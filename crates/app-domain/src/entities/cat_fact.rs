@@ -1,11 +1,20 @@
 #[derive(Debug, Clone)]
 pub struct CatFactEntity {
     pub fact_txt: String,
+    /// `UNSTORED_FACT_ID` until stored
     pub fact_id: i32,
+    /// Goes up by one with every update, an update made from an older
+    /// version is refused
+    pub version: i32,
 }
 
 impl CatFactEntity {
+    /// At the version a fact is first stored with
     pub fn new(fact_txt: String, fact_id: i32) -> Self {
-        CatFactEntity { fact_txt, fact_id }
+        CatFactEntity {
+            fact_txt,
+            fact_id,
+            version: 1,
+        }
     }
 }
@@ -1,11 +1,20 @@
 #[derive(Debug, Clone)]
 pub struct DogFactEntity {
+    /// `UNSTORED_FACT_ID` until stored
     pub fact_id: i32,
     pub fact: String,
+    /// Goes up by one with every update, an update made from an older
+    /// version is refused
+    pub version: i32,
 }
 
 impl DogFactEntity {
+    /// At the version a fact is first stored with
     pub fn new(fact_id: i32, fact: String) -> Self {
-        DogFactEntity { fact_id, fact }
+        DogFactEntity {
+            fact_id,
+            fact,
+            version: 1,
+        }
     }
 }
@@ -0,0 +1,8 @@
+/// A schema migration shipped with the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationEntity {
+    pub version: i64,
+    pub description: String,
+    /// Pending until applied to the database
+    pub applied: bool,
+}
@@ -1,5 +1,25 @@
+mod auth_event;
 mod cat_fact;
 mod dog_fact;
+mod migration;
+mod outbox_event;
+mod password_reset;
+mod session;
+mod sync_run;
+mod tenant;
+mod user;
 
+pub use auth_event::{AuthEventEntity, AuthEventKind};
 pub use cat_fact::CatFactEntity;
 pub use dog_fact::DogFactEntity;
+pub use migration::MigrationEntity;
+pub use outbox_event::OutboxEventEntity;
+pub use password_reset::PasswordResetEntity;
+pub use session::SessionEntity;
+pub use sync_run::SyncRunEntity;
+pub use tenant::TenantEntity;
+pub use user::UserEntity;
+
+/// The id of a fact not stored yet, e.g. fresh from upstream or a payload,
+/// ids being given by the database
+pub const UNSTORED_FACT_ID: i32 = 0;
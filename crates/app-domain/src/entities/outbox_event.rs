@@ -0,0 +1,12 @@
+use crate::events::DomainEvent;
+
+/// A domain event kept in the outbox until every subscriber handled it
+#[derive(Debug, Clone)]
+pub struct OutboxEventEntity {
+    pub event_id: i64,
+    pub event: DomainEvent,
+    /// Deliveries tried so far, this one included
+    pub attempts: i32,
+    /// Subscribers having handled it already, not told again
+    pub delivered_to: Vec<String>,
+}
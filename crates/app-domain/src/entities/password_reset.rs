@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+
+/// A pending password reset, the token mailed to the user is only kept
+/// hashed, and used once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordResetEntity {
+    pub token_hash: String,
+    pub username: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PasswordResetEntity {
+    pub fn new(token_hash: String, username: String, expires_at: DateTime<Utc>) -> Self {
+        PasswordResetEntity {
+            token_hash,
+            username,
+            expires_at,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone)]
+pub struct SessionEntity {
+    pub session_id: String,
+    pub username: String,
+    pub roles: Vec<String>,
+    pub csrf_token: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SessionEntity {
+    pub fn new(
+        session_id: String,
+        username: String,
+        roles: Vec<String>,
+        csrf_token: String,
+        created_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Self {
+        SessionEntity {
+            session_id,
+            username,
+            roles,
+            csrf_token,
+            created_at,
+            expires_at,
+        }
+    }
+}
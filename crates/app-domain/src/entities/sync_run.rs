@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+
+/// One synchronisation of a tenant's facts with an upstream source
+#[derive(Debug, Clone)]
+pub struct SyncRunEntity {
+    /// Assigned once stored
+    pub run_id: i64,
+    pub source: String,
+    pub tenant_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Upstream facts stored for the first time
+    pub inserted: i32,
+    /// Upstream facts already stored as-is
+    pub unchanged: i32,
+    /// Upstream facts differing from the stored ones, which are kept and flagged
+    pub changed: i32,
+    pub error: Option<String>,
+}
+
+impl SyncRunEntity {
+    pub fn start(source: String, tenant_id: String) -> Self {
+        SyncRunEntity {
+            run_id: 0,
+            source,
+            tenant_id,
+            started_at: Utc::now(),
+            finished_at: None,
+            inserted: 0,
+            unchanged: 0,
+            changed: 0,
+            error: None,
+        }
+    }
+
+    pub fn finish(&mut self, error: Option<String>) {
+        self.finished_at = Some(Utc::now());
+        self.error = error;
+    }
+}
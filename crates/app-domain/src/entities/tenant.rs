@@ -0,0 +1,21 @@
+/// A partner brand with its own, isolated fact catalogues
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TenantEntity {
+    pub tenant_id: String,
+}
+
+impl TenantEntity {
+    pub fn new(tenant_id: String) -> Self {
+        TenantEntity { tenant_id }
+    }
+
+    /// Tenant ids end up in hostnames and database settings,
+    /// so they are kept to a conservative charset
+    pub fn is_valid_id(tenant_id: &str) -> bool {
+        !tenant_id.is_empty()
+            && tenant_id.len() <= 63
+            && tenant_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
@@ -0,0 +1,19 @@
+/// An account logging in with a password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserEntity {
+    pub username: String,
+    /// Lower-cased, where password reset mails go
+    pub email: String,
+    /// As given by a `PasswordHasher`, never the password itself
+    pub password_hash: String,
+}
+
+impl UserEntity {
+    pub fn new(username: String, email: String, password_hash: String) -> Self {
+        UserEntity {
+            username,
+            email,
+            password_hash,
+        }
+    }
+}
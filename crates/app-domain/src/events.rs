@@ -0,0 +1,54 @@
+/// Which catalogue a fact belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Species {
+    Cat,
+    Dog,
+}
+
+/// The fact an event is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactRef {
+    pub species: Species,
+    pub tenant_id: String,
+    pub fact_id: i32,
+}
+
+impl FactRef {
+    pub fn new(species: Species, tenant_id: String, fact_id: i32) -> Self {
+        FactRef {
+            species,
+            tenant_id,
+            fact_id,
+        }
+    }
+}
+
+/// Something that happened to a fact, told once committed to whatever
+/// reacts to it: caches, webhooks, search indexes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainEvent {
+    FactCreated {
+        fact: FactRef,
+        text: String,
+    },
+    FactUpdated {
+        fact: FactRef,
+        text: String,
+        version: i32,
+    },
+}
+
+impl DomainEvent {
+    pub fn fact(&self) -> &FactRef {
+        match self {
+            DomainEvent::FactCreated { fact, .. } | DomainEvent::FactUpdated { fact, .. } => fact,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::FactCreated { .. } => "fact_created",
+            DomainEvent::FactUpdated { .. } => "fact_updated",
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use app_core::services::{EventPublisher, EventSubscriber};
+use app_domain::events::DomainEvent;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+/// Hands the events of use cases over to subscribers, in process
+///
+/// Every subscriber has a queue of its own, handled in the background one
+/// event at a time and in the order published. A subscriber failing or
+/// panicking is logged, and neither slows down the use case nor keeps the
+/// other subscribers, or its own next events, from being handled.
+pub struct EventBus {
+    queues: Vec<mpsc::UnboundedSender<DomainEvent>>,
+}
+
+impl EventBus {
+    /// Start handling events for `subscribers`, from within the runtime
+    pub fn new(subscribers: Vec<Arc<dyn EventSubscriber>>) -> Self {
+        let queues = subscribers
+            .into_iter()
+            .map(|subscriber| {
+                let (queue, events) = mpsc::unbounded_channel();
+                tokio::spawn(deliver(subscriber, events));
+                queue
+            })
+            .collect();
+        EventBus { queues }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for EventBus {
+    async fn publish(&self, event: DomainEvent) {
+        for queue in &self.queues {
+            // a subscriber's queue only closes when the process stops
+            let _ = queue.send(event.clone());
+        }
+    }
+}
+
+async fn deliver(
+    subscriber: Arc<dyn EventSubscriber>,
+    mut events: mpsc::UnboundedReceiver<DomainEvent>,
+) {
+    while let Some(event) = events.recv().await {
+        if let Err(e) = handle(&subscriber, &event).await {
+            log::error!(
+                "{} couldn't handle {} of {:?}: {}",
+                subscriber.name(),
+                event.name(),
+                event.fact(),
+                e
+            );
+        }
+    }
+}
+
+/// Have `subscriber` handle `event` in a task of its own, for a panic to
+/// only end the handling, and be told like an error
+pub(crate) async fn handle(
+    subscriber: &Arc<dyn EventSubscriber>,
+    event: &DomainEvent,
+) -> Result<(), String> {
+    let handling = {
+        let subscriber = subscriber.clone();
+        let event = event.clone();
+        tokio::spawn(async move { subscriber.handle(&event).await })
+    };
+    match handling.await {
+        Ok(handled) => handled.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
@@ -1,46 +1,315 @@
-use std::{env, net::TcpListener};
+mod events;
+mod outbox;
+mod settings;
+mod sync;
+mod tls;
+
+use std::{env, net::TcpListener, sync::Arc};
 
 use actix_web::middleware::Logger;
 use actix_web::{rt, web, App, HttpServer};
+use app_core::{
+    services::{
+        AuthAuditLog, AuthService, CatRepo, DogFactsService, DogRepo, EventPublisher, Mailer,
+        Outbox, Persistence, SchemaMigrations, SessionRepo, SsoService, SyncRunRepo, Transaction,
+        UserRepo,
+    },
+    usecases::{create_user::CreateUserUseCase, UseCaseError},
+};
 use presenter_rest::RestAppState;
-use service_auth::{cat_facts_service::CatFactsserviceHTTP, connection::HttpConnection};
-use service_db::db_service::{CatRepoPG, DogRepoPG, PersistencePG};
+use service_auth::{
+    cache::HttpCache,
+    cat_facts_service::CatFactsserviceHTTP,
+    connection::HttpConnection,
+    dev_auth_service::DevAuthService,
+    dog_facts_service::DogFactsServiceHTTP,
+    lockout_service::LockoutAuthService,
+    mailer::{HttpMailer, LogMailer},
+    oidc_service::OidcServiceHTTP,
+    password_auth_service::PasswordAuthService,
+    password_hasher::ScryptPasswordHasher,
+};
+#[cfg(feature = "postgres")]
+use service_db::db_service::{
+    AuthAuditLogPG, CatRepoPG, DogRepoPG, OutboxPG, PersistencePG, SchemaMigrationsPG,
+    SessionRepoPG, SyncRunRepoPG, UserRepoPG,
+};
+#[cfg(feature = "memory")]
+use service_memory::memory_service::{
+    AuthAuditLogMemory, CatRepoMemory, DogRepoMemory, PersistenceMemory, SchemaMigrationsMemory,
+    SessionRepoMemory, SyncRunRepoMemory, UserRepoMemory,
+};
+#[cfg(feature = "sqlite")]
+use service_sqlite::db_service::{
+    AuthAuditLogSqlite, CatRepoSqlite, DogRepoSqlite, PersistenceSqlite, SchemaMigrationsSqlite,
+    SessionRepoSqlite, SyncRunRepoSqlite, UserRepoSqlite,
+};
 
-pub async fn setup(
-    listener: TcpListener,
-    db_name: String,
-    cats_source: String,
-) -> Result<(), std::io::Error> {
+pub use events::EventBus;
+pub use outbox::{OutboxRelay, OutboxSettings};
+pub use settings::{AuthMode, BootstrapUser, PersistenceSettings, Settings};
+pub use sync::SyncSettings;
+pub use tls::{TlsProfile, TlsSettings};
+
+pub async fn setup(listener: TcpListener, settings: Settings) -> Result<(), std::io::Error> {
     let _ = env_logger::try_init(); //.expect("Environment error");
 
-    let http_connection = HttpConnection {};
+    // one instantiation of the app per backend built in
+    match &settings.persistence {
+        #[cfg(feature = "postgres")]
+        PersistenceSettings::Postgres => {
+            let persistence_service = PersistencePG::new(&settings.db_name)
+                .await
+                .map_err(std::io::Error::other)?
+                .with_replicas(settings.replicas.clone())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            if settings.migrate_on_startup {
+                persistence_service
+                    .migrate()
+                    .await
+                    .map_err(std::io::Error::other)?;
+            }
+            let auth_audit_log = Box::new(AuthAuditLogPG::new(persistence_service.clone()));
+            let schema_migrations = Box::new(SchemaMigrationsPG::new(persistence_service.clone()));
+            let outbox = Box::new(OutboxPG::new(persistence_service.clone()));
+            serve::<_, DogRepoPG, CatRepoPG, SessionRepoPG, SyncRunRepoPG, UserRepoPG>(
+                listener,
+                settings,
+                persistence_service,
+                auth_audit_log,
+                schema_migrations,
+                Some(outbox),
+            )
+            .await
+        }
+        #[cfg(feature = "sqlite")]
+        PersistenceSettings::Sqlite { database_url } => {
+            let persistence_service = PersistenceSqlite::new(database_url)
+                .await
+                .map_err(std::io::Error::other)?;
+            if settings.migrate_on_startup {
+                persistence_service
+                    .migrate()
+                    .await
+                    .map_err(std::io::Error::other)?;
+            }
+            let auth_audit_log = Box::new(AuthAuditLogSqlite::new(persistence_service.clone()));
+            let schema_migrations =
+                Box::new(SchemaMigrationsSqlite::new(persistence_service.clone()));
+            serve::<
+                _,
+                DogRepoSqlite,
+                CatRepoSqlite,
+                SessionRepoSqlite,
+                SyncRunRepoSqlite,
+                UserRepoSqlite,
+            >(
+                listener,
+                settings,
+                persistence_service,
+                auth_audit_log,
+                schema_migrations,
+                None,
+            )
+            .await
+        }
+        #[cfg(feature = "memory")]
+        PersistenceSettings::Memory { fixtures_dir } => {
+            let persistence_service = match fixtures_dir {
+                Some(dir) => PersistenceMemory::from_fixtures(dir)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+                None => PersistenceMemory::new(),
+            };
+            let auth_audit_log = Box::new(AuthAuditLogMemory::new(persistence_service.clone()));
+            serve::<
+                _,
+                DogRepoMemory,
+                CatRepoMemory,
+                SessionRepoMemory,
+                SyncRunRepoMemory,
+                UserRepoMemory,
+            >(
+                listener,
+                settings,
+                persistence_service,
+                auth_audit_log,
+                Box::new(SchemaMigrationsMemory {}),
+                None,
+            )
+            .await
+        }
+        #[allow(unreachable_patterns)]
+        persistence => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "DATABASE_URL asks for {0} persistence, built without the {0} feature",
+                persistence.name()
+            ),
+        )),
+    }
+}
+
+async fn serve<P, D, C, S, R, U>(
+    listener: TcpListener,
+    settings: Settings,
+    persistence_service: P,
+    auth_audit_log: Box<dyn AuthAuditLog>,
+    schema_migrations: Box<dyn SchemaMigrations>,
+    outbox: Option<Box<dyn Outbox>>,
+) -> Result<(), std::io::Error>
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    D: DogRepo<P> + Send + Sync,
+    C: CatRepo<P> + Send + Sync,
+    S: SessionRepo<P>,
+    R: SyncRunRepo<P> + Send + Sync,
+    U: UserRepo<P> + Send + Sync,
+{
+    bootstrap_users::<P, U>(&persistence_service, settings.bootstrap_users).await?;
+
+    let auth_service: Box<dyn AuthService> = match settings.auth_mode {
+        AuthMode::Passwords => Box::new(LockoutAuthService::new(
+            PasswordAuthService::<P, U>::new(
+                persistence_service.clone(),
+                Box::new(ScryptPasswordHasher::default()),
+                settings.admin_users,
+                settings.token_ttl,
+            )
+            .with_editor_users(settings.editor_users)
+            .with_user_tenants(settings.user_tenants),
+            settings.lockout_policy,
+            auth_audit_log,
+        )),
+        AuthMode::Dev => {
+            log::warn!("AUTH_MODE=dev, logins are accepted whatever the password");
+            Box::new(LockoutAuthService::new(
+                DevAuthService::new(settings.admin_users, settings.token_ttl)
+                    .with_editor_users(settings.editor_users)
+                    .with_user_tenants(settings.user_tenants),
+                settings.lockout_policy,
+                auth_audit_log,
+            ))
+        }
+    };
+
+    // one pool, and one circuit breaker per upstream host, for every service
+    let http_connection = HttpConnection::new(settings.http);
+    http_connection.log_metrics();
+
+    let sso_service = settings.oidc.map(|oidc| {
+        Box::new(OidcServiceHTTP::new(http_connection.clone(), oidc)) as Box<dyn SsoService>
+    });
+
+    let mailer: Box<dyn Mailer> = match settings.mailer_url {
+        Some(url) => Box::new(HttpMailer::new(http_connection.clone(), url)),
+        None => Box::new(LogMailer {}),
+    };
+
+    // fact sources are cached, single sign-on and mails are not
+    let upstream_connection = match settings.http_cache {
+        Some(cache) => http_connection.with_cache(HttpCache::new(cache)),
+        None => http_connection,
+    };
+
+    // events kept with their changes are relayed, others told once committed
+    let events: Arc<dyn EventPublisher> = match outbox {
+        Some(outbox) => Arc::new(OutboxRelay::start(
+            outbox,
+            settings.event_subscribers,
+            settings.outbox,
+        )),
+        None => Arc::new(EventBus::new(settings.event_subscribers)),
+    };
 
     let data = web::Data::new(RestAppState {
-        auth_service: Box::new(CatFactsserviceHTTP {
-            http_connection,
-            source: cats_source,
+        auth_service,
+        sso_service,
+        password_hasher: Box::new(ScryptPasswordHasher::default()),
+        mailer,
+        password_reset: settings.password_reset,
+        cat_facts_service: Arc::new(CatFactsserviceHTTP::new(
+            upstream_connection.clone(),
+            settings.cats_source,
+        )),
+        cat_facts_fallback: settings.cat_facts_fallback,
+        dog_facts_service: settings.dogs_source.map(|dogs_source| {
+            Box::new(DogFactsServiceHTTP::new(upstream_connection, dogs_source))
+                as Box<dyn DogFactsService>
         }),
-        persistence_service: PersistencePG::new(&db_name).await.unwrap(), //FIXME
+        session_mode: settings.session_mode,
+        tenancy: settings.tenancy,
+        client_certificates: settings.client_certificates,
+        persistence_service,
+        schema_migrations,
+        events,
     });
 
+    if let Some(sync_settings) = settings.cat_facts_sync {
+        sync::schedule_cat_facts_sync::<_, C, R>(data.clone(), sync_settings);
+    }
+    if let Some(sync_settings) = settings.dog_facts_sync {
+        sync::schedule_dog_facts_sync::<_, D, R>(data.clone(), sync_settings);
+    }
+
     let port = listener.local_addr().unwrap().to_string();
 
     let server = HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
             .wrap(Logger::default())
-            .configure(
-                presenter_rest::RestControllers::<PersistencePG, DogRepoPG, CatRepoPG>::routes,
-            )
+            .configure(presenter_rest::RestControllers::<P, D, C, S, R, U>::routes)
     })
-    .listen(listener)?
-    .run();
+    .on_connect(tls::on_connect);
 
-    println!("Server started on http://{}", port);
+    let server = match settings.tls {
+        Some(tls_settings) => {
+            let reloader = tls::CertificateReloader::new(tls_settings)?;
+            reloader.watch()?;
+            let server = server.listen_openssl(listener, reloader.acceptor()?)?;
+            println!("Server started on https://{}", port);
+            server
+        }
+        None => {
+            let server = server.listen(listener)?;
+            println!("Server started on http://{}", port);
+            server
+        }
+    }
+    .run();
 
     server.await
 }
 
+/// Create the bootstrap users that don't exist yet, existing ones are left
+/// as they are, their password may have changed since
+async fn bootstrap_users<P, U>(
+    persistence_service: &P,
+    users: Vec<BootstrapUser>,
+) -> Result<(), std::io::Error>
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    U: UserRepo<P>,
+{
+    let password_hasher = ScryptPasswordHasher::default();
+    for user in users {
+        let create_user_usecase =
+            CreateUserUseCase::<P, U, _>::new(persistence_service.clone(), &password_hasher);
+        match create_user_usecase
+            .execute(&user.username, &user.email, &user.password)
+            .await
+        {
+            Ok(_) => log::info!("Created bootstrap user {}", user.username),
+            Err(UseCaseError::Business(e)) => {
+                log::info!("Bootstrap user {} not created: {}", user.username, e)
+            }
+            Err(e) => return Err(std::io::Error::other(e.to_string())),
+        }
+    }
+    Ok(())
+}
+
 pub fn run(listener: TcpListener) -> Result<(), std::io::Error> {
     let environment_file;
     if let Ok(e) = env::var("ENV") {
@@ -51,8 +320,5 @@ pub fn run(listener: TcpListener) -> Result<(), std::io::Error> {
 
     dotenv::from_filename(environment_file).ok();
 
-    let db_name = dotenv::var("DATABASE_NAME").expect("DATABASE_NAME must be set");
-    let cats_source = dotenv::var("CATS_SOURCE").expect("CATS_SOURCE must be set");
-
-    rt::System::new().block_on(setup(listener, db_name, cats_source))
+    rt::System::new().block_on(setup(listener, Settings::from_env()))
 }
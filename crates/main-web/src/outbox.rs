@@ -0,0 +1,168 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use app_core::services::{EventPublisher, EventSubscriber, Outbox};
+use app_domain::{entities::OutboxEventEntity, events::DomainEvent};
+use async_trait::async_trait;
+use tokio::sync::Notify;
+
+use crate::events::handle;
+
+pub struct OutboxSettings {
+    /// Time between two looks at the outbox, when no event wakes the relay
+    pub interval: Duration,
+    /// Events claimed at once
+    pub batch_size: i64,
+    /// Time a relay has to deliver the events it claimed, before they are
+    /// delivered again, by it or another instance
+    pub lease: Duration,
+    /// Deliveries of an event before it is dead-lettered
+    pub max_attempts: i32,
+    /// Time before the first retry of an event, doubling with every attempt
+    pub retry_delay: Duration,
+    /// Time delivered events are kept, for a look at what was told lately
+    pub retention: Duration,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        OutboxSettings {
+            interval: Duration::from_secs(1),
+            batch_size: 100,
+            lease: Duration::from_secs(60),
+            max_attempts: 10,
+            retry_delay: Duration::from_secs(1),
+            retention: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// Delivers the events kept in the outbox to subscribers, at least once
+///
+/// Events of the same fact are delivered in the order they were kept, the
+/// next one waiting for the previous one to be delivered or dead-lettered.
+/// Subscribers failing are tried again later, alone, so should handle an
+/// event told twice as if told once. Delivered events are purged once past
+/// their retention, dead-lettered ones are kept.
+pub struct OutboxRelay {
+    wake: Arc<Notify>,
+}
+
+impl OutboxRelay {
+    /// Start relaying in the background, from within the runtime
+    pub fn start(
+        outbox: Box<dyn Outbox>,
+        subscribers: Vec<Arc<dyn EventSubscriber>>,
+        settings: OutboxSettings,
+    ) -> Self {
+        let wake = Arc::new(Notify::new());
+        tokio::spawn(relay(outbox, subscribers, settings, wake.clone()));
+        OutboxRelay { wake }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for OutboxRelay {
+    /// The event is in the outbox already, committed with its change, the
+    /// relay only needs to get to it
+    async fn publish(&self, _event: DomainEvent) {
+        self.wake.notify_one();
+    }
+}
+
+async fn relay(
+    outbox: Box<dyn Outbox>,
+    subscribers: Vec<Arc<dyn EventSubscriber>>,
+    settings: OutboxSettings,
+    wake: Arc<Notify>,
+) {
+    // purged as often as events expire, at least hourly, when idle
+    let purge_interval = settings.retention.min(Duration::from_secs(60 * 60));
+    let mut purged_at: Option<Instant> = None;
+    loop {
+        match outbox
+            .claim_events(settings.batch_size, settings.lease)
+            .await
+        {
+            Ok(events) if !events.is_empty() => {
+                for event in events {
+                    deliver(outbox.as_ref(), &subscribers, &settings, event).await;
+                }
+                // the next events of the facts may be due already
+                continue;
+            }
+            Ok(_) => {
+                if purged_at.is_none_or(|at| at.elapsed() >= purge_interval) {
+                    purged_at = Some(Instant::now());
+                    purge(outbox.as_ref(), settings.retention).await;
+                }
+            }
+            Err(e) => log::error!("Can't claim events from the outbox: {}", e),
+        }
+        let _ = tokio::time::timeout(settings.interval, wake.notified()).await;
+    }
+}
+
+async fn deliver(
+    outbox: &dyn Outbox,
+    subscribers: &[Arc<dyn EventSubscriber>],
+    settings: &OutboxSettings,
+    mut event: OutboxEventEntity,
+) {
+    let mut errors = vec![];
+    for subscriber in subscribers {
+        let name = subscriber.name().to_string();
+        if event.delivered_to.contains(&name) {
+            continue;
+        }
+        match handle(subscriber, &event.event).await {
+            Ok(()) => event.delivered_to.push(name),
+            Err(e) => errors.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    let stored = if errors.is_empty() {
+        outbox.mark_delivered(event.event_id).await
+    } else if event.attempts >= settings.max_attempts {
+        let error = errors.join(", ");
+        log::error!(
+            "Giving up on {} {} after {} attempts, {}",
+            event.event.name(),
+            event.event_id,
+            event.attempts,
+            error
+        );
+        outbox.dead_letter_event(&event, &error).await
+    } else {
+        let error = errors.join(", ");
+        log::warn!(
+            "Couldn't deliver {} {}, {}",
+            event.event.name(),
+            event.event_id,
+            error
+        );
+        let delay = settings
+            .retry_delay
+            .saturating_mul(1 << (event.attempts - 1).clamp(0, 16));
+        outbox.retry_event(&event, &error, delay).await
+    };
+    // the claim expiring, the event is delivered again
+    if let Err(e) = stored {
+        log::error!(
+            "Can't record the delivery of {} {}: {}",
+            event.event.name(),
+            event.event_id,
+            e
+        );
+    }
+}
+
+async fn purge(outbox: &dyn Outbox, retention: Duration) {
+    match outbox.purge_delivered(retention).await {
+        Ok(0) => {}
+        Ok(purged) => log::info!("Purged {} delivered events from the outbox", purged),
+        Err(e) => log::error!("Can't purge delivered events from the outbox: {}", e),
+    }
+}
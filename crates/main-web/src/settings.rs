@@ -0,0 +1,584 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time};
+
+use actix_web::cookie::SameSite;
+use app_core::{
+    services::{CatFactsFallback, EventSubscriber},
+    usecases::{create_session::SessionPolicy, request_password_reset::PasswordResetPolicy},
+};
+use chrono::Duration;
+use presenter_rest::{
+    client_certs::ClientCertificateSettings,
+    sessions::{CookieSessionSettings, SessionMode},
+    tenants::TenantSettings,
+};
+use service_auth::{
+    cache::CacheSettings, connection::HttpSettings, lockout_service::LockoutPolicy,
+    oidc_service::OidcSettings,
+};
+#[cfg(feature = "postgres")]
+use service_db::replicas::ReplicaSettings;
+
+use crate::{
+    outbox::OutboxSettings,
+    sync::SyncSettings,
+    tls::{TlsProfile, TlsSettings},
+};
+
+/// Where catalogues, sessions and synchronisation runs are kept
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PersistenceSettings {
+    /// The `db_name` database of the `DATABASE_URL` server
+    #[default]
+    Postgres,
+    /// A single file database, e.g. `sqlite:animal_facts.db`, created and
+    /// migrated on startup
+    Sqlite { database_url: String },
+    /// The process memory, lost on restart, optionally starting with the
+    /// facts of a fixtures directory
+    Memory { fixtures_dir: Option<PathBuf> },
+}
+
+impl PersistenceSettings {
+    /// The backend selected by the scheme of a database URL: `postgres://`
+    /// or `postgresql://`, `sqlite:`, or `memory:` optionally followed by a
+    /// fixtures directory, e.g. `memory:fixtures`
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        let (scheme, rest) = database_url.split_once(':')?;
+        match scheme {
+            "postgres" | "postgresql" => Some(PersistenceSettings::Postgres),
+            "sqlite" => Some(PersistenceSettings::Sqlite {
+                database_url: database_url.into(),
+            }),
+            "memory" => Some(PersistenceSettings::Memory {
+                fixtures_dir: Some(rest).filter(|dir| !dir.is_empty()).map(PathBuf::from),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Also the cargo feature building the backend in
+    pub fn name(&self) -> &'static str {
+        match self {
+            PersistenceSettings::Postgres => "postgres",
+            PersistenceSettings::Sqlite { .. } => "sqlite",
+            PersistenceSettings::Memory { .. } => "memory",
+        }
+    }
+}
+
+/// How logins are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuthMode {
+    /// Against the password hashes of the user store
+    #[default]
+    Passwords,
+    /// Any password is accepted, for local development only
+    Dev,
+}
+
+/// A user created on startup unless it exists, e.g. the first admin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapUser {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
+
+pub struct Settings {
+    pub persistence: PersistenceSettings,
+    pub db_name: String,
+    /// Postgres replicas serving the read-only use cases
+    #[cfg(feature = "postgres")]
+    pub replicas: ReplicaSettings,
+    /// Apply pending Postgres or SQLite migrations at startup
+    pub migrate_on_startup: bool,
+    pub cats_source: String,
+    /// Where mails are posted as JSON, they are only logged when unset
+    pub mailer_url: Option<String>,
+    pub password_reset: PasswordResetPolicy,
+    /// Keep a tenant's cat facts in step with `cats_source` in the background
+    pub cat_facts_sync: Option<SyncSettings>,
+    /// Serve random cat facts from `cats_source` when the catalogue can't
+    pub cat_facts_fallback: CatFactsFallback,
+    /// Base URL of a dogapi.dog compatible API, dog facts are only ever
+    /// stored locally when unset
+    pub dogs_source: Option<String>,
+    /// Keep a tenant's dog facts in step with `dogs_source` in the background
+    pub dog_facts_sync: Option<SyncSettings>,
+    pub oidc: Option<OidcSettings>,
+    /// Timeouts, retries and circuit breaking of requests to upstream services
+    pub http: HttpSettings,
+    /// Keep responses of `cats_source` as its caching headers allow
+    pub http_cache: Option<CacheSettings>,
+    /// Serve HTTPS, optionally with client certificates, instead of plain HTTP
+    pub tls: Option<TlsSettings>,
+    pub client_certificates: ClientCertificateSettings,
+    pub session_mode: SessionMode,
+    pub tenancy: TenantSettings,
+    /// Username to tenant id, binding users to one tenant's catalogues
+    pub user_tenants: HashMap<String, String>,
+    pub lockout_policy: LockoutPolicy,
+    pub auth_mode: AuthMode,
+    pub bootstrap_users: Vec<BootstrapUser>,
+    pub admin_users: Vec<String>,
+    /// Allowed to change facts, as admin users are too
+    pub editor_users: Vec<String>,
+    /// How long bearer tokens stay valid
+    pub token_ttl: time::Duration,
+    /// Reactions to the facts created and updated, each in the background
+    pub event_subscribers: Vec<Arc<dyn EventSubscriber>>,
+    /// How Postgres relays the events it keeps to `event_subscribers`
+    pub outbox: OutboxSettings,
+}
+
+impl Settings {
+    pub fn new(db_name: String, cats_source: String) -> Self {
+        Settings {
+            persistence: PersistenceSettings::default(),
+            db_name,
+            #[cfg(feature = "postgres")]
+            replicas: ReplicaSettings::default(),
+            migrate_on_startup: false,
+            cats_source,
+            mailer_url: None,
+            password_reset: PasswordResetPolicy {
+                token_ttl: Duration::minutes(30),
+                reset_url: String::from("http://localhost:8080/reset-password?token="),
+            },
+            cat_facts_sync: None,
+            cat_facts_fallback: CatFactsFallback::default(),
+            dogs_source: None,
+            dog_facts_sync: None,
+            oidc: None,
+            http: HttpSettings::default(),
+            http_cache: Some(CacheSettings::default()),
+            tls: None,
+            client_certificates: ClientCertificateSettings::default(),
+            session_mode: SessionMode::default(),
+            tenancy: TenantSettings::default(),
+            user_tenants: HashMap::new(),
+            lockout_policy: LockoutPolicy::default(),
+            auth_mode: AuthMode::default(),
+            bootstrap_users: vec![],
+            admin_users: vec![],
+            editor_users: vec![],
+            token_ttl: time::Duration::from_secs(60 * 60),
+            event_subscribers: vec![],
+            outbox: OutboxSettings::default(),
+        }
+    }
+
+    /// Read settings from the environment, once the `.env` file is loaded
+    pub fn from_env() -> Self {
+        let persistence = persistence_from_env();
+        let db_name = match persistence {
+            PersistenceSettings::Postgres => {
+                dotenv::var("DATABASE_NAME").expect("DATABASE_NAME must be set")
+            }
+            _ => String::new(),
+        };
+        let cats_source = dotenv::var("CATS_SOURCE").expect("CATS_SOURCE must be set");
+        let defaults = Settings::new(db_name, cats_source);
+
+        Settings {
+            mailer_url: dotenv::var("MAILER_URL").ok(),
+            password_reset: PasswordResetPolicy {
+                token_ttl: dotenv::var("PASSWORD_RESET_TTL_SECS")
+                    .map(|secs| {
+                        Duration::seconds(
+                            secs.parse()
+                                .expect("PASSWORD_RESET_TTL_SECS must be a number"),
+                        )
+                    })
+                    .unwrap_or(defaults.password_reset.token_ttl),
+                reset_url: dotenv::var("PASSWORD_RESET_URL")
+                    .unwrap_or(defaults.password_reset.reset_url.clone()),
+            },
+            persistence,
+            #[cfg(feature = "postgres")]
+            replicas: replicas_from_env(),
+            migrate_on_startup: dotenv::var("MIGRATE_ON_STARTUP")
+                .map(|migrate| migrate == "true")
+                .unwrap_or(defaults.migrate_on_startup),
+            cat_facts_sync: sync_from_env("CAT_FACTS_SYNC"),
+            cat_facts_fallback: cat_facts_fallback_from_env(),
+            dogs_source: dotenv::var("DOGS_SOURCE").ok(),
+            dog_facts_sync: sync_from_env("DOG_FACTS_SYNC"),
+            oidc: oidc_from_env(),
+            http: http_from_env(),
+            http_cache: http_cache_from_env(),
+            tls: tls_from_env(),
+            client_certificates: ClientCertificateSettings {
+                scopes: dotenv::var("CLIENT_CERT_SCOPES")
+                    .map(|map| {
+                        parse_pairs(&map)
+                            .into_iter()
+                            .map(|(name, scopes)| {
+                                (name, scopes.split_whitespace().map(String::from).collect())
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            session_mode: session_mode_from_env(),
+            tenancy: tenancy_from_env(),
+            user_tenants: dotenv::var("USER_TENANTS")
+                .map(|map| parse_pairs(&map))
+                .unwrap_or_default(),
+            lockout_policy: lockout_policy_from_env(),
+            outbox: outbox_from_env(),
+            auth_mode: match dotenv::var("AUTH_MODE").as_deref() {
+                Ok("passwords") | Err(_) => AuthMode::Passwords,
+                Ok("dev") => AuthMode::Dev,
+                Ok(mode) => panic!("AUTH_MODE must be passwords or dev, got {}", mode),
+            },
+            bootstrap_users: dotenv::var("BOOTSTRAP_USERS")
+                .map(|users| parse_bootstrap_users(&users))
+                .unwrap_or_default(),
+            admin_users: dotenv::var("ADMIN_USERS")
+                .map(|users| users.split(',').map(|u| u.trim().to_string()).collect())
+                .unwrap_or_default(),
+            editor_users: dotenv::var("EDITOR_USERS")
+                .map(|users| users.split(',').map(|u| u.trim().to_string()).collect())
+                .unwrap_or_default(),
+            token_ttl: dotenv::var("AUTH_TOKEN_TTL_SECS")
+                .map(|secs| {
+                    time::Duration::from_secs(
+                        secs.parse().expect("AUTH_TOKEN_TTL_SECS must be a number"),
+                    )
+                })
+                .unwrap_or(defaults.token_ttl),
+            ..defaults
+        }
+    }
+}
+
+/// The backend is chosen by the scheme of `DATABASE_URL`
+fn persistence_from_env() -> PersistenceSettings {
+    let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    PersistenceSettings::from_url(&database_url).unwrap_or_else(|| {
+        // the rest of the URL may hold credentials
+        let scheme = database_url.split(':').next().unwrap_or_default();
+        panic!(
+            "DATABASE_URL must start with postgres://, sqlite: or memory:, got {}:",
+            scheme
+        )
+    })
+}
+
+/// Reads stay on the primary unless `REPLICA_DATABASE_URLS` lists replicas
+#[cfg(feature = "postgres")]
+fn replicas_from_env() -> ReplicaSettings {
+    let defaults = ReplicaSettings::default();
+    let millis = |name: &str, default: time::Duration| {
+        dotenv::var(name)
+            .map(|value| {
+                time::Duration::from_millis(
+                    value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{} must be a number", name)),
+                )
+            })
+            .unwrap_or(default)
+    };
+
+    ReplicaSettings {
+        urls: dotenv::var("REPLICA_DATABASE_URLS")
+            .map(|urls| urls.split(',').map(|url| url.trim().to_string()).collect())
+            .unwrap_or_default(),
+        max_lag: millis("REPLICA_MAX_LAG_MS", defaults.max_lag),
+        retry_after: millis("REPLICA_RETRY_AFTER_MS", defaults.retry_after),
+        connect_timeout: millis("REPLICA_CONNECT_TIMEOUT_MS", defaults.connect_timeout),
+    }
+}
+
+/// Single sign-on is enabled by setting `OIDC_ISSUER_URL`
+fn oidc_from_env() -> Option<OidcSettings> {
+    let issuer_url = dotenv::var("OIDC_ISSUER_URL").ok()?;
+
+    Some(OidcSettings {
+        issuer_url,
+        client_id: dotenv::var("OIDC_CLIENT_ID").expect("OIDC_CLIENT_ID must be set"),
+        client_secret: dotenv::var("OIDC_CLIENT_SECRET").ok(),
+        redirect_url: dotenv::var("OIDC_REDIRECT_URL").expect("OIDC_REDIRECT_URL must be set"),
+        scopes: dotenv::var("OIDC_SCOPES")
+            .unwrap_or_else(|_| String::from("openid email profile"))
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+        roles_claim: dotenv::var("OIDC_ROLES_CLAIM").unwrap_or_else(|_| String::from("roles")),
+        role_map: dotenv::var("OIDC_ROLE_MAP")
+            .map(|map| parse_pairs(&map))
+            .unwrap_or_default(),
+        state_key: dotenv::var("OIDC_STATE_KEY").ok(),
+    })
+}
+
+fn http_from_env() -> HttpSettings {
+    let defaults = HttpSettings::default();
+    let number = |name: &str| {
+        dotenv::var(name).ok().map(|value| {
+            value
+                .parse::<u64>()
+                .unwrap_or_else(|_| panic!("{} must be a number", name))
+        })
+    };
+    let millis = |name: &str, default: time::Duration| {
+        number(name)
+            .map(time::Duration::from_millis)
+            .unwrap_or(default)
+    };
+
+    HttpSettings {
+        connect_timeout: millis("HTTP_CONNECT_TIMEOUT_MS", defaults.connect_timeout),
+        request_timeout: millis("HTTP_REQUEST_TIMEOUT_MS", defaults.request_timeout),
+        max_retries: number("HTTP_MAX_RETRIES")
+            .map(|retries| retries as u32)
+            .unwrap_or(defaults.max_retries),
+        base_backoff: millis("HTTP_RETRY_BACKOFF_MS", defaults.base_backoff),
+        max_backoff: millis("HTTP_RETRY_MAX_BACKOFF_MS", defaults.max_backoff),
+        breaker_threshold: number("HTTP_BREAKER_THRESHOLD")
+            .map(|threshold| threshold as u32)
+            .unwrap_or(defaults.breaker_threshold),
+        breaker_open_duration: millis("HTTP_BREAKER_OPEN_MS", defaults.breaker_open_duration),
+        metrics_log_interval: match number("HTTP_METRICS_LOG_SECS") {
+            Some(0) => None,
+            Some(secs) => Some(time::Duration::from_secs(secs)),
+            None => defaults.metrics_log_interval,
+        },
+    }
+}
+
+/// Caching is disabled by setting `HTTP_CACHE_CAPACITY` to 0 without a
+/// `HTTP_CACHE_DIR`
+fn http_cache_from_env() -> Option<CacheSettings> {
+    let defaults = CacheSettings::default();
+    let capacity = dotenv::var("HTTP_CACHE_CAPACITY")
+        .map(|capacity| {
+            capacity
+                .parse()
+                .expect("HTTP_CACHE_CAPACITY must be a number")
+        })
+        .unwrap_or(defaults.capacity);
+    let disk_dir = dotenv::var("HTTP_CACHE_DIR").ok().map(PathBuf::from);
+    if capacity == 0 && disk_dir.is_none() {
+        return None;
+    }
+
+    Some(CacheSettings {
+        capacity,
+        disk_dir,
+        max_stale: dotenv::var("HTTP_CACHE_MAX_STALE_SECS")
+            .map(|secs| {
+                time::Duration::from_secs(
+                    secs.parse()
+                        .expect("HTTP_CACHE_MAX_STALE_SECS must be a number"),
+                )
+            })
+            .unwrap_or(defaults.max_stale),
+    })
+}
+
+/// Scheduled synchronisation is enabled by setting `<prefix>_INTERVAL_SECS`,
+/// e.g. `CAT_FACTS_SYNC_INTERVAL_SECS`
+fn sync_from_env(prefix: &str) -> Option<SyncSettings> {
+    let interval = format!("{}_INTERVAL_SECS", prefix);
+    let secs: u64 = dotenv::var(&interval)
+        .ok()?
+        .parse()
+        .unwrap_or_else(|_| panic!("{} must be a number", interval));
+    if secs == 0 {
+        return None;
+    }
+
+    Some(SyncSettings {
+        interval: time::Duration::from_secs(secs),
+        tenant: dotenv::var(format!("{}_TENANT", prefix))
+            .unwrap_or_else(|_| String::from("default")),
+    })
+}
+
+/// HTTPS is enabled by setting `TLS_CERT_FILE`, client certificates
+/// are asked for once `TLS_CLIENT_CA_FILE` is set too. The files are
+/// reloaded on SIGHUP, or when they change if `TLS_RELOAD_INTERVAL_SECS` is set
+fn tls_from_env() -> Option<TlsSettings> {
+    let cert_path = dotenv::var("TLS_CERT_FILE").ok()?;
+
+    Some(TlsSettings {
+        cert_path: PathBuf::from(cert_path),
+        key_path: dotenv::var("TLS_KEY_FILE")
+            .expect("TLS_KEY_FILE must be set")
+            .into(),
+        client_ca_path: dotenv::var("TLS_CLIENT_CA_FILE").ok().map(PathBuf::from),
+        client_crl_path: dotenv::var("TLS_CLIENT_CRL_FILE").ok().map(PathBuf::from),
+        require_client_cert: dotenv::var("TLS_REQUIRE_CLIENT_CERT")
+            .map(|require| require == "true")
+            .unwrap_or(false),
+        profile: match dotenv::var("TLS_PROFILE").as_deref() {
+            Ok("intermediate") | Err(_) => TlsProfile::Intermediate,
+            Ok("modern") => TlsProfile::Modern,
+            Ok(profile) => panic!(
+                "TLS_PROFILE must be intermediate or modern, got {}",
+                profile
+            ),
+        },
+        reload_interval: dotenv::var("TLS_RELOAD_INTERVAL_SECS")
+            .ok()
+            .map(|secs| {
+                secs.parse()
+                    .expect("TLS_RELOAD_INTERVAL_SECS must be a number")
+            })
+            .filter(|secs| *secs > 0)
+            .map(time::Duration::from_secs),
+    })
+}
+
+/// Random cat facts fall back on `CATS_SOURCE` with `CAT_FACTS_FALLBACK=upstream`,
+/// and are kept in the catalogue too with `CAT_FACTS_FALLBACK=write-through`
+fn cat_facts_fallback_from_env() -> CatFactsFallback {
+    match dotenv::var("CAT_FACTS_FALLBACK").as_deref() {
+        Ok("disabled") | Err(_) => CatFactsFallback::Disabled,
+        Ok("upstream") => CatFactsFallback::Upstream,
+        Ok("write-through") => CatFactsFallback::WriteThrough,
+        Ok(fallback) => panic!(
+            "CAT_FACTS_FALLBACK must be disabled, upstream or write-through, got {}",
+            fallback
+        ),
+    }
+}
+
+/// Cookie sessions are enabled with `SESSION_MODE=cookie`
+fn session_mode_from_env() -> SessionMode {
+    match dotenv::var("SESSION_MODE").as_deref() {
+        Ok("cookie") => {}
+        Ok("bearer") | Err(_) => return SessionMode::Bearer,
+        Ok(mode) => panic!("SESSION_MODE must be bearer or cookie, got {}", mode),
+    }
+
+    let defaults = CookieSessionSettings::default();
+    let seconds = |name: &str, default: Duration| {
+        dotenv::var(name)
+            .map(|secs| {
+                Duration::seconds(
+                    secs.parse()
+                        .unwrap_or_else(|_| panic!("{} must be a number", name)),
+                )
+            })
+            .unwrap_or(default)
+    };
+
+    SessionMode::Cookie(CookieSessionSettings {
+        secure: dotenv::var("SESSION_COOKIE_SECURE")
+            .map(|secure| secure != "false")
+            .unwrap_or(defaults.secure),
+        same_site: match dotenv::var("SESSION_COOKIE_SAME_SITE").as_deref() {
+            Ok("lax") => SameSite::Lax,
+            Ok("none") => SameSite::None,
+            _ => defaults.same_site,
+        },
+        policy: SessionPolicy {
+            idle_timeout: seconds("SESSION_IDLE_TIMEOUT_SECS", defaults.policy.idle_timeout),
+            absolute_timeout: seconds(
+                "SESSION_ABSOLUTE_TIMEOUT_SECS",
+                defaults.policy.absolute_timeout,
+            ),
+        },
+        ..defaults
+    })
+}
+
+/// Tenants come from `TENANT_HEADER`, sent by `TENANT_TRUSTED_PROXIES`,
+/// `TENANT_HOSTS` then `DEFAULT_TENANT`, an empty default tenant rejects
+/// requests naming none
+fn tenancy_from_env() -> TenantSettings {
+    let defaults = TenantSettings::default();
+
+    TenantSettings {
+        header_name: dotenv::var("TENANT_HEADER").unwrap_or(defaults.header_name),
+        trusted_proxies: dotenv::var("TENANT_TRUSTED_PROXIES")
+            .map(|proxies| {
+                proxies
+                    .split(',')
+                    .filter_map(|proxy| proxy.trim().parse().ok())
+                    .collect()
+            })
+            .unwrap_or(defaults.trusted_proxies),
+        hosts: dotenv::var("TENANT_HOSTS")
+            .map(|map| parse_pairs(&map))
+            .unwrap_or_default(),
+        default_tenant: match dotenv::var("DEFAULT_TENANT") {
+            Ok(tenant) if tenant.is_empty() => None,
+            Ok(tenant) => Some(tenant),
+            Err(_) => defaults.default_tenant,
+        },
+    }
+}
+
+fn lockout_policy_from_env() -> LockoutPolicy {
+    let defaults = LockoutPolicy::default();
+    let number = |name: &str| {
+        dotenv::var(name).ok().map(|n| {
+            n.parse::<u64>()
+                .unwrap_or_else(|_| panic!("{} must be a number", name))
+        })
+    };
+
+    LockoutPolicy {
+        max_failures_per_account: number("LOGIN_MAX_FAILURES")
+            .map_or(defaults.max_failures_per_account, |n| n as u32),
+        max_failures_per_ip: number("LOGIN_MAX_FAILURES_PER_IP")
+            .map_or(defaults.max_failures_per_ip, |n| n as u32),
+        base_backoff: number("LOGIN_BACKOFF_MS")
+            .map_or(defaults.base_backoff, time::Duration::from_millis),
+        lockout_duration: number("LOGIN_LOCKOUT_SECS")
+            .map_or(defaults.lockout_duration, time::Duration::from_secs),
+        max_tracked: number("LOGIN_MAX_TRACKED").map_or(defaults.max_tracked, |n| n as usize),
+    }
+}
+
+fn outbox_from_env() -> OutboxSettings {
+    let defaults = OutboxSettings::default();
+    let number = |name: &str| {
+        dotenv::var(name).ok().map(|n| {
+            n.parse::<u64>()
+                .unwrap_or_else(|_| panic!("{} must be a number", name))
+        })
+    };
+
+    OutboxSettings {
+        max_attempts: number("OUTBOX_MAX_ATTEMPTS").map_or(defaults.max_attempts, |n| n as i32),
+        retry_delay: number("OUTBOX_RETRY_DELAY_MS")
+            .map_or(defaults.retry_delay, time::Duration::from_millis),
+        retention: number("OUTBOX_RETENTION_SECS")
+            .map_or(defaults.retention, time::Duration::from_secs),
+        ..defaults
+    }
+}
+
+/// `username:email:password` triples, comma separated, the password
+/// taking everything after the second colon
+fn parse_bootstrap_users(users: &str) -> Vec<BootstrapUser> {
+    users
+        .split(',')
+        .filter(|user| !user.trim().is_empty())
+        .map(|user| {
+            let mut parts = user.trim().splitn(3, ':');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(username), Some(email), Some(password)) => BootstrapUser {
+                    username: username.into(),
+                    email: email.into(),
+                    password: password.into(),
+                },
+                _ => panic!("BOOTSTRAP_USERS must hold username:email:password triples"),
+            }
+        })
+        .collect()
+}
+
+/// `key=value` pairs, comma separated, e.g. `provider-role=local-role`
+fn parse_pairs(map: &str) -> HashMap<String, String> {
+    map.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(from, to)| (from.trim().to_string(), to.trim().to_string()))
+        .collect()
+}
@@ -0,0 +1,99 @@
+use std::{future::Future, time::Duration};
+
+use actix_web::web;
+use app_core::{
+    services::{CatRepo, DogRepo, Persistence, SyncRunRepo, Transaction},
+    usecases::{
+        sync_cat_facts::SyncCatFactsUseCase, sync_dog_facts::SyncDogFactsUseCase, UseCaseError,
+    },
+};
+use app_domain::entities::{SyncRunEntity, TenantEntity};
+use presenter_rest::RestAppState;
+
+pub struct SyncSettings {
+    /// Time between the end of a run and the start of the next one
+    pub interval: Duration,
+    /// Tenant whose catalogue is kept in step with upstream
+    pub tenant: String,
+}
+
+/// Synchronise the cat facts from upstream now, and then on every interval
+pub fn schedule_cat_facts_sync<P, CR, SR>(data: web::Data<RestAppState<P>>, settings: SyncSettings)
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    CR: CatRepo<P> + Send + Sync,
+    SR: SyncRunRepo<P> + Send + Sync,
+{
+    schedule("cat", settings, move |tenant| {
+        let data = data.clone();
+        async move {
+            let sync_cat_facts_usecase = SyncCatFactsUseCase::<_, CR, SR, _>::new(
+                data.persistence_service.clone(),
+                data.cat_facts_service.as_ref(),
+                data.events.as_ref(),
+            );
+            sync_cat_facts_usecase.execute(&tenant).await
+        }
+    });
+}
+
+/// Synchronise the dog facts from upstream now, and then on every interval,
+/// provided there is an upstream to synchronise from
+pub fn schedule_dog_facts_sync<P, DR, SR>(data: web::Data<RestAppState<P>>, settings: SyncSettings)
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    DR: DogRepo<P> + Send + Sync,
+    SR: SyncRunRepo<P> + Send + Sync,
+{
+    if data.dog_facts_service.is_none() {
+        log::warn!("Not synchronising dog facts, no source is configured");
+        return;
+    }
+
+    schedule("dog", settings, move |tenant| {
+        let data = data.clone();
+        async move {
+            let dog_facts_service = data
+                .dog_facts_service
+                .as_deref()
+                .expect("dog facts source checked on scheduling");
+            let sync_dog_facts_usecase = SyncDogFactsUseCase::<_, DR, SR, _>::new(
+                data.persistence_service.clone(),
+                dog_facts_service,
+                data.events.as_ref(),
+            );
+            sync_dog_facts_usecase.execute(&tenant).await
+        }
+    });
+}
+
+fn schedule<F, Fut>(species: &'static str, settings: SyncSettings, synchronise: F)
+where
+    F: Fn(TenantEntity) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<SyncRunEntity, UseCaseError>> + Send,
+{
+    tokio::spawn(async move {
+        loop {
+            let tenant = TenantEntity::new(settings.tenant.clone());
+            match synchronise(tenant).await {
+                Ok(run) => log::info!(
+                    "Synchronised {} facts of {}: {} inserted, {} unchanged, {} changed",
+                    species,
+                    run.tenant_id,
+                    run.inserted,
+                    run.unchanged,
+                    run.changed
+                ),
+                Err(e) => log::error!(
+                    "Can't synchronise {} facts of {}: {}",
+                    species,
+                    settings.tenant,
+                    e
+                ),
+            }
+            tokio::time::sleep(settings.interval).await;
+        }
+    });
+}
@@ -0,0 +1,221 @@
+use std::{
+    any::Any,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use actix_tls::accept::openssl::TlsStream;
+use actix_web::{dev::Extensions, rt::net::TcpStream};
+use openssl::{
+    nid::Nid,
+    ssl::{
+        self, AlpnError, SniError, SslAcceptor, SslAcceptorBuilder, SslContext, SslFiletype,
+        SslMethod, SslVerifyMode,
+    },
+    x509::{
+        store::{X509Lookup, X509StoreBuilder},
+        verify::X509VerifyFlags,
+        X509,
+    },
+};
+use presenter_rest::client_certs::PeerCertificate;
+
+/// Protocols offered through ALPN, by order of preference
+const ALPN_PROTOCOLS: &[u8] = b"\x02h2\x08http/1.1";
+
+/// Mozilla's server side TLS recommendations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TlsProfile {
+    /// TLS 1.2 and 1.3 with forward secret AEAD ciphers only
+    #[default]
+    Intermediate,
+    /// TLS 1.3 only, for deployments without legacy clients
+    Modern,
+}
+
+pub struct TlsSettings {
+    /// PEM server certificate, followed by its intermediates
+    pub cert_path: PathBuf,
+    /// PEM private key of the server certificate
+    pub key_path: PathBuf,
+    /// PEM CA certificates trusted to sign client certificates,
+    /// clients are not asked for any when unset
+    pub client_ca_path: Option<PathBuf>,
+    /// PEM revocation list of client certificates
+    pub client_crl_path: Option<PathBuf>,
+    /// Reject connections without a client certificate, instead of only
+    /// authenticating the ones that have one
+    pub require_client_cert: bool,
+    pub profile: TlsProfile,
+    /// How often the files are checked for changes, they are only reloaded
+    /// on SIGHUP when unset
+    pub reload_interval: Option<Duration>,
+}
+
+impl TlsSettings {
+    fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        [&self.cert_path, &self.key_path]
+            .into_iter()
+            .chain(&self.client_ca_path)
+            .chain(&self.client_crl_path)
+    }
+}
+
+/// Build the server side of TLS, verifying client certificates when a CA is set
+fn context_builder(settings: &TlsSettings) -> io::Result<SslAcceptorBuilder> {
+    let mut builder = match settings.profile {
+        TlsProfile::Intermediate => SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()),
+        TlsProfile::Modern => SslAcceptor::mozilla_modern_v5(SslMethod::tls_server()),
+    }?;
+    builder.set_private_key_file(&settings.key_path, SslFiletype::PEM)?;
+    builder.set_certificate_chain_file(&settings.cert_path)?;
+    builder.check_private_key()?;
+    // contexts swapped in on reload negotiate the protocol themselves
+    builder.set_alpn_select_callback(|_, client| {
+        ssl::select_next_proto(ALPN_PROTOCOLS, client).ok_or(AlpnError::NOACK)
+    });
+
+    let client_ca_path = match &settings.client_ca_path {
+        Some(path) => path,
+        None => return Ok(builder),
+    };
+
+    let mut store = X509StoreBuilder::new()?;
+    for ca in X509::stack_from_pem(&fs::read(client_ca_path)?)? {
+        builder.add_client_ca(&ca)?;
+        store.add_cert(ca)?;
+    }
+    if let Some(crl_path) = &settings.client_crl_path {
+        store
+            .add_lookup(X509Lookup::file())?
+            .load_crl_file(crl_path, SslFiletype::PEM)?;
+        store.set_flags(X509VerifyFlags::CRL_CHECK)?;
+    }
+    builder.set_verify_cert_store(store.build())?;
+
+    let mut mode = SslVerifyMode::PEER;
+    if settings.require_client_cert {
+        mode |= SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+    }
+    builder.set_verify(mode);
+
+    Ok(builder)
+}
+
+/// Certificates, CA and CRL as last loaded from disk
+///
+/// Every handshake switches to the current context from the server name
+/// callback, which OpenSSL runs whether or not the client sent SNI. A
+/// reload only affects new connections, established ones keep the
+/// context they started with.
+#[derive(Clone)]
+pub struct CertificateReloader {
+    settings: Arc<TlsSettings>,
+    context: Arc<RwLock<SslContext>>,
+}
+
+impl CertificateReloader {
+    pub fn new(settings: TlsSettings) -> io::Result<Self> {
+        let context = context_builder(&settings)?.build().into_context();
+        Ok(CertificateReloader {
+            settings: Arc::new(settings),
+            context: Arc::new(RwLock::new(context)),
+        })
+    }
+
+    /// The acceptor to listen with, always serving the current context
+    pub fn acceptor(&self) -> io::Result<SslAcceptorBuilder> {
+        let mut builder = context_builder(&self.settings)?;
+        let context = self.context.clone();
+        builder.set_servername_callback(move |ssl, _| {
+            ssl.set_ssl_context(&context.read().unwrap())
+                .map_err(|_| SniError::ALERT_FATAL)
+        });
+        Ok(builder)
+    }
+
+    /// Load the files again, the current context is kept when they are invalid
+    pub fn reload(&self) -> io::Result<()> {
+        let context = context_builder(&self.settings)?.build().into_context();
+        *self.context.write().unwrap() = context;
+        Ok(())
+    }
+
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        self.settings
+            .paths()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    fn reload_logged(&self, reason: &str) {
+        match self.reload() {
+            Ok(()) => log::info!("Reloaded TLS certificates on {}", reason),
+            Err(e) => log::error!("Can't reload TLS certificates on {}: {}", reason, e),
+        }
+    }
+
+    /// Reload on SIGHUP, and whenever one of the files changes if polling is enabled
+    pub fn watch(&self) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut hangups = signal(SignalKind::hangup())?;
+            let reloader = self.clone();
+            tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    reloader.reload_logged("SIGHUP");
+                }
+            });
+        }
+
+        if let Some(period) = self.settings.reload_interval {
+            let reloader = self.clone();
+            tokio::spawn(async move {
+                let mut last_modified = reloader.modified();
+                let mut interval = tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    let modified = reloader.modified();
+                    if modified != last_modified {
+                        // a half written pair fails to load, and is retried
+                        // once the other file changes too
+                        reloader.reload_logged("file change");
+                        last_modified = modified;
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Keep the verified client certificate of the connection for the presenter
+pub fn on_connect(connection: &dyn Any, extensions: &mut Extensions) {
+    let certificate = connection
+        .downcast_ref::<TlsStream<TcpStream>>()
+        .and_then(|stream| stream.ssl().peer_certificate());
+
+    if let Some(certificate) = certificate {
+        let common_name = certificate
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .next()
+            .and_then(|entry| entry.data().to_string().ok());
+        let serial_number = certificate
+            .serial_number()
+            .to_bn()
+            .and_then(|serial| serial.to_hex_str())
+            .map(|serial| serial.to_string())
+            .unwrap_or_default();
+
+        extensions.insert(PeerCertificate {
+            common_name,
+            serial_number,
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use sqlx::{PgConnection, Postgres, QueryBuilder};
+use sqlx::{PgConnection, Postgres, QueryBuilder, SqliteConnection};
 
 use crate::{
     integration_tests::fixtures::fixtures_struct::DogFactJson, utils::utils_file::read_from_file,
@@ -22,7 +22,8 @@ async fn import_dog_facts_fixtures(conn: &mut PgConnection) {
     });
 
     let query = query_builder.build();
-    query.execute(conn).await.expect("can't insert data");
+    query.execute(&mut *conn).await.expect("can't insert data");
+    reset_sequence(conn, "dog_facts").await;
 }
 
 async fn import_cat_facts_fixtures(conn: &mut PgConnection) {
@@ -38,5 +39,40 @@ async fn import_cat_facts_fixtures(conn: &mut PgConnection) {
     });
 
     let query = query_builder.build();
-    query.execute(conn).await.expect("can't insert data");
+    query.execute(&mut *conn).await.expect("can't insert data");
+    reset_sequence(conn, "cat_facts").await;
+}
+
+// fixtures come with their ids, rows inserted afterwards must not reuse them
+async fn reset_sequence(conn: &mut PgConnection, table: &str) {
+    sqlx::query(&format!(
+        "SELECT setval(pg_get_serial_sequence('{0}', 'id'), (SELECT MAX(id) FROM {0}))",
+        table
+    ))
+    .execute(conn)
+    .await
+    .expect("can't reset sequence");
+}
+
+/// The same fixtures, for the SQLite backend whose ids never go back
+pub async fn execute_sqlite_imports(conn: &mut SqliteConnection) {
+    for (table, path) in [
+        (
+            "dog_facts",
+            "tests/integration_tests/fixtures/dog_facts.json",
+        ),
+        (
+            "cat_facts",
+            "tests/integration_tests/fixtures/cat_facts.json",
+        ),
+    ] {
+        for fact in read_from_file::<Vec<DogFactJson>>(path).unwrap() {
+            sqlx::query(&format!("INSERT INTO {}(id, fact) VALUES (?1, ?2)", table))
+                .bind(fact.id)
+                .bind(fact.fact)
+                .execute(&mut *conn)
+                .await
+                .expect("can't insert data");
+        }
+    }
 }
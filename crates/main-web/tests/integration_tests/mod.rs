@@ -1,3 +1,25 @@
 pub mod fixtures;
+pub mod test_admin;
+pub mod test_backends;
 pub mod test_cat_facts;
+pub mod test_cat_facts_sync;
 pub mod test_dog_facts;
+pub mod test_dog_facts_sync;
+pub mod test_events;
+pub mod test_fact_versions;
+pub mod test_https;
+pub mod test_memory;
+pub mod test_migrations;
+pub mod test_mtls;
+pub mod test_outbox;
+pub mod test_passwords;
+pub mod test_replicas;
+pub mod test_sessions;
+pub mod test_sqlite;
+pub mod test_sso;
+pub mod test_tenants;
+pub mod test_tokens;
+pub mod test_transactions;
+pub mod test_upstream_cache;
+pub mod test_upstream_cassettes;
+pub mod test_upstream_cat_facts;
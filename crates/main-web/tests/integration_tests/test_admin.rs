@@ -0,0 +1,102 @@
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_session::{cookie_header, login, spawn_app_with_cookie_sessions},
+};
+use actix_web::cookie::Cookie;
+use chrono::Duration;
+use presenter_rest::admin::UserPresenter;
+use reqwest::{header, StatusCode};
+use serde_json::json;
+
+backend_tests!(
+    test_should_let_admin_unlock_account,
+    test_should_forbid_unlock_to_other_users,
+    test_should_only_let_admin_create_users,
+);
+
+async fn test_should_let_admin_unlock_account(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+
+    // given an admin session
+    let (session, csrf) = login(&api_address, "root").await;
+
+    // when unlocking an account
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/accounts/jane/unlock", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .header("X-CSRF-Token", csrf.value())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect success, with both the login and the unlock audited
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let events: Vec<(String, String)> = db
+        .fetch_all("SELECT kind, username FROM auth_events ORDER BY id")
+        .await;
+    assert_eq!(
+        events,
+        vec![
+            ("login_succeeded".into(), "root".into()),
+            ("account_unlocked".into(), "jane".into()),
+        ]
+    );
+}
+
+async fn test_should_forbid_unlock_to_other_users(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+
+    // given a regular user session
+    let (session, csrf) = login(&api_address, "jane").await;
+
+    // when unlocking an account
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/accounts/jane/unlock", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .header("X-CSRF-Token", csrf.value())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect forbidden
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+async fn test_should_only_let_admin_create_users(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+    let create_jane = |session: Cookie<'static>, csrf: Cookie<'static>, email: &'static str| {
+        let api_address = api_address.clone();
+        async move {
+            reqwest::Client::new()
+                .post(format!("{}/api/v1/admin/users", api_address))
+                .header(header::COOKIE, cookie_header(&session, &csrf))
+                .header("X-CSRF-Token", csrf.value())
+                .json(&json!({"username": "jane", "email": email, "password": "old secret"}))
+                .send()
+                .await
+                .expect("Failed to execute request.")
+        }
+    };
+
+    // given a regular user session, creating a user is forbidden
+    let (session, csrf) = login(&api_address, "john").await;
+    let response = create_jane(session, csrf, "jane@example.com").await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // when the admin creates them
+    let (session, csrf) = login(&api_address, "root").await;
+    let response = create_jane(session.clone(), csrf.clone(), "Jane@example.com").await;
+
+    // then expect them created, never twice
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let user = response.json::<UserPresenter>().await.unwrap();
+    assert_eq!(user.username, "jane");
+    assert_eq!(user.email, "jane@example.com");
+
+    let response = create_jane(session, csrf, "jane@example.com").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -1,12 +1,22 @@
-use crate::utils::utils_setup::{setup, spawn_app};
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_catfacts::{spawn_cat_facts_upstream, upstream_cat_facts},
+};
+use app_core::services::CatFactsFallback;
 use presenter_rest::cat_facts::CatFactPresenter;
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use reqwest::StatusCode;
 
-#[sqlx::test(migrations = "../service-db/migrations")]
-async fn test_should_return_multiple_results(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+backend_tests!(
+    test_should_return_multiple_results,
+    test_should_return_one_results_only,
+    test_should_fall_back_to_upstream_when_empty,
+    test_should_write_through_upstream_fact,
+    test_should_fail_when_empty_without_fallback,
+);
+
+async fn test_should_return_multiple_results(db: TestDatabase) {
     // setup
-    setup(&connopts).await;
-    let api_address = spawn_app(&connopts).await;
+    let api_address = db.spawn_app().await;
 
     // given the "all cat facts" route
 
@@ -25,14 +35,12 @@ async fn test_should_return_multiple_results(_opts: PgPoolOptions, connopts: PgC
         content_json[0].fact,
         "The first true cats came into existence about 12 million years ago and were the Proailurus."
     );
-    assert_eq!(content_json[0].id, 1);
+    assert_eq!(content_json[0].id, Some(1));
 }
 
-#[sqlx::test(migrations = "../service-db/migrations")]
-async fn test_should_return_one_results_only(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+async fn test_should_return_one_results_only(db: TestDatabase) {
     // setup
-    setup(&connopts).await;
-    let api_address = spawn_app(&connopts).await;
+    let api_address = db.spawn_app().await;
 
     // given the "random cat fact" route
     // when getting
@@ -46,5 +54,72 @@ async fn test_should_return_one_results_only(_opts: PgPoolOptions, connopts: PgC
     let content_json = response.json::<CatFactPresenter>().await.unwrap();
 
     assert_eq!(content_json.fact, "The first true cats came into existence about 12 million years ago and were the Proailurus.");
-    assert_eq!(content_json.id, 1);
+    assert_eq!(content_json.id, Some(1));
+}
+
+async fn spawn_app_with_empty_catalogue(db: &TestDatabase, fallback: CatFactsFallback) -> String {
+    db.execute("DELETE FROM cat_facts").await;
+
+    let cats_source = spawn_cat_facts_upstream();
+    db.spawn_app_with(|settings| {
+        settings.cats_source = cats_source;
+        settings.cat_facts_fallback = fallback;
+    })
+    .await
+}
+
+async fn get_random(api_address: &str) -> reqwest::Response {
+    reqwest::get(&format!("{}/api/v1/cats/random", api_address))
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn test_should_fall_back_to_upstream_when_empty(db: TestDatabase) {
+    // given an empty catalogue, falling back to upstream
+    let api_address = spawn_app_with_empty_catalogue(&db, CatFactsFallback::Upstream).await;
+
+    // when getting a random fact
+    let response = get_random(&api_address).await;
+
+    // then expect an upstream fact, not kept in the catalogue
+    assert!(response.status().is_success());
+    let content_json = response.json::<CatFactPresenter>().await.unwrap();
+    assert!(upstream_cat_facts().contains(&content_json.fact));
+    assert_eq!(content_json.id, None);
+    let response = reqwest::get(&format!("{}/api/v1/cats/", &api_address))
+        .await
+        .expect("Failed to execute request.");
+    assert!(response
+        .json::<Vec<CatFactPresenter>>()
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn test_should_write_through_upstream_fact(db: TestDatabase) {
+    // given an empty catalogue, writing upstream facts through
+    let api_address = spawn_app_with_empty_catalogue(&db, CatFactsFallback::WriteThrough).await;
+
+    // when getting a random fact twice
+    let first = get_random(&api_address).await;
+    let second = get_random(&api_address).await;
+
+    // then the upstream fact is kept, with an id never handed out before,
+    // and served from the catalogue after
+    let first = first.json::<CatFactPresenter>().await.unwrap();
+    let second = second.json::<CatFactPresenter>().await.unwrap();
+    assert!(upstream_cat_facts().contains(&first.fact));
+    assert!(first.id.unwrap() > 10);
+    assert_eq!((second.id, second.fact), (first.id, first.fact));
+}
+
+async fn test_should_fail_when_empty_without_fallback(db: TestDatabase) {
+    // given an empty catalogue, without fallback
+    let api_address = spawn_app_with_empty_catalogue(&db, CatFactsFallback::Disabled).await;
+
+    // when getting a random fact
+    let response = get_random(&api_address).await;
+
+    // then expect an error
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
 }
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_catfacts::{spawn_cat_facts_upstream, upstream_cat_facts},
+};
+use main_web::{Settings, SyncSettings};
+use presenter_rest::{admin::SyncRunPresenter, sessions::TokenPresenter};
+use reqwest::StatusCode;
+use serde_json::json;
+
+backend_tests!(
+    test_should_sync_upstream_facts_on_demand,
+    test_should_flag_facts_changed_upstream,
+    test_should_record_failed_sync,
+    test_should_forbid_sync_to_other_users,
+    test_should_sync_on_schedule,
+);
+
+async fn spawn_app_syncing_from(
+    db: &TestDatabase,
+    cats_source: String,
+    configure: impl FnOnce(&mut Settings),
+) -> String {
+    db.spawn_app_with(|settings| {
+        settings.cats_source = cats_source;
+        settings.admin_users = vec!["root".into()];
+        configure(settings);
+    })
+    .await
+}
+
+async fn login(api_address: &str, username: &str) -> String {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": username, "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token
+}
+
+async fn sync(api_address: &str, token: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/cats/sync", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn test_should_sync_upstream_facts_on_demand(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, spawn_cat_facts_upstream(), |_| {}).await;
+    let token = login(&api_address, "root").await;
+
+    // given a first sync storing every upstream fact
+    let first = sync(&api_address, &token).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let first = first.json::<SyncRunPresenter>().await.unwrap();
+    assert_eq!(first.inserted, upstream_cat_facts().len() as i32);
+    assert!(first.finished_at.is_some());
+
+    // when syncing again
+    let second = sync(&api_address, &token)
+        .await
+        .json::<SyncRunPresenter>()
+        .await
+        .unwrap();
+
+    // then nothing new is stored, and both runs are recorded
+    assert_eq!(second.inserted, 0);
+    assert_eq!(second.unchanged, upstream_cat_facts().len() as i32);
+
+    let runs = reqwest::Client::new()
+        .get(format!("{}/api/v1/admin/sync-runs", api_address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<SyncRunPresenter>>()
+        .await
+        .unwrap();
+    let ids: Vec<i64> = runs.iter().map(|run| run.id).collect();
+    assert_eq!(ids, vec![second.id, first.id]);
+    assert_eq!(runs[0].tenant, "default");
+}
+
+async fn test_should_flag_facts_changed_upstream(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, spawn_cat_facts_upstream(), |_| {}).await;
+    let token = login(&api_address, "root").await;
+    sync(&api_address, &token).await;
+
+    // given a synchronised fact edited locally
+    db.execute("UPDATE cat_facts SET fact = 'Edited' WHERE upstream_index = 1")
+        .await;
+
+    // when syncing again
+    let run = sync(&api_address, &token)
+        .await
+        .json::<SyncRunPresenter>()
+        .await
+        .unwrap();
+
+    // then the edit is kept, and flagged for review
+    assert_eq!(run.changed, 1);
+    let (fact, flagged): (String, bool) = db
+        .fetch_one("SELECT fact, upstream_changed FROM cat_facts WHERE upstream_index = 1")
+        .await;
+    assert_eq!(fact, "Edited");
+    assert!(flagged);
+}
+
+async fn test_should_record_failed_sync(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, "http://127.0.0.1:1".into(), |_| {}).await;
+    let token = login(&api_address, "root").await;
+
+    // given an unreachable upstream
+    // when syncing
+    let response = sync(&api_address, &token).await;
+
+    // then expect bad gateway, and the failure recorded
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let (error,): (Option<String>,) = db.fetch_one("SELECT error FROM sync_runs").await;
+    assert!(error.unwrap().starts_with("Upstream error"));
+}
+
+async fn test_should_forbid_sync_to_other_users(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, spawn_cat_facts_upstream(), |_| {}).await;
+
+    // given a regular user
+    let token = login(&api_address, "jane").await;
+
+    // when syncing
+    let response = sync(&api_address, &token).await;
+
+    // then expect forbidden
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+async fn test_should_sync_on_schedule(db: TestDatabase) {
+    // given a sync scheduled for the default tenant
+    spawn_app_syncing_from(&db, spawn_cat_facts_upstream(), |settings| {
+        settings.cat_facts_sync = Some(SyncSettings {
+            interval: Duration::from_secs(3600),
+            tenant: "default".into(),
+        });
+    })
+    .await;
+
+    // when the app starts
+    let mut inserted = None;
+    for _ in 0..50 {
+        inserted = db
+            .fetch_all::<(i32,)>("SELECT inserted FROM sync_runs WHERE finished_at IS NOT NULL")
+            .await
+            .first()
+            .map(|(inserted,)| *inserted);
+        if inserted.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    // then a run is done straight away
+    assert_eq!(inserted, Some(upstream_cat_facts().len() as i32));
+}
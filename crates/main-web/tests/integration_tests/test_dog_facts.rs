@@ -1,12 +1,14 @@
-use crate::utils::utils_setup::{setup, spawn_app};
+use crate::utils::utils_backend::{backend_tests, TestDatabase};
 use presenter_rest::dog_facts::DogFactPresenter;
-use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
-#[sqlx::test(migrations = "../service-db/migrations")]
-async fn test_should_return_multiple_results(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+backend_tests!(
+    test_should_return_multiple_results,
+    test_should_return_one_results_only,
+);
+
+async fn test_should_return_multiple_results(db: TestDatabase) {
     // setup
-    setup(&connopts).await;
-    let api_address = spawn_app(&connopts).await;
+    let api_address = db.spawn_app().await;
 
     // given the "all dog facts" route
 
@@ -28,11 +30,9 @@ async fn test_should_return_multiple_results(_opts: PgPoolOptions, connopts: PgC
     assert_eq!(content_json[0].fact_id, 1);
 }
 
-#[sqlx::test(migrations = "../service-db/migrations")]
-async fn test_should_return_one_results_only(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+async fn test_should_return_one_results_only(db: TestDatabase) {
     // setup
-    setup(&connopts).await;
-    let api_address = spawn_app(&connopts).await;
+    let api_address = db.spawn_app().await;
 
     // given the "single dog facts" route
     let dog_fact_id: i8 = 2;
@@ -0,0 +1,80 @@
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_dogfacts::{spawn_dog_facts_upstream, upstream_dog_facts},
+};
+use presenter_rest::{admin::SyncRunPresenter, sessions::TokenPresenter};
+use reqwest::StatusCode;
+use serde_json::json;
+
+backend_tests!(
+    test_should_sync_upstream_dog_facts,
+    test_should_refuse_dog_sync_without_source,
+);
+
+async fn spawn_app_syncing_from(db: &TestDatabase, dogs_source: Option<String>) -> String {
+    db.spawn_app_with(|settings| {
+        settings.dogs_source = dogs_source;
+        settings.admin_users = vec!["root".into()];
+    })
+    .await
+}
+
+async fn sync(api_address: &str) -> reqwest::Response {
+    let client = reqwest::Client::new();
+    let token = client
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "root", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    client
+        .post(format!("{}/api/v1/admin/dogs/sync", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn test_should_sync_upstream_dog_facts(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, Some(spawn_dog_facts_upstream())).await;
+
+    // given a first sync storing every upstream fact
+    let first = sync(&api_address).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let first = first.json::<SyncRunPresenter>().await.unwrap();
+    assert_eq!(first.source, "dog_facts");
+    assert_eq!(first.inserted, upstream_dog_facts().len() as i32);
+
+    // when syncing again
+    let second = sync(&api_address)
+        .await
+        .json::<SyncRunPresenter>()
+        .await
+        .unwrap();
+
+    // then nothing new is stored
+    assert_eq!(second.inserted, 0);
+    assert_eq!(second.unchanged, upstream_dog_facts().len() as i32);
+    let (synchronised,): (i64,) = db
+        .fetch_one("SELECT COUNT(*) FROM dog_facts WHERE upstream_index IS NOT NULL")
+        .await;
+    assert_eq!(synchronised, upstream_dog_facts().len() as i64);
+}
+
+async fn test_should_refuse_dog_sync_without_source(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_syncing_from(&db, None).await;
+
+    // given no dog facts source
+    // when syncing
+    let response = sync(&api_address).await;
+
+    // then expect bad request
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
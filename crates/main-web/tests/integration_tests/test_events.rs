@@ -0,0 +1,124 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use app_core::services::{EventError, EventSubscriber};
+use app_domain::events::{DomainEvent, Species};
+use async_trait::async_trait;
+use presenter_rest::sessions::TokenPresenter;
+use reqwest::{header, StatusCode};
+use serde_json::json;
+
+use crate::utils::utils_setup::spawn_app_in_memory;
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<DomainEvent>>,
+}
+
+#[async_trait]
+impl EventSubscriber for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    async fn handle(&self, event: &DomainEvent) -> Result<(), EventError> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+struct Failing;
+
+#[async_trait]
+impl EventSubscriber for Failing {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    async fn handle(&self, _event: &DomainEvent) -> Result<(), EventError> {
+        Err(EventError("webhook unreachable".into()))
+    }
+}
+
+struct Panicking;
+
+#[async_trait]
+impl EventSubscriber for Panicking {
+    fn name(&self) -> &str {
+        "panicking"
+    }
+
+    async fn handle(&self, _event: &DomainEvent) -> Result<(), EventError> {
+        panic!("search index corrupted")
+    }
+}
+
+impl Recorder {
+    /// The events handled so far, waiting a little for `count` of them
+    async fn recorded(&self, count: usize) -> Vec<DomainEvent> {
+        for _ in 0..50 {
+            if self.events.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_should_tell_subscribers_of_updates_despite_failing_ones() {
+    // given a subscriber recording events, next to one failing and one
+    // panicking on every event
+    let recorder = Arc::new(Recorder::default());
+    let subscribers: Vec<Arc<dyn EventSubscriber>> =
+        vec![Arc::new(Failing), Arc::new(Panicking), recorder.clone()];
+    let api_address = spawn_app_in_memory(|settings| settings.event_subscribers = subscribers);
+    let client = reqwest::Client::new();
+    let token = client
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "jane", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when updating a fact twice
+    for (version, txt) in [(1, "Dogs dream"), (2, "Dogs snore")] {
+        let response = client
+            .put(format!("{}/api/v1/dogs/2", api_address))
+            .bearer_auth(&token)
+            .header(header::IF_MATCH, format!("\"{}\"", version))
+            .json(&json!({ "txt": txt }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // then both updates are told, in order
+    let events = recorder.recorded(2).await;
+    let updates = events
+        .iter()
+        .map(|event| match event {
+            DomainEvent::FactUpdated {
+                fact,
+                text,
+                version,
+            } => (fact.species, fact.fact_id, text.as_str(), *version),
+            _ => panic!("unexpected {:?}", event),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        updates,
+        vec![
+            (Species::Dog, 2, "Dogs dream", 2),
+            (Species::Dog, 2, "Dogs snore", 3)
+        ]
+    );
+}
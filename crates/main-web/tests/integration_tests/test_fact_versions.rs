@@ -0,0 +1,255 @@
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_setup::spawn_app_in_memory,
+};
+use presenter_rest::{
+    cat_facts::CatFactPresenter, dog_facts::DogFactPresenter, sessions::TokenPresenter,
+    PresenterError,
+};
+use reqwest::{header, StatusCode};
+use serde_json::json;
+
+backend_tests!(
+    test_should_update_dog_fact_from_current_version,
+    test_should_refuse_update_from_stale_version,
+    test_should_require_version_on_update,
+    test_should_update_cat_fact_from_current_version,
+    test_should_forbid_updates_to_other_users_than_editors,
+    test_should_not_find_missing_fact_on_update,
+);
+
+/// Logs jane in, an editor
+async fn login(api_address: &str) -> String {
+    login_as(api_address, "jane").await
+}
+
+async fn login_as(api_address: &str, username: &str) -> String {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": username, "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token
+}
+
+async fn update_dog(
+    api_address: &str,
+    token: &str,
+    if_match: Option<&str>,
+    payload: serde_json::Value,
+) -> reqwest::Response {
+    let mut request = reqwest::Client::new()
+        .put(format!("{}/api/v1/dogs/2", api_address))
+        .bearer_auth(token)
+        .json(&payload);
+    if let Some(if_match) = if_match {
+        request = request.header(header::IF_MATCH, if_match);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn test_should_update_dog_fact_from_current_version(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login(&api_address).await;
+
+    // given a fact read at its first version
+    let response = reqwest::get(format!("{}/api/v1/dogs/2", api_address))
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.headers()[header::ETAG], "\"1\"");
+    assert_eq!(
+        response.json::<DogFactPresenter>().await.unwrap().version,
+        1
+    );
+
+    // when updating it from that version
+    let response = update_dog(
+        &api_address,
+        &token,
+        Some("\"1\""),
+        json!({"txt": "Dogs dream"}),
+    )
+    .await;
+
+    // then it is stored at the next one
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ETAG], "\"2\"");
+    let fact = response.json::<DogFactPresenter>().await.unwrap();
+    assert_eq!((fact.txt.as_str(), fact.version), ("Dogs dream", 2));
+}
+
+async fn test_should_refuse_update_from_stale_version(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login(&api_address).await;
+
+    // given two editors having read a fact at its first version, the
+    // first one updating it
+    let first = update_dog(
+        &api_address,
+        &token,
+        None,
+        json!({"txt": "Dogs dream", "version": 1}),
+    )
+    .await;
+    assert_eq!(first.status(), StatusCode::OK);
+
+    // when the second one updates it too
+    let second = update_dog(
+        &api_address,
+        &token,
+        Some("\"1\""),
+        json!({"txt": "Dogs snore"}),
+    )
+    .await;
+
+    // then conflict, with the version to start over from
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+    assert_eq!(second.headers()[header::ETAG], "\"2\"");
+    let error = second.json::<PresenterError>().await.unwrap();
+    assert_eq!(error.current_version, Some(2));
+    let fact = reqwest::get(format!("{}/api/v1/dogs/2", api_address))
+        .await
+        .expect("Failed to execute request.")
+        .json::<DogFactPresenter>()
+        .await
+        .unwrap();
+    assert_eq!(fact.txt, "Dogs dream");
+}
+
+async fn test_should_require_version_on_update(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login(&api_address).await;
+
+    // given an update saying nothing of the version it was made from
+
+    // when sending it
+    let response = update_dog(&api_address, &token, None, json!({"txt": "Dogs dream"})).await;
+
+    // then expect precondition required
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+}
+
+async fn test_should_update_cat_fact_from_current_version(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login(&api_address).await;
+    let client = reqwest::Client::new();
+    let update = |version: i32| {
+        client
+            .put(format!("{}/api/v1/cats/1", api_address))
+            .bearer_auth(&token)
+            .json(&json!({"fact": "Cats purr", "version": version}))
+            .send()
+    };
+
+    // given a cat fact updated once
+    let first = update(1).await.expect("Failed to execute request.");
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(first.json::<CatFactPresenter>().await.unwrap().version, 2);
+
+    // when updating it again from its first version
+    let second = update(1).await.expect("Failed to execute request.");
+
+    // then conflict
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+}
+
+async fn test_should_forbid_updates_to_other_users_than_editors(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login_as(&api_address, "john").await;
+
+    // given a user who is no editor
+
+    // when updating a cat fact and a dog fact
+    let cat = reqwest::Client::new()
+        .put(format!("{}/api/v1/cats/1", api_address))
+        .bearer_auth(&token)
+        .json(&json!({"fact": "Cats purr", "version": 1}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let dog = update_dog(
+        &api_address,
+        &token,
+        Some("\"1\""),
+        json!({"txt": "Dogs dream"}),
+    )
+    .await;
+
+    // then expect forbidden, the facts left as they were
+    assert_eq!(cat.status(), StatusCode::FORBIDDEN);
+    assert_eq!(dog.status(), StatusCode::FORBIDDEN);
+    let fact = reqwest::get(format!("{}/api/v1/dogs/2", api_address))
+        .await
+        .expect("Failed to execute request.")
+        .json::<DogFactPresenter>()
+        .await
+        .unwrap();
+    assert_eq!(fact.version, 1);
+}
+
+async fn test_should_not_find_missing_fact_on_update(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+    let token = login(&api_address).await;
+    let client = reqwest::Client::new();
+
+    // given facts that don't exist
+
+    // when updating them
+    let cat = client
+        .put(format!("{}/api/v1/cats/999", api_address))
+        .bearer_auth(&token)
+        .json(&json!({"fact": "Cats purr", "version": 1}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let dog = client
+        .put(format!("{}/api/v1/dogs/999", api_address))
+        .bearer_auth(&token)
+        .json(&json!({"txt": "Dogs dream", "version": 1}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect not found
+    assert_eq!(cat.status(), StatusCode::NOT_FOUND);
+    assert_eq!(dog.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_should_refuse_update_from_stale_version_in_memory() {
+    // given the app kept in memory, a fact having been updated once
+    let api_address = spawn_app_in_memory(|_| {});
+    let token = login(&api_address).await;
+    let first = update_dog(
+        &api_address,
+        &token,
+        Some("\"1\""),
+        json!({"txt": "Dogs dream"}),
+    )
+    .await;
+    assert_eq!(first.status(), StatusCode::OK);
+
+    // when updating it again from its first version
+    let second = update_dog(
+        &api_address,
+        &token,
+        Some("\"1\""),
+        json!({"txt": "Dogs snore"}),
+    )
+    .await;
+
+    // then conflict
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+    let error = second.json::<PresenterError>().await.unwrap();
+    assert_eq!(error.current_version, Some(2));
+}
@@ -0,0 +1,91 @@
+use std::{
+    io::{Read, Write},
+    time::{Duration, Instant},
+};
+
+use crate::utils::{
+    utils_setup::{setup, spawn_app_with},
+    utils_tls::{server_serial, TestPki},
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+const H2_AND_HTTP1: &[u8] = b"\x02h2\x08http/1.1";
+const HTTP1: &[u8] = b"\x08http/1.1";
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_negotiate_http2(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+    // setup
+    setup(&connopts).await;
+    let pki = TestPki::generate();
+    let api_address = spawn_app_with(&connopts, |settings| {
+        settings.tls = Some(pki.settings(false));
+    })
+    .await;
+
+    // given a client offering both HTTP/2 and HTTP/1.1
+    // when connecting
+    let protocol = tokio::task::spawn_blocking(move || {
+        let stream = pki.connect(&api_address, H2_AND_HTTP1);
+        stream.ssl().selected_alpn_protocol().map(<[u8]>::to_vec)
+    })
+    .await
+    .unwrap();
+
+    // then HTTP/2 is picked
+    assert_eq!(protocol.as_deref(), Some(&b"h2"[..]));
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_reload_renewed_certificate(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+    // setup
+    setup(&connopts).await;
+    let mut pki = TestPki::generate();
+    let api_address = spawn_app_with(&connopts, |settings| {
+        let mut tls = pki.settings(false);
+        tls.reload_interval = Some(Duration::from_millis(50));
+        settings.tls = Some(tls);
+    })
+    .await;
+
+    let (old_serial, new_serial, reloaded, reloaded_h2, kept_alive) =
+        tokio::task::spawn_blocking(move || {
+            // given a connection established with the initial certificate
+            let mut established = pki.connect(&api_address, HTTP1);
+            let old_serial = server_serial(&established);
+
+            // when the certificate is renewed on disk
+            let new_serial = pki.renew_server_certificate();
+
+            // then new connections get it once the change is noticed
+            let deadline = Instant::now() + Duration::from_secs(5);
+            let mut reloaded = pki.connect(&api_address, H2_AND_HTTP1);
+            while server_serial(&reloaded) != new_serial && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(50));
+                reloaded = pki.connect(&api_address, H2_AND_HTTP1);
+            }
+
+            // and the established connection keeps being served
+            established
+                .write_all(
+                    b"GET /api/v1/dogs/ HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                )
+                .unwrap();
+            let mut response = String::new();
+            let _ = established.read_to_string(&mut response);
+
+            (
+                old_serial,
+                new_serial,
+                server_serial(&reloaded),
+                reloaded.ssl().selected_alpn_protocol() == Some(&b"h2"[..]),
+                response,
+            )
+        })
+        .await
+        .unwrap();
+
+    assert_ne!(old_serial, new_serial);
+    assert_eq!(reloaded, new_serial);
+    assert!(reloaded_h2);
+    assert!(kept_alive.starts_with("HTTP/1.1 "), "{}", kept_alive);
+}
@@ -0,0 +1,137 @@
+use std::net::Ipv4Addr;
+
+use crate::utils::{
+    utils_dogfacts::{spawn_dog_facts_upstream, upstream_dog_facts},
+    utils_setup::spawn_app_in_memory,
+};
+use app_core::{
+    services::{CatRepo, DogRepo, Persistence},
+    usecases::{unit_of_work::UnitOfWork, UseCaseError},
+};
+use app_domain::entities::TenantEntity;
+use presenter_rest::{
+    admin::SyncRunPresenter, dog_facts::DogFactPresenter, sessions::TokenPresenter,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use service_memory::memory_service::{CatRepoMemory, DogRepoMemory, PersistenceMemory};
+
+async fn get_dogs(api_address: &str, tenant: &str) -> Vec<DogFactPresenter> {
+    reqwest::Client::new()
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .header("X-Tenant-ID", tenant)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<Vec<DogFactPresenter>>()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_should_serve_fixtures_without_database() {
+    // given the app kept in memory, started with the fixtures
+    let api_address = spawn_app_in_memory(|_| {});
+
+    // when getting all dog facts
+    let facts = get_dogs(&api_address, "default").await;
+
+    // then expect the 3 facts of the fixtures
+    assert_eq!(facts.len(), 3);
+    assert_eq!(
+        facts[0].txt,
+        "Forty-five percent of U.S. dogs sleep in their owner's bed"
+    );
+    assert_eq!(facts[0].fact_id, 1);
+}
+
+#[tokio::test]
+async fn test_should_keep_tenants_apart_in_memory() {
+    // given the fixtures, which belong to the default tenant
+    let api_address = spawn_app_in_memory(|settings| {
+        settings.tenancy.trusted_proxies = vec![Ipv4Addr::LOCALHOST.into()];
+    });
+
+    // when getting all dog facts of another tenant
+    let facts = get_dogs(&api_address, "acme").await;
+
+    // then expect none
+    assert!(facts.is_empty());
+}
+
+#[tokio::test]
+async fn test_should_sync_dog_facts_in_memory() {
+    // given the app kept in memory, with a dog facts source
+    let dogs_source = spawn_dog_facts_upstream();
+    let api_address = spawn_app_in_memory(|settings| {
+        settings.dogs_source = Some(dogs_source);
+        settings.admin_users = vec!["root".into()];
+    });
+    let client = reqwest::Client::new();
+    let token = client
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "root", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when syncing
+    let response = client
+        .post(format!("{}/api/v1/admin/dogs/sync", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then the upstream facts are listed after the fixtures
+    assert_eq!(response.status(), StatusCode::OK);
+    let run = response.json::<SyncRunPresenter>().await.unwrap();
+    assert_eq!(run.inserted, upstream_dog_facts().len() as i32);
+    let facts = get_dogs(&api_address, "default").await;
+    assert_eq!(facts.len(), 3 + upstream_dog_facts().len());
+    assert_eq!(facts[3].fact_id, 4);
+    assert_eq!(facts[3].txt, upstream_dog_facts()[0]);
+}
+
+#[tokio::test]
+async fn test_should_undo_failed_nested_part_in_memory() {
+    // given an empty store
+    let persistence = PersistenceMemory::new();
+    let tenant = TenantEntity::new("default".into());
+    let tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+
+    // when a unit of work stores a dog fact, then fails to store a cat fact
+    let done = UnitOfWork::new(tx)
+        .run(|uow| {
+            Box::pin(async move {
+                DogRepoMemory::insert_upstream_dog_fact(uow.tx(), 1, "dog1".into()).await?;
+                let nested = uow
+                    .nested(|uow| {
+                        Box::pin(async move {
+                            CatRepoMemory::insert_upstream_cat_fact(uow.tx(), 1, "cat1".into())
+                                .await?;
+                            Err::<(), _>(UseCaseError::Business("cat fact refused".into()))
+                        })
+                    })
+                    .await;
+                Ok(nested.is_err())
+            })
+        })
+        .await;
+
+    // then only the dog fact is stored
+    assert!(done.unwrap());
+    let mut tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+    let dog = DogRepoMemory::get_upstream_dog_fact(&mut tx, 1)
+        .await
+        .unwrap();
+    let cat = CatRepoMemory::get_upstream_cat_fact(&mut tx, 1)
+        .await
+        .unwrap();
+    assert_eq!(dog.unwrap().fact, "dog1");
+    assert!(cat.is_none());
+}
@@ -0,0 +1,158 @@
+use std::net::TcpListener;
+
+use crate::utils::utils_setup::spawn_app_with;
+use app_core::services::SchemaMigrations;
+use main_web::{PersistenceSettings, Settings};
+use presenter_rest::{admin::MigrationPresenter, sessions::TokenPresenter};
+use reqwest::StatusCode;
+use serde_json::json;
+use service_db::db_service::{PersistencePG, SchemaMigrationsPG};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions,
+};
+
+/// The `.up.sql` files of `service-db`
+fn shipped_migrations() -> usize {
+    std::fs::read_dir("../service-db/migrations")
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".up.sql")
+        })
+        .count()
+}
+
+async fn persistence(connopts: &PgConnectOptions) -> PersistencePG {
+    PersistencePG::new(connopts.get_database().unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_should_fail_startup_on_missing_database() {
+    // given a PostgreSQL database that doesn't exist
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut settings = Settings::new(
+        format!("missing_{}", uuid::Uuid::new_v4().simple()),
+        "http://127.0.0.1:3333".into(),
+    );
+    settings.persistence = PersistenceSettings::Postgres;
+
+    // when starting the app
+    let result = main_web::setup(listener, settings).await;
+
+    // then expect an error rather than a panic
+    assert!(result.is_err());
+}
+
+#[sqlx::test(migrations = false)]
+async fn test_should_migrate_empty_database_on_startup(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // given an empty database, migrated on startup
+    let api_address = spawn_app_with(&connopts, |settings| {
+        settings.migrate_on_startup = true;
+        settings.admin_users = vec!["root".into()];
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let token = client
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "root", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when getting the migrations status
+    let response = client
+        .get(format!("{}/api/v1/admin/migrations", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then every shipped migration is applied, in order
+    assert_eq!(response.status(), StatusCode::OK);
+    let migrations = response.json::<Vec<MigrationPresenter>>().await.unwrap();
+    assert_eq!(migrations.len(), shipped_migrations());
+    assert!(migrations.iter().all(|m| m.applied));
+    assert!(migrations.windows(2).all(|w| w[0].version < w[1].version));
+    assert_eq!(migrations[0].description, "init");
+}
+
+#[sqlx::test(migrations = false)]
+async fn test_should_report_pending_migrations(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+    // given an empty database, never migrated
+    let schema_migrations = SchemaMigrationsPG::new(persistence(&connopts).await);
+
+    // when getting the migrations
+    let migrations = schema_migrations.get_migrations().await.unwrap();
+
+    // then they are all pending
+    assert_eq!(migrations.len(), shipped_migrations());
+    assert!(migrations.iter().all(|m| !m.applied));
+}
+
+#[sqlx::test(migrations = false)]
+async fn test_should_let_one_instance_migrate_at_a_time(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // given two instances starting on the same empty database
+    let first = persistence(&connopts).await;
+    let second = persistence(&connopts).await;
+
+    // when both migrate at once
+    let (first_migrated, second_migrated) = tokio::join!(first.migrate(), second.migrate());
+
+    // then neither trips over the other, and each migration is applied once
+    first_migrated.unwrap();
+    second_migrated.unwrap();
+    let mut conn = connopts.connect().await.unwrap();
+    let (applied,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM _sqlx_migrations")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(applied as usize, shipped_migrations());
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_forbid_migrations_status_to_other_users(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // given a regular user
+    let api_address = spawn_app_with(&connopts, |_| {}).await;
+    let client = reqwest::Client::new();
+    let token = client
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "jane", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when getting the migrations status
+    let response = client
+        .get(format!("{}/api/v1/admin/migrations", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect forbidden
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::utils::{
+    utils_setup::{setup, spawn_app_with},
+    utils_tls::TestPki,
+};
+use reqwest::StatusCode;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+async fn spawn_app_with_mtls(
+    connopts: &PgConnectOptions,
+    pki: &TestPki,
+    require_client_cert: bool,
+) -> String {
+    spawn_app_with(connopts, |settings| {
+        settings.tls = Some(pki.settings(require_client_cert));
+        settings.client_certificates.scopes =
+            HashMap::from([("partner-a".into(), vec!["admin".into()])]);
+    })
+    .await
+}
+
+async fn unlock(client: &reqwest::Client, api_address: &str) -> reqwest::Result<StatusCode> {
+    client
+        .post(format!("{}/api/v1/admin/accounts/jane/unlock", api_address))
+        .send()
+        .await
+        .map(|response| response.status())
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_map_client_certificate_to_principal(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let mut pki = TestPki::generate();
+    let partner = pki.issue("partner-a");
+    let api_address = spawn_app_with_mtls(&connopts, &pki, false).await;
+
+    // given a partner granted the admin scope, and an anonymous client
+    // when both call an admin route
+    let with_certificate = unlock(&pki.client(Some(&partner)), &api_address).await;
+    let without_certificate = unlock(&pki.client(None), &api_address).await;
+
+    // then only the partner is authenticated
+    assert_eq!(with_certificate.unwrap(), StatusCode::NO_CONTENT);
+    assert_eq!(without_certificate.unwrap(), StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_reject_unknown_client_certificate(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let mut pki = TestPki::generate();
+    let stranger = pki.issue("partner-z");
+    let api_address = spawn_app_with_mtls(&connopts, &pki, false).await;
+
+    // given a certificate of the trusted CA without any scopes
+    // when calling a public route
+    let response = pki
+        .client(Some(&stranger))
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .send()
+        .await
+        .unwrap();
+
+    // then expect forbidden
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_refuse_revoked_client_certificate(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let mut pki = TestPki::generate();
+    let revoked = pki.issue_revoked("partner-a");
+    let api_address = spawn_app_with_mtls(&connopts, &pki, false).await;
+
+    // given a partner certificate on the CRL
+    // when calling with it
+    let result = unlock(&pki.client(Some(&revoked)), &api_address).await;
+
+    // then the TLS handshake fails
+    assert!(result.is_err());
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_require_client_certificate_when_configured(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let pki = TestPki::generate();
+    let api_address = spawn_app_with_mtls(&connopts, &pki, true).await;
+
+    // given client certificates being mandatory
+    // when calling without one
+    let result = pki
+        .client(None)
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .send()
+        .await;
+
+    // then the TLS handshake fails
+    assert!(result.is_err());
+}
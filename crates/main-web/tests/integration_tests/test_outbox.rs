@@ -0,0 +1,309 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::utils::utils_setup::{setup, spawn_app_with};
+use app_core::services::{EventError, EventSubscriber};
+use app_domain::events::DomainEvent;
+use async_trait::async_trait;
+use main_web::Settings;
+use presenter_rest::sessions::TokenPresenter;
+use reqwest::{header, StatusCode};
+use serde_json::json;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions,
+};
+
+/// Records the events it handles, after failing the first `failures` ones
+struct Recorder {
+    name: &'static str,
+    failures: Mutex<usize>,
+    events: Mutex<Vec<DomainEvent>>,
+}
+
+impl Recorder {
+    fn new(name: &'static str, failures: usize) -> Arc<Self> {
+        Arc::new(Recorder {
+            name,
+            failures: Mutex::new(failures),
+            events: Mutex::new(vec![]),
+        })
+    }
+
+    /// The versions of the updates handled so far, waiting a little for
+    /// `count` of them
+    async fn updates(&self, count: usize) -> Vec<i32> {
+        for _ in 0..100 {
+            if self.events.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| match event {
+                DomainEvent::FactUpdated { version, .. } => *version,
+                _ => panic!("unexpected {:?}", event),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for Recorder {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn handle(&self, event: &DomainEvent) -> Result<(), EventError> {
+        {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(EventError("webhook unreachable".into()));
+            }
+        }
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+/// Relaying, and retrying, without waiting long
+fn relay_quickly(settings: &mut Settings) {
+    settings.outbox.interval = Duration::from_millis(20);
+    settings.outbox.retry_delay = Duration::from_millis(20);
+}
+
+async fn login(api_address: &str) -> String {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "jane", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token
+}
+
+async fn update_dog(api_address: &str, token: &str, version: i32, txt: &str) -> StatusCode {
+    reqwest::Client::new()
+        .put(format!("{}/api/v1/dogs/2", api_address))
+        .bearer_auth(token)
+        .header(header::IF_MATCH, format!("\"{}\"", version))
+        .json(&json!({ "txt": txt }))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+/// Status and attempts of the events kept, oldest first, once none is
+/// pending or after a while
+async fn outbox(connopts: &PgConnectOptions) -> Vec<(String, i32, Option<String>)> {
+    let mut conn = connopts.connect().await.unwrap();
+    for _ in 0..100 {
+        let pending: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM outbox WHERE status = 'pending'")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        if pending == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    sqlx::query_as("SELECT status, attempts, last_error FROM outbox ORDER BY id")
+        .fetch_all(&mut conn)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_keep_events_with_their_changes_and_relay_them(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let recorder = Recorder::new("recorder", 0);
+    let subscribers: Vec<Arc<dyn EventSubscriber>> = vec![recorder.clone()];
+    let api_address = spawn_app_with(&connopts, |settings| {
+        settings.event_subscribers = subscribers;
+        relay_quickly(settings);
+    })
+    .await;
+    let token = login(&api_address).await;
+
+    // given a fact updated, and an update refused
+    assert_eq!(
+        update_dog(&api_address, &token, 1, "Dogs dream").await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        update_dog(&api_address, &token, 1, "Dogs snore").await,
+        StatusCode::CONFLICT
+    );
+
+    // when the relay gets to the outbox
+    let kept = outbox(&connopts).await;
+
+    // then only the update made is kept, and delivered
+    assert_eq!(kept, vec![("delivered".into(), 1, None)]);
+    assert_eq!(recorder.updates(1).await, vec![2]);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_deliver_events_of_a_fact_in_order_despite_retries(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let flaky = Recorder::new("flaky", 1);
+    let recorder = Recorder::new("recorder", 0);
+    let subscribers: Vec<Arc<dyn EventSubscriber>> = vec![flaky.clone(), recorder.clone()];
+    let api_address = spawn_app_with(&connopts, |settings| {
+        settings.event_subscribers = subscribers;
+        relay_quickly(settings);
+    })
+    .await;
+    let token = login(&api_address).await;
+
+    // given a subscriber failing the first update of a fact
+    assert_eq!(
+        update_dog(&api_address, &token, 1, "Dogs dream").await,
+        StatusCode::OK
+    );
+
+    // when the fact is updated again
+    assert_eq!(
+        update_dog(&api_address, &token, 2, "Dogs snore").await,
+        StatusCode::OK
+    );
+
+    // then the first update is retried before the second is delivered, the
+    // subscribers having handled it not being told again
+    assert_eq!(flaky.updates(2).await, vec![2, 3]);
+    assert_eq!(recorder.updates(2).await, vec![2, 3]);
+    let kept = outbox(&connopts).await;
+    assert_eq!(
+        kept,
+        vec![("delivered".into(), 2, None), ("delivered".into(), 1, None)]
+    );
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_dead_letter_event_after_max_attempts(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let failing = Recorder::new("failing", usize::MAX);
+    let subscribers: Vec<Arc<dyn EventSubscriber>> = vec![failing];
+    let api_address = spawn_app_with(&connopts, |settings| {
+        settings.event_subscribers = subscribers;
+        settings.outbox.max_attempts = 3;
+        relay_quickly(settings);
+    })
+    .await;
+    let token = login(&api_address).await;
+
+    // given a subscriber failing every event
+
+    // when a fact is updated
+    assert_eq!(
+        update_dog(&api_address, &token, 1, "Dogs dream").await,
+        StatusCode::OK
+    );
+
+    // then the event is given up on, with the error it last got
+    let kept = outbox(&connopts).await;
+    assert_eq!(
+        kept,
+        vec![(
+            "dead".into(),
+            3,
+            Some("failing: Event error: webhook unreachable".into())
+        )]
+    );
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_deliver_again_events_claimed_by_a_stopped_relay(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+
+    // given an event claimed by a relay which stopped before delivering it
+    let mut conn = connopts.connect().await.unwrap();
+    sqlx::query(
+        "INSERT INTO outbox (event_type, species, tenant_id, fact_id, text, version, attempts, claimed_until) VALUES ('fact_updated', 'dog', 'default', 2, 'Dogs dream', 2, 1, now() - interval '1 second')",
+    )
+    .execute(&mut conn)
+    .await
+    .unwrap();
+
+    // when the app starts again
+    let recorder = Recorder::new("recorder", 0);
+    let subscribers: Vec<Arc<dyn EventSubscriber>> = vec![recorder.clone()];
+    spawn_app_with(&connopts, |settings| {
+        settings.event_subscribers = subscribers;
+        relay_quickly(settings);
+    })
+    .await;
+
+    // then the event is delivered
+    assert_eq!(recorder.updates(1).await, vec![2]);
+    assert_eq!(outbox(&connopts).await, vec![("delivered".into(), 2, None)]);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_purge_events_delivered_past_retention(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+
+    // given events delivered two days and a minute ago, and one given up on
+    let mut conn = connopts.connect().await.unwrap();
+    sqlx::query(
+        "INSERT INTO outbox (event_type, species, tenant_id, fact_id, text, version, status, attempts, delivered_at, last_error) VALUES
+            ('fact_updated', 'dog', 'default', 2, 'Dogs dream', 2, 'delivered', 1, now() - interval '2 days', NULL),
+            ('fact_updated', 'dog', 'default', 2, 'Dogs snore', 3, 'dead', 3, NULL, 'failing'),
+            ('fact_updated', 'dog', 'default', 2, 'Dogs nap', 4, 'delivered', 1, now() - interval '1 minute', NULL)",
+    )
+    .execute(&mut conn)
+    .await
+    .unwrap();
+
+    // when the app starts, keeping delivered events for a day
+    spawn_app_with(&connopts, |settings| {
+        settings.outbox.retention = Duration::from_secs(24 * 60 * 60);
+        relay_quickly(settings);
+    })
+    .await;
+
+    // then only the event delivered past that day is forgotten
+    let mut kept = vec![];
+    for _ in 0..100 {
+        kept = sqlx::query_scalar::<_, String>("SELECT text FROM outbox ORDER BY id")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        if kept.len() < 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(kept, vec!["Dogs snore", "Dogs nap"]);
+}
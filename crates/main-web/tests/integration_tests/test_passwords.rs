@@ -0,0 +1,269 @@
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_mail::{reset_token, spawn_mail_api, wait_for_mail, Outbox},
+};
+use main_web::{AuthMode, BootstrapUser, Settings};
+use presenter_rest::{admin::UserPresenter, sessions::TokenPresenter};
+use reqwest::StatusCode;
+use serde_json::json;
+
+backend_tests!(
+    test_should_not_tell_whether_email_exists,
+    test_should_reset_password_once_with_mailed_token,
+    test_should_refuse_expired_reset_token,
+    test_should_change_password_given_current_one,
+    test_should_refuse_anonymous_password_change,
+);
+
+/// Passwords checked against the user store, where root and john exist
+fn with_users(settings: &mut Settings) {
+    settings.auth_mode = AuthMode::Passwords;
+    settings.bootstrap_users = ["root", "john"]
+        .map(|username| BootstrapUser {
+            username: username.into(),
+            email: format!("{}@example.com", username),
+            password: format!("{} secret", username),
+        })
+        .to_vec();
+    settings.admin_users = vec!["root".into()];
+}
+
+async fn spawn_app_with_mailer(db: &TestDatabase) -> (String, Outbox) {
+    let (mailer_url, outbox) = spawn_mail_api();
+    let api_address = db
+        .spawn_app_with(|settings| {
+            with_users(settings);
+            settings.mailer_url = Some(mailer_url);
+            settings.password_reset.reset_url = String::from("https://example.com/reset?token=");
+        })
+        .await;
+    (api_address, outbox)
+}
+
+async fn login(api_address: &str, username: &str, password: &str) -> TokenPresenter {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": username, "password": password}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    response.json::<TokenPresenter>().await.unwrap()
+}
+
+async fn create_jane(api_address: &str) {
+    let admin = login(api_address, "root", "root secret").await;
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/users", api_address))
+        .bearer_auth(&admin.token)
+        .json(&json!({"username": "jane", "email": "Jane@example.com", "password": "old secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let user = response.json::<UserPresenter>().await.unwrap();
+    assert_eq!(user.email, "jane@example.com");
+}
+
+async fn post(api_address: &str, path: &str, body: serde_json::Value) -> StatusCode {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/{}", api_address, path))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+async fn change_password(api_address: &str, token: &str, current: &str, new: &str) -> StatusCode {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/password/change", api_address))
+        .bearer_auth(token)
+        .json(&json!({"current_password": current, "new_password": new}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+async fn token_status(api_address: &str, token: &str) -> StatusCode {
+    reqwest::Client::new()
+        .get(format!("{}/api/v1/cats", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+async fn test_should_not_tell_whether_email_exists(db: TestDatabase) {
+    // setup
+    let (api_address, outbox) = spawn_app_with_mailer(&db).await;
+    create_jane(&api_address).await;
+
+    // when asking for resets of an unknown and a known email
+    let unknown = post(
+        &api_address,
+        "password/forgot",
+        json!({"email": "nobody@example.com"}),
+    )
+    .await;
+    let known = post(
+        &api_address,
+        "password/forgot",
+        json!({"email": "JANE@example.com"}),
+    )
+    .await;
+
+    // then both are answered alike
+    assert_eq!(unknown, StatusCode::ACCEPTED);
+    assert_eq!(known, StatusCode::ACCEPTED);
+
+    // and only the known one is mailed
+    wait_for_mail(&outbox, "jane@example.com").await;
+    let mails = outbox.lock().unwrap().clone();
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].to, "jane@example.com");
+}
+
+async fn test_should_reset_password_once_with_mailed_token(db: TestDatabase) {
+    // setup
+    let (api_address, outbox) = spawn_app_with_mailer(&db).await;
+    create_jane(&api_address).await;
+    let before = login(&api_address, "jane", "old secret").await;
+
+    // given a reset token mailed
+    let status = post(
+        &api_address,
+        "password/forgot",
+        json!({"email": "jane@example.com"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let token = reset_token(&outbox, "jane@example.com").await;
+
+    // when resetting the password with it
+    let reset = json!({"token": token, "password": "brand new secret"});
+    let status = post(&api_address, "password/reset", reset.clone()).await;
+
+    // then the password is replaced
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let after = login(&api_address, "jane", "brand new secret").await;
+    assert_eq!(
+        change_password(
+            &api_address,
+            &after.token,
+            "brand new secret",
+            "newer secret"
+        )
+        .await,
+        StatusCode::NO_CONTENT
+    );
+
+    // and the tokens of the old password are revoked
+    assert_eq!(
+        token_status(&api_address, &before.token).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // and the reset token can't be used again
+    assert_eq!(
+        post(&api_address, "password/reset", reset).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // and the old password no longer opens
+    assert_eq!(
+        post(
+            &api_address,
+            "login",
+            json!({"username": "jane", "password": "old secret"})
+        )
+        .await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+async fn test_should_refuse_expired_reset_token(db: TestDatabase) {
+    // setup
+    let (mailer_url, outbox) = spawn_mail_api();
+    let api_address = db
+        .spawn_app_with(|settings| {
+            with_users(settings);
+            settings.mailer_url = Some(mailer_url);
+            settings.password_reset.token_ttl = chrono::Duration::zero();
+        })
+        .await;
+    create_jane(&api_address).await;
+
+    // given a reset token mailed, expiring straight away
+    post(
+        &api_address,
+        "password/forgot",
+        json!({"email": "jane@example.com"}),
+    )
+    .await;
+    let token = reset_token(&outbox, "jane@example.com").await;
+
+    // when resetting the password with it
+    let status = post(
+        &api_address,
+        "password/reset",
+        json!({"token": token, "password": "brand new secret"}),
+    )
+    .await;
+
+    // then it is refused
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+async fn test_should_change_password_given_current_one(db: TestDatabase) {
+    // setup
+    let (api_address, _outbox) = spawn_app_with_mailer(&db).await;
+    create_jane(&api_address).await;
+    let jane = login(&api_address, "jane", "old secret").await;
+
+    // when changing the password with a wrong current one, or a new one too short
+    // then it is refused
+    assert_eq!(
+        change_password(&api_address, &jane.token, "wrong secret", "new secret").await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        change_password(&api_address, &jane.token, "old secret", "short").await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // when changing it with the current one
+    let status = change_password(&api_address, &jane.token, "old secret", "new secret").await;
+
+    // then it is changed, revoking the tokens of the old one
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(
+        token_status(&api_address, &jane.token).await,
+        StatusCode::UNAUTHORIZED
+    );
+    let jane = login(&api_address, "jane", "new secret").await;
+    assert_eq!(
+        change_password(&api_address, &jane.token, "new secret", "newer secret").await,
+        StatusCode::NO_CONTENT
+    );
+}
+
+async fn test_should_refuse_anonymous_password_change(db: TestDatabase) {
+    // setup
+    let (api_address, _outbox) = spawn_app_with_mailer(&db).await;
+
+    // when changing a password without being logged in
+    let status = post(
+        &api_address,
+        "password/change",
+        json!({"current_password": "old secret", "new_password": "new secret"}),
+    )
+    .await;
+
+    // then unauthorized
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,149 @@
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_session::{cookie_header, login, spawn_app_with_cookie_sessions},
+};
+use actix_web::cookie::SameSite;
+use chrono::Duration;
+use presenter_rest::sessions::SessionPresenter;
+use reqwest::{header, StatusCode};
+use sha2::{Digest, Sha256};
+
+backend_tests!(
+    test_should_issue_secure_cookies_on_login,
+    test_should_require_csrf_token_on_mutating_routes,
+    test_should_store_only_hash_of_session_id,
+    test_should_expire_idle_sessions,
+    test_should_return_not_found_when_cookie_sessions_disabled,
+);
+
+async fn test_should_issue_secure_cookies_on_login(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+
+    // given the login route
+    // when logging in
+    let (session, csrf) = login(&api_address, "jane").await;
+
+    // then expect an HttpOnly session cookie and a CSRF cookie readable by scripts
+    assert_eq!(session.http_only(), Some(true));
+    assert_eq!(session.secure(), Some(true));
+    assert_eq!(session.same_site(), Some(SameSite::Strict));
+    assert_ne!(csrf.http_only(), Some(true));
+    assert_eq!(csrf.secure(), Some(true));
+
+    // and the session to authenticate following requests
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/auth/session", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let content_json = response.json::<SessionPresenter>().await.unwrap();
+
+    assert_eq!(content_json.username, "jane");
+    assert_eq!(content_json.csrf_token, csrf.value());
+}
+
+async fn test_should_require_csrf_token_on_mutating_routes(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+    let client = reqwest::Client::new();
+    let (session, csrf) = login(&api_address, "jane").await;
+
+    // given the logout route
+    // when posting with the cookies only
+    let response = client
+        .post(format!("{}/api/v1/auth/logout", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect forbidden
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // when echoing the CSRF cookie in the header
+    let response = client
+        .post(format!("{}/api/v1/auth/logout", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .header("X-CSRF-Token", csrf.value())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect the session to be gone
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client
+        .get(format!("{}/api/v1/auth/session", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn test_should_store_only_hash_of_session_id(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::minutes(30)).await;
+
+    // given a logged in user
+    let (session, csrf) = login(&api_address, "jane").await;
+
+    // when reading the sessions table
+    let stored: Vec<(String,)> = db.fetch_all("SELECT id_hash FROM sessions").await;
+
+    // then expect the SHA-256 of the cookie, never the cookie itself
+    let id_hash: String = Sha256::digest(session.value().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    assert_eq!(stored, vec![(id_hash,)]);
+
+    // and the cookie still opens the session
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/auth/session", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+}
+
+async fn test_should_expire_idle_sessions(db: TestDatabase) {
+    // setup
+    let api_address = spawn_app_with_cookie_sessions(&db, Duration::seconds(1)).await;
+
+    // given a session left idle past its timeout
+    let (session, csrf) = login(&api_address, "jane").await;
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+    // when getting
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/auth/session", api_address))
+        .header(header::COOKIE, cookie_header(&session, &csrf))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect unauthorized
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn test_should_return_not_found_when_cookie_sessions_disabled(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+
+    // given the default bearer mode
+    // when reading the session
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/auth/session", api_address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect not found
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
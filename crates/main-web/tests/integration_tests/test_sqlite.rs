@@ -0,0 +1,91 @@
+use std::net::TcpListener;
+
+use crate::utils::utils_setup::{setup_sqlite, spawn_app_on_sqlite};
+use app_core::services::{DogRepo, Persistence, Transaction, TransactionOptions};
+use app_domain::entities::TenantEntity;
+use main_web::{PersistenceSettings, Settings};
+use reqwest::StatusCode;
+use service_sqlite::db_service::{DogRepoSqlite, PersistenceSqlite};
+
+async fn get_dogs(api_address: &str, path: &str, tenant: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/api/v1/dogs/{}", api_address, path))
+        .header("X-Tenant-ID", tenant)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn test_should_only_migrate_sqlite_on_startup_when_asked() {
+    // given empty SQLite databases, one migrated on startup
+    let database_url = |name: &str| {
+        let path = std::env::temp_dir().join(format!("{}-{}.db", name, uuid::Uuid::new_v4()));
+        format!("sqlite:{}", path.display())
+    };
+    let migrated = spawn_app_on_sqlite(&database_url("migrated"), |settings| {
+        settings.migrate_on_startup = true;
+    });
+    let unmigrated = spawn_app_on_sqlite(&database_url("unmigrated"), |_| {});
+
+    // when listing their facts
+    let migrated = get_dogs(&migrated, "", "default").await;
+    let unmigrated = get_dogs(&unmigrated, "", "default").await;
+
+    // then only the migrated one has the tables
+    assert_eq!(migrated.status(), StatusCode::OK);
+    assert_eq!(unmigrated.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_should_fail_startup_on_unusable_sqlite_database() {
+    // given a SQLite database in a directory that doesn't exist
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut settings = Settings::new(String::new(), "http://127.0.0.1:3333".into());
+    settings.persistence =
+        PersistenceSettings::from_url("sqlite:/nonexistent/animal_facts.db").unwrap();
+
+    // when starting the app
+    let result = main_web::setup(listener, settings).await;
+
+    // then expect an error rather than a panic
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_should_hide_facts_from_transactions_of_no_tenant_in_sqlite() {
+    // given the fixtures of the default tenant
+    let database_url = setup_sqlite().await;
+    let persistence = PersistenceSqlite::new(&database_url).await.unwrap();
+
+    // when reading and writing facts outside of any tenant
+    let mut tx = persistence.get_transaction().await.unwrap();
+    let facts = DogRepoSqlite::get_all_dog_facts(&mut tx).await.unwrap();
+    let inserted = DogRepoSqlite::insert_upstream_dog_fact(&mut tx, 0, "Dogs nap".into()).await;
+
+    // then none is seen and none written, as with row level security
+    assert!(facts.is_empty());
+    assert!(inserted.is_err());
+}
+
+#[tokio::test]
+async fn test_should_refuse_writes_of_read_only_transactions_in_sqlite() {
+    // given the fixtures of the default tenant
+    let database_url = setup_sqlite().await;
+    let persistence = PersistenceSqlite::new(&database_url).await.unwrap();
+    let tenant = TenantEntity::new("default".into());
+
+    // when writing in a read-only transaction, then in the next one
+    let mut tx = persistence
+        .get_tenant_transaction_with(&tenant, TransactionOptions::read_only())
+        .await
+        .unwrap();
+    let read_only = DogRepoSqlite::insert_upstream_dog_fact(&mut tx, 0, "Dogs nap".into()).await;
+    tx.rollback().await.unwrap();
+    let mut tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+    let writable = DogRepoSqlite::insert_upstream_dog_fact(&mut tx, 0, "Dogs nap".into()).await;
+
+    // then only the read-only one fails, its connection is writable again
+    assert!(read_only.is_err());
+    assert!(writable.is_ok());
+}
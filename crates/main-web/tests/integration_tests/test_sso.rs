@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_oidc::{spawn_oidc_provider, CLIENT_ID},
+};
+use actix_web::cookie::Cookie;
+use presenter_rest::sessions::{
+    CookieSessionSettings, SessionMode, SessionPresenter, TokenPresenter,
+};
+use reqwest::{header, redirect::Policy, StatusCode};
+use service_auth::oidc_service::OidcSettings;
+
+backend_tests!(
+    test_should_issue_token_through_identity_provider,
+    test_should_open_cookie_session_through_identity_provider,
+    test_should_reject_replayed_callback,
+    test_should_reject_callback_in_another_browser,
+    test_should_reject_unknown_state,
+    test_should_return_not_found_when_sso_disabled,
+);
+
+const REDIRECT_URL: &str = "http://app.test/api/v1/auth/sso/callback";
+
+/// Returns the app address and the issuer of its identity provider
+async fn spawn_app_with_sso(db: &TestDatabase, session_mode: SessionMode) -> (String, String) {
+    let issuer_url = spawn_oidc_provider();
+
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.oidc = Some(OidcSettings {
+                issuer_url: issuer_url.clone(),
+                client_id: CLIENT_ID.into(),
+                client_secret: None,
+                redirect_url: REDIRECT_URL.into(),
+                scopes: vec!["openid".into(), "email".into()],
+                roles_claim: "groups".into(),
+                role_map: HashMap::from([("idp-admins".into(), "admin".into())]),
+                state_key: Some("test-state-key".into()),
+            });
+            settings.session_mode = session_mode;
+        })
+        .await;
+    (api_address, issuer_url)
+}
+
+fn no_redirects() -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap()
+}
+
+fn cookies(response: &reqwest::Response) -> Vec<Cookie<'static>> {
+    response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .map(|value| Cookie::parse(value.to_str().unwrap().to_string()).unwrap())
+        .collect()
+}
+
+fn find<'c>(cookies: &'c [Cookie<'static>], name: &str) -> &'c Cookie<'static> {
+    cookies
+        .iter()
+        .find(|cookie| cookie.name() == name)
+        .unwrap_or_else(|| panic!("no {} cookie", name))
+}
+
+/// Follows the redirects a browser would, up to the callback on our side,
+/// returns the callback url and the cookie the browser keeps meanwhile
+async fn login_at_provider(
+    client: &reqwest::Client,
+    api_address: &str,
+) -> (String, Cookie<'static>) {
+    let response = client
+        .get(format!("{}/api/v1/auth/sso/login", api_address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let pending = find(&cookies(&response), "sso_login").clone();
+    assert_eq!(pending.http_only(), Some(true));
+    let authorize_url = response.headers()[header::LOCATION].to_str().unwrap();
+
+    let response = client
+        .get(authorize_url)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let callback_url = response.headers()[header::LOCATION].to_str().unwrap();
+
+    let callback = url::Url::parse(callback_url).unwrap();
+    assert!(callback.as_str().starts_with(REDIRECT_URL));
+    let callback = format!(
+        "{}{}?{}",
+        api_address,
+        callback.path(),
+        callback.query().unwrap_or_default()
+    );
+    (callback, pending)
+}
+
+async fn come_back(
+    client: &reqwest::Client,
+    callback: &str,
+    pending: &Cookie<'_>,
+) -> reqwest::Response {
+    client
+        .get(callback)
+        .header(header::COOKIE, pending.stripped().to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn test_should_issue_token_through_identity_provider(db: TestDatabase) {
+    // setup
+    let (api_address, issuer) = spawn_app_with_sso(&db, SessionMode::Bearer).await;
+    let client = no_redirects();
+
+    // given a user authenticated at the identity provider
+    let (callback, pending) = login_at_provider(&client, &api_address).await;
+
+    // when coming back with the authorization code
+    let response = come_back(&client, &callback, &pending).await;
+
+    // then expect a token with provider groups mapped to local roles
+    assert!(response.status().is_success());
+    assert_eq!(find(&cookies(&response), "sso_login").value(), "");
+    let content_json = response.json::<TokenPresenter>().await.unwrap();
+    assert_eq!(content_json.username, format!("oidc:{}:jane", issuer));
+    assert_eq!(content_json.roles, vec!["admin"]);
+
+    // and the token opens the admin routes
+    let response = client
+        .get(format!("{}/api/v1/admin/migrations", &api_address))
+        .bearer_auth(&content_json.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+}
+
+async fn test_should_open_cookie_session_through_identity_provider(db: TestDatabase) {
+    // setup
+    let (api_address, issuer) =
+        spawn_app_with_sso(&db, SessionMode::Cookie(CookieSessionSettings::default())).await;
+    let client = no_redirects();
+
+    // given a user authenticated at the identity provider
+    let (callback, pending) = login_at_provider(&client, &api_address).await;
+
+    // when coming back with the authorization code
+    let response = come_back(&client, &callback, &pending).await;
+
+    // then expect a session like a password login would open
+    assert!(response.status().is_success());
+    let cookies = cookies(&response);
+    let session = find(&cookies, "session");
+    assert_eq!(session.http_only(), Some(true));
+
+    let response = client
+        .get(format!("{}/api/v1/auth/session", &api_address))
+        .header(header::COOKIE, session.stripped().to_string())
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let content_json = response.json::<SessionPresenter>().await.unwrap();
+    assert_eq!(content_json.username, format!("oidc:{}:jane", issuer));
+}
+
+async fn test_should_reject_replayed_callback(db: TestDatabase) {
+    // setup
+    let (api_address, _) = spawn_app_with_sso(&db, SessionMode::Bearer).await;
+    let client = no_redirects();
+
+    // given a login already completed once
+    let (callback, pending) = login_at_provider(&client, &api_address).await;
+    let response = come_back(&client, &callback, &pending).await;
+    assert!(response.status().is_success());
+
+    // when replaying the callback, pending login cookie included
+    let response = come_back(&client, &callback, &pending).await;
+
+    // then expect unauthorized
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn test_should_reject_callback_in_another_browser(db: TestDatabase) {
+    // setup
+    let (api_address, _) = spawn_app_with_sso(&db, SessionMode::Bearer).await;
+    let client = no_redirects();
+
+    // given a login started in the attacker's browser
+    let (callback, _pending) = login_at_provider(&client, &api_address).await;
+    let (_other_callback, other_pending) = login_at_provider(&client, &api_address).await;
+
+    // when the victim's browser, pending another login or none, is sent to its callback
+    let response = come_back(&client, &callback, &other_pending).await;
+    let without_cookie = client
+        .get(&callback)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect unauthorized
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(without_cookie.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn test_should_reject_unknown_state(db: TestDatabase) {
+    // setup
+    let (api_address, _) = spawn_app_with_sso(&db, SessionMode::Bearer).await;
+
+    // given a callback that was never initiated by us
+    // when getting
+    let response = reqwest::get(&format!(
+        "{}/api/v1/auth/sso/callback?code=forged&state=forged",
+        &api_address
+    ))
+    .await
+    .expect("Failed to execute request.");
+
+    // then expect unauthorized
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+async fn test_should_return_not_found_when_sso_disabled(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+
+    // given no identity provider configured
+    // when getting
+    let response = reqwest::get(&format!("{}/api/v1/auth/sso/login", &api_address))
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect not found
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,263 @@
+use std::{collections::HashMap, net::Ipv4Addr};
+
+use crate::utils::{
+    utils_backend::{backend_tests, TestDatabase},
+    utils_setup::setup,
+};
+use app_core::services::{Persistence, Transaction};
+use app_domain::entities::TenantEntity;
+use presenter_rest::{dog_facts::DogFactPresenter, sessions::TokenPresenter};
+use reqwest::StatusCode;
+use serde_json::json;
+use service_db::db_service::PersistencePG;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions,
+};
+
+backend_tests!(
+    test_should_only_list_facts_of_the_tenant,
+    test_should_not_find_facts_of_another_tenant_by_id,
+    test_should_resolve_tenant_from_host,
+    test_should_ignore_tenant_header_of_anonymous_requests,
+    test_should_ignore_forwarded_host_of_untrusted_clients,
+    test_should_honour_tenant_header_of_admins,
+    test_should_keep_token_bound_to_its_tenant,
+);
+
+/// Facts of another brand, next to the default tenant's fixtures
+const INSERT_ACME_FACT: &str =
+    "INSERT INTO dog_facts (id, fact, tenant_id) VALUES (101, 'Acme dogs fetch', 'acme')";
+
+/// The app behind a gateway naming the tenant of each request
+async fn spawn_behind_gateway(db: &TestDatabase) -> String {
+    db.spawn_app_with(|settings| {
+        settings.tenancy.trusted_proxies = vec![Ipv4Addr::LOCALHOST.into()];
+    })
+    .await
+}
+
+async fn get_dogs(api_address: &str, path: &str, tenant: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/api/v1/dogs/{}", api_address, path))
+        .header("X-Tenant-ID", tenant)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn test_should_only_list_facts_of_the_tenant(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = spawn_behind_gateway(&db).await;
+
+    // given facts of two tenants
+    // when listing them for each tenant
+    let acme = get_dogs(&api_address, "", "acme").await;
+    let default = get_dogs(&api_address, "", "default").await;
+
+    // then each only sees its own
+    let acme = acme.json::<Vec<DogFactPresenter>>().await.unwrap();
+    let default = default.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(
+        acme.iter().map(|f| f.fact_id).collect::<Vec<_>>(),
+        vec![101]
+    );
+    assert_eq!(default.len(), 3);
+    assert!(default.iter().all(|f| f.fact_id != 101));
+}
+
+async fn test_should_not_find_facts_of_another_tenant_by_id(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = spawn_behind_gateway(&db).await;
+
+    // given a fact of the default tenant
+    // when acme gets it by id
+    let response = get_dogs(&api_address, "1", "acme").await;
+
+    // then it does not exist for acme
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+async fn test_should_resolve_tenant_from_host(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.tenancy.hosts = HashMap::from([("127.0.0.1".into(), "acme".into())]);
+        })
+        .await;
+
+    // given the address of the app mapped to acme
+    // when listing without any header
+    let response = reqwest::get(format!("{}/api/v1/dogs/", api_address))
+        .await
+        .expect("Failed to execute request.");
+
+    // then acme's facts are returned
+    let facts = response.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].fact_id, 101);
+}
+
+async fn test_should_ignore_tenant_header_of_anonymous_requests(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.tenancy.hosts = HashMap::from([("127.0.0.1".into(), "acme".into())]);
+        })
+        .await;
+
+    // given the address of the app mapped to acme
+    // when an anonymous request names the default tenant
+    let response = get_dogs(&api_address, "", "default").await;
+
+    // then the host wins, acme's facts are returned
+    let facts = response.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].fact_id, 101);
+}
+
+async fn test_should_ignore_forwarded_host_of_untrusted_clients(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.tenancy.hosts = HashMap::from([
+                ("127.0.0.1".into(), "acme".into()),
+                ("default.example".into(), "default".into()),
+            ]);
+        })
+        .await;
+
+    // given the address of the app mapped to acme
+    // when a client not behind our proxies forwards another host
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .header("X-Forwarded-Host", "default.example")
+        .header("Forwarded", "host=default.example")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then the host it connected to wins, acme's facts are returned
+    let facts = response.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].fact_id, 101);
+}
+
+async fn test_should_honour_tenant_header_of_admins(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.admin_users = vec!["root".into()];
+        })
+        .await;
+
+    // given a token of an admin, bound to no tenant
+    let token = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "root", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when listing acme's facts
+    let response = reqwest::Client::new()
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .bearer_auth(&token)
+        .header("X-Tenant-ID", "acme")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then the header is honoured
+    let facts = response.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].fact_id, 101);
+}
+
+async fn test_should_keep_token_bound_to_its_tenant(db: TestDatabase) {
+    // setup
+    db.execute(INSERT_ACME_FACT).await;
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.user_tenants = HashMap::from([("jane".into(), "acme".into())]);
+        })
+        .await;
+
+    // given a token of an acme user
+    let token = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": "jane", "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json::<TokenPresenter>()
+        .await
+        .unwrap()
+        .token;
+
+    // when listing facts without and with another tenant's header
+    let client = reqwest::Client::new();
+    let own = client
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let other = client
+        .get(format!("{}/api/v1/dogs/", api_address))
+        .bearer_auth(&token)
+        .header("X-Tenant-ID", "default")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // then only acme's are allowed
+    let own = own.json::<Vec<DogFactPresenter>>().await.unwrap();
+    assert_eq!(own.len(), 1);
+    assert_eq!(other.status(), StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_enforce_isolation_in_the_database(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let mut conn = connopts.connect().await.unwrap();
+    sqlx::query(INSERT_ACME_FACT)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    let persistence = PersistencePG::new(connopts.get_database().unwrap())
+        .await
+        .unwrap();
+    let acme = TenantEntity::new("acme".into());
+
+    // given a transaction of acme, bypassing the repositories
+    let mut tx = persistence.get_tenant_transaction(&acme).await.unwrap();
+
+    // when reading every fact, then writing one for another tenant
+    let visible: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT tenant_id FROM dog_facts")
+        .fetch_all(&mut *tx.0)
+        .await
+        .unwrap();
+    let smuggled = sqlx::query("INSERT INTO dog_facts (fact, tenant_id) VALUES ('x', 'default')")
+        .execute(&mut *tx.0)
+        .await;
+
+    // then the row level security policies hide and reject the other tenant's rows
+    assert_eq!(visible, vec![("acme".to_string(),)]);
+    assert!(smuggled.is_err());
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,154 @@
+use crate::utils::utils_backend::{backend_tests, TestDatabase};
+use main_web::{AuthMode, BootstrapUser};
+use presenter_rest::sessions::TokenPresenter;
+use reqwest::StatusCode;
+use serde_json::json;
+
+backend_tests!(
+    test_should_authorize_bearer_token_by_role,
+    test_should_reject_token_after_logout,
+    test_should_return_too_many_requests_when_locked_out,
+    test_should_lock_out_after_wrong_passwords,
+);
+
+async fn login(api_address: &str, username: &str) -> TokenPresenter {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": username, "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    response.json::<TokenPresenter>().await.unwrap()
+}
+
+async fn unlock(api_address: &str, token: &str) -> StatusCode {
+    reqwest::Client::new()
+        .post(format!("{}/api/v1/admin/accounts/jane/unlock", api_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .status()
+}
+
+async fn test_should_authorize_bearer_token_by_role(db: TestDatabase) {
+    // setup
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.admin_users = vec!["root".into()];
+        })
+        .await;
+
+    // given tokens for an admin and a regular user
+    let admin = login(&api_address, "root").await;
+    let user = login(&api_address, "jane").await;
+    assert_eq!(admin.roles, vec!["admin"]);
+
+    // when both unlock an account
+    // then only the admin is allowed to
+    assert_eq!(
+        unlock(&api_address, &admin.token).await,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        unlock(&api_address, &user.token).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+async fn test_should_reject_token_after_logout(db: TestDatabase) {
+    // setup
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.admin_users = vec!["root".into()];
+        })
+        .await;
+
+    // given a logged out token
+    let admin = login(&api_address, "root").await;
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/logout", api_address))
+        .bearer_auth(&admin.token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // when using it again
+    // then expect unauthorized
+    assert_eq!(
+        unlock(&api_address, &admin.token).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+async fn test_should_return_too_many_requests_when_locked_out(db: TestDatabase) {
+    // setup
+    let api_address = db.spawn_app().await;
+
+    // given an empty username, always rejected, failing up to the backoff
+    let client = reqwest::Client::new();
+    let attempt = || {
+        client
+            .post(format!("{}/api/v1/auth/login", api_address))
+            .json(&json!({"username": " ", "password": "wrong"}))
+            .send()
+    };
+    let first = attempt().await.expect("Failed to execute request.");
+    assert_eq!(first.status(), StatusCode::UNAUTHORIZED);
+
+    // when retrying straight away
+    let second = attempt().await.expect("Failed to execute request.");
+
+    // then expect too many requests, saying when to retry
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = second
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .expect("Missing Retry-After header");
+    assert!(retry_after >= 1);
+}
+
+async fn test_should_lock_out_after_wrong_passwords(db: TestDatabase) {
+    // setup
+    let api_address = db
+        .spawn_app_with(|settings| {
+            settings.auth_mode = AuthMode::Passwords;
+            settings.bootstrap_users = vec![BootstrapUser {
+                username: "jane".into(),
+                email: "jane@example.com".into(),
+                password: "right secret".into(),
+            }];
+            settings.lockout_policy.max_failures_per_account = 2;
+            settings.lockout_policy.base_backoff = std::time::Duration::ZERO;
+        })
+        .await;
+
+    let client = reqwest::Client::new();
+    let attempt = |password: &'static str| {
+        client
+            .post(format!("{}/api/v1/auth/login", api_address))
+            .json(&json!({"username": "jane", "password": password}))
+            .send()
+    };
+
+    // given a user whose password is guessed wrong as many times as allowed
+    for _ in 0..2 {
+        let response = attempt("wrong secret")
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // when logging in with the right password
+    let response = attempt("right secret")
+        .await
+        .expect("Failed to execute request.");
+
+    // then expect too many requests
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,152 @@
+use crate::utils::utils_setup::setup;
+use app_core::{
+    services::{CatRepo, DogRepo, IsolationLevel, Persistence, Transaction, TransactionOptions},
+    usecases::{unit_of_work::UnitOfWork, UseCaseError},
+};
+use app_domain::entities::TenantEntity;
+use service_db::db_service::{CatRepoPG, DogRepoPG, PersistencePG};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+async fn persistence(connopts: &PgConnectOptions) -> PersistencePG {
+    PersistencePG::new(connopts.get_database().unwrap())
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_open_transaction_as_asked(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+    // given a persistence
+    let persistence = persistence(&connopts).await;
+
+    // when asking for a read-only repeatable read transaction
+    let mut tx = persistence
+        .get_transaction_with(TransactionOptions {
+            isolation: IsolationLevel::RepeatableRead,
+            read_only: true,
+        })
+        .await
+        .unwrap();
+
+    // then the database keeps it so
+    let (isolation, read_only): (String, String) = sqlx::query_as(
+        "SELECT current_setting('transaction_isolation'), current_setting('transaction_read_only')",
+    )
+    .fetch_one(&mut *tx.0)
+    .await
+    .unwrap();
+    assert_eq!(
+        (isolation.as_str(), read_only.as_str()),
+        ("repeatable read", "on")
+    );
+    let written = sqlx::query("INSERT INTO dog_facts (fact) VALUES ('x')")
+        .execute(&mut *tx.0)
+        .await;
+    assert!(written.is_err());
+    tx.rollback().await.unwrap();
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_report_serialization_failure_as_transient(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let persistence = persistence(&connopts).await;
+    let tenant = TenantEntity::new("default".into());
+
+    // given two serializable transactions each inserting the fact both
+    // found missing
+    let mut first = persistence
+        .get_tenant_transaction_with(&tenant, TransactionOptions::serializable())
+        .await
+        .unwrap();
+    let mut second = persistence
+        .get_tenant_transaction_with(&tenant, TransactionOptions::serializable())
+        .await
+        .unwrap();
+    for tx in [&mut first, &mut second] {
+        let stored = DogRepoPG::get_upstream_dog_fact(tx, 1).await.unwrap();
+        assert!(stored.is_none());
+    }
+    DogRepoPG::insert_upstream_dog_fact(&mut first, 1, "fact1".into())
+        .await
+        .unwrap();
+    first.commit().await.unwrap();
+
+    // when the second one inserts too
+    let inserted = DogRepoPG::insert_upstream_dog_fact(&mut second, 1, "fact1".into()).await;
+
+    // then it fails, as worth running again
+    let error = inserted.unwrap_err();
+    assert!(error.transient, "{}", error);
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_keep_unit_of_work_when_nested_part_fails(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let persistence = persistence(&connopts).await;
+    let tenant = TenantEntity::new("default".into());
+    let tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+
+    // when a unit of work stores a dog fact, then fails to store a cat fact
+    let done = UnitOfWork::new(tx)
+        .run(|uow| {
+            Box::pin(async move {
+                DogRepoPG::insert_upstream_dog_fact(uow.tx(), 1, "dog1".into()).await?;
+                let nested = uow
+                    .nested(|uow| {
+                        Box::pin(async move {
+                            CatRepoPG::insert_upstream_cat_fact(uow.tx(), 1, "cat1".into()).await?;
+                            Err::<(), _>(UseCaseError::Business("cat fact refused".into()))
+                        })
+                    })
+                    .await;
+                Ok(nested.is_err())
+            })
+        })
+        .await;
+
+    // then only the dog fact is stored
+    assert!(done.unwrap());
+    let mut tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+    let dog = DogRepoPG::get_upstream_dog_fact(&mut tx, 1).await.unwrap();
+    let cat = CatRepoPG::get_upstream_cat_fact(&mut tx, 1).await.unwrap();
+    assert_eq!(dog.unwrap().fact, "dog1");
+    assert!(cat.is_none());
+}
+
+#[sqlx::test(migrations = "../service-db/migrations")]
+async fn test_should_roll_back_unit_of_work_on_early_return(
+    _opts: PgPoolOptions,
+    connopts: PgConnectOptions,
+) {
+    // setup
+    setup(&connopts).await;
+    let persistence = persistence(&connopts).await;
+    let tenant = TenantEntity::new("default".into());
+    let tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+
+    // when a unit of work stores a dog and a cat fact, then fails
+    let done = UnitOfWork::new(tx)
+        .run(|uow| {
+            Box::pin(async move {
+                DogRepoPG::insert_upstream_dog_fact(uow.tx(), 1, "dog1".into()).await?;
+                CatRepoPG::insert_upstream_cat_fact(uow.tx(), 1, "cat1".into()).await?;
+                Err::<(), _>(UseCaseError::Business("changed my mind".into()))
+            })
+        })
+        .await;
+
+    // then neither is stored
+    assert!(done.is_err());
+    let mut tx = persistence.get_tenant_transaction(&tenant).await.unwrap();
+    let dog = DogRepoPG::get_upstream_dog_fact(&mut tx, 1).await.unwrap();
+    let cat = CatRepoPG::get_upstream_cat_fact(&mut tx, 1).await.unwrap();
+    assert!(dog.is_none());
+    assert!(cat.is_none());
+}
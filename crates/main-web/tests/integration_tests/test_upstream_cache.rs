@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use app_core::usecases::{
+    get_upstream_random_cat_fact::GetUpstreamRandomCatFactUseCase, UseCaseError,
+};
+use service_auth::{
+    cache::{CacheSettings, HttpCache},
+    cat_facts_service::CatFactsserviceHTTP,
+    connection::{HttpConnection, HttpSettings},
+};
+
+use crate::utils::utils_catfacts::{spawn_caching_cat_facts_upstream, upstream_cat_facts};
+
+fn cached_service(address: &str, cache: CacheSettings) -> CatFactsserviceHTTP {
+    let connection = HttpConnection::new(HttpSettings {
+        max_retries: 0,
+        ..HttpSettings::default()
+    })
+    .with_cache(HttpCache::new(cache));
+    CatFactsserviceHTTP::new(connection, address.into())
+}
+
+#[tokio::test]
+async fn test_should_serve_fresh_response_from_cache() {
+    // given an upstream allowing its fact to be kept a minute
+    let upstream = spawn_caching_cat_facts_upstream("max-age=60");
+    let service = cached_service(&upstream.address, CacheSettings::default());
+    let usecase = GetUpstreamRandomCatFactUseCase::new(&service);
+
+    // when asking for a fact twice
+    let first = usecase.execute().await.unwrap();
+    let second = usecase.execute().await.unwrap();
+
+    // then upstream is only asked once
+    assert_eq!(first.fact_txt, second.fact_txt);
+    assert_eq!(upstream.hits(), 1);
+}
+
+#[tokio::test]
+async fn test_should_revalidate_no_cache_response() {
+    // given an upstream asking for its fact to be revalidated
+    let upstream = spawn_caching_cat_facts_upstream("no-cache");
+    let service = cached_service(&upstream.address, CacheSettings::default());
+    let usecase = GetUpstreamRandomCatFactUseCase::new(&service);
+
+    // when asking for a fact twice
+    let first = usecase.execute().await.unwrap();
+    let second = usecase.execute().await.unwrap();
+
+    // then the second one is confirmed by a 304
+    assert_eq!(first.fact_txt, second.fact_txt);
+    assert_eq!(upstream.hits(), 2);
+    assert_eq!(upstream.not_modified(), 1);
+}
+
+#[tokio::test]
+async fn test_should_serve_stale_response_while_upstream_is_down() {
+    // given a cached fact already expired, and an upstream now failing
+    let upstream = spawn_caching_cat_facts_upstream("max-age=0");
+    let tolerant = cached_service(&upstream.address, CacheSettings::default());
+    let strict = cached_service(
+        &upstream.address,
+        CacheSettings {
+            max_stale: Duration::ZERO,
+            ..CacheSettings::default()
+        },
+    );
+    let fact = GetUpstreamRandomCatFactUseCase::new(&tolerant)
+        .execute()
+        .await
+        .unwrap();
+    GetUpstreamRandomCatFactUseCase::new(&strict)
+        .execute()
+        .await
+        .unwrap();
+    upstream.fail_next(2);
+
+    // when asking for a fact again
+    let tolerated = GetUpstreamRandomCatFactUseCase::new(&tolerant)
+        .execute()
+        .await;
+    let refused = GetUpstreamRandomCatFactUseCase::new(&strict)
+        .execute()
+        .await;
+
+    // then the stale fact is served only when staleness is allowed
+    assert_eq!(tolerated.unwrap().fact_txt, fact.fact_txt);
+    assert!(matches!(refused, Err(UseCaseError::Upstream(_))));
+}
+
+#[tokio::test]
+async fn test_should_not_store_no_store_response() {
+    // given an upstream forbidding its fact to be stored
+    let upstream = spawn_caching_cat_facts_upstream("no-store");
+    let service = cached_service(&upstream.address, CacheSettings::default());
+    let usecase = GetUpstreamRandomCatFactUseCase::new(&service);
+    usecase.execute().await.unwrap();
+
+    // when upstream fails the next request
+    upstream.fail_next(1);
+    let result = usecase.execute().await;
+
+    // then there is nothing to fall back on
+    assert!(matches!(result, Err(UseCaseError::Upstream(_))));
+    assert_eq!(upstream.not_modified(), 0);
+}
+
+#[tokio::test]
+async fn test_should_keep_responses_on_disk() {
+    // given a fact cached on disk only
+    let dir = std::env::temp_dir().join(format!("http-cache-{}", uuid::Uuid::new_v4()));
+    let upstream = spawn_caching_cat_facts_upstream("max-age=60");
+    let on_disk = || CacheSettings {
+        capacity: 0,
+        disk_dir: Some(dir.clone()),
+        ..CacheSettings::default()
+    };
+    GetUpstreamRandomCatFactUseCase::new(&cached_service(&upstream.address, on_disk()))
+        .execute()
+        .await
+        .unwrap();
+
+    // when a new cache is asked for it, as after a restart
+    let fact = GetUpstreamRandomCatFactUseCase::new(&cached_service(&upstream.address, on_disk()))
+        .execute()
+        .await
+        .unwrap();
+
+    // then it comes from disk
+    assert!(upstream_cat_facts().contains(&fact.fact_txt));
+    assert_eq!(upstream.hits(), 1);
+    let _ = std::fs::remove_dir_all(dir);
+}
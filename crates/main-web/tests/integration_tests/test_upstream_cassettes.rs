@@ -0,0 +1,113 @@
+use std::sync::atomic::Ordering;
+
+use app_core::{
+    services::CatFactsService,
+    usecases::{
+        get_all_upstream_cat_facts::GetAllUpstreamCatFactsUseCase,
+        get_upstream_random_cat_fact::GetUpstreamRandomCatFactUseCase,
+    },
+};
+use service_auth::{
+    cassette::{Cassette, CassetteMode},
+    cat_facts_service::CatFactsserviceHTTP,
+    connection::{HttpConnection, HttpSettings},
+};
+
+use crate::utils::{
+    utils_catfacts::{spawn_flaky_cat_facts_upstream, upstream_cat_facts},
+    utils_http::cassette_connection,
+};
+
+const CATFACT_NINJA: &str = "https://catfact.ninja";
+
+#[tokio::test]
+async fn test_should_replay_recorded_random_fact() {
+    // given the recorded catfact.ninja
+    let (connection, cassette) = cassette_connection("catfact_ninja_random");
+    let service = CatFactsserviceHTTP::new(connection, CATFACT_NINJA.into());
+
+    // when asking for a random fact
+    let fact = GetUpstreamRandomCatFactUseCase::new(&service)
+        .execute()
+        .await
+        .unwrap();
+
+    // then expect a fact, whichever was recorded
+    assert!(!fact.fact_txt.trim().is_empty());
+    assert!(cassette.unplayed().is_empty());
+    cassette.eject();
+}
+
+#[tokio::test]
+async fn test_should_replay_recorded_listing() {
+    // given the recorded catfact.ninja
+    let (connection, cassette) = cassette_connection("catfact_ninja_listing");
+    let service = CatFactsserviceHTTP::new(connection, CATFACT_NINJA.into());
+
+    // when walking its listing
+    let facts = GetAllUpstreamCatFactsUseCase::new(&service)
+        .execute()
+        .await
+        .unwrap();
+
+    // then expect facts from every recorded page, whatever they say
+    assert!(!facts.is_empty());
+    assert!(facts.iter().all(|fact| !fact.fact_txt.trim().is_empty()));
+    assert!(cassette.unplayed().is_empty());
+    cassette.eject();
+}
+
+#[tokio::test]
+async fn test_should_report_requests_missing_from_cassette() {
+    // given the recorded catfact.ninja
+    let (connection, cassette) = cassette_connection("catfact_ninja_listing");
+    let service = CatFactsserviceHTTP::new(connection, CATFACT_NINJA.into());
+
+    // when asking for a page that was never recorded
+    let result = service.get_cat_facts_page(99).await;
+
+    // then expect an error, and the request reported
+    let error = result.unwrap_err().0;
+    assert!(error.starts_with("No recorded response"), "{}", error);
+    let unmatched = cassette.unmatched();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].url, "https://catfact.ninja/facts?page=99");
+}
+
+#[tokio::test]
+async fn test_should_replay_what_was_recorded() {
+    // given interactions recorded from an upstream
+    let (address, hits) = spawn_flaky_cat_facts_upstream(0);
+    let path = std::env::temp_dir().join(format!("cassette-{}.json", uuid::Uuid::new_v4()));
+    let recorder = Cassette::new(&path, CassetteMode::Record).unwrap();
+    let recording = CatFactsserviceHTTP::new(
+        HttpConnection::new(HttpSettings::default()).with_cassette(recorder.clone()),
+        address.clone(),
+    );
+    let recorded = GetAllUpstreamCatFactsUseCase::new(&recording)
+        .execute()
+        .await
+        .unwrap();
+    recorder.eject();
+    let hits_recording = hits.load(Ordering::SeqCst);
+
+    // when replaying them
+    let player = Cassette::new(&path, CassetteMode::Replay).unwrap();
+    let replaying = CatFactsserviceHTTP::new(
+        HttpConnection::new(HttpSettings::default()).with_cassette(player.clone()),
+        address,
+    );
+    let replayed = GetAllUpstreamCatFactsUseCase::new(&replaying)
+        .execute()
+        .await
+        .unwrap();
+
+    // then the same facts come back, without upstream being asked again
+    let replayed: Vec<_> = replayed.into_iter().map(|f| f.fact_txt).collect();
+    assert_eq!(replayed, upstream_cat_facts());
+    assert_eq!(recorded.len(), replayed.len());
+    assert_eq!(hits.load(Ordering::SeqCst), hits_recording);
+    assert!(player.unplayed().is_empty());
+    player.eject();
+    let _ = std::fs::remove_file(path);
+}
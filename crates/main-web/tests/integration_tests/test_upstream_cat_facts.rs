@@ -0,0 +1,153 @@
+use std::{
+    net::TcpListener,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use app_core::usecases::{
+    get_all_upstream_cat_facts::GetAllUpstreamCatFactsUseCase,
+    get_upstream_random_cat_fact::GetUpstreamRandomCatFactUseCase, UseCaseError,
+};
+use service_auth::{
+    cat_facts_service::CatFactsserviceHTTP,
+    connection::{HttpConnection, HttpSettings},
+};
+
+use crate::utils::utils_catfacts::{
+    spawn_cat_facts_upstream, spawn_flaky_cat_facts_upstream, upstream_cat_facts,
+};
+
+fn quick_settings() -> HttpSettings {
+    HttpSettings {
+        base_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..HttpSettings::default()
+    }
+}
+
+#[tokio::test]
+async fn test_should_walk_all_upstream_pages() {
+    // given an upstream listing the fixtures over several pages
+    let service = CatFactsserviceHTTP::new(
+        HttpConnection::new(HttpSettings::default()),
+        spawn_cat_facts_upstream(),
+    );
+
+    // when fetching all of them
+    let facts = GetAllUpstreamCatFactsUseCase::new(&service)
+        .execute()
+        .await
+        .unwrap();
+
+    // then expect every fact once, in order
+    let facts: Vec<_> = facts.into_iter().map(|f| f.fact_txt).collect();
+    assert_eq!(facts, upstream_cat_facts());
+}
+
+#[tokio::test]
+async fn test_should_get_upstream_random_fact() {
+    // given an upstream serving facts
+    let service = CatFactsserviceHTTP::new(
+        HttpConnection::new(HttpSettings::default()),
+        spawn_cat_facts_upstream(),
+    );
+
+    // when asking for a random fact
+    let fact = GetUpstreamRandomCatFactUseCase::new(&service)
+        .execute()
+        .await
+        .unwrap();
+
+    // then expect one of the upstream facts
+    assert!(upstream_cat_facts().contains(&fact.fact_txt));
+}
+
+#[tokio::test]
+async fn test_should_report_unreachable_upstream() {
+    // given an upstream nobody listens on
+    let service = CatFactsserviceHTTP::new(
+        HttpConnection::new(quick_settings()),
+        "http://127.0.0.1:1".into(),
+    );
+
+    // when asking for a random fact
+    let result = GetUpstreamRandomCatFactUseCase::new(&service)
+        .execute()
+        .await;
+
+    // then expect an upstream error
+    assert!(matches!(result, Err(UseCaseError::Upstream(_))));
+}
+
+#[tokio::test]
+async fn test_should_retry_transient_upstream_errors() {
+    // given an upstream failing twice before recovering
+    let (address, hits) = spawn_flaky_cat_facts_upstream(2);
+    let connection = HttpConnection::new(quick_settings());
+    let service = CatFactsserviceHTTP::new(connection.clone(), address);
+
+    // when asking for a random fact
+    let result = GetUpstreamRandomCatFactUseCase::new(&service)
+        .execute()
+        .await;
+
+    // then expect the fact after two retries
+    assert!(result.is_ok());
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    let metrics = connection.metrics().into_values().next().unwrap();
+    assert_eq!((metrics.failures, metrics.retries), (2, 2));
+}
+
+#[tokio::test]
+async fn test_should_stop_calling_failing_upstream() {
+    // given an upstream that keeps failing, and a breaker opening after two failures
+    let (address, hits) = spawn_flaky_cat_facts_upstream(usize::MAX);
+    let connection = HttpConnection::new(HttpSettings {
+        max_retries: 0,
+        breaker_threshold: 2,
+        ..quick_settings()
+    });
+    let service = CatFactsserviceHTTP::new(connection.clone(), address);
+    let usecase = GetUpstreamRandomCatFactUseCase::new(&service);
+    assert!(usecase.execute().await.is_err());
+    assert!(usecase.execute().await.is_err());
+
+    // when asking again
+    let result = usecase.execute().await;
+
+    // then the request is refused without reaching upstream
+    match result {
+        Err(UseCaseError::Upstream(e)) => assert!(e.starts_with("Circuit open"), "{}", e),
+        _ => panic!("expected an upstream error"),
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    let metrics = connection.metrics().into_values().next().unwrap();
+    assert_eq!((metrics.circuit_opened, metrics.rejected), (1, 1));
+}
+
+#[tokio::test]
+async fn test_should_time_out_silent_upstream() {
+    // given an upstream accepting connections but never answering
+    let silent = TcpListener::bind("127.0.0.1:0").unwrap();
+    let connection = HttpConnection::new(HttpSettings {
+        request_timeout: Duration::from_millis(200),
+        max_retries: 1,
+        ..quick_settings()
+    });
+    let service = CatFactsserviceHTTP::new(
+        connection.clone(),
+        format!("http://{}", silent.local_addr().unwrap()),
+    );
+
+    // when asking for a random fact
+    let started = Instant::now();
+    let result = GetUpstreamRandomCatFactUseCase::new(&service)
+        .execute()
+        .await;
+
+    // then expect an upstream error once both attempts timed out
+    assert!(matches!(result, Err(UseCaseError::Upstream(_))));
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let metrics = connection.metrics().into_values().next().unwrap();
+    assert_eq!(metrics.timeouts, 2);
+}
@@ -1,2 +1,10 @@
+pub mod utils_backend;
+pub mod utils_catfacts;
+pub mod utils_dogfacts;
 pub mod utils_file;
+pub mod utils_http;
+pub mod utils_mail;
+pub mod utils_oidc;
+pub mod utils_session;
 pub mod utils_setup;
+pub mod utils_tls;
@@ -0,0 +1,105 @@
+use main_web::Settings;
+use sqlx::{
+    postgres::{PgConnectOptions, PgRow},
+    sqlite::SqliteRow,
+    ConnectOptions, Connection, FromRow, SqliteConnection,
+};
+
+use crate::utils::utils_setup::{setup, setup_sqlite, spawn_app_on_sqlite, spawn_app_with};
+
+/// A database holding the fixtures, on one of the backends every test of
+/// the shared suites runs on, see `backend_tests!`
+pub enum TestDatabase {
+    /// The database sqlx made for the test
+    Postgres(Box<PgConnectOptions>),
+    /// Its URL
+    Sqlite(String),
+}
+
+impl TestDatabase {
+    pub async fn postgres(connopts: PgConnectOptions) -> Self {
+        setup(&connopts).await;
+        TestDatabase::Postgres(Box::new(connopts))
+    }
+
+    pub async fn sqlite() -> Self {
+        TestDatabase::Sqlite(setup_sqlite().await)
+    }
+
+    pub async fn spawn_app(&self) -> String {
+        self.spawn_app_with(|_| {}).await
+    }
+
+    pub async fn spawn_app_with(&self, configure: impl FnOnce(&mut Settings)) -> String {
+        match self {
+            TestDatabase::Postgres(connopts) => spawn_app_with(connopts, configure).await,
+            TestDatabase::Sqlite(database_url) => spawn_app_on_sqlite(database_url, configure),
+        }
+    }
+
+    /// Run a statement past the app, as the owner of the tables
+    pub async fn execute(&self, sql: &str) {
+        match self {
+            TestDatabase::Postgres(connopts) => {
+                let mut conn = connopts.connect().await.unwrap();
+                sqlx::query(sql).execute(&mut conn).await.unwrap();
+            }
+            TestDatabase::Sqlite(database_url) => {
+                let mut conn = SqliteConnection::connect(database_url).await.unwrap();
+                sqlx::query(sql).execute(&mut conn).await.unwrap();
+            }
+        }
+    }
+
+    /// Read rows past the app, in types both backends decode alike
+    pub async fn fetch_all<T>(&self, sql: &str) -> Vec<T>
+    where
+        T: Send + Unpin + for<'r> FromRow<'r, PgRow> + for<'r> FromRow<'r, SqliteRow>,
+    {
+        match self {
+            TestDatabase::Postgres(connopts) => {
+                let mut conn = connopts.connect().await.unwrap();
+                sqlx::query_as(sql).fetch_all(&mut conn).await.unwrap()
+            }
+            TestDatabase::Sqlite(database_url) => {
+                let mut conn = SqliteConnection::connect(database_url).await.unwrap();
+                sqlx::query_as(sql).fetch_all(&mut conn).await.unwrap()
+            }
+        }
+    }
+
+    pub async fn fetch_one<T>(&self, sql: &str) -> T
+    where
+        T: Send + Unpin + for<'r> FromRow<'r, PgRow> + for<'r> FromRow<'r, SqliteRow>,
+    {
+        let mut rows = self.fetch_all(sql).await;
+        assert_eq!(rows.len(), 1, "expected one row of {}", sql);
+        rows.remove(0)
+    }
+}
+
+/// Run each test, an async function taking a `TestDatabase`, once on
+/// PostgreSQL and once on SQLite, as `<test>::postgres` and `<test>::sqlite`
+macro_rules! backend_tests {
+    ($($test:ident),+ $(,)?) => {
+        $(
+            mod $test {
+                use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+                use $crate::utils::utils_backend::TestDatabase;
+
+                #[sqlx::test(migrations = "../service-db/migrations")]
+                async fn postgres(_opts: PgPoolOptions, connopts: PgConnectOptions) {
+                    super::$test(TestDatabase::postgres(connopts).await).await;
+                }
+
+                #[tokio::test]
+                async fn sqlite() {
+                    super::$test(TestDatabase::sqlite().await).await;
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use backend_tests;
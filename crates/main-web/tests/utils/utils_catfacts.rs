@@ -0,0 +1,184 @@
+use std::{
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use actix_web::{
+    http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    web, App, HttpRequest, HttpResponse, HttpServer,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    integration_tests::fixtures::fixtures_struct::DogFactJson, utils::utils_file::read_from_file,
+};
+
+pub const FACTS_PER_PAGE: usize = 4;
+
+/// Validator of every response, facts never change upstream
+const FACTS_ETAG: &str = "\"facts-v1\"";
+
+struct UpstreamState {
+    facts: Vec<String>,
+    /// Requests answered with a server error before serving facts
+    failures_left: AtomicUsize,
+    hits: Arc<AtomicUsize>,
+    /// Sent along every fact, which is then validated by `FACTS_ETAG`
+    cache_control: Option<String>,
+    not_modified: AtomicUsize,
+}
+
+impl UpstreamState {
+    fn failing(&self) -> bool {
+        self.hits.fetch_add(1, Ordering::SeqCst);
+        self.failures_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    page: Option<usize>,
+}
+
+async fn fact_route(state: web::Data<UpstreamState>, request: HttpRequest) -> HttpResponse {
+    if state.failing() {
+        return HttpResponse::ServiceUnavailable().finish();
+    }
+    let cache_control = match &state.cache_control {
+        Some(cache_control) => cache_control,
+        None => {
+            let fact = &state.facts[0];
+            return HttpResponse::Ok().json(json!({ "fact": fact, "length": fact.len() }));
+        }
+    };
+
+    let revalidated = request
+        .headers()
+        .get(IF_NONE_MATCH)
+        .is_some_and(|etag| etag == FACTS_ETAG);
+    if revalidated {
+        state.not_modified.fetch_add(1, Ordering::SeqCst);
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, FACTS_ETAG))
+            .insert_header((CACHE_CONTROL, cache_control.as_str()))
+            .finish();
+    }
+    let fact = &state.facts[0];
+    HttpResponse::Ok()
+        .insert_header((ETAG, FACTS_ETAG))
+        .insert_header((CACHE_CONTROL, cache_control.as_str()))
+        .json(json!({ "fact": fact, "length": fact.len() }))
+}
+
+// paginated like catfact.ninja, out of range pages are empty
+async fn facts_route(
+    state: web::Data<UpstreamState>,
+    query: web::Query<PageQuery>,
+) -> HttpResponse {
+    let page = query.page.unwrap_or(1).max(1);
+    let last_page = state.facts.len().div_ceil(FACTS_PER_PAGE);
+    let data: Vec<_> = state
+        .facts
+        .iter()
+        .skip((page - 1) * FACTS_PER_PAGE)
+        .take(FACTS_PER_PAGE)
+        .map(|fact| json!({ "fact": fact, "length": fact.len() }))
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "current_page": page,
+        "last_page": last_page,
+        "per_page": FACTS_PER_PAGE,
+        "total": state.facts.len(),
+        "data": data,
+    }))
+}
+
+/// The cat facts of the fixtures, in upstream order
+pub fn upstream_cat_facts() -> Vec<String> {
+    read_from_file::<Vec<DogFactJson>>("tests/integration_tests/fixtures/cat_facts.json")
+        .unwrap()
+        .into_iter()
+        .map(|cat| cat.fact)
+        .collect()
+}
+
+/// Start a fake catfact.ninja serving the fixtures, returns its base url
+pub fn spawn_cat_facts_upstream() -> String {
+    spawn_flaky_cat_facts_upstream(0).0
+}
+
+/// Start a fake catfact.ninja failing the given number of requests first,
+/// returns its base url and the count of requests it got
+pub fn spawn_flaky_cat_facts_upstream(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let upstream = spawn_upstream(failures, None);
+    (upstream.address, upstream.state.hits.clone())
+}
+
+/// A fake catfact.ninja with caching headers on its random fact
+pub struct CachingUpstream {
+    pub address: String,
+    state: web::Data<UpstreamState>,
+}
+
+impl CachingUpstream {
+    /// Requests received so far, revalidations included
+    pub fn hits(&self) -> usize {
+        self.state.hits.load(Ordering::SeqCst)
+    }
+
+    /// Revalidations answered with a 304
+    pub fn not_modified(&self) -> usize {
+        self.state.not_modified.load(Ordering::SeqCst)
+    }
+
+    /// Answer the next requests with a server error
+    pub fn fail_next(&self, failures: usize) {
+        self.state.failures_left.store(failures, Ordering::SeqCst);
+    }
+}
+
+/// Start a fake catfact.ninja sending `Cache-Control: <cache_control>` and
+/// an `ETag` along its random fact
+pub fn spawn_caching_cat_facts_upstream(cache_control: &str) -> CachingUpstream {
+    spawn_upstream(0, Some(cache_control.into()))
+}
+
+fn spawn_upstream(failures: usize, cache_control: Option<String>) -> CachingUpstream {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let address = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+
+    let state = web::Data::new(UpstreamState {
+        facts: upstream_cat_facts(),
+        failures_left: AtomicUsize::new(failures),
+        hits: Arc::new(AtomicUsize::new(0)),
+        cache_control,
+        not_modified: AtomicUsize::new(0),
+    });
+    let upstream = CachingUpstream {
+        address,
+        state: state.clone(),
+    };
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route("/fact", web::get().to(fact_route))
+            .route("/facts", web::get().to(facts_route))
+    })
+    .listen(listener)
+    .expect("Failed to listen")
+    .run();
+
+    tokio::spawn(server);
+
+    upstream
+}
@@ -0,0 +1,63 @@
+use std::net::TcpListener;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    integration_tests::fixtures::fixtures_struct::DogFactJson, utils::utils_file::read_from_file,
+};
+
+pub const FACTS_PER_PAGE: usize = 4;
+
+#[derive(Deserialize)]
+struct PageQuery {
+    #[serde(rename = "page[number]")]
+    page: Option<usize>,
+}
+
+// paginated like dogapi.dog, out of range pages are empty
+async fn facts_route(facts: web::Data<Vec<String>>, query: web::Query<PageQuery>) -> HttpResponse {
+    let page = query.page.unwrap_or(1).max(1);
+    let data: Vec<_> = facts
+        .iter()
+        .enumerate()
+        .skip((page - 1) * FACTS_PER_PAGE)
+        .take(FACTS_PER_PAGE)
+        .map(|(id, fact)| json!({ "id": id.to_string(), "type": "fact", "attributes": { "body": fact } }))
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "data": data,
+        "meta": { "pagination": { "current": page, "last": facts.len().div_ceil(FACTS_PER_PAGE) } },
+    }))
+}
+
+/// The dog facts of the fixtures, in upstream order
+pub fn upstream_dog_facts() -> Vec<String> {
+    read_from_file::<Vec<DogFactJson>>("tests/integration_tests/fixtures/dog_facts.json")
+        .unwrap()
+        .into_iter()
+        .map(|dog| dog.fact)
+        .collect()
+}
+
+/// Start a fake dogapi.dog serving the fixtures, returns its base url
+pub fn spawn_dog_facts_upstream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let address = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+
+    let facts = web::Data::new(upstream_dog_facts());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(facts.clone())
+            .route("/facts", web::get().to(facts_route))
+    })
+    .listen(listener)
+    .expect("Failed to listen")
+    .run();
+
+    tokio::spawn(server);
+
+    address
+}
@@ -1,17 +1,21 @@
-use httpmock::{prelude::*, Mock};
+use std::path::PathBuf;
 
-pub fn mock_http_spi(path: &str) -> Mock {
-    // Start a lightweight mock server.
-    let server = MockServer::start();
+use service_auth::{
+    cassette::Cassette,
+    connection::{HttpConnection, HttpSettings},
+};
 
-    // Create a mock on the server.
-    let hello_mock = server.mock(|when, then| {
-        when.method(GET).path("/translate").query_param("word", "hello");
-        then.status(200).header("content-type", "text/html; charset=UTF-8").body("Привет");
-    });
+/// Where cassettes of real upstream interactions are kept
+pub const CASSETTES_DIR: &str = "tests/integration_tests/fixtures/cassettes";
 
-    // Send an HTTP request to the mock server. This simulates your code.
-    let response = isahc::get(server.url("/translate?word=hello")).unwrap();
+pub fn cassette_path(name: &str) -> PathBuf {
+    PathBuf::from(CASSETTES_DIR).join(format!("{}.json", name))
+}
 
-    http_spi_mock
+/// A connection answering from the named cassette, or recording it again
+/// from the real upstream with `CASSETTE_MODE=record`
+pub fn cassette_connection(name: &str) -> (HttpConnection, Cassette) {
+    let cassette = Cassette::from_env(cassette_path(name)).expect("Can't load the cassette");
+    let connection = HttpConnection::new(HttpSettings::default()).with_cassette(cassette.clone());
+    (connection, cassette)
 }
@@ -0,0 +1,76 @@
+use std::{
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SentMail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub type Outbox = Arc<Mutex<Vec<SentMail>>>;
+
+async fn send_route(outbox: web::Data<Outbox>, mail: web::Json<SentMail>) -> HttpResponse {
+    outbox.lock().unwrap().push(mail.into_inner());
+    HttpResponse::Accepted().finish()
+}
+
+/// Spawns a mail API keeping what it is sent, returns its url and the mails
+pub fn spawn_mail_api() -> (String, Outbox) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let url = format!(
+        "http://127.0.0.1:{}/send",
+        listener.local_addr().unwrap().port()
+    );
+
+    let outbox = Outbox::default();
+    let state = web::Data::new(outbox.clone());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route("/send", web::post().to(send_route))
+    })
+    .listen(listener)
+    .expect("Failed to listen")
+    .run();
+
+    tokio::spawn(server);
+
+    (url, outbox)
+}
+
+/// The last mail to `to`, waited for as mails are sent in the background
+pub async fn wait_for_mail(outbox: &Outbox, to: &str) -> SentMail {
+    for _ in 0..100 {
+        let mail = outbox
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|mail| mail.to == to)
+            .cloned();
+        if let Some(mail) = mail {
+            return mail;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("no mail to {}", to)
+}
+
+/// The token of the last password reset mailed to `to`
+pub async fn reset_token(outbox: &Outbox, to: &str) -> String {
+    wait_for_mail(outbox, to)
+        .await
+        .body
+        .split("token=")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .expect("no token in the mail")
+        .to_string()
+}
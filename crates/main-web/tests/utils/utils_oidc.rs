@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    net::TcpListener,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{http::header, web, App, HttpResponse, HttpServer};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use openssl::{rsa::Rsa, sha::sha256};
+use serde::Deserialize;
+use serde_json::json;
+
+pub const CLIENT_ID: &str = "animal-facts";
+const KEY_ID: &str = "test-key";
+
+struct PendingCode {
+    nonce: String,
+    code_challenge: String,
+}
+
+struct ProviderState {
+    issuer: String,
+    encoding_key: EncodingKey,
+    jwks: serde_json::Value,
+    codes: Mutex<HashMap<String, PendingCode>>,
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    redirect_uri: String,
+    state: String,
+    nonce: String,
+    code_challenge: String,
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    code: String,
+    code_verifier: String,
+    client_id: String,
+}
+
+async fn discovery_route(state: web::Data<ProviderState>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "issuer": state.issuer,
+        "authorization_endpoint": format!("{}/authorize", state.issuer),
+        "token_endpoint": format!("{}/token", state.issuer),
+        "jwks_uri": format!("{}/jwks", state.issuer),
+    }))
+}
+
+async fn jwks_route(state: web::Data<ProviderState>) -> HttpResponse {
+    HttpResponse::Ok().json(&state.jwks)
+}
+
+// logs the user in straight away and sends them back with a code
+async fn authorize_route(
+    state: web::Data<ProviderState>,
+    query: web::Query<AuthorizeQuery>,
+) -> HttpResponse {
+    let code = uuid::Uuid::new_v4().to_string();
+    state.codes.lock().unwrap().insert(
+        code.clone(),
+        PendingCode {
+            nonce: query.nonce.clone(),
+            code_challenge: query.code_challenge.clone(),
+        },
+    );
+
+    let location = url::Url::parse_with_params(
+        &query.redirect_uri,
+        &[("code", code.as_str()), ("state", query.state.as_str())],
+    )
+    .unwrap();
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, location.to_string()))
+        .finish()
+}
+
+async fn token_route(state: web::Data<ProviderState>, form: web::Form<TokenForm>) -> HttpResponse {
+    let pending = match state.codes.lock().unwrap().remove(&form.code) {
+        Some(pending) => pending,
+        None => return HttpResponse::BadRequest().json(json!({"error": "invalid_grant"})),
+    };
+
+    let challenge = URL_SAFE_NO_PAD.encode(sha256(form.code_verifier.as_bytes()));
+    if challenge != pending.code_challenge || form.client_id != CLIENT_ID {
+        return HttpResponse::BadRequest().json(json!({"error": "invalid_grant"}));
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let claims = json!({
+        "iss": state.issuer,
+        "sub": "jane",
+        "aud": CLIENT_ID,
+        "iat": now,
+        "exp": now + 300,
+        "nonce": pending.nonce,
+        "email": "jane@example.com",
+        "name": "Jane Doe",
+        "groups": ["idp-admins", "idp-unknown"],
+    });
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(KEY_ID.into());
+    let id_token = jsonwebtoken::encode(&header, &claims, &state.encoding_key).unwrap();
+
+    HttpResponse::Ok().json(json!({
+        "id_token": id_token,
+        "access_token": "opaque",
+        "token_type": "Bearer",
+    }))
+}
+
+/// Spawns a minimal OpenID Connect provider signing ID tokens with a fresh RSA key,
+/// returns its issuer url
+pub fn spawn_oidc_provider() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+    let issuer = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+
+    let rsa = Rsa::generate(2048).unwrap();
+    let jwks = json!({
+        "keys": [{
+            "kty": "RSA",
+            "kid": KEY_ID,
+            "use": "sig",
+            "alg": "RS256",
+            "n": URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            "e": URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+        }]
+    });
+    let state = web::Data::new(ProviderState {
+        issuer: issuer.clone(),
+        encoding_key: EncodingKey::from_rsa_pem(&rsa.private_key_to_pem().unwrap()).unwrap(),
+        jwks,
+        codes: Mutex::new(HashMap::new()),
+    });
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route(
+                "/.well-known/openid-configuration",
+                web::get().to(discovery_route),
+            )
+            .route("/jwks", web::get().to(jwks_route))
+            .route("/authorize", web::get().to(authorize_route))
+            .route("/token", web::post().to(token_route))
+    })
+    .listen(listener)
+    .expect("Failed to listen")
+    .run();
+
+    tokio::spawn(server);
+
+    issuer
+}
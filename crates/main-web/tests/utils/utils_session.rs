@@ -0,0 +1,46 @@
+use crate::utils::utils_backend::TestDatabase;
+use actix_web::cookie::Cookie;
+use chrono::Duration;
+use presenter_rest::sessions::{CookieSessionSettings, SessionMode};
+use reqwest::header;
+use serde_json::json;
+
+pub async fn spawn_app_with_cookie_sessions(db: &TestDatabase, idle_timeout: Duration) -> String {
+    db.spawn_app_with(|settings| {
+        let mut cookie_settings = CookieSessionSettings::default();
+        cookie_settings.policy.idle_timeout = idle_timeout;
+        settings.session_mode = SessionMode::Cookie(cookie_settings);
+        settings.admin_users = vec!["root".into()];
+    })
+    .await
+}
+
+pub async fn login(api_address: &str, username: &str) -> (Cookie<'static>, Cookie<'static>) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/auth/login", api_address))
+        .json(&json!({"username": username, "password": "secret"}))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let cookies: Vec<Cookie> = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .map(|value| Cookie::parse(value.to_str().unwrap().to_string()).unwrap())
+        .collect();
+    let find = |name: &str| {
+        cookies
+            .iter()
+            .find(|cookie| cookie.name() == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("no {} cookie", name))
+    };
+
+    (find("session"), find("csrf_token"))
+}
+
+pub fn cookie_header(session: &Cookie, csrf: &Cookie) -> String {
+    format!("{}; {}", session.stripped(), csrf.stripped())
+}
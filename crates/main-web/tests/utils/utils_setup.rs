@@ -1,66 +1,122 @@
-use sqlx::{postgres::PgConnectOptions, ConnectOptions};
+use main_web::{AuthMode, PersistenceSettings, Settings};
+use service_sqlite::db_service::PersistenceSqlite;
+use sqlx::{postgres::PgConnectOptions, ConnectOptions, Connection, SqliteConnection};
 use std::net::TcpListener;
 
-use crate::integration_tests::fixtures::fixtures_run::execute_imports;
-// use crate::utils::utils_file::read_from_file;
+use crate::integration_tests::fixtures::fixtures_run::{execute_imports, execute_sqlite_imports};
 
 pub async fn spawn_app(connopts: &PgConnectOptions) -> String {
-    // Let the OS assign a port (:0)
-    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
-
-    let port = listener.local_addr().unwrap().port();
+    spawn_app_with(connopts, |_| {}).await
+}
 
+pub async fn spawn_app_with(
+    connopts: &PgConnectOptions,
+    configure: impl FnOnce(&mut Settings),
+) -> String {
     let db_name = connopts
         .get_database()
         .expect("Can't get test database name");
 
-    let server = main_web::setup(
-        listener,
-        db_name.to_string(),
-        "http://127.0.0.1:3333".to_string(),
-    );
+    spawn_app_on(db_name.to_string(), configure)
+}
+
+/// Run the app without a database, starting with the facts of the fixtures
+pub fn spawn_app_in_memory(configure: impl FnOnce(&mut Settings)) -> String {
+    spawn_app_on(String::new(), |settings| {
+        settings.persistence =
+            PersistenceSettings::from_url("memory:tests/integration_tests/fixtures").unwrap();
+        configure(settings);
+    })
+}
+
+/// Run the app on a SQLite database, see `setup_sqlite`
+pub fn spawn_app_on_sqlite(database_url: &str, configure: impl FnOnce(&mut Settings)) -> String {
+    let persistence = PersistenceSettings::from_url(database_url).unwrap();
+    spawn_app_on(String::new(), |settings| {
+        settings.persistence = persistence;
+        configure(settings);
+    })
+}
+
+fn spawn_app_on(db_name: String, configure: impl FnOnce(&mut Settings)) -> String {
+    // Let the OS assign a port (:0)
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
+
+    let port = listener.local_addr().unwrap().port();
+
+    let mut settings = Settings::new(db_name, "http://127.0.0.1:3333".to_string());
+    // any password opens, tests checking passwords switch back
+    settings.auth_mode = AuthMode::Dev;
+    // jane edits the facts, tests of the other users' rights pick someone else
+    settings.editor_users = vec!["jane".into()];
+    configure(&mut settings);
+    let scheme = if settings.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
 
-    // if TcpStream::connect("127.0.0.1:3333").is_ok() {
-    //     println!("Http source faked server already running");
-    // } else {
-    //     spawn_http_spi();
-    // }
+    let server = main_web::setup(listener, settings);
 
     tokio::spawn(server);
 
-    format!("http://127.0.0.1:{}", port)
+    format!("{}://127.0.0.1:{}", scheme, port)
 }
 
-// pub fn spawn_http_spi() -> String {
-//     async fn facts_route() -> HttpResponse {
-//         let json =
-//             read_from_file::<CatFactsApiModel>("tests/integration_tests/fixtures/cat_facts.json")
-//                 .unwrap();
-//         HttpResponse::Ok().json(json)
-//     }
-
-//     async fn random_fact_route() -> HttpResponse {
-//         HttpResponse::Ok().json(CatFactApiModel {
-//             fact: String::from("In the 1930s, two Russian biologists discovered that color change in Siamese kittens depend on their body temperature. Siamese cats carry albino genes that work only when the body temperature is above 98° F. If these kittens are left in a very warm room, their points won’t darken and they will stay a creamy white."),
-//             length: 315,
-//         })
-//     }
-
-//     let s1 = HttpServer::new(move || {
-//         App::new()
-//             .route("facts", web::get().to(facts_route))
-//             .route("fact", web::get().to(random_fact_route))
-//     })
-//     .bind("127.0.0.1:3333")
-//     .expect("woops")
-//     .run();
-
-//     let _ = tokio::spawn(s1);
-
-//     "http://127.0.0.1:3333".to_string()
-// }
-
 pub async fn setup(connopts: &PgConnectOptions) {
     let mut db_connection_postgres_db = connopts.connect().await.unwrap();
     execute_imports(&mut db_connection_postgres_db).await;
 }
+
+/// A second database next to the test one, migrated and holding the
+/// fixtures, to stand in for a read replica, returns its URL
+pub async fn setup_replica(connopts: &PgConnectOptions) -> String {
+    let replica_name = format!("{}_replica", connopts.get_database().unwrap());
+    let mut conn = connopts.connect().await.unwrap();
+    sqlx::query(&format!("CREATE DATABASE {}", replica_name))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    let mut replica = connopts
+        .clone()
+        .database(&replica_name)
+        .connect()
+        .await
+        .unwrap();
+    sqlx::migrate!("../service-db/migrations")
+        .run(&mut replica)
+        .await
+        .unwrap();
+    execute_imports(&mut replica).await;
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let (server, _) = database_url.rsplit_once('/').unwrap();
+    format!("{}/{}", server, replica_name)
+}
+
+/// The test databases are cleaned up by sqlx, the replica isn't
+pub async fn drop_replica(connopts: &PgConnectOptions) {
+    let replica_name = format!("{}_replica", connopts.get_database().unwrap());
+    let mut conn = connopts.connect().await.unwrap();
+    sqlx::query(&format!("DROP DATABASE {} WITH (FORCE)", replica_name))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+}
+
+/// A new SQLite database holding the fixtures, returns its URL
+pub async fn setup_sqlite() -> String {
+    let path = std::env::temp_dir().join(format!("animal-facts-{}.db", uuid::Uuid::new_v4()));
+    let database_url = format!("sqlite:{}", path.display());
+    PersistenceSqlite::new(&database_url)
+        .await
+        .unwrap()
+        .migrate()
+        .await
+        .unwrap();
+
+    let mut conn = SqliteConnection::connect(&database_url).await.unwrap();
+    execute_sqlite_imports(&mut conn).await;
+    database_url
+}
@@ -0,0 +1,225 @@
+use std::{fs, net::TcpStream, path::PathBuf};
+
+use main_web::{TlsProfile, TlsSettings};
+use openssl::{
+    asn1::{Asn1Integer, Asn1Time},
+    bn::BigNum,
+    hash::MessageDigest,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    ssl::{SslConnector, SslMethod, SslStream},
+    x509::{
+        extension::{AuthorityKeyIdentifier, BasicConstraints, KeyUsage, SubjectAlternativeName},
+        CrlNumber, X509Builder, X509CrlBuilder, X509Name, X509RevokedBuilder, X509,
+    },
+};
+
+pub struct Pem {
+    pub cert: Vec<u8>,
+    /// PKCS#8, as expected by reqwest identities
+    pub key: Vec<u8>,
+}
+
+/// A throwaway CA with a server certificate for 127.0.0.1, written to a
+/// temporary directory along with the client CRL
+pub struct TestPki {
+    dir: PathBuf,
+    ca_cert: X509,
+    ca_key: PKey<Private>,
+    revoked: Vec<u32>,
+    next_serial: u32,
+}
+
+impl TestPki {
+    pub fn generate() -> Self {
+        let dir = std::env::temp_dir().join(format!("mtls-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut builder = Self::builder("Test CA", 1, &ca_key);
+        builder.set_issuer_name(&Self::name("Test CA")).unwrap();
+        builder
+            .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+            .unwrap();
+        builder
+            .append_extension(
+                KeyUsage::new()
+                    .critical()
+                    .key_cert_sign()
+                    .crl_sign()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        builder.sign(&ca_key, MessageDigest::sha256()).unwrap();
+        let ca_cert = builder.build();
+
+        let mut pki = TestPki {
+            dir,
+            ca_cert,
+            ca_key,
+            // openssl refuses to build an empty CRL, this serial is never issued
+            revoked: vec![u32::MAX],
+            next_serial: 2,
+        };
+        fs::write(pki.dir.join("ca.pem"), pki.ca_cert.to_pem().unwrap()).unwrap();
+        pki.renew_server_certificate();
+        pki.write_crl();
+        pki
+    }
+
+    /// Replace the server certificate on disk, returns its serial number
+    pub fn renew_server_certificate(&mut self) -> u32 {
+        let serial = self.next_serial;
+        let server = self.issue("localhost");
+        fs::write(self.dir.join("server.pem"), server.cert).unwrap();
+        fs::write(self.dir.join("server.key"), server.key).unwrap();
+        serial
+    }
+
+    fn name(common_name: &str) -> X509Name {
+        let mut name = X509Name::builder().unwrap();
+        name.append_entry_by_text("CN", common_name).unwrap();
+        name.build()
+    }
+
+    fn builder(common_name: &str, serial: u32, key: &PKey<Private>) -> X509Builder {
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = Asn1Integer::from_bn(&BigNum::from_u32(serial).unwrap()).unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&Self::name(common_name)).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder
+    }
+
+    /// Issue a certificate, usable both by the server and by clients
+    pub fn issue(&mut self, common_name: &str) -> Pem {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut builder = Self::builder(common_name, self.next_serial, &key);
+        self.next_serial += 1;
+        builder
+            .set_issuer_name(self.ca_cert.subject_name())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(Some(&self.ca_cert), None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&self.ca_key, MessageDigest::sha256()).unwrap();
+
+        Pem {
+            cert: builder.build().to_pem().unwrap(),
+            key: key.private_key_to_pem_pkcs8().unwrap(),
+        }
+    }
+
+    /// Issue a certificate and put it on the CRL straight away
+    pub fn issue_revoked(&mut self, common_name: &str) -> Pem {
+        self.revoked.push(self.next_serial);
+        let pem = self.issue(common_name);
+        self.write_crl();
+        pem
+    }
+
+    fn write_crl(&self) {
+        let mut crl = X509CrlBuilder::new().unwrap();
+        crl.set_issuer_name(self.ca_cert.subject_name()).unwrap();
+        crl.set_last_update(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        crl.set_next_update(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let context = X509Builder::new().unwrap();
+        let aki = AuthorityKeyIdentifier::new()
+            .issuer(true)
+            .build(&context.x509v3_context(Some(&self.ca_cert), None))
+            .unwrap();
+        crl.append_extension(aki).unwrap();
+        let number = BigNum::from_u32(self.revoked.len() as u32).unwrap();
+        crl.append_extension(CrlNumber::new(number).unwrap().build().unwrap())
+            .unwrap();
+        for serial in &self.revoked {
+            let mut revoked = X509RevokedBuilder::new().unwrap();
+            let serial = Asn1Integer::from_bn(&BigNum::from_u32(*serial).unwrap()).unwrap();
+            revoked.set_serial_number(&serial).unwrap();
+            revoked
+                .set_revocation_date(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            crl.add_revoked(revoked.build()).unwrap();
+        }
+        crl.sign(&self.ca_key, MessageDigest::sha256()).unwrap();
+        fs::write(
+            self.dir.join("crl.pem"),
+            crl.build().unwrap().to_pem().unwrap(),
+        )
+        .unwrap();
+    }
+
+    pub fn ca_pem(&self) -> Vec<u8> {
+        self.ca_cert.to_pem().unwrap()
+    }
+
+    pub fn settings(&self, require_client_cert: bool) -> TlsSettings {
+        TlsSettings {
+            cert_path: self.dir.join("server.pem"),
+            key_path: self.dir.join("server.key"),
+            client_ca_path: Some(self.dir.join("ca.pem")),
+            client_crl_path: Some(self.dir.join("crl.pem")),
+            require_client_cert,
+            profile: TlsProfile::default(),
+            reload_interval: None,
+        }
+    }
+
+    /// An HTTPS client trusting the test CA, presenting the given certificate
+    pub fn client(&self, identity: Option<&Pem>) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&self.ca_pem()).unwrap());
+        if let Some(pem) = identity {
+            builder =
+                builder.identity(reqwest::Identity::from_pkcs8_pem(&pem.cert, &pem.key).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    /// A blocking TLS connection offering the given ALPN protocols, to look
+    /// at what the server negotiated
+    pub fn connect(&self, api_address: &str, protocols: &[u8]) -> SslStream<TcpStream> {
+        let address = api_address.trim_start_matches("https://");
+        let mut connector = SslConnector::builder(SslMethod::tls_client()).unwrap();
+        connector
+            .cert_store_mut()
+            .add_cert(self.ca_cert.clone())
+            .unwrap();
+        connector.set_alpn_protos(protocols).unwrap();
+        connector
+            .build()
+            .connect("localhost", TcpStream::connect(address).unwrap())
+            .unwrap()
+    }
+}
+
+/// Serial number of the certificate the server presented
+pub fn server_serial(stream: &SslStream<TcpStream>) -> u32 {
+    let serial = stream
+        .ssl()
+        .peer_certificate()
+        .unwrap()
+        .serial_number()
+        .to_bn()
+        .unwrap();
+    serial.to_dec_str().unwrap().parse().unwrap()
+}
+
+impl Drop for TestPki {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use super::{
+    mappers::{MigrationPresenterMapper, SyncRunPresenterMapper, UserPresenterMapper},
+    payloads::{NewUserPayload, SyncRunsQuery},
+    presenters::{MigrationPresenter, SyncRunPresenter},
+};
+use crate::{
+    sessions::CurrentPrincipal,
+    shared::{app_state::RestAppState, error::ErrorReponse},
+    tenants::CurrentTenant,
+};
+use actix_web::{web, HttpResponse};
+use app_core::{
+    services::{CatRepo, DogRepo, Persistence, SyncRunRepo, Transaction, UserRepo, ADMIN_ROLE},
+    usecases::{
+        create_user::CreateUserUseCase, get_migrations::GetMigrationsUseCase,
+        get_sync_runs::GetSyncRunsUseCase, sync_cat_facts::SyncCatFactsUseCase,
+        sync_dog_facts::SyncDogFactsUseCase, unlock_account::UnlockAccountUseCase, UseCaseError,
+    },
+};
+
+/// Runs listed when the query doesn't say
+const DEFAULT_SYNC_RUNS_LIMIT: i64 = 20;
+
+pub struct AdminControllers<P, C, D, R, U> {
+    persistance: PhantomData<P>,
+    cat_repository: PhantomData<C>,
+    dog_repository: PhantomData<D>,
+    sync_run_repository: PhantomData<R>,
+    user_repository: PhantomData<U>,
+}
+
+impl<P, C, D, R, U> AdminControllers<P, C, D, R, U>
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    C: CatRepo<P>,
+    D: DogRepo<P>,
+    R: SyncRunRepo<P>,
+    U: UserRepo<P>,
+{
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/users").route(web::post().to(Self::create_user)))
+            .service(
+                web::resource("/accounts/{username}/unlock").route(web::post().to(Self::unlock)),
+            )
+            .service(web::resource("/cats/sync").route(web::post().to(Self::sync_cat_facts)))
+            .service(web::resource("/dogs/sync").route(web::post().to(Self::sync_dog_facts)))
+            .service(web::resource("/sync-runs").route(web::get().to(Self::get_sync_runs)))
+            .service(web::resource("/migrations").route(web::get().to(Self::get_migrations)));
+    }
+
+    fn ensure_admin(principal: &CurrentPrincipal) -> Result<(), ErrorReponse> {
+        if principal.0.has_role(ADMIN_ROLE) {
+            Ok(())
+        } else {
+            Err(UseCaseError::Forbidden("Admin only".into()).into())
+        }
+    }
+
+    async fn create_user(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        payload: web::Json<NewUserPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let create_user_usecase = CreateUserUseCase::<P, U, _>::new(
+            data.persistence_service.clone(),
+            data.password_hasher.as_ref(),
+        );
+        let user = create_user_usecase
+            .execute(&payload.username, &payload.email, &payload.password)
+            .await?;
+
+        Ok(HttpResponse::Created().json(UserPresenterMapper::to_api(user)))
+    }
+
+    async fn unlock(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        path: web::Path<(String,)>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let username = path.into_inner().0;
+        let unlock_account_usecase = UnlockAccountUseCase::new(data.auth_service.as_ref());
+        unlock_account_usecase.execute(&username).await?;
+
+        Ok(HttpResponse::NoContent().finish())
+    }
+
+    async fn sync_cat_facts(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        tenant: CurrentTenant,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let sync_cat_facts_usecase = SyncCatFactsUseCase::<P, C, R, _>::new(
+            data.persistence_service.clone(),
+            data.cat_facts_service.as_ref(),
+            data.events.as_ref(),
+        );
+        let run = sync_cat_facts_usecase.execute(&tenant.0).await?;
+
+        Ok(HttpResponse::Ok().json(SyncRunPresenterMapper::to_api(run)))
+    }
+
+    async fn sync_dog_facts(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        tenant: CurrentTenant,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let dog_facts_service = data
+            .dog_facts_service
+            .as_deref()
+            .ok_or_else(|| UseCaseError::Business("No dog facts source configured".into()))?;
+        let sync_dog_facts_usecase = SyncDogFactsUseCase::<P, D, R, _>::new(
+            data.persistence_service.clone(),
+            dog_facts_service,
+            data.events.as_ref(),
+        );
+        let run = sync_dog_facts_usecase.execute(&tenant.0).await?;
+
+        Ok(HttpResponse::Ok().json(SyncRunPresenterMapper::to_api(run)))
+    }
+
+    async fn get_sync_runs(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        query: web::Query<SyncRunsQuery>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let get_sync_runs_usecase =
+            GetSyncRunsUseCase::<P, R>::new(data.persistence_service.clone());
+        let runs = get_sync_runs_usecase
+            .execute(query.limit.unwrap_or(DEFAULT_SYNC_RUNS_LIMIT))
+            .await?;
+
+        Ok(HttpResponse::Ok().json(
+            runs.into_iter()
+                .map(SyncRunPresenterMapper::to_api)
+                .collect::<Vec<SyncRunPresenter>>(),
+        ))
+    }
+
+    async fn get_migrations(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::ensure_admin(&principal)?;
+
+        let get_migrations_usecase = GetMigrationsUseCase::new(data.schema_migrations.as_ref());
+        let migrations = get_migrations_usecase.execute().await?;
+
+        Ok(HttpResponse::Ok().json(
+            migrations
+                .into_iter()
+                .map(MigrationPresenterMapper::to_api)
+                .collect::<Vec<MigrationPresenter>>(),
+        ))
+    }
+}
@@ -0,0 +1,44 @@
+use super::presenters::{MigrationPresenter, SyncRunPresenter, UserPresenter};
+use app_domain::entities::{MigrationEntity, SyncRunEntity, UserEntity};
+
+pub struct SyncRunPresenterMapper {}
+
+impl SyncRunPresenterMapper {
+    pub fn to_api(entity: SyncRunEntity) -> SyncRunPresenter {
+        SyncRunPresenter {
+            id: entity.run_id,
+            source: entity.source,
+            tenant: entity.tenant_id,
+            started_at: entity.started_at.to_rfc3339(),
+            finished_at: entity.finished_at.map(|at| at.to_rfc3339()),
+            inserted: entity.inserted,
+            unchanged: entity.unchanged,
+            changed: entity.changed,
+            error: entity.error,
+        }
+    }
+}
+
+pub struct UserPresenterMapper {}
+
+impl UserPresenterMapper {
+    /// Never the password hash
+    pub fn to_api(entity: UserEntity) -> UserPresenter {
+        UserPresenter {
+            username: entity.username,
+            email: entity.email,
+        }
+    }
+}
+
+pub struct MigrationPresenterMapper {}
+
+impl MigrationPresenterMapper {
+    pub fn to_api(entity: MigrationEntity) -> MigrationPresenter {
+        MigrationPresenter {
+            version: entity.version,
+            description: entity.description,
+            applied: entity.applied,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod controllers;
+mod mappers;
+mod payloads;
+mod presenters;
+
+pub use controllers::AdminControllers;
+pub use payloads::NewUserPayload;
+pub use presenters::{MigrationPresenter, SyncRunPresenter, UserPresenter};
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SyncRunPresenter {
+    pub id: i64,
+    pub source: String,
+    pub tenant: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub inserted: i32,
+    pub unchanged: i32,
+    pub changed: i32,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserPresenter {
+    pub username: String,
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MigrationPresenter {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
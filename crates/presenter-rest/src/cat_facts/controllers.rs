@@ -1,17 +1,27 @@
 use std::marker::PhantomData;
 
-use super::{mappers::CatFactPresenterMapper, presenters::CatFactPresenter};
-use crate::shared::{app_state::RestAppState, error::ErrorReponse};
-use actix_web::{web, HttpResponse};
+use super::{
+    mappers::CatFactPresenterMapper, payloads::CatFactPayload, presenters::CatFactPresenter,
+};
+use crate::{
+    sessions::CurrentPrincipal,
+    shared::{
+        app_state::RestAppState,
+        error::ErrorReponse,
+        versions::{etag, expected_version},
+    },
+    tenants::CurrentTenant,
+};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
 use app_core::{
     mappers::presenter::ApiMapper,
-    services::{CatRepo, Persistence},
+    services::{CatRepo, FallbackCatRepo, FallbackPersistence, Persistence},
 };
 use app_core::{
     services::Transaction,
     usecases::{
         get_all_cat_facts::GetAllCatFactsUseCase,
-        get_one_random_cat_fact::GetOneRandomCatFactUseCase,
+        get_one_random_cat_fact::GetOneRandomCatFactUseCase, update_cat_fact::UpdateCatFactUseCase,
     },
 };
 
@@ -28,15 +38,17 @@ where
 {
     pub fn routes(cfg: &mut web::ServiceConfig) {
         cfg.service(web::resource("/").route(web::get().to(Self::get_all_cat_facts)))
-            .service(web::resource("/random").route(web::get().to(Self::get_one_random_cat_fact)));
+            .service(web::resource("/random").route(web::get().to(Self::get_one_random_cat_fact)))
+            .service(web::resource("/{fact_id}").route(web::put().to(Self::update_cat_fact)));
     }
 
     async fn get_all_cat_facts(
         data: web::Data<RestAppState<P>>,
+        tenant: CurrentTenant,
     ) -> Result<HttpResponse, ErrorReponse> {
         let get_all_cat_facts_usecase =
             GetAllCatFactsUseCase::<P, R>::new(data.persistence_service.clone());
-        let facts = get_all_cat_facts_usecase.execute().await?;
+        let facts = get_all_cat_facts_usecase.execute(&tenant.0).await?;
 
         Ok(HttpResponse::Ok().json(
             facts
@@ -48,11 +60,46 @@ where
 
     async fn get_one_random_cat_fact(
         data: web::Data<RestAppState<P>>,
+        tenant: CurrentTenant,
     ) -> Result<HttpResponse, ErrorReponse> {
+        let persistence = FallbackPersistence::new(
+            data.persistence_service.clone(),
+            data.cat_facts_service.clone(),
+            data.cat_facts_fallback,
+        )
+        .with_events(data.events.clone());
         let get_one_random_cat_fact_usecase =
-            GetOneRandomCatFactUseCase::<P, R>::new(data.persistence_service.clone());
-        let fact = get_one_random_cat_fact_usecase.execute().await?;
+            GetOneRandomCatFactUseCase::<_, FallbackCatRepo<R>>::new(persistence);
+        let fact = get_one_random_cat_fact_usecase.execute(&tenant.0).await?;
 
         Ok(HttpResponse::Ok().json(CatFactPresenterMapper::to_api(fact)))
     }
+
+    /// Refused with 409 Conflict when the fact changed since the version
+    /// given, as `If-Match` or in the payload, and to others than editors
+    async fn update_cat_fact(
+        data: web::Data<RestAppState<P>>,
+        req: HttpRequest,
+        principal: CurrentPrincipal,
+        tenant: CurrentTenant,
+        path: web::Path<(i32,)>,
+        payload: web::Json<CatFactPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        principal.ensure_editor()?;
+
+        let fact_id = path.into_inner().0;
+        let version = expected_version(&req, payload.version)?;
+        let fact = CatFactPresenterMapper::to_entity(payload.into_inner());
+        let update_cat_fact_usecase = UpdateCatFactUseCase::<P, R>::new(
+            data.persistence_service.clone(),
+            data.events.as_ref(),
+        );
+        let fact = update_cat_fact_usecase
+            .execute(&tenant.0, fact_id, fact.fact_txt, version)
+            .await?;
+
+        Ok(HttpResponse::Ok()
+            .insert_header(header::ETag(etag(fact.version)))
+            .json(CatFactPresenterMapper::to_api(fact)))
+    }
 }
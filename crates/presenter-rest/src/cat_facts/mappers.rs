@@ -1,6 +1,6 @@
 use super::{payloads::CatFactPayload, presenters::CatFactPresenter};
 use app_core::mappers::presenter::ApiMapper;
-use app_domain::entities::CatFactEntity;
+use app_domain::entities::{CatFactEntity, UNSTORED_FACT_ID};
 
 pub struct CatFactPresenterMapper {}
 
@@ -8,11 +8,17 @@ impl ApiMapper<CatFactEntity, CatFactPresenter, CatFactPayload> for CatFactPrese
     fn to_api(entity: CatFactEntity) -> CatFactPresenter {
         CatFactPresenter {
             fact: entity.fact_txt,
-            id: entity.fact_id,
+            id: (entity.fact_id != UNSTORED_FACT_ID).then_some(entity.fact_id),
+            version: entity.version,
         }
     }
 
-    fn to_entity(_payload: CatFactPayload) -> CatFactEntity {
-        panic!("not implemented");
+    /// The id comes from the path, the version from `If-Match` or the payload
+    fn to_entity(payload: CatFactPayload) -> CatFactEntity {
+        CatFactEntity {
+            fact_txt: payload.fact,
+            fact_id: UNSTORED_FACT_ID,
+            version: payload.version.unwrap_or_default(),
+        }
     }
 }
@@ -3,5 +3,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CatFactPresenter {
     pub fact: String,
-    pub id: i32,
+    /// Left out of facts served from upstream without being stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    pub version: i32,
 }
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+/// The client certificate of a mutual TLS connection, already verified
+/// against the trusted CA and CRL by the server
+#[derive(Debug, Clone)]
+pub struct PeerCertificate {
+    pub common_name: Option<String>,
+    pub serial_number: String,
+}
+
+/// Which partners may authenticate with a client certificate
+#[derive(Debug, Clone, Default)]
+pub struct ClientCertificateSettings {
+    /// Certificate common name to the scopes granted to it, which become
+    /// the roles of its principal (e.g. `admin`, `tenant:acme`)
+    pub scopes: HashMap<String, Vec<String>>,
+}
@@ -0,0 +1,52 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, HttpMessage,
+};
+use app_core::{services::Principal, usecases::UseCaseError};
+
+use super::PeerCertificate;
+use crate::shared::{app_state::RestAppState, error::ErrorReponse};
+
+/// Authenticate partners by the client certificate of the connection
+///
+/// Principals from a session or a token take precedence. A certificate
+/// signed by the trusted CA but not mapped to any scopes is rejected.
+pub async fn client_certificate<P, B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, actix_web::Error>
+where
+    P: 'static,
+    B: MessageBody,
+{
+    let certificate = match req.conn_data::<PeerCertificate>() {
+        Some(certificate) => certificate.clone(),
+        None => return next.call(req).await,
+    };
+    if req.extensions().contains::<Principal>() {
+        return next.call(req).await;
+    }
+
+    let data = req
+        .app_data::<web::Data<RestAppState<P>>>()
+        .cloned()
+        .expect("RestAppState must be registered");
+
+    let principal = certificate.common_name.and_then(|common_name| {
+        data.client_certificates
+            .scopes
+            .get(&common_name)
+            .map(|scopes| Principal::new(common_name, scopes.clone()))
+    });
+    let principal = principal.ok_or_else(|| {
+        ErrorReponse::from(UseCaseError::Forbidden(format!(
+            "Unknown client certificate {}",
+            certificate.serial_number
+        )))
+    })?;
+
+    req.extensions_mut().insert(principal);
+    next.call(req).await
+}
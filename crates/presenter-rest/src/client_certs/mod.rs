@@ -0,0 +1,5 @@
+mod config;
+mod middleware;
+
+pub use config::{ClientCertificateSettings, PeerCertificate};
+pub use middleware::client_certificate;
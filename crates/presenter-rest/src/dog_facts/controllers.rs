@@ -1,13 +1,24 @@
 use std::marker::PhantomData;
 
-use super::{mappers::DogFactPresenterMapper, presenters::DogFactPresenter};
-use crate::shared::{app_state::RestAppState, error::ErrorReponse};
-use actix_web::{web, HttpResponse};
+use super::{
+    mappers::DogFactPresenterMapper, payloads::DogFactPayload, presenters::DogFactPresenter,
+};
+use crate::{
+    sessions::CurrentPrincipal,
+    shared::{
+        app_state::RestAppState,
+        error::ErrorReponse,
+        versions::{etag, expected_version},
+    },
+    tenants::CurrentTenant,
+};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
 use app_core::{
     mappers::presenter::ApiMapper,
     services::{DogRepo, Persistence, Transaction},
     usecases::{
         get_all_dog_facts::GetAllDogFactsUseCase, get_one_dog_fact_by_id::GetOneDogFactByIdUseCase,
+        update_dog_fact::UpdateDogFactUseCase,
     },
 };
 
@@ -24,13 +35,20 @@ where
 {
     pub fn routes(cfg: &mut web::ServiceConfig) {
         cfg.service(web::resource("/").route(web::get().to(Self::get_all)))
-            .service(web::resource("/{fact_id}").route(web::get().to(Self::get_one_by_id)));
+            .service(
+                web::resource("/{fact_id}")
+                    .route(web::get().to(Self::get_one_by_id))
+                    .route(web::put().to(Self::update)),
+            );
     }
 
-    async fn get_all(data: web::Data<RestAppState<P>>) -> Result<HttpResponse, ErrorReponse> {
+    async fn get_all(
+        data: web::Data<RestAppState<P>>,
+        tenant: CurrentTenant,
+    ) -> Result<HttpResponse, ErrorReponse> {
         let get_all_dog_facts_usecase =
             GetAllDogFactsUseCase::<P, R>::new(data.persistence_service.clone());
-        let facts = get_all_dog_facts_usecase.execute().await?;
+        let facts = get_all_dog_facts_usecase.execute(&tenant.0).await?;
 
         Ok(HttpResponse::Ok().json(
             facts
@@ -42,13 +60,46 @@ where
 
     async fn get_one_by_id(
         data: web::Data<RestAppState<P>>,
+        tenant: CurrentTenant,
         path: web::Path<(i32,)>,
     ) -> Result<HttpResponse, ErrorReponse> {
         let fact_id = path.into_inner().0;
         let get_one_dog_fact_by_id_usecase =
             GetOneDogFactByIdUseCase::<P, R>::new(data.persistence_service.clone());
-        let fact = get_one_dog_fact_by_id_usecase.execute(&fact_id).await?;
+        let fact = get_one_dog_fact_by_id_usecase
+            .execute(&tenant.0, &fact_id)
+            .await?;
+
+        Ok(HttpResponse::Ok()
+            .insert_header(header::ETag(etag(fact.version)))
+            .json(DogFactPresenterMapper::to_api(fact)))
+    }
+
+    /// Refused with 409 Conflict when the fact changed since the version
+    /// given, as `If-Match` or in the payload, and to others than editors
+    async fn update(
+        data: web::Data<RestAppState<P>>,
+        req: HttpRequest,
+        principal: CurrentPrincipal,
+        tenant: CurrentTenant,
+        path: web::Path<(i32,)>,
+        payload: web::Json<DogFactPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        principal.ensure_editor()?;
+
+        let fact_id = path.into_inner().0;
+        let version = expected_version(&req, payload.version)?;
+        let fact = DogFactPresenterMapper::to_entity(payload.into_inner());
+        let update_dog_fact_usecase = UpdateDogFactUseCase::<P, R>::new(
+            data.persistence_service.clone(),
+            data.events.as_ref(),
+        );
+        let fact = update_dog_fact_usecase
+            .execute(&tenant.0, fact_id, fact.fact, version)
+            .await?;
 
-        Ok(HttpResponse::Ok().json(DogFactPresenterMapper::to_api(fact)))
+        Ok(HttpResponse::Ok()
+            .insert_header(header::ETag(etag(fact.version)))
+            .json(DogFactPresenterMapper::to_api(fact)))
     }
 }
@@ -1,6 +1,6 @@
 use super::{payloads::DogFactPayload, presenters::DogFactPresenter};
 use app_core::mappers::presenter::ApiMapper;
-use app_domain::entities::DogFactEntity;
+use app_domain::entities::{DogFactEntity, UNSTORED_FACT_ID};
 
 pub struct DogFactPresenterMapper {}
 
@@ -9,10 +9,16 @@ impl ApiMapper<DogFactEntity, DogFactPresenter, DogFactPayload> for DogFactPrese
         DogFactPresenter {
             fact_id: entity.fact_id,
             txt: entity.fact,
+            version: entity.version,
         }
     }
 
-    fn to_entity(_payload: DogFactPayload) -> DogFactEntity {
-        panic!("not implemented");
+    /// The id comes from the path, the version from `If-Match` or the payload
+    fn to_entity(payload: DogFactPayload) -> DogFactEntity {
+        DogFactEntity {
+            fact_id: UNSTORED_FACT_ID,
+            fact: payload.txt,
+            version: payload.version.unwrap_or_default(),
+        }
     }
 }
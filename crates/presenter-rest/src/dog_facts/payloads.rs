@@ -2,5 +2,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DogFactPayload {
-    // implement for POST/UPDATE requests
+    pub txt: String,
+    /// Version the update is made from, unless given as `If-Match`
+    pub version: Option<i32>,
 }
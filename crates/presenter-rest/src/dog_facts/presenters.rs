@@ -4,4 +4,5 @@ use serde::{Deserialize, Serialize};
 pub struct DogFactPresenter {
     pub fact_id: i32,
     pub txt: String,
+    pub version: i32,
 }
@@ -1,5 +1,11 @@
+pub mod admin;
 pub mod cat_facts;
+pub mod client_certs;
 pub mod dog_facts;
+pub mod passwords;
+pub mod sessions;
 mod shared;
+pub mod sso;
+pub mod tenants;
 
-pub use shared::{app_state::RestAppState, routes::RestControllers};
+pub use shared::{app_state::RestAppState, error::PresenterError, routes::RestControllers};
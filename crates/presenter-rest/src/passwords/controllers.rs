@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+
+use super::payloads::{ChangePasswordPayload, ForgotPasswordPayload, ResetPasswordPayload};
+use crate::{
+    sessions::CurrentPrincipal,
+    shared::{app_state::RestAppState, error::ErrorReponse},
+};
+use actix_web::{web, HttpResponse};
+use app_core::{
+    services::{Persistence, SessionRepo, Transaction, UserRepo},
+    usecases::{
+        change_password::ChangePasswordUseCase,
+        request_password_reset::RequestPasswordResetUseCase, reset_password::ResetPasswordUseCase,
+    },
+};
+
+pub struct PasswordControllers<P, S, U> {
+    persistance: PhantomData<P>,
+    session_repository: PhantomData<S>,
+    user_repository: PhantomData<U>,
+}
+
+impl<P, S, U> PasswordControllers<P, S, U>
+where
+    P: Persistence + Clone + 'static,
+    S: SessionRepo<P>,
+    U: UserRepo<P> + 'static,
+    <P as Persistence>::Transaction: Transaction,
+{
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/forgot").route(web::post().to(Self::forgot_password)))
+            .service(web::resource("/reset").route(web::post().to(Self::reset_password)))
+            .service(web::resource("/change").route(web::post().to(Self::change_password)));
+    }
+
+    /// Accepted whether the email is known or not, and as fast: the user is
+    /// looked up and mailed in the background
+    async fn forgot_password(
+        data: web::Data<RestAppState<P>>,
+        payload: web::Json<ForgotPasswordPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let email = payload.into_inner().email;
+        actix_web::rt::spawn(async move {
+            let request_password_reset_usecase = RequestPasswordResetUseCase::<P, U, _>::new(
+                data.persistence_service.clone(),
+                data.mailer.as_ref(),
+                data.password_reset.clone(),
+            );
+            if let Err(e) = request_password_reset_usecase.execute(&email).await {
+                log::error!("Could not request a password reset: {}", e);
+            }
+        });
+
+        Ok(HttpResponse::Accepted().finish())
+    }
+
+    async fn reset_password(
+        data: web::Data<RestAppState<P>>,
+        payload: web::Json<ResetPasswordPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let reset_password_usecase = ResetPasswordUseCase::<P, U, S, _, _>::new(
+            data.persistence_service.clone(),
+            data.password_hasher.as_ref(),
+            data.auth_service.as_ref(),
+        );
+        reset_password_usecase
+            .execute(&payload.token, &payload.password)
+            .await?;
+
+        Ok(HttpResponse::NoContent().finish())
+    }
+
+    /// Ends every session and token of the user, this one included
+    async fn change_password(
+        data: web::Data<RestAppState<P>>,
+        principal: CurrentPrincipal,
+        payload: web::Json<ChangePasswordPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let change_password_usecase = ChangePasswordUseCase::<P, U, S, _, _>::new(
+            data.persistence_service.clone(),
+            data.password_hasher.as_ref(),
+            data.auth_service.as_ref(),
+        );
+        change_password_usecase
+            .execute(
+                &principal.0.username,
+                &payload.current_password,
+                &payload.new_password,
+            )
+            .await?;
+
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
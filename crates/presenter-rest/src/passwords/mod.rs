@@ -0,0 +1,5 @@
+mod controllers;
+mod payloads;
+
+pub use controllers::PasswordControllers;
+pub use payloads::{ChangePasswordPayload, ForgotPasswordPayload, ResetPasswordPayload};
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ForgotPasswordPayload {
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ResetPasswordPayload {
+    /// As mailed
+    pub token: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChangePasswordPayload {
+    pub current_password: String,
+    pub new_password: String,
+}
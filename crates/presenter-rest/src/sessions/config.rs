@@ -0,0 +1,63 @@
+use actix_web::cookie::{Cookie, SameSite};
+use app_core::usecases::create_session::SessionPolicy;
+use app_domain::entities::SessionEntity;
+use chrono::Duration;
+
+/// How browser clients keep their authentication between requests
+#[derive(Debug, Clone, Default)]
+pub enum SessionMode {
+    /// No cookies are issued, clients exchange their credentials for a token
+    /// and send it in the `Authorization` header
+    #[default]
+    Bearer,
+    /// Clients get a server-side session behind an HttpOnly cookie,
+    /// mutating requests must echo the CSRF cookie in a header
+    Cookie(CookieSessionSettings),
+}
+
+#[derive(Debug, Clone)]
+pub struct CookieSessionSettings {
+    pub cookie_name: String,
+    pub csrf_cookie_name: String,
+    pub csrf_header_name: String,
+    /// Only send cookies over HTTPS, to be disabled for local development only
+    pub secure: bool,
+    pub same_site: SameSite,
+    pub policy: SessionPolicy,
+}
+
+impl Default for CookieSessionSettings {
+    fn default() -> Self {
+        CookieSessionSettings {
+            cookie_name: String::from("session"),
+            csrf_cookie_name: String::from("csrf_token"),
+            csrf_header_name: String::from("X-CSRF-Token"),
+            secure: true,
+            same_site: SameSite::Strict,
+            policy: SessionPolicy {
+                idle_timeout: Duration::minutes(30),
+                absolute_timeout: Duration::hours(12),
+            },
+        }
+    }
+}
+
+impl CookieSessionSettings {
+    pub(crate) fn cookie<'c>(&self, name: &'c str, value: String) -> Cookie<'c> {
+        Cookie::build(name, value)
+            .path("/")
+            .secure(self.secure)
+            .same_site(self.same_site)
+            .finish()
+    }
+
+    /// The HttpOnly session cookie and the CSRF one, readable by the
+    /// frontend so it can echo it in the CSRF header
+    pub(crate) fn session_cookies(&self, session: &SessionEntity) -> [Cookie<'_>; 2] {
+        let mut session_cookie = self.cookie(&self.cookie_name, session.session_id.clone());
+        session_cookie.set_http_only(true);
+        let csrf_cookie = self.cookie(&self.csrf_cookie_name, session.csrf_token.clone());
+
+        [session_cookie, csrf_cookie]
+    }
+}
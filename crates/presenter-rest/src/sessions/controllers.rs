@@ -0,0 +1,120 @@
+use std::marker::PhantomData;
+
+use super::{
+    config::CookieSessionSettings,
+    extractors::{bearer_token, CurrentSession},
+    mappers::{SessionPresenterMapper, TokenPresenterMapper},
+    payloads::LoginPayload,
+    SessionMode,
+};
+use crate::shared::{app_state::RestAppState, error::ErrorReponse};
+use actix_web::{cookie::time::Duration, web, FromRequest, HttpRequest, HttpResponse};
+use app_core::{
+    services::{Persistence, SessionRepo, Transaction},
+    usecases::{
+        create_session::CreateSessionUseCase, create_token::CreateTokenUseCase,
+        delete_session::DeleteSessionUseCase, revoke_token::RevokeTokenUseCase, UseCaseError,
+    },
+};
+
+pub struct SessionControllers<P, R> {
+    persistance: PhantomData<P>,
+    session_repository: PhantomData<R>,
+}
+
+impl<P, R> SessionControllers<P, R>
+where
+    P: Persistence + Clone,
+    R: SessionRepo<P>,
+    <P as Persistence>::Transaction: Transaction,
+{
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/login").route(web::post().to(Self::login)))
+            .service(web::resource("/logout").route(web::post().to(Self::logout)))
+            .service(web::resource("/session").route(web::get().to(Self::get_session)));
+    }
+
+    fn cookie_settings(data: &RestAppState<P>) -> Result<&CookieSessionSettings, ErrorReponse> {
+        match &data.session_mode {
+            SessionMode::Cookie(settings) => Ok(settings),
+            SessionMode::Bearer => Err(ErrorReponse::not_found("Cookie sessions are disabled")),
+        }
+    }
+
+    async fn login(
+        req: HttpRequest,
+        data: web::Data<RestAppState<P>>,
+        payload: web::Json<LoginPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        // the socket address, forwarded headers can't be trusted for throttling
+        let client_ip = req.peer_addr().map(|addr| addr.ip());
+
+        let settings = match &data.session_mode {
+            SessionMode::Cookie(settings) => settings,
+            SessionMode::Bearer => {
+                let create_token_usecase = CreateTokenUseCase::new(data.auth_service.as_ref());
+                let token = create_token_usecase
+                    .execute(&payload.username, &payload.password, client_ip)
+                    .await?;
+                return Ok(HttpResponse::Ok().json(TokenPresenterMapper::to_api(token)));
+            }
+        };
+
+        let create_session_usecase = CreateSessionUseCase::<P, R, _>::new(
+            data.persistence_service.clone(),
+            data.auth_service.as_ref(),
+            settings.policy,
+        );
+        let session = create_session_usecase
+            .execute(&payload.username, &payload.password, client_ip)
+            .await?;
+
+        let mut response = HttpResponse::Ok();
+        for cookie in settings.session_cookies(&session) {
+            response.cookie(cookie);
+        }
+
+        Ok(response.json(SessionPresenterMapper::to_api(session)))
+    }
+
+    async fn logout(
+        req: HttpRequest,
+        data: web::Data<RestAppState<P>>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let settings = match &data.session_mode {
+            SessionMode::Cookie(settings) => settings,
+            SessionMode::Bearer => {
+                let token = bearer_token(&req)
+                    .ok_or_else(|| UseCaseError::Unauthorized("No bearer token".into()))?;
+                let revoke_token_usecase = RevokeTokenUseCase::new(data.auth_service.as_ref());
+                revoke_token_usecase.execute(token).await?;
+                return Ok(HttpResponse::NoContent().finish());
+            }
+        };
+        let session = CurrentSession::extract(&req).await?;
+
+        let delete_session_usecase =
+            DeleteSessionUseCase::<P, R>::new(data.persistence_service.clone());
+        delete_session_usecase
+            .execute(&session.0.session_id)
+            .await?;
+
+        let mut response = HttpResponse::NoContent();
+        for name in [&settings.cookie_name, &settings.csrf_cookie_name] {
+            let mut cookie = settings.cookie(name, String::new());
+            cookie.set_max_age(Duration::ZERO);
+            response.cookie(cookie);
+        }
+        Ok(response.finish())
+    }
+
+    async fn get_session(
+        req: HttpRequest,
+        data: web::Data<RestAppState<P>>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        Self::cookie_settings(&data)?;
+        let session = CurrentSession::extract(&req).await?;
+
+        Ok(HttpResponse::Ok().json(SessionPresenterMapper::to_api(session.0)))
+    }
+}
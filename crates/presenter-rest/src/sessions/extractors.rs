@@ -0,0 +1,64 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use app_core::{services::Principal, usecases::UseCaseError};
+use app_domain::entities::SessionEntity;
+
+use crate::shared::error::ErrorReponse;
+
+/// The session attached to the request by the cookie session middleware
+pub struct CurrentSession(pub SessionEntity);
+
+impl FromRequest for CurrentSession {
+    type Error = ErrorReponse;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<SessionEntity>()
+                .cloned()
+                .map(CurrentSession)
+                .ok_or_else(|| UseCaseError::Unauthorized("No session".into()).into()),
+        )
+    }
+}
+
+/// Who is authenticated, through a cookie session or a bearer token
+pub struct CurrentPrincipal(pub Principal);
+
+impl CurrentPrincipal {
+    /// Refuse with 403 Forbidden unless the principal may change facts
+    pub fn ensure_editor(&self) -> Result<(), ErrorReponse> {
+        if self.0.is_editor() {
+            Ok(())
+        } else {
+            Err(UseCaseError::Forbidden("Editors only".into()).into())
+        }
+    }
+}
+
+impl FromRequest for CurrentPrincipal {
+    type Error = ErrorReponse;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Principal>()
+                .cloned()
+                .map(CurrentPrincipal)
+                .ok_or_else(|| UseCaseError::Unauthorized("Not authenticated".into()).into()),
+        )
+    }
+}
+
+/// The raw token of the `Authorization: Bearer` header, if any
+pub(crate) fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
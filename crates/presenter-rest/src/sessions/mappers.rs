@@ -0,0 +1,27 @@
+use super::presenters::{SessionPresenter, TokenPresenter};
+use app_core::services::AuthToken;
+use app_domain::entities::SessionEntity;
+
+pub struct SessionPresenterMapper {}
+
+impl SessionPresenterMapper {
+    pub fn to_api(entity: SessionEntity) -> SessionPresenter {
+        SessionPresenter {
+            username: entity.username,
+            csrf_token: entity.csrf_token,
+            expires_at: entity.expires_at.to_rfc3339(),
+        }
+    }
+}
+
+pub struct TokenPresenterMapper {}
+
+impl TokenPresenterMapper {
+    pub fn to_api(token: AuthToken) -> TokenPresenter {
+        TokenPresenter {
+            token: token.token,
+            username: token.principal.username,
+            roles: token.principal.roles,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    web, HttpMessage,
+};
+use app_core::{
+    services::{Persistence, Principal, SessionRepo, Transaction},
+    usecases::{get_session::GetSessionUseCase, verify_token::VerifyTokenUseCase, UseCaseError},
+};
+
+use super::{extractors::bearer_token, SessionMode};
+use crate::shared::{app_state::RestAppState, error::ErrorReponse};
+
+/// Attach the cookie session to the request and enforce double-submit
+/// CSRF tokens on mutating requests made with it
+pub async fn cookie_session<P, SR, B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, actix_web::Error>
+where
+    P: Persistence + Clone,
+    <P as Persistence>::Transaction: Transaction,
+    SR: SessionRepo<P>,
+    B: MessageBody,
+{
+    let data = req
+        .app_data::<web::Data<RestAppState<P>>>()
+        .cloned()
+        .expect("RestAppState must be registered");
+    let settings = match &data.session_mode {
+        SessionMode::Cookie(settings) => settings,
+        SessionMode::Bearer => return next.call(req).await,
+    };
+
+    let session_id = match req.cookie(&settings.cookie_name) {
+        Some(cookie) => cookie.value().to_string(),
+        None => return next.call(req).await,
+    };
+
+    let get_session_usecase =
+        GetSessionUseCase::<P, SR>::new(data.persistence_service.clone(), settings.policy);
+    let session = match get_session_usecase.execute(&session_id).await {
+        Ok(session) => session,
+        // stale cookies are simply ignored, the client has to login again
+        Err(UseCaseError::Unauthorized(_)) => return next.call(req).await,
+        Err(e) => return Err(ErrorReponse::from(e).into()),
+    };
+
+    if !matches!(
+        *req.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    ) {
+        let header = req
+            .headers()
+            .get(settings.csrf_header_name.as_str())
+            .and_then(|value| value.to_str().ok());
+        let cookie = req.cookie(&settings.csrf_cookie_name);
+
+        let valid = match (header, cookie) {
+            (Some(header), Some(cookie)) => {
+                constant_time_eq(header, cookie.value())
+                    && constant_time_eq(header, &session.csrf_token)
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(ErrorReponse::from(UseCaseError::Forbidden(
+                "Missing or invalid CSRF token".into(),
+            ))
+            .into());
+        }
+    }
+
+    let principal = Principal::new(session.username.clone(), session.roles.clone());
+    req.extensions_mut().insert(session);
+    req.extensions_mut().insert(principal);
+    next.call(req).await
+}
+
+/// Attach the principal of the `Authorization: Bearer` token to the request
+///
+/// Requests without a token go through anonymously, an invalid token is
+/// rejected rather than silently ignored.
+pub async fn bearer_auth<P, B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, actix_web::Error>
+where
+    P: 'static,
+    B: MessageBody,
+{
+    let data = req
+        .app_data::<web::Data<RestAppState<P>>>()
+        .cloned()
+        .expect("RestAppState must be registered");
+    if !matches!(data.session_mode, SessionMode::Bearer) {
+        return next.call(req).await;
+    }
+
+    let token = match bearer_token(req.request()) {
+        Some(token) => token.to_string(),
+        None => return next.call(req).await,
+    };
+
+    let verify_token_usecase = VerifyTokenUseCase::new(data.auth_service.as_ref());
+    let principal = verify_token_usecase
+        .execute(&token)
+        .await
+        .map_err(ErrorReponse::from)?;
+
+    req.extensions_mut().insert(principal);
+    next.call(req).await
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
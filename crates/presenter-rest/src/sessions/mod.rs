@@ -0,0 +1,15 @@
+mod config;
+mod controllers;
+mod extractors;
+mod mappers;
+mod middleware;
+mod payloads;
+mod presenters;
+
+pub use config::{CookieSessionSettings, SessionMode};
+pub use controllers::SessionControllers;
+pub use extractors::{CurrentPrincipal, CurrentSession};
+pub(crate) use mappers::{SessionPresenterMapper, TokenPresenterMapper};
+pub use middleware::{bearer_auth, cookie_session};
+pub use payloads::LoginPayload;
+pub use presenters::{SessionPresenter, TokenPresenter};
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionPresenter {
+    pub username: String,
+    pub csrf_token: String,
+    pub expires_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TokenPresenter {
+    pub token: String,
+    pub username: String,
+    pub roles: Vec<String>,
+}
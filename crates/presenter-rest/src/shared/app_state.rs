@@ -1,6 +1,35 @@
-use app_core::services::AuthService;
+use std::sync::Arc;
+
+use app_core::{
+    services::{
+        AuthService, CatFactsFallback, CatFactsService, DogFactsService, EventPublisher, Mailer,
+        PasswordHasher, SchemaMigrations, SsoService,
+    },
+    usecases::request_password_reset::PasswordResetPolicy,
+};
+
+use crate::{
+    client_certs::ClientCertificateSettings, sessions::SessionMode, tenants::TenantSettings,
+};
 
 pub struct RestAppState<P> {
-    pub auth_service: Box<dyn AuthService + Send + Sync>,
+    pub auth_service: Box<dyn AuthService>,
+    pub sso_service: Option<Box<dyn SsoService>>,
+    pub password_hasher: Box<dyn PasswordHasher>,
+    /// Where password reset tokens go
+    pub mailer: Box<dyn Mailer>,
+    pub password_reset: PasswordResetPolicy,
+    /// Live cat facts, synchronised into the catalogues
+    pub cat_facts_service: Arc<dyn CatFactsService>,
+    /// How `cat_facts_service` stands in for an empty or unreachable catalogue
+    pub cat_facts_fallback: CatFactsFallback,
+    /// Live dog facts, synchronised into the catalogues when there is a source
+    pub dog_facts_service: Option<Box<dyn DogFactsService>>,
+    pub session_mode: SessionMode,
+    pub tenancy: TenantSettings,
+    pub client_certificates: ClientCertificateSettings,
     pub persistence_service: P,
+    pub schema_migrations: Box<dyn SchemaMigrations>,
+    /// Where use cases tell what they changed
+    pub events: Arc<dyn EventPublisher>,
 }
@@ -1,15 +1,26 @@
-use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+use std::time::Duration;
+
+use actix_web::{
+    error::ResponseError,
+    http::{header, StatusCode},
+    HttpResponse,
+};
 use app_core::usecases::UseCaseError;
 use derive_more::Display;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
 
+use super::versions::etag;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PresenterError {
     pub code: u16,
     pub error: String,
     pub message: String,
+    /// Version the resource is at, on conflicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_version: Option<i32>,
 }
 
 #[derive(Error, Debug, Display)]
@@ -17,6 +28,28 @@ pub struct PresenterError {
 pub struct ErrorReponse {
     status_code: StatusCode,
     error: String,
+    current_version: Option<i32>,
+    retry_after: Option<Duration>,
+}
+
+impl ErrorReponse {
+    fn new(status_code: StatusCode, error: String) -> Self {
+        ErrorReponse {
+            status_code,
+            error,
+            current_version: None,
+            retry_after: None,
+        }
+    }
+
+    pub fn not_found(error: &str) -> Self {
+        ErrorReponse::new(StatusCode::NOT_FOUND, error.into())
+    }
+
+    /// An update didn't say which version it was made from
+    pub fn precondition_required(error: &str) -> Self {
+        ErrorReponse::new(StatusCode::PRECONDITION_REQUIRED, error.into())
+    }
 }
 
 impl ResponseError for ErrorReponse {
@@ -30,29 +63,38 @@ impl ResponseError for ErrorReponse {
             code: status_code.as_u16(),
             message: status_code.to_string(),
             error: self.error.clone(),
+            current_version: self.current_version,
         };
-        HttpResponse::build(status_code).json(error_response)
+        let mut response = HttpResponse::build(status_code);
+        if let Some(version) = self.current_version {
+            response.insert_header(header::ETag(etag(version)));
+        }
+        if let Some(retry_after) = self.retry_after {
+            // whole seconds, rounded up not to invite a retry that is still too early
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response.insert_header((header::RETRY_AFTER, seconds.max(1)));
+        }
+        response.json(error_response)
     }
 }
 
 impl From<UseCaseError> for ErrorReponse {
     fn from(value: UseCaseError) -> Self {
         match value {
-            UseCaseError::Repository(e) => ErrorReponse {
-                status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                error: e,
-            },
-            UseCaseError::Business(e) => ErrorReponse {
-                status_code: StatusCode::BAD_REQUEST,
-                error: e,
-            },
-            UseCaseError::Unauthorized(e) => ErrorReponse {
-                status_code: StatusCode::UNAUTHORIZED,
-                error: e,
+            UseCaseError::Repository(e) => ErrorReponse::new(StatusCode::INTERNAL_SERVER_ERROR, e),
+            UseCaseError::Transient(e) => ErrorReponse::new(StatusCode::SERVICE_UNAVAILABLE, e),
+            UseCaseError::Business(e) => ErrorReponse::new(StatusCode::BAD_REQUEST, e),
+            UseCaseError::NotFound(e) => ErrorReponse::new(StatusCode::NOT_FOUND, e),
+            UseCaseError::Unauthorized(e) => ErrorReponse::new(StatusCode::UNAUTHORIZED, e),
+            UseCaseError::Forbidden(e) => ErrorReponse::new(StatusCode::FORBIDDEN, e),
+            UseCaseError::Upstream(e) => ErrorReponse::new(StatusCode::BAD_GATEWAY, e),
+            e @ UseCaseError::TooManyRequests { retry_after } => ErrorReponse {
+                retry_after: Some(retry_after),
+                ..ErrorReponse::new(StatusCode::TOO_MANY_REQUESTS, e.to_string())
             },
-            UseCaseError::Forbidden(e) => ErrorReponse {
-                status_code: StatusCode::FORBIDDEN,
-                error: e,
+            e @ UseCaseError::Conflict { current_version } => ErrorReponse {
+                current_version: Some(current_version),
+                ..ErrorReponse::new(StatusCode::CONFLICT, e.to_string())
             },
         }
     }
@@ -1,3 +1,4 @@
 pub mod app_state;
 pub mod error;
 pub mod routes;
+pub mod versions;
@@ -1,26 +1,55 @@
 use std::marker::PhantomData;
 
-use actix_web::web;
-use app_core::services::{CatRepo, DogRepo, Persistence, Transaction};
+use actix_web::{middleware::from_fn, web};
+use app_core::services::{
+    CatRepo, DogRepo, Persistence, SessionRepo, SyncRunRepo, Transaction, UserRepo,
+};
 
-use crate::{cat_facts::CatFactControllers, dog_facts::DogFactControllers};
+use crate::{
+    admin::AdminControllers,
+    cat_facts::CatFactControllers,
+    client_certs::client_certificate,
+    dog_facts::DogFactControllers,
+    passwords::PasswordControllers,
+    sessions::{bearer_auth, cookie_session, SessionControllers},
+    sso::SsoControllers,
+    tenants::resolve_tenant,
+};
 
-pub struct RestControllers<P, D, C> {
+pub struct RestControllers<P, D, C, S, R, U> {
     persistance: PhantomData<P>,
     dog_repository: PhantomData<D>,
     cat_repository: PhantomData<C>,
+    session_repository: PhantomData<S>,
+    sync_run_repository: PhantomData<R>,
+    user_repository: PhantomData<U>,
 }
 
-impl<P, D, C> RestControllers<P, D, C>
+impl<P, D, C, S, R, U> RestControllers<P, D, C, S, R, U>
 where
     P: Persistence + Clone,
     <P as Persistence>::Transaction: Transaction,
     D: DogRepo<P>,
     C: CatRepo<P>,
+    S: SessionRepo<P>,
+    R: SyncRunRepo<P>,
+    U: UserRepo<P>,
 {
     pub fn routes(config: &mut web::ServiceConfig) {
-        config
-            .service(web::scope("/api/v1/dogs").configure(DogFactControllers::<P, D>::routes))
-            .service(web::scope("/api/v1/cats").configure(CatFactControllers::<P, C>::routes));
+        config.service(
+            web::scope("/api/v1")
+                .wrap(from_fn(resolve_tenant::<P, _>))
+                .wrap(from_fn(client_certificate::<P, _>))
+                .wrap(from_fn(cookie_session::<P, S, _>))
+                .wrap(from_fn(bearer_auth::<P, _>))
+                .service(web::scope("/dogs").configure(DogFactControllers::<P, D>::routes))
+                .service(web::scope("/cats").configure(CatFactControllers::<P, C>::routes))
+                .service(web::scope("/auth/sso").configure(SsoControllers::<P, S>::routes))
+                .service(
+                    web::scope("/auth/password").configure(PasswordControllers::<P, S, U>::routes),
+                )
+                .service(web::scope("/auth").configure(SessionControllers::<P, S>::routes))
+                .service(web::scope("/admin").configure(AdminControllers::<P, C, D, R, U>::routes)),
+        );
     }
 }
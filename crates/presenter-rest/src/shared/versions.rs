@@ -0,0 +1,31 @@
+use actix_web::{
+    http::header::{self, EntityTag, Header},
+    HttpRequest,
+};
+use app_core::usecases::UseCaseError;
+
+use super::error::ErrorReponse;
+
+/// The `ETag` of a resource at `version`
+pub fn etag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// The version an update was made from, as given by `If-Match` or else by
+/// the payload, one of them being required
+pub fn expected_version(req: &HttpRequest, in_payload: Option<i32>) -> Result<i32, ErrorReponse> {
+    let if_match = header::IfMatch::parse(req).ok();
+    let tag = match if_match {
+        Some(header::IfMatch::Items(tags)) => tags.into_iter().next(),
+        _ => None,
+    };
+    match (tag, in_payload) {
+        (Some(tag), _) => tag.tag().parse::<i32>().map_err(|_| {
+            UseCaseError::Business(format!("If-Match \"{}\" is not a version", tag.tag())).into()
+        }),
+        (None, Some(version)) => Ok(version),
+        (None, None) => Err(ErrorReponse::precondition_required(
+            "The version the update is made from is required, as If-Match or in the payload",
+        )),
+    }
+}
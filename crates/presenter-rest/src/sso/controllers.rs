@@ -0,0 +1,143 @@
+use std::marker::PhantomData;
+
+use super::payloads::SsoCallbackPayload;
+use crate::{
+    sessions::{SessionMode, SessionPresenterMapper, TokenPresenterMapper},
+    shared::{app_state::RestAppState, error::ErrorReponse},
+};
+use actix_web::{
+    cookie::{time::Duration, Cookie, SameSite},
+    http::header,
+    web, HttpRequest, HttpResponse,
+};
+use app_core::{
+    services::{Persistence, Principal, SessionRepo, Transaction, SSO_PENDING_LOGIN_TTL},
+    usecases::{
+        begin_sso_login::BeginSsoLoginUseCase, complete_sso_login::CompleteSsoLoginUseCase,
+        create_session::CreateSessionUseCase, create_token::CreateTokenUseCase, UseCaseError,
+    },
+};
+
+/// Keeps the sealed pending login in the browser that started it
+const PENDING_LOGIN_COOKIE: &str = "sso_login";
+
+pub struct SsoControllers<P, R> {
+    persistance: PhantomData<P>,
+    session_repository: PhantomData<R>,
+}
+
+impl<P, R> SsoControllers<P, R>
+where
+    P: Persistence + Clone,
+    R: SessionRepo<P>,
+    <P as Persistence>::Transaction: Transaction,
+{
+    pub fn routes(cfg: &mut web::ServiceConfig) {
+        cfg.service(web::resource("/login").route(web::get().to(Self::login)))
+            .service(web::resource("/callback").route(web::get().to(Self::callback)));
+    }
+
+    /// HttpOnly, and sent along the top-level redirect back from the provider
+    fn pending_login_cookie(
+        req: &HttpRequest,
+        data: &RestAppState<P>,
+        value: String,
+    ) -> Cookie<'static> {
+        let secure = match &data.session_mode {
+            SessionMode::Cookie(settings) => settings.secure,
+            SessionMode::Bearer => req.connection_info().scheme() == "https",
+        };
+
+        Cookie::build(PENDING_LOGIN_COOKIE, value)
+            .path("/")
+            .http_only(true)
+            .secure(secure)
+            .same_site(SameSite::Lax)
+            .finish()
+    }
+
+    async fn login(
+        req: HttpRequest,
+        data: web::Data<RestAppState<P>>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let sso_service = data
+            .sso_service
+            .as_deref()
+            .ok_or_else(|| ErrorReponse::not_found("Single sign-on is not configured"))?;
+
+        let begin_sso_login_usecase = BeginSsoLoginUseCase::new(sso_service);
+        let redirect = begin_sso_login_usecase.execute().await?;
+
+        let mut cookie = Self::pending_login_cookie(&req, &data, redirect.pending);
+        cookie.set_max_age(Duration::seconds(SSO_PENDING_LOGIN_TTL.as_secs() as i64));
+
+        Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, redirect.url))
+            .cookie(cookie)
+            .finish())
+    }
+
+    /// Signs the user in the way `/auth/login` does, with a cookie session
+    /// or a bearer token
+    async fn callback(
+        req: HttpRequest,
+        data: web::Data<RestAppState<P>>,
+        query: web::Query<SsoCallbackPayload>,
+    ) -> Result<HttpResponse, ErrorReponse> {
+        let sso_service = data
+            .sso_service
+            .as_deref()
+            .ok_or_else(|| ErrorReponse::not_found("Single sign-on is not configured"))?;
+
+        let payload = query.into_inner();
+        if let Some(error) = payload.error {
+            return Err(
+                UseCaseError::Unauthorized(payload.error_description.unwrap_or(error)).into(),
+            );
+        }
+
+        let pending = req
+            .cookie(PENDING_LOGIN_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+            .unwrap_or_default();
+
+        let complete_sso_login_usecase = CompleteSsoLoginUseCase::new(sso_service);
+        let identity = complete_sso_login_usecase
+            .execute(
+                payload.code.as_deref().unwrap_or_default(),
+                payload.state.as_deref().unwrap_or_default(),
+                &pending,
+            )
+            .await?;
+        let principal = Principal::new(identity.principal_name(), identity.roles);
+
+        // the pending login is done with, whatever the session mode
+        let mut cleared = Self::pending_login_cookie(&req, &data, String::new());
+        cleared.set_max_age(Duration::ZERO);
+        let mut response = HttpResponse::Ok();
+        response.cookie(cleared);
+
+        match &data.session_mode {
+            SessionMode::Bearer => {
+                let create_token_usecase = CreateTokenUseCase::new(data.auth_service.as_ref());
+                let token = create_token_usecase.execute_for(&principal).await?;
+
+                Ok(response.json(TokenPresenterMapper::to_api(token)))
+            }
+            SessionMode::Cookie(settings) => {
+                let create_session_usecase = CreateSessionUseCase::<P, R, _>::new(
+                    data.persistence_service.clone(),
+                    data.auth_service.as_ref(),
+                    settings.policy,
+                );
+                let session = create_session_usecase.execute_for(principal).await?;
+
+                for cookie in settings.session_cookies(&session) {
+                    response.cookie(cookie);
+                }
+
+                Ok(response.json(SessionPresenterMapper::to_api(session)))
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod controllers;
+mod payloads;
+
+pub use controllers::SsoControllers;
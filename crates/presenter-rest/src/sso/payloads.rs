@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// Query string the identity provider redirects back with
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SsoCallbackPayload {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
@@ -0,0 +1,30 @@
+use std::{collections::HashMap, net::IpAddr};
+
+/// How the tenant of a request is resolved
+///
+/// A principal bound to a tenant always gets that one. Otherwise the
+/// tenant comes from the header, when sent by an admin or a trusted proxy,
+/// then from the host the request was sent to, then falls back to the
+/// default. Anyone else's header is ignored.
+#[derive(Debug, Clone)]
+pub struct TenantSettings {
+    pub header_name: String,
+    /// Peers whose tenant header is taken as is, like a gateway resolving
+    /// tenants itself, and whose forwarded host is the one looked up
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Host name, without port, to tenant id
+    pub hosts: HashMap<String, String>,
+    /// Requests naming no tenant are rejected when unset
+    pub default_tenant: Option<String>,
+}
+
+impl Default for TenantSettings {
+    fn default() -> Self {
+        TenantSettings {
+            header_name: String::from("X-Tenant-ID"),
+            trusted_proxies: vec![],
+            hosts: HashMap::new(),
+            default_tenant: Some(String::from("default")),
+        }
+    }
+}
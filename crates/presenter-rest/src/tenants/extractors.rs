@@ -0,0 +1,31 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use app_core::usecases::UseCaseError;
+use app_domain::entities::TenantEntity;
+
+use crate::shared::error::ErrorReponse;
+
+/// Outcome of the tenant middleware, only failing the routes that need a tenant
+#[derive(Clone)]
+pub(crate) struct TenantResolution(pub Result<TenantEntity, UseCaseError>);
+
+/// The tenant whose catalogues the request is allowed to see
+pub struct CurrentTenant(pub TenantEntity);
+
+impl FromRequest for CurrentTenant {
+    type Error = ErrorReponse;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<TenantResolution>()
+                .cloned()
+                .ok_or_else(|| UseCaseError::Business("No tenant given".into()))
+                .and_then(|resolution| resolution.0)
+                .map(CurrentTenant)
+                .map_err(ErrorReponse::from),
+        )
+    }
+}
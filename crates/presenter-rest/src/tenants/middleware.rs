@@ -0,0 +1,92 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, HttpMessage,
+};
+use app_core::{
+    services::{Principal, ADMIN_ROLE},
+    usecases::UseCaseError,
+};
+use app_domain::entities::TenantEntity;
+
+use super::{extractors::TenantResolution, TenantSettings};
+use crate::shared::app_state::RestAppState;
+
+/// Resolve the tenant of the request, once authentication attached a principal
+pub async fn resolve_tenant<P, B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, actix_web::Error>
+where
+    P: 'static,
+    B: MessageBody,
+{
+    let data = req
+        .app_data::<web::Data<RestAppState<P>>>()
+        .cloned()
+        .expect("RestAppState must be registered");
+
+    let resolution = resolve(&req, &data.tenancy);
+    req.extensions_mut().insert(TenantResolution(resolution));
+    next.call(req).await
+}
+
+fn resolve(req: &ServiceRequest, settings: &TenantSettings) -> Result<TenantEntity, UseCaseError> {
+    let header = req
+        .headers()
+        .get(settings.header_name.as_str())
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| UseCaseError::Business("Invalid tenant header".into()))
+        })
+        .transpose()?;
+    let from_proxy = req
+        .peer_addr()
+        .is_some_and(|peer| settings.trusted_proxies.contains(&peer.ip()));
+    // forwarded headers name the host only when a proxy of ours sets them,
+    // anyone else could pick the tenant with them
+    let host = if from_proxy {
+        req.connection_info().host().to_string()
+    } else {
+        req.head()
+            .headers()
+            .get(header::HOST)
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
+            .unwrap_or_default()
+            .to_string()
+    };
+    let host = host
+        .rsplit_once(':')
+        .map_or(host.as_str(), |(name, _)| name);
+
+    let extensions = req.extensions();
+    let principal = extensions.get::<Principal>();
+    let own = principal.and_then(Principal::tenant);
+    let trusted = from_proxy || principal.is_some_and(|p| p.has_role(ADMIN_ROLE));
+
+    let tenant_id = match (own, header) {
+        (Some(own), Some(requested)) if own != requested => {
+            return Err(UseCaseError::Forbidden(format!(
+                "Not a member of tenant {}",
+                requested
+            )))
+        }
+        (Some(own), _) => own,
+        (None, Some(requested)) if trusted => requested,
+        (None, _) => settings
+            .hosts
+            .get(host)
+            .or(settings.default_tenant.as_ref())
+            .map(String::as_str)
+            .ok_or_else(|| UseCaseError::Business("No tenant given".into()))?,
+    };
+
+    if !TenantEntity::is_valid_id(tenant_id) {
+        return Err(UseCaseError::Business("Invalid tenant id".into()));
+    }
+    Ok(TenantEntity::new(tenant_id.into()))
+}
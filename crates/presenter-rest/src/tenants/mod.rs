@@ -0,0 +1,7 @@
+mod config;
+mod extractors;
+mod middleware;
+
+pub use config::TenantSettings;
+pub use extractors::CurrentTenant;
+pub use middleware::resolve_tenant;
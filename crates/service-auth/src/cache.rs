@@ -0,0 +1,492 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::DateTime;
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG, EXPIRES, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone)]
+pub struct CacheSettings {
+    /// Responses kept in memory, the least recently used ones go first
+    pub capacity: usize,
+    /// Also keep responses on disk, to survive restarts
+    pub disk_dir: Option<PathBuf>,
+    /// How long past its expiry a response may still be served while
+    /// upstream is down, unless it must be revalidated
+    pub max_stale: Duration,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            capacity: 256,
+            disk_dir: None,
+            max_stale: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// The directives of a `Cache-Control` response header this cache acts on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CacheControl {
+    pub no_store: bool,
+    /// Stored, but revalidated before every use
+    pub no_cache: bool,
+    /// Never served stale
+    pub must_revalidate: bool,
+    pub max_age: Option<Duration>,
+}
+
+impl CacheControl {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .fold(CacheControl::default(), |control, value| {
+                control.merge(&CacheControl::parse(value))
+            })
+    }
+
+    pub fn parse(value: &str) -> Self {
+        let mut control = CacheControl::default();
+        let mut shared_max_age = None;
+        for directive in value.split(',') {
+            let (name, argument) = match directive.split_once('=') {
+                Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                None => (directive, None),
+            };
+            let seconds = argument
+                .and_then(|a| a.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            match name.trim().to_ascii_lowercase().as_str() {
+                "no-store" => control.no_store = true,
+                "no-cache" => control.no_cache = true,
+                "must-revalidate" | "proxy-revalidate" => control.must_revalidate = true,
+                "max-age" => control.max_age = seconds.or(Some(Duration::ZERO)),
+                // this cache is shared by every user of the app
+                "s-maxage" => shared_max_age = seconds.or(Some(Duration::ZERO)),
+                _ => {}
+            }
+        }
+        control.max_age = shared_max_age.or(control.max_age);
+        control
+    }
+
+    fn merge(self, other: &CacheControl) -> Self {
+        CacheControl {
+            no_store: self.no_store || other.no_store,
+            no_cache: self.no_cache || other.no_cache,
+            must_revalidate: self.must_revalidate || other.must_revalidate,
+            max_age: other.max_age.or(self.max_age),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    pub body: Vec<u8>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: SystemTime,
+    pub control: CacheControl,
+    /// From `Expires`, only used without a max age
+    pub expires: Option<SystemTime>,
+}
+
+/// An invalid date, like `0`, means already expired
+fn parse_expires(value: &str) -> SystemTime {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .and_then(|date| u64::try_from(date.timestamp()).ok())
+        .map_or(UNIX_EPOCH, |secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+impl CachedResponse {
+    pub fn new(body: Vec<u8>, headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+                .map(String::from)
+        };
+        CachedResponse {
+            body,
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            stored_at: SystemTime::now(),
+            control: CacheControl::from_headers(headers),
+            expires: header(EXPIRES).map(|value| parse_expires(&value)),
+        }
+    }
+
+    /// The same body, as just confirmed by a 304 carrying these headers
+    ///
+    /// Headers the 304 sends replace the stored ones, those it leaves out
+    /// are kept, see RFC 9111 section 4.3.4.
+    pub fn revalidated(self, headers: &HeaderMap) -> Self {
+        let fresh = CachedResponse::new(self.body, headers);
+        CachedResponse {
+            etag: fresh.etag.or(self.etag),
+            last_modified: fresh.last_modified.or(self.last_modified),
+            control: if headers.contains_key(CACHE_CONTROL) {
+                fresh.control
+            } else {
+                self.control
+            },
+            expires: fresh.expires.or(self.expires),
+            ..fresh
+        }
+    }
+
+    fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.stored_at)
+            .unwrap_or_default()
+    }
+
+    /// How long the response is fresh for, the max age winning over `Expires`
+    fn lifetime(&self) -> Option<Duration> {
+        self.control.max_age.or_else(|| {
+            self.expires
+                .map(|expires| expires.duration_since(self.stored_at).unwrap_or_default())
+        })
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        !self.control.no_cache
+            && self
+                .lifetime()
+                .is_some_and(|lifetime| self.age() < lifetime)
+    }
+
+    pub fn is_usable_when_down(&self, max_stale: Duration) -> bool {
+        !self.control.must_revalidate
+            && self.age() <= self.lifetime().unwrap_or_default() + max_stale
+    }
+}
+
+/// How a response is written to disk
+#[derive(Serialize, Deserialize)]
+struct StoredResponse {
+    url: String,
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    stored_at: u64,
+    no_cache: bool,
+    must_revalidate: bool,
+    max_age: Option<u64>,
+    /// Seconds since the epoch, missing from files written before it was kept
+    #[serde(default)]
+    expires: Option<u64>,
+}
+
+impl StoredResponse {
+    fn new(url: &str, response: &CachedResponse) -> Self {
+        StoredResponse {
+            url: url.into(),
+            body: STANDARD.encode(&response.body),
+            etag: response.etag.clone(),
+            last_modified: response.last_modified.clone(),
+            stored_at: response
+                .stored_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            no_cache: response.control.no_cache,
+            must_revalidate: response.control.must_revalidate,
+            max_age: response.control.max_age.map(|max_age| max_age.as_secs()),
+            expires: response.expires.map(|expires| {
+                expires
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+        }
+    }
+
+    fn into_response(self) -> Option<CachedResponse> {
+        Some(CachedResponse {
+            body: STANDARD.decode(self.body).ok()?,
+            etag: self.etag,
+            last_modified: self.last_modified,
+            stored_at: UNIX_EPOCH + Duration::from_secs(self.stored_at),
+            control: CacheControl {
+                no_store: false,
+                no_cache: self.no_cache,
+                must_revalidate: self.must_revalidate,
+                max_age: self.max_age.map(Duration::from_secs),
+            },
+            expires: self
+                .expires
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        })
+    }
+}
+
+struct Entries {
+    responses: HashMap<String, (CachedResponse, u64)>,
+    /// Bumped on every use, the smallest one is the least recently used
+    clock: u64,
+}
+
+/// Upstream responses by URL, in memory and optionally on disk
+pub struct HttpCache {
+    settings: CacheSettings,
+    entries: Mutex<Entries>,
+}
+
+impl HttpCache {
+    pub fn new(settings: CacheSettings) -> Self {
+        HttpCache {
+            settings,
+            entries: Mutex::new(Entries {
+                responses: HashMap::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    pub fn max_stale(&self) -> Duration {
+        self.settings.max_stale
+    }
+
+    pub(crate) fn get(&self, url: &str) -> Option<CachedResponse> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.clock += 1;
+            let clock = entries.clock;
+            if let Some((response, used)) = entries.responses.get_mut(url) {
+                *used = clock;
+                return Some(response.clone());
+            }
+        }
+
+        let response = self.read_disk(url)?;
+        self.remember(url, response.clone());
+        Some(response)
+    }
+
+    /// Store the response, or forget the one stored when it says `no-store`
+    pub(crate) fn put(&self, url: &str, response: CachedResponse) {
+        if response.control.no_store {
+            self.remove(url);
+            return;
+        }
+        self.write_disk(url, &response);
+        self.remember(url, response);
+    }
+
+    fn remove(&self, url: &str) {
+        self.entries.lock().unwrap().responses.remove(url);
+        if let Some(path) = self.disk_path(url) {
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::error!("Can't remove {} from the disk cache: {}", url, e);
+                }
+            }
+        }
+    }
+
+    fn remember(&self, url: &str, response: CachedResponse) {
+        if self.settings.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+        entries.responses.insert(url.into(), (response, clock));
+        while entries.responses.len() > self.settings.capacity {
+            let oldest = entries
+                .responses
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(url, _)| url.clone());
+            if let Some(oldest) = oldest {
+                entries.responses.remove(&oldest);
+            }
+        }
+    }
+
+    fn disk_path(&self, url: &str) -> Option<PathBuf> {
+        let dir = self.settings.disk_dir.as_ref()?;
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Some(dir.join(format!("{}.json", key)))
+    }
+
+    fn read_disk(&self, url: &str) -> Option<CachedResponse> {
+        let stored: StoredResponse =
+            serde_json::from_slice(&fs::read(self.disk_path(url)?).ok()?).ok()?;
+        // a digest collision must not serve another url
+        if stored.url != url {
+            return None;
+        }
+        stored.into_response()
+    }
+
+    /// Losing the disk copy only costs a request, so failures are logged
+    fn write_disk(&self, url: &str, response: &CachedResponse) {
+        let path = match self.disk_path(url) {
+            Some(path) => path,
+            None => return,
+        };
+        let written = serde_json::to_vec(&StoredResponse::new(url, response))
+            .map_err(std::io::Error::from)
+            .and_then(|json| {
+                fs::create_dir_all(path.parent().unwrap_or(&path))?;
+                // readers never see a half written file
+                let partial = path.with_extension("part");
+                fs::write(&partial, json)?;
+                fs::rename(&partial, &path)
+            });
+        if let Err(e) = written {
+            log::error!("Can't write {} to the disk cache: {}", url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str, cache_control: &str) -> CachedResponse {
+        CachedResponse {
+            body: body.into(),
+            etag: None,
+            last_modified: None,
+            stored_at: SystemTime::now(),
+            control: CacheControl::parse(cache_control),
+            expires: None,
+        }
+    }
+
+    fn headers(pairs: &[(reqwest::header::HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_should_parse_cache_control() {
+        // given directives as sent by upstream
+        let control = CacheControl::parse("public, max-age=60, s-maxage=\"120\", Must-Revalidate");
+
+        // then the shared max age wins
+        assert_eq!(
+            control,
+            CacheControl {
+                no_store: false,
+                no_cache: false,
+                must_revalidate: true,
+                max_age: Some(Duration::from_secs(120)),
+            }
+        );
+        assert!(CacheControl::parse("no-store").no_store);
+        assert!(!response("", "no-cache, max-age=60").is_fresh());
+        assert!(response("", "max-age=60").is_fresh());
+    }
+
+    #[test]
+    fn test_should_evict_least_recently_used() {
+        // given a full cache of two responses
+        let cache = HttpCache::new(CacheSettings {
+            capacity: 2,
+            ..CacheSettings::default()
+        });
+        cache.put("/a", response("a", "max-age=60"));
+        cache.put("/b", response("b", "max-age=60"));
+
+        // when the first is used again before a third comes in
+        assert!(cache.get("/a").is_some());
+        cache.put("/c", response("c", "max-age=60"));
+
+        // then the second one is gone
+        assert!(cache.get("/a").is_some());
+        assert!(cache.get("/b").is_none());
+        assert!(cache.get("/c").is_some());
+    }
+
+    #[test]
+    fn test_should_only_serve_stale_within_limit() {
+        // given a response expired a minute ago
+        let mut expired = response("a", "max-age=60");
+        expired.stored_at = SystemTime::now() - Duration::from_secs(120);
+
+        // then it is only usable with enough staleness allowed, and never
+        // once it must be revalidated
+        assert!(expired.is_usable_when_down(Duration::from_secs(90)));
+        assert!(!expired.is_usable_when_down(Duration::from_secs(30)));
+        expired.control.must_revalidate = true;
+        assert!(!expired.is_usable_when_down(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn test_should_keep_stored_freshness_when_304_omits_it() {
+        // given a response to be revalidated after a minute
+        let stored = response("a", "no-cache, max-age=60");
+
+        // when a 304 confirms it, with and without its own directives
+        let bare = stored.clone().revalidated(&headers(&[(ETAG, "\"v2\"")]));
+        let updated = stored.revalidated(&headers(&[(CACHE_CONTROL, "max-age=120")]));
+
+        // then the stored directives only go when replaced
+        assert_eq!(bare.control, CacheControl::parse("no-cache, max-age=60"));
+        assert_eq!(bare.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(updated.control, CacheControl::parse("max-age=120"));
+    }
+
+    #[test]
+    fn test_should_honour_expires_without_max_age() {
+        // given responses expiring in an hour, already, or in an hour but
+        // with a max age of zero
+        let in_an_hour = chrono::Utc::now() + chrono::Duration::hours(1);
+        let in_an_hour = in_an_hour.to_rfc2822();
+        let later = CachedResponse::new(vec![], &headers(&[(EXPIRES, &in_an_hour)]));
+        let past = CachedResponse::new(vec![], &headers(&[(EXPIRES, "0")]));
+        let overridden = CachedResponse::new(
+            vec![],
+            &headers(&[(EXPIRES, &in_an_hour), (CACHE_CONTROL, "max-age=0")]),
+        );
+
+        // then only the first is fresh
+        assert!(later.is_fresh());
+        assert!(!past.is_fresh());
+        assert!(!overridden.is_fresh());
+    }
+
+    #[test]
+    fn test_should_evict_response_turned_no_store() {
+        // given a stored response, on disk too
+        let dir = std::env::temp_dir().join(format!("http-cache-{}", rand::random::<u64>()));
+        let cache = HttpCache::new(CacheSettings {
+            disk_dir: Some(dir.clone()),
+            ..CacheSettings::default()
+        });
+        cache.put("/a", response("a", "max-age=60"));
+
+        // when a revalidation says it must not be stored anymore
+        let stored = cache.get("/a").unwrap();
+        cache.put(
+            "/a",
+            stored.revalidated(&headers(&[(CACHE_CONTROL, "no-store")])),
+        );
+
+        // then it is gone from memory and disk
+        assert!(cache.get("/a").is_none());
+        assert!(fs::read_dir(&dir).unwrap().next().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::HttpError;
+
+/// Response headers never written to a cassette, which is committed
+const SENSITIVE_HEADERS: &[&str] = &[
+    "set-cookie",
+    "authorization",
+    "proxy-authorization",
+    "www-authenticate",
+    "proxy-authenticate",
+];
+
+/// Credentials and whatever looks like them, like `x-api-key` or
+/// `x-amz-security-token`
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_HEADERS.contains(&name.as_str())
+        || ["token", "secret", "key", "session", "signature"]
+            .iter()
+            .any(|part| name.contains(part))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests upstream, and keep them along their responses
+    Record,
+    /// Answer requests from the cassette, without ever reaching upstream
+    Replay,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl RecordedRequest {
+    fn new(request: &Request) -> Self {
+        RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| String::from_utf8_lossy(body).into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl RecordedResponse {
+    fn to_response(&self) -> Result<Response, HttpError> {
+        let mut builder = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        let response = builder
+            .body(self.body.clone())
+            .map_err(|e| HttpError::Cassette(e.to_string()))?;
+        Ok(Response::from(response))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+    unmatched: Vec<RecordedRequest>,
+}
+
+/// Upstream interactions kept in a JSON file, to test against real
+/// responses offline
+///
+/// Identical requests are answered in the order they were recorded, each
+/// interaction once. Clones share the tape.
+#[derive(Clone)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    tape: Arc<Mutex<Tape>>,
+}
+
+impl Cassette {
+    /// Load the file to replay it, or start an empty tape to record
+    pub fn new(path: impl Into<PathBuf>, mode: CassetteMode) -> io::Result<Self> {
+        let path = path.into();
+        let interactions: Vec<Interaction> = match mode {
+            CassetteMode::Record => vec![],
+            CassetteMode::Replay => serde_json::from_slice(&fs::read(&path)?)?,
+        };
+
+        Ok(Cassette {
+            path,
+            mode,
+            tape: Arc::new(Mutex::new(Tape {
+                played: vec![false; interactions.len()],
+                interactions,
+                unmatched: vec![],
+            })),
+        })
+    }
+
+    /// Replay, unless `CASSETTE_MODE=record` asks for the file to be
+    /// recorded again
+    pub fn from_env(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let mode = match std::env::var("CASSETTE_MODE").as_deref() {
+            Ok("record") => CassetteMode::Record,
+            _ => CassetteMode::Replay,
+        };
+        Cassette::new(path, mode)
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub(crate) fn replay(&self, request: &Request) -> Result<Response, HttpError> {
+        let request = RecordedRequest::new(request);
+        let mut tape = self.tape.lock().unwrap();
+        let Tape {
+            interactions,
+            played,
+            unmatched,
+        } = &mut *tape;
+
+        let next = interactions
+            .iter()
+            .zip(played.iter_mut())
+            .find(|(interaction, played)| !**played && interaction.request == request);
+        match next {
+            Some((interaction, played)) => {
+                *played = true;
+                interaction.response.to_response()
+            }
+            None => {
+                let error = HttpError::Unmatched(format!("{} {}", request.method, request.url));
+                unmatched.push(request);
+                Err(error)
+            }
+        }
+    }
+
+    /// Keep the interaction, handing back a response as good as the one read
+    ///
+    /// Sensitive headers are left out of the cassette, and of the response
+    /// handed back for it to behave as it will when replayed.
+    pub(crate) async fn record(
+        &self,
+        request: &Request,
+        response: Response,
+    ) -> Result<Response, HttpError> {
+        let request = RecordedRequest::new(request);
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| !is_sensitive(name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = String::from_utf8_lossy(&response.bytes().await?).into_owned();
+        let recorded = RecordedResponse {
+            status,
+            headers,
+            body,
+        };
+
+        let response = recorded.to_response()?;
+        let mut tape = self.tape.lock().unwrap();
+        tape.interactions.push(Interaction {
+            request,
+            response: recorded,
+        });
+        tape.played.push(true);
+        Ok(response)
+    }
+
+    /// Requests the cassette had no answer for
+    pub fn unmatched(&self) -> Vec<RecordedRequest> {
+        self.tape.lock().unwrap().unmatched.clone()
+    }
+
+    /// Interactions never asked for
+    pub fn unplayed(&self) -> Vec<Interaction> {
+        let tape = self.tape.lock().unwrap();
+        tape.interactions
+            .iter()
+            .zip(&tape.played)
+            .filter(|(_, played)| !**played)
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    /// Write what was recorded to the file
+    pub fn save(&self) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.tape.lock().unwrap().interactions)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, json)
+    }
+
+    /// End of a test: save what was recorded, or panic when replay got a
+    /// request it had no answer for
+    pub fn eject(&self) {
+        match self.mode {
+            CassetteMode::Record => self.save().expect("Can't save the cassette"),
+            CassetteMode::Replay => {
+                let unmatched = self.unmatched();
+                assert!(
+                    unmatched.is_empty(),
+                    "{} got requests it has no answer for: {:?}",
+                    self.path.display(),
+                    unmatched
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cassette(interactions: Vec<Interaction>) -> Cassette {
+        Cassette {
+            path: PathBuf::from("unused.json"),
+            mode: CassetteMode::Replay,
+            tape: Arc::new(Mutex::new(Tape {
+                played: vec![false; interactions.len()],
+                interactions,
+                unmatched: vec![],
+            })),
+        }
+    }
+
+    fn interaction(url: &str, body: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest {
+                method: String::from("GET"),
+                url: url.into(),
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: vec![(String::from("content-type"), String::from("text/plain"))],
+                body: body.into(),
+            },
+        }
+    }
+
+    fn get(url: &str) -> Request {
+        reqwest::Client::new().get(url).build().unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_should_replay_identical_requests_in_order() {
+        // given the same request recorded twice
+        let cassette = cassette(vec![
+            interaction("http://upstream/fact", "first"),
+            interaction("http://upstream/fact", "second"),
+        ]);
+
+        // when replaying it twice
+        let first = cassette.replay(&get("http://upstream/fact")).unwrap();
+        let second = cassette.replay(&get("http://upstream/fact")).unwrap();
+
+        // then each answer is given once, in order
+        assert_eq!(first.text().await.unwrap(), "first");
+        assert_eq!(second.text().await.unwrap(), "second");
+        assert!(cassette.unplayed().is_empty());
+    }
+
+    #[test]
+    fn test_should_report_unmatched_requests() {
+        // given a cassette of a single interaction
+        let cassette = cassette(vec![interaction("http://upstream/fact", "first")]);
+
+        // when asked for something else
+        let result = cassette.replay(&get("http://upstream/facts?page=1"));
+
+        // then expect an error, and the request reported
+        assert!(matches!(result, Err(HttpError::Unmatched(_))));
+        assert_eq!(
+            cassette.unmatched()[0].url,
+            "http://upstream/facts?page=1".to_string()
+        );
+        assert_eq!(cassette.unplayed().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_leave_sensitive_headers_out_of_recording() {
+        // given an upstream response setting credentials
+        let cassette = Cassette {
+            mode: CassetteMode::Record,
+            ..cassette(vec![])
+        };
+        let response = http::Response::builder()
+            .header("content-type", "application/json")
+            .header("set-cookie", "session=s3cr3t")
+            .header("x-api-key", "k3y")
+            .header("X-Amz-Security-Token", "t0k3n")
+            .body("{}")
+            .unwrap();
+
+        // when recording it
+        let replayed = cassette
+            .record(&get("http://upstream/fact"), Response::from(response))
+            .await
+            .unwrap();
+
+        // then only the harmless headers are kept
+        let recorded = &cassette.tape.lock().unwrap().interactions[0];
+        assert_eq!(
+            recorded.response.headers,
+            vec![(
+                String::from("content-type"),
+                String::from("application/json")
+            )]
+        );
+        assert_eq!(replayed.headers().len(), 1);
+    }
+}
@@ -1,17 +1,66 @@
 use async_trait::async_trait;
-use std::error::Error;
+use serde::de::DeserializeOwned;
 
-use crate::connection::HttpConnection;
-use app_core::services::AuthService;
+use crate::{
+    connection::HttpConnection,
+    mappers::CatFactHttpMapper,
+    models::{CatFactApiModel, CatFactsApiModel},
+};
+use app_core::{
+    mappers::service::ServiceMapper,
+    services::{CatFactsService, FactsPage, UpstreamError},
+};
+use app_domain::entities::CatFactEntity;
 
+/// Live cat facts from a catfact.ninja compatible API
 pub struct CatFactsserviceHTTP {
     pub http_connection: HttpConnection,
+    /// Base URL of the API, e.g. `https://catfact.ninja`
     pub source: String,
 }
 
-#[async_trait(?Send)]
-impl AuthService for CatFactsserviceHTTP {
-    async fn login(&self, _username: &str, _password: &str) -> Result<(), Box<dyn Error>> {
-        Ok(())
+impl CatFactsserviceHTTP {
+    pub fn new(http_connection: HttpConnection, source: String) -> Self {
+        CatFactsserviceHTTP {
+            http_connection,
+            source,
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, UpstreamError> {
+        let url = format!("{}{}", self.source.trim_end_matches('/'), path);
+        let body = self
+            .http_connection
+            .fetch(&url)
+            .await
+            .map_err(|e| UpstreamError(e.to_string()))?;
+        serde_json::from_slice(&body).map_err(|e| UpstreamError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl CatFactsService for CatFactsserviceHTTP {
+    async fn get_random_cat_fact(&self) -> Result<CatFactEntity, UpstreamError> {
+        let fact = self.get_json::<CatFactApiModel>("/fact").await?;
+        Ok(CatFactHttpMapper::to_entity(fact))
+    }
+
+    async fn get_cat_facts_page(
+        &self,
+        page: i32,
+    ) -> Result<FactsPage<CatFactEntity>, UpstreamError> {
+        let facts = self
+            .get_json::<CatFactsApiModel>(&format!("/facts?page={}", page))
+            .await?;
+
+        Ok(FactsPage {
+            facts: facts
+                .data
+                .into_iter()
+                .map(CatFactHttpMapper::to_entity)
+                .collect(),
+            current_page: facts.current_page,
+            last_page: facts.last_page,
+        })
     }
 }
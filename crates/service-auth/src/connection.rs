@@ -1,9 +1,478 @@
-use reqwest;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-pub struct HttpConnection {}
+use rand::Rng;
+use reqwest::{
+    header::{IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    Method, Request, RequestBuilder, Response, StatusCode,
+};
+use thiserror::Error;
+
+use crate::{
+    cache::{CachedResponse, HttpCache},
+    cassette::{Cassette, CassetteMode},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct HttpSettings {
+    /// Time allowed to open a connection, TLS handshake included
+    pub connect_timeout: Duration,
+    /// Time allowed for a whole request, until the body is read
+    pub request_timeout: Duration,
+    /// Retries of idempotent requests after a connection error, a timeout or
+    /// a server error, others are sent once
+    pub max_retries: u32,
+    /// Ceiling of the first retry delay, doubling after each one, the actual
+    /// delay is picked at random below it
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Failures in a row before requests to a host are refused outright
+    pub breaker_threshold: u32,
+    /// How long requests are refused before one is let through to probe the host
+    pub breaker_open_duration: Duration,
+    /// Time between two logs of the metrics of every host, none when unset
+    pub metrics_log_interval: Option<Duration>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            connect_timeout: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(10),
+            max_retries: 2,
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            breaker_threshold: 5,
+            breaker_open_duration: Duration::from_secs(30),
+            metrics_log_interval: Some(Duration::from_secs(300)),
+        }
+    }
+}
+
+/// Counters of the requests sent to one host
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostMetrics {
+    /// Attempts sent, retries included
+    pub requests: u64,
+    /// Attempts ending in a connection error, a timeout or a server error
+    pub failures: u64,
+    pub timeouts: u64,
+    pub retries: u64,
+    /// Requests refused while the circuit was open
+    pub rejected: u64,
+    /// Times the circuit opened
+    pub circuit_opened: u64,
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// A probe is in flight once the circuit is half open
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn allow(&mut self, now: Instant, open_duration: Duration) -> bool {
+        match self.open_until {
+            None => true,
+            Some(until) if now < until => false,
+            // half open, a single probe at a time, in case one got cancelled
+            // without telling how it went
+            Some(_) => match self.probe_started {
+                Some(started) if now - started < open_duration => false,
+                _ => {
+                    self.probe_started = Some(now);
+                    true
+                }
+            },
+        }
+    }
+
+    fn on_success(&mut self) {
+        *self = CircuitBreaker::default();
+    }
+
+    /// Returns whether the circuit just opened
+    fn on_failure(&mut self, now: Instant, threshold: u32, open_duration: Duration) -> bool {
+        self.consecutive_failures += 1;
+        let probe_failed = self.probe_started.take().is_some();
+        if probe_failed || self.consecutive_failures >= threshold {
+            let was_closed = self.open_until.is_none();
+            self.open_until = Some(now + open_duration);
+            was_closed
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Default)]
+struct HostState {
+    breaker: CircuitBreaker,
+    metrics: HostMetrics,
+}
+
+#[derive(Error, Debug)]
+pub enum HttpError {
+    #[error("{0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Circuit open for {0}, not sending requests for now")]
+    CircuitOpen(String),
+    #[error("No recorded response for {0}")]
+    Unmatched(String),
+    #[error("Cassette error: {0}")]
+    Cassette(String),
+}
+
+/// The HTTP client shared by every upstream service
+///
+/// Clones share the connection pool, the circuit breakers, the metrics and
+/// the cache.
+#[derive(Clone)]
+pub struct HttpConnection {
+    client: reqwest::Client,
+    settings: HttpSettings,
+    hosts: Arc<Mutex<HashMap<String, HostState>>>,
+    cache: Option<Arc<HttpCache>>,
+    cassette: Option<Cassette>,
+}
 
 impl HttpConnection {
-    pub fn client(&self) -> reqwest::Client {
-        reqwest::Client::new()
+    pub fn new(settings: HttpSettings) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.request_timeout)
+            .build()
+            .expect("Can't build the HTTP client");
+
+        HttpConnection {
+            client,
+            settings,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+            cache: None,
+            cassette: None,
+        }
+    }
+
+    /// Keep the responses of `fetch` as upstream allows
+    pub fn with_cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Record requests to the cassette, or answer them from it, for tests
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// A GET on the shared client, for `send` to apply the circuit
+    /// breakers, retries and metrics
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// A POST on the shared client, for `send` to apply the circuit
+    /// breakers and metrics
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Counters of every host requested so far
+    pub fn metrics(&self) -> HashMap<String, HostMetrics> {
+        self.hosts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(host, state)| (host.clone(), state.metrics))
+            .collect()
+    }
+
+    /// Log the metrics of every host on each `metrics_log_interval`, in the
+    /// background, for as long as the runtime lives
+    pub fn log_metrics(&self) {
+        let interval = match self.settings.metrics_log_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let connection = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for (host, metrics) in connection.metrics() {
+                    log::info!(
+                        "Requests to {}: {} sent, {} failed, {} timed out, {} retried, {} rejected, circuit opened {} times",
+                        host,
+                        metrics.requests,
+                        metrics.failures,
+                        metrics.timeouts,
+                        metrics.retries,
+                        metrics.rejected,
+                        metrics.circuit_opened
+                    );
+                }
+            }
+        });
+    }
+
+    /// Send a request through the circuit breaker of its host, retrying
+    /// idempotent ones
+    ///
+    /// A server error is returned as a response once retries are exhausted,
+    /// for the caller to handle like any other status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpError> {
+        let request = request.build()?;
+        match &self.cassette {
+            None => self.send_live(request).await,
+            // replayed requests never fail, nor reach the circuit breakers
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => cassette.replay(&request),
+            Some(cassette) => {
+                let recorded = request.try_clone();
+                let response = self.send_live(request).await?;
+                match recorded {
+                    Some(recorded) => cassette.record(&recorded, response).await,
+                    None => Ok(response),
+                }
+            }
+        }
+    }
+
+    async fn send_live(&self, request: Request) -> Result<Response, HttpError> {
+        let host = match (
+            request.url().host_str(),
+            request.url().port_or_known_default(),
+        ) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (host, _) => host.unwrap_or_default().to_string(),
+        };
+        let idempotent = matches!(
+            *request.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+        );
+        let max_retries = if idempotent {
+            self.settings.max_retries
+        } else {
+            0
+        };
+
+        let mut request = Some(request);
+        let mut attempt = 0;
+        loop {
+            let current = request.take().expect("request consumed");
+            // streamed bodies can't be sent twice
+            let next = current.try_clone();
+            let retries_left = attempt < max_retries && next.is_some();
+
+            if !self.allow(&host) {
+                return Err(HttpError::CircuitOpen(host));
+            }
+
+            match self.client.execute(current).await {
+                Ok(response) if !is_failure(response.status()) => {
+                    self.record(&host, None);
+                    return Ok(response);
+                }
+                Ok(response) => {
+                    self.record(&host, Some(false));
+                    if !retries_left {
+                        return Ok(response);
+                    }
+                }
+                Err(e) => {
+                    self.record(&host, Some(e.is_timeout()));
+                    if !retries_left {
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            tokio::time::sleep(self.backoff(attempt)).await;
+            if let Some(state) = self.hosts.lock().unwrap().get_mut(&host) {
+                state.metrics.retries += 1;
+            }
+            request = next;
+            attempt += 1;
+        }
+    }
+
+    /// GET the body at `url`, through the cache when there is one
+    ///
+    /// Fresh responses are served without a request, expired ones are
+    /// revalidated with their `ETag` and `Last-Modified`, and served stale
+    /// within the allowed staleness while upstream is failing.
+    pub async fn fetch(&self, url: &str) -> Result<Vec<u8>, HttpError> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => {
+                let response = self.send(self.client.get(url)).await?;
+                return Ok(response.error_for_status()?.bytes().await?.to_vec());
+            }
+        };
+
+        let cached = cache.get(url);
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.is_fresh()) {
+            return Ok(cached.body.clone());
+        }
+
+        let mut request = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match (self.send(request).await, cached) {
+            (Ok(response), Some(cached)) if response.status() == StatusCode::NOT_MODIFIED => {
+                let revalidated = cached.revalidated(response.headers());
+                let body = revalidated.body.clone();
+                cache.put(url, revalidated);
+                return Ok(body);
+            }
+            (Ok(response), _) if !is_failure(response.status()) => response,
+            (result, Some(cached)) if cached.is_usable_when_down(cache.max_stale()) => {
+                let reason = match result {
+                    Ok(response) => response.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                log::warn!("Serving a stale response of {}: {}", url, reason);
+                return Ok(cached.body);
+            }
+            (result, _) => result?,
+        };
+
+        let response = response.error_for_status()?;
+        let headers = response.headers().clone();
+        let body = response.bytes().await?.to_vec();
+        cache.put(url, CachedResponse::new(body.clone(), &headers));
+        Ok(body)
+    }
+
+    fn allow(&self, host: &str) -> bool {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        let allowed = state
+            .breaker
+            .allow(Instant::now(), self.settings.breaker_open_duration);
+        if allowed {
+            state.metrics.requests += 1;
+        } else {
+            state.metrics.rejected += 1;
+        }
+        allowed
+    }
+
+    /// Record the outcome of an attempt, `None` for a success, otherwise
+    /// whether the failure was a timeout
+    fn record(&self, host: &str, failure: Option<bool>) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        let timeout = match failure {
+            None => return state.breaker.on_success(),
+            Some(timeout) => timeout,
+        };
+
+        state.metrics.failures += 1;
+        if timeout {
+            state.metrics.timeouts += 1;
+        }
+        let opened = state.breaker.on_failure(
+            Instant::now(),
+            self.settings.breaker_threshold,
+            self.settings.breaker_open_duration,
+        );
+        if opened {
+            state.metrics.circuit_opened += 1;
+            log::warn!(
+                "Circuit opened for {} after {} failures in a row",
+                host,
+                state.breaker.consecutive_failures
+            );
+        }
+    }
+
+    /// Full jitter, anywhere between nothing and the exponential ceiling
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .settings
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.settings.max_backoff);
+        ceiling.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+    }
+}
+
+fn is_failure(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn test_should_open_after_threshold_and_probe_once() {
+        // given a breaker failing up to the threshold
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+        assert!(!breaker.on_failure(now, 2, OPEN));
+        assert!(breaker.on_failure(now, 2, OPEN));
+
+        // when requests come while open, and after
+        let while_open = breaker.allow(now + OPEN / 2, OPEN);
+        let probe = breaker.allow(now + OPEN, OPEN);
+        let during_probe = breaker.allow(now + OPEN, OPEN);
+
+        // then only a single probe goes through
+        assert!(!while_open);
+        assert!(probe);
+        assert!(!during_probe);
+    }
+
+    #[test]
+    fn test_should_close_on_successful_probe_and_reopen_on_failed_one() {
+        // given two open breakers letting a probe through
+        let now = Instant::now();
+        let mut recovered = CircuitBreaker::default();
+        let mut still_down = CircuitBreaker::default();
+        for breaker in [&mut recovered, &mut still_down] {
+            breaker.on_failure(now, 1, OPEN);
+            assert!(breaker.allow(now + OPEN, OPEN));
+        }
+
+        // when the probes succeed and fail
+        recovered.on_success();
+        still_down.on_failure(now + OPEN, 1, OPEN);
+
+        // then one is closed, the other open again
+        assert!(recovered.allow(now + OPEN, OPEN));
+        assert!(!still_down.allow(now + OPEN, OPEN));
+    }
+
+    #[test]
+    fn test_should_cap_jittered_backoff() {
+        // given a connection with a small ceiling
+        let connection = HttpConnection::new(HttpSettings {
+            base_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            ..HttpSettings::default()
+        });
+
+        // when backing off after many attempts
+        // then never beyond the ceiling
+        for attempt in 0..10 {
+            assert!(connection.backoff(attempt) <= Duration::from_millis(300));
+        }
+        assert!(connection.backoff(0) <= Duration::from_millis(100));
     }
 }
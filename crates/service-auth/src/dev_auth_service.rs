@@ -0,0 +1,175 @@
+use std::{collections::HashMap, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::local_accounts::{LocalRoles, TokenStore};
+use app_core::services::{AuthError, AuthService, AuthToken, Principal};
+
+/// Accepts any password, for local development only
+///
+/// Roles come from configuration, see `LocalRoles`, tokens are kept in
+/// memory, see `TokenStore`.
+pub struct DevAuthService {
+    roles: LocalRoles,
+    tokens: TokenStore,
+}
+
+impl DevAuthService {
+    pub fn new(admin_users: Vec<String>, token_ttl: Duration) -> Self {
+        DevAuthService {
+            roles: LocalRoles {
+                admin_users,
+                editor_users: vec![],
+                user_tenants: HashMap::new(),
+            },
+            tokens: TokenStore::new(token_ttl),
+        }
+    }
+
+    pub fn with_editor_users(mut self, editor_users: Vec<String>) -> Self {
+        self.roles.editor_users = editor_users;
+        self
+    }
+
+    pub fn with_user_tenants(mut self, user_tenants: HashMap<String, String>) -> Self {
+        self.roles.user_tenants = user_tenants;
+        self
+    }
+}
+
+#[async_trait]
+impl AuthService for DevAuthService {
+    async fn login(
+        &self,
+        username: &str,
+        _password: &str,
+        _client_ip: Option<IpAddr>,
+    ) -> Result<Principal, AuthError> {
+        if username.trim().is_empty() {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        Ok(self.roles.principal(username))
+    }
+
+    async fn issue_token(&self, principal: &Principal) -> Result<AuthToken, AuthError> {
+        Ok(self.tokens.issue(principal))
+    }
+
+    async fn verify_token(&self, token: &str) -> Result<Principal, AuthError> {
+        self.tokens.verify(token)
+    }
+
+    async fn logout(&self, token: &str) -> Result<(), AuthError> {
+        self.tokens.revoke(token);
+        Ok(())
+    }
+
+    async fn revoke_tokens(&self, username: &str) -> Result<(), AuthError> {
+        self.tokens.revoke_user(username);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app_core::services::{ADMIN_ROLE, EDITOR_ROLE};
+
+    #[actix_rt::test]
+    async fn test_should_grant_admin_role_to_admin_users() {
+        // given a service with one admin
+        let service = DevAuthService::new(vec!["root".into()], Duration::from_secs(60));
+
+        // when both an admin and a user log in
+        let admin = service.login("root", "any", None).await.unwrap();
+        let user = service.login("jane", "any", None).await.unwrap();
+
+        // then only the admin gets the role
+        assert!(admin.has_role(ADMIN_ROLE));
+        assert!(!user.has_role(ADMIN_ROLE));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_grant_editor_role_to_editor_users() {
+        // given a service with one editor
+        let service = DevAuthService::new(vec![], Duration::from_secs(60))
+            .with_editor_users(vec!["jane".into()]);
+
+        // when both the editor and another user log in
+        let editor = service.login("jane", "any", None).await.unwrap();
+        let user = service.login("john", "any", None).await.unwrap();
+
+        // then only the editor may change facts
+        assert!(editor.has_role(EDITOR_ROLE));
+        assert!(editor.is_editor());
+        assert!(!user.is_editor());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_bind_users_to_their_tenant() {
+        // given a user assigned to a tenant
+        let service = DevAuthService::new(vec![], Duration::from_secs(60))
+            .with_user_tenants(HashMap::from([("jane".into(), "acme".into())]));
+
+        // when logging in
+        let principal = service.login("jane", "any", None).await.unwrap();
+
+        // then bound to that tenant
+        assert_eq!(principal.tenant(), Some("acme"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_revoke_token_on_logout() {
+        // given a token issued to a principal
+        let service = DevAuthService::new(vec![], Duration::from_secs(60));
+        let principal = service.login("jane", "any", None).await.unwrap();
+        let issued = service.issue_token(&principal).await.unwrap();
+        assert_eq!(
+            service.verify_token(&issued.token).await.unwrap(),
+            principal
+        );
+
+        // when logging out
+        service.logout(&issued.token).await.unwrap();
+
+        // then the token is no longer valid
+        assert!(matches!(
+            service.verify_token(&issued.token).await,
+            Err(AuthError::InvalidToken)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_expire_token() {
+        // given a service issuing already expired tokens
+        let service = DevAuthService::new(vec![], Duration::ZERO);
+        let principal = service.login("jane", "any", None).await.unwrap();
+        let issued = service.issue_token(&principal).await.unwrap();
+
+        // when verifying the token
+        let result = service.verify_token(&issued.token).await;
+
+        // then it is rejected
+        assert!(matches!(result, Err(AuthError::InvalidToken)));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_revoke_every_token_of_a_user() {
+        // given tokens issued to two users
+        let service = DevAuthService::new(vec![], Duration::from_secs(60));
+        let jane = service.login("jane", "any", None).await.unwrap();
+        let john = service.login("john", "any", None).await.unwrap();
+        let first = service.issue_token(&jane).await.unwrap();
+        let second = service.issue_token(&jane).await.unwrap();
+        let other = service.issue_token(&john).await.unwrap();
+
+        // when revoking the tokens of one
+        service.revoke_tokens("jane").await.unwrap();
+
+        // then only theirs are no longer valid
+        assert!(service.verify_token(&first.token).await.is_err());
+        assert!(service.verify_token(&second.token).await.is_err());
+        assert_eq!(service.verify_token(&other.token).await.unwrap(), john);
+    }
+}
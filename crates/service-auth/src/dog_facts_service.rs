@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+use crate::{connection::HttpConnection, mappers::DogFactHttpMapper, models::DogFactsApiModel};
+use app_core::{
+    mappers::service::ServiceMapper,
+    services::{DogFactsService, FactsPage, UpstreamError},
+};
+use app_domain::entities::DogFactEntity;
+
+/// Live dog facts from a dogapi.dog compatible API
+pub struct DogFactsServiceHTTP {
+    pub http_connection: HttpConnection,
+    /// Base URL of the API, e.g. `https://dogapi.dog/api/v2`
+    pub source: String,
+}
+
+impl DogFactsServiceHTTP {
+    pub fn new(http_connection: HttpConnection, source: String) -> Self {
+        DogFactsServiceHTTP {
+            http_connection,
+            source,
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, UpstreamError> {
+        let url = format!("{}{}", self.source.trim_end_matches('/'), path);
+        let body = self
+            .http_connection
+            .fetch(&url)
+            .await
+            .map_err(|e| UpstreamError(e.to_string()))?;
+        serde_json::from_slice(&body).map_err(|e| UpstreamError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl DogFactsService for DogFactsServiceHTTP {
+    async fn get_dog_facts_page(
+        &self,
+        page: i32,
+    ) -> Result<FactsPage<DogFactEntity>, UpstreamError> {
+        let facts = self
+            .get_json::<DogFactsApiModel>(&format!("/facts?page[number]={}", page))
+            .await?;
+
+        Ok(FactsPage {
+            facts: facts
+                .data
+                .into_iter()
+                .map(DogFactHttpMapper::to_entity)
+                .collect(),
+            current_page: facts.meta.pagination.current,
+            last_page: facts.meta.pagination.last,
+        })
+    }
+}
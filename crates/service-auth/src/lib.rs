@@ -1,4 +1,14 @@
+pub mod cache;
+pub mod cassette;
 pub mod cat_facts_service;
 pub mod connection;
+pub mod dev_auth_service;
+pub mod dog_facts_service;
+pub mod local_accounts;
+pub mod lockout_service;
+pub mod mailer;
 pub mod mappers;
 pub mod models;
+pub mod oidc_service;
+pub mod password_auth_service;
+pub mod password_hasher;
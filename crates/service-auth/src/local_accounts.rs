@@ -0,0 +1,109 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+
+use app_core::services::{
+    AuthError, AuthToken, Principal, ADMIN_ROLE, EDITOR_ROLE, TENANT_ROLE_PREFIX,
+};
+
+/// Roles given to local accounts by configuration
+///
+/// Configured admin and editor users get the matching role, users assigned
+/// to a tenant get bound to it.
+#[derive(Default)]
+pub struct LocalRoles {
+    pub admin_users: Vec<String>,
+    pub editor_users: Vec<String>,
+    /// Username to tenant id
+    pub user_tenants: HashMap<String, String>,
+}
+
+impl LocalRoles {
+    pub fn principal(&self, username: &str) -> Principal {
+        let mut roles = vec![];
+        if self.admin_users.iter().any(|admin| admin == username) {
+            roles.push(ADMIN_ROLE.into());
+        }
+        if self.editor_users.iter().any(|editor| editor == username) {
+            roles.push(EDITOR_ROLE.into());
+        }
+        if let Some(tenant) = self.user_tenants.get(username) {
+            roles.push(format!("{}{}", TENANT_ROLE_PREFIX, tenant));
+        }
+        Principal::new(username.into(), roles)
+    }
+}
+
+struct IssuedToken {
+    principal: Principal,
+    expires_at: Instant,
+}
+
+/// Bearer tokens kept in memory, so they don't survive a restart and
+/// aren't shared between instances
+pub struct TokenStore {
+    token_ttl: Duration,
+    tokens: Mutex<HashMap<String, IssuedToken>>,
+}
+
+impl TokenStore {
+    pub fn new(token_ttl: Duration) -> Self {
+        TokenStore {
+            token_ttl,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn issue(&self, principal: &Principal) -> AuthToken {
+        let token = random_token();
+        let now = Instant::now();
+
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, issued| issued.expires_at > now);
+        tokens.insert(
+            token.clone(),
+            IssuedToken {
+                principal: principal.clone(),
+                expires_at: now + self.token_ttl,
+            },
+        );
+
+        AuthToken {
+            token,
+            principal: principal.clone(),
+        }
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Principal, AuthError> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .get(token)
+            .filter(|issued| issued.expires_at > Instant::now())
+            .map(|issued| issued.principal.clone())
+            .ok_or(AuthError::InvalidToken)
+    }
+
+    pub fn revoke(&self, token: &str) {
+        self.tokens.lock().unwrap().remove(token);
+    }
+
+    /// Revoke every token issued to a user
+    pub fn revoke_user(&self, username: &str) {
+        self.tokens
+            .lock()
+            .unwrap()
+            .retain(|_, issued| issued.principal.username != username);
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
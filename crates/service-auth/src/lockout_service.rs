@@ -0,0 +1,494 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use app_core::services::{AuthAuditLog, AuthError, AuthService, AuthToken, Principal};
+use app_domain::entities::{AuthEventEntity, AuthEventKind};
+
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    /// Failures in a row before an account is locked
+    pub max_failures_per_account: u32,
+    /// Failures before an address is locked, higher as users may share it behind NAT
+    pub max_failures_per_ip: u32,
+    /// Wait imposed after the first failure, doubling after each one
+    pub base_backoff: Duration,
+    /// How long a lockout lasts, failures older than that are forgotten
+    pub lockout_duration: Duration,
+    /// Accounts and addresses tracked at most, beyond it the failures of the
+    /// longest quiet one are forgotten, for memory to stay bounded whatever
+    /// names and addresses an attacker tries
+    pub max_tracked: usize,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        LockoutPolicy {
+            max_failures_per_account: 5,
+            max_failures_per_ip: 20,
+            base_backoff: Duration::from_secs(1),
+            lockout_duration: Duration::from_secs(15 * 60),
+            max_tracked: 100_000,
+        }
+    }
+}
+
+struct Failures {
+    count: u32,
+    last_failure: Instant,
+    blocked_until: Instant,
+    /// Position in `FailureTracker::by_recency`
+    seq: u64,
+}
+
+/// Failures by key, ordered by their last one for the expired and the
+/// quietest ones to be dropped without a scan
+#[derive(Default)]
+struct FailureTracker {
+    by_key: HashMap<String, Failures>,
+    by_recency: BTreeMap<u64, String>,
+    next_seq: u64,
+}
+
+impl FailureTracker {
+    fn get(&self, key: &str) -> Option<&Failures> {
+        self.by_key.get(key)
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(failures) = self.by_key.remove(key) {
+            self.by_recency.remove(&failures.seq);
+        }
+    }
+
+    /// Forget the failures last seen `ttl` ago or more
+    fn expire(&mut self, now: Instant, ttl: Duration) {
+        while let Some(entry) = self.by_recency.first_entry() {
+            let expired = self
+                .by_key
+                .get(entry.get())
+                .is_none_or(|f| now - f.last_failure >= ttl);
+            if !expired {
+                break;
+            }
+            let key = entry.remove();
+            self.by_key.remove(&key);
+        }
+    }
+
+    /// Count a failure of a key, making room for it by forgetting the
+    /// quietest key when `capacity` keys are tracked already
+    fn fail(&mut self, key: &str, now: Instant, capacity: usize) -> &mut Failures {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        if let Some(failures) = self.by_key.get_mut(key) {
+            self.by_recency.remove(&failures.seq);
+        } else if self.by_key.len() >= capacity {
+            if let Some((_, quietest)) = self.by_recency.pop_first() {
+                self.by_key.remove(&quietest);
+            }
+        }
+        self.by_recency.insert(seq, key.to_string());
+
+        let failures = self.by_key.entry(key.to_string()).or_insert(Failures {
+            count: 0,
+            last_failure: now,
+            blocked_until: now,
+            seq,
+        });
+        failures.count += 1;
+        failures.last_failure = now;
+        failures.seq = seq;
+        failures
+    }
+}
+
+/// Brute-force protection around any `AuthService`
+///
+/// Failed logins are counted per account and per client address. Each
+/// failure blocks further attempts for an exponentially growing delay,
+/// until the threshold is reached and the whole lockout duration applies.
+pub struct LockoutAuthService<A> {
+    inner: A,
+    policy: LockoutPolicy,
+    audit_log: Box<dyn AuthAuditLog>,
+    failures: Mutex<FailureTracker>,
+}
+
+impl<A> LockoutAuthService<A> {
+    pub fn new(inner: A, policy: LockoutPolicy, audit_log: Box<dyn AuthAuditLog>) -> Self {
+        LockoutAuthService {
+            inner,
+            policy,
+            audit_log,
+            failures: Mutex::new(FailureTracker::default()),
+        }
+    }
+
+    fn account_key(username: &str) -> String {
+        format!("account:{}", username.trim().to_lowercase())
+    }
+
+    fn keys(username: &str, client_ip: Option<IpAddr>) -> Vec<(String, bool)> {
+        let mut keys = vec![(Self::account_key(username), true)];
+        if let Some(ip) = client_ip {
+            keys.push((format!("ip:{}", ip), false));
+        }
+        keys
+    }
+
+    fn retry_after(&self, keys: &[(String, bool)]) -> Option<Duration> {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        failures.expire(now, self.policy.lockout_duration);
+
+        keys.iter()
+            .filter_map(|(key, _)| failures.get(key))
+            .filter(|f| f.blocked_until > now)
+            .map(|f| f.blocked_until - now)
+            .max()
+    }
+
+    /// Count a failure against every key, returns whether the account got locked
+    fn register_failure(&self, keys: &[(String, bool)]) -> bool {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+        failures.expire(now, self.policy.lockout_duration);
+
+        let mut account_locked = false;
+        for (key, is_account) in keys {
+            let max_failures = if *is_account {
+                self.policy.max_failures_per_account
+            } else {
+                self.policy.max_failures_per_ip
+            };
+
+            let entry = failures.fail(key, now, self.policy.max_tracked);
+
+            let delay = if entry.count >= max_failures {
+                account_locked |= *is_account && entry.count == max_failures;
+                self.policy.lockout_duration
+            } else {
+                self.policy
+                    .base_backoff
+                    .saturating_mul(2u32.saturating_pow(entry.count - 1))
+                    .min(self.policy.lockout_duration)
+            };
+            entry.blocked_until = now + delay;
+        }
+        account_locked
+    }
+
+    async fn record(&self, kind: AuthEventKind, username: &str, client_ip: Option<IpAddr>) {
+        let event = AuthEventEntity::new(kind, username.into(), client_ip.map(|ip| ip.to_string()));
+        // losing an audit entry must not lock everybody out
+        if let Err(e) = self.audit_log.record(event).await {
+            log::error!("Can't record {} for {}: {}", kind, username, e);
+        }
+    }
+}
+
+#[async_trait]
+impl<A> AuthService for LockoutAuthService<A>
+where
+    A: AuthService,
+{
+    async fn login(
+        &self,
+        username: &str,
+        password: &str,
+        client_ip: Option<IpAddr>,
+    ) -> Result<Principal, AuthError> {
+        let keys = Self::keys(username, client_ip);
+
+        if let Some(retry_after) = self.retry_after(&keys) {
+            self.record(AuthEventKind::LoginThrottled, username, client_ip)
+                .await;
+            return Err(AuthError::Throttled { retry_after });
+        }
+
+        match self.inner.login(username, password, client_ip).await {
+            Ok(principal) => {
+                self.failures
+                    .lock()
+                    .unwrap()
+                    .remove(&Self::account_key(username));
+                self.record(AuthEventKind::LoginSucceeded, username, client_ip)
+                    .await;
+                Ok(principal)
+            }
+            Err(AuthError::InvalidCredentials) => {
+                let account_locked = self.register_failure(&keys);
+                self.record(AuthEventKind::LoginFailed, username, client_ip)
+                    .await;
+                if account_locked {
+                    self.record(AuthEventKind::AccountLocked, username, client_ip)
+                        .await;
+                }
+                Err(AuthError::InvalidCredentials)
+            }
+            // an unavailable backend says nothing about the credentials
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn issue_token(&self, principal: &Principal) -> Result<AuthToken, AuthError> {
+        self.inner.issue_token(principal).await
+    }
+
+    async fn verify_token(&self, token: &str) -> Result<Principal, AuthError> {
+        self.inner.verify_token(token).await
+    }
+
+    async fn logout(&self, token: &str) -> Result<(), AuthError> {
+        self.inner.logout(token).await
+    }
+
+    async fn revoke_tokens(&self, username: &str) -> Result<(), AuthError> {
+        self.inner.revoke_tokens(username).await
+    }
+
+    async fn unlock(&self, username: &str) -> Result<(), AuthError> {
+        self.failures
+            .lock()
+            .unwrap()
+            .remove(&Self::account_key(username));
+        self.record(AuthEventKind::AccountUnlocked, username, None)
+            .await;
+
+        self.inner.unlock(username).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app_core::services::RepositoryError;
+    use mockall::mock;
+    use std::sync::Arc;
+
+    mock! {
+        AuthService {}
+
+        #[async_trait]
+        impl AuthService for AuthService {
+            async fn login(
+                &self,
+                username: &str,
+                password: &str,
+                client_ip: Option<IpAddr>,
+            ) -> Result<Principal, AuthError>;
+            async fn issue_token(&self, principal: &Principal) -> Result<AuthToken, AuthError>;
+            async fn verify_token(&self, token: &str) -> Result<Principal, AuthError>;
+            async fn logout(&self, token: &str) -> Result<(), AuthError>;
+        }
+    }
+
+    /// Accepts "secret", fails as unavailable on "unavailable"
+    fn auth_service() -> MockAuthService {
+        let mut auth_service = MockAuthService::new();
+        auth_service
+            .expect_login()
+            .returning(|username, password, _client_ip| match password {
+                "secret" => Ok(Principal::new(username.into(), vec![])),
+                "unavailable" => Err(AuthError::Backend("Connection refused".into())),
+                _ => Err(AuthError::InvalidCredentials),
+            });
+        auth_service
+    }
+
+    #[derive(Default, Clone)]
+    struct FakeAuditLog(Arc<Mutex<Vec<AuthEventKind>>>);
+
+    #[async_trait]
+    impl AuthAuditLog for FakeAuditLog {
+        async fn record(&self, event: AuthEventEntity) -> Result<(), RepositoryError> {
+            self.0.lock().unwrap().push(event.kind);
+            Ok(())
+        }
+    }
+
+    const POLICY: LockoutPolicy = LockoutPolicy {
+        max_failures_per_account: 3,
+        max_failures_per_ip: 5,
+        base_backoff: Duration::ZERO,
+        lockout_duration: Duration::from_secs(60),
+        max_tracked: 100,
+    };
+
+    fn service(audit_log: &FakeAuditLog) -> LockoutAuthService<MockAuthService> {
+        LockoutAuthService::new(auth_service(), POLICY, Box::new(audit_log.clone()))
+    }
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[actix_rt::test]
+    async fn test_should_lock_account_after_threshold() {
+        // given an account failing to log in up to the threshold
+        let audit_log = FakeAuditLog::default();
+        let service = service(&audit_log);
+        for _ in 0..3 {
+            assert!(service.login("jane", "wrong", ip(1)).await.is_err());
+        }
+
+        // when logging in with the right password
+        let result = service.login("Jane", "secret", ip(2)).await;
+
+        // then locked out, whatever the address and case
+        assert!(matches!(result, Err(AuthError::Throttled { .. })));
+        assert_eq!(
+            *audit_log.0.lock().unwrap(),
+            vec![
+                AuthEventKind::LoginFailed,
+                AuthEventKind::LoginFailed,
+                AuthEventKind::LoginFailed,
+                AuthEventKind::AccountLocked,
+                AuthEventKind::LoginThrottled,
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_lock_address_across_accounts() {
+        // given one address failing against many accounts
+        let audit_log = FakeAuditLog::default();
+        let service = service(&audit_log);
+        for user in ["a", "b", "c", "d", "e"] {
+            assert!(service.login(user, "wrong", ip(1)).await.is_err());
+        }
+
+        // when another account logs in from it
+        let from_attacker = service.login("jane", "secret", ip(1)).await;
+        let from_elsewhere = service.login("jane", "secret", ip(2)).await;
+
+        // then only that address is locked
+        assert!(from_attacker.is_err());
+        assert!(from_elsewhere.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_back_off_exponentially() {
+        // given a policy with a noticeable backoff
+        let audit_log = FakeAuditLog::default();
+        let service = LockoutAuthService::new(
+            auth_service(),
+            LockoutPolicy {
+                base_backoff: Duration::from_secs(2),
+                ..POLICY
+            },
+            Box::new(audit_log.clone()),
+        );
+        assert!(service.login("jane", "wrong", None).await.is_err());
+
+        // when retrying straight away
+        let result = service.login("jane", "secret", None).await;
+
+        // then asked to wait for the backoff
+        match result {
+            Err(AuthError::Throttled { retry_after }) => {
+                assert!(retry_after > Duration::from_secs(1));
+                assert!(retry_after <= Duration::from_secs(2));
+            }
+            _ => panic!("expected the login to be throttled"),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_not_count_backend_errors_as_failures() {
+        // given a backend failing more often than the threshold
+        let audit_log = FakeAuditLog::default();
+        let service = service(&audit_log);
+        for _ in 0..5 {
+            let result = service.login("jane", "unavailable", None).await;
+            assert!(matches!(result, Err(AuthError::Backend(_))));
+        }
+
+        // when it is back
+        let result = service.login("jane", "secret", None).await;
+
+        // then the account is not locked
+        assert!(result.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_let_admin_unlock_account() {
+        // given a locked account
+        let audit_log = FakeAuditLog::default();
+        let service = service(&audit_log);
+        for _ in 0..3 {
+            assert!(service.login("jane", "wrong", None).await.is_err());
+        }
+
+        // when unlocked
+        service.unlock("jane").await.unwrap();
+
+        // then the right password works again
+        assert!(service.login("jane", "secret", None).await.is_ok());
+        assert!(audit_log
+            .0
+            .lock()
+            .unwrap()
+            .contains(&AuthEventKind::AccountUnlocked));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_forget_quietest_failures_beyond_capacity() {
+        // given a service tracking two keys at most
+        let audit_log = FakeAuditLog::default();
+        let service = LockoutAuthService::new(
+            auth_service(),
+            LockoutPolicy {
+                max_tracked: 2,
+                ..POLICY
+            },
+            Box::new(audit_log.clone()),
+        );
+
+        // when failing against more accounts than that
+        for user in ["a", "b", "c"] {
+            assert!(service.login(user, "wrong", None).await.is_err());
+        }
+
+        // then the oldest failures are forgotten to make room
+        let failures = service.failures.lock().unwrap();
+        assert_eq!(failures.by_key.len(), 2);
+        assert_eq!(failures.by_recency.len(), 2);
+        assert!(failures.get("account:a").is_none());
+        assert!(failures.get("account:c").is_some());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_forget_failures_once_lockout_is_over() {
+        // given a locked account, with a short lockout
+        let audit_log = FakeAuditLog::default();
+        let service = LockoutAuthService::new(
+            auth_service(),
+            LockoutPolicy {
+                lockout_duration: Duration::from_millis(50),
+                ..POLICY
+            },
+            Box::new(audit_log.clone()),
+        );
+        for _ in 0..3 {
+            assert!(service.login("jane", "wrong", ip(1)).await.is_err());
+        }
+
+        // when the lockout is over
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let result = service.login("jane", "secret", None).await;
+
+        // then the right password works again and the failures are gone
+        assert!(result.is_ok());
+        let failures = service.failures.lock().unwrap();
+        assert!(failures.by_key.is_empty());
+        assert!(failures.by_recency.is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::connection::HttpConnection;
+use app_core::services::{Mail, MailError, Mailer};
+
+#[derive(Serialize)]
+struct MailPayload<'m> {
+    to: &'m str,
+    subject: &'m str,
+    body: &'m str,
+}
+
+/// Sends mails through an HTTP mail API, as a JSON
+/// `{"to", "subject", "body"}` posted to its URL
+pub struct HttpMailer {
+    connection: HttpConnection,
+    url: String,
+}
+
+impl HttpMailer {
+    pub fn new(connection: HttpConnection, url: String) -> Self {
+        HttpMailer { connection, url }
+    }
+}
+
+#[async_trait]
+impl Mailer for HttpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), MailError> {
+        let payload = MailPayload {
+            to: &mail.to,
+            subject: &mail.subject,
+            body: &mail.body,
+        };
+        let request = self.connection.post(&self.url).json(&payload);
+        let response = self
+            .connection
+            .send(request)
+            .await
+            .map_err(|e| MailError(e.to_string()))?;
+        response
+            .error_for_status()
+            .map_err(|e| MailError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Only logs who a mail would go to, for development without a mail API;
+/// the body, which may hold secrets, is only logged at debug level
+pub struct LogMailer {}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: Mail) -> Result<(), MailError> {
+        log::info!("Not mailing \"{}\" to {}", mail.subject, mail.to);
+        log::debug!("{}", mail.body);
+        Ok(())
+    }
+}
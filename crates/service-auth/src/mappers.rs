@@ -1,21 +1,35 @@
-use crate::models::CatFactApiModel;
+use crate::models::{CatFactApiModel, DogFactApiModel, DogFactAttributesApiModel};
 use app_core::mappers::service::ServiceMapper;
-use app_domain::entities::CatFactEntity;
+use app_domain::entities::{CatFactEntity, DogFactEntity, UNSTORED_FACT_ID};
 
 pub struct CatFactHttpMapper {}
 
 impl ServiceMapper<CatFactEntity, CatFactApiModel> for CatFactHttpMapper {
     fn to_service(entity: CatFactEntity) -> CatFactApiModel {
         CatFactApiModel {
+            length: entity.fact_txt.len() as i32,
             fact: entity.fact_txt,
-            length: entity.fact_id,
         }
     }
 
+    // upstream facts have no id, they get one once stored
     fn to_entity(http_obj: CatFactApiModel) -> CatFactEntity {
-        CatFactEntity {
-            fact_txt: http_obj.fact,
-            fact_id: http_obj.length,
+        CatFactEntity::new(http_obj.fact, UNSTORED_FACT_ID)
+    }
+}
+
+pub struct DogFactHttpMapper {}
+
+impl ServiceMapper<DogFactEntity, DogFactApiModel> for DogFactHttpMapper {
+    fn to_service(entity: DogFactEntity) -> DogFactApiModel {
+        DogFactApiModel {
+            id: entity.fact_id.to_string(),
+            attributes: DogFactAttributesApiModel { body: entity.fact },
         }
     }
+
+    // upstream ids are not ours, facts get one once stored
+    fn to_entity(http_obj: DogFactApiModel) -> DogFactEntity {
+        DogFactEntity::new(UNSTORED_FACT_ID, http_obj.attributes.body)
+    }
 }
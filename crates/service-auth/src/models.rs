@@ -1,8 +1,11 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CatFactsApiModel {
     pub current_page: i32,
+    pub last_page: i32,
     pub data: Vec<CatFactApiModel>,
 }
 
@@ -11,3 +14,59 @@ pub struct CatFactApiModel {
     pub fact: String,
     pub length: i32,
 }
+
+/// A page of a JSON:API fact listing, as served by dogapi.dog
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DogFactsApiModel {
+    pub data: Vec<DogFactApiModel>,
+    pub meta: DogFactsMetaApiModel,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DogFactsMetaApiModel {
+    pub pagination: PaginationApiModel,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PaginationApiModel {
+    pub current: i32,
+    pub last: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DogFactApiModel {
+    pub id: String,
+    pub attributes: DogFactAttributesApiModel,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DogFactAttributesApiModel {
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OidcDiscoveryApiModel {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OidcTokenApiModel {
+    pub id_token: String,
+    pub access_token: Option<String>,
+    pub token_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IdTokenClaimsModel {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use openssl::{
+    memcmp,
+    symm::{decrypt_aead, encrypt_aead, Cipher},
+};
+use rand::RngCore;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::{
+    connection::HttpConnection,
+    models::{IdTokenClaimsModel, OidcDiscoveryApiModel, OidcTokenApiModel},
+};
+use app_core::services::{SsoError, SsoIdentity, SsoRedirect, SsoService, SSO_PENDING_LOGIN_TTL};
+
+/// Binds the sealed pending logins to their purpose
+const PENDING_LOGIN_AAD: &[u8] = b"oidc-pending-login";
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// ID tokens must be signed with the provider's private key, never a shared secret
+const ALLOWED_ALGORITHMS: [Algorithm; 8] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+pub struct OidcSettings {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: Vec<String>,
+    /// Claim holding the provider roles, dots walk into nested objects
+    /// (e.g. `realm_access.roles`)
+    pub roles_claim: String,
+    /// Provider role to local role, provider roles not in it are dropped so
+    /// that none is given when it is empty
+    pub role_map: HashMap<String, String>,
+    /// Secret sealing the pending logins kept in browsers, to be shared by
+    /// every instance; a random one is drawn at startup when unset
+    pub state_key: Option<String>,
+}
+
+/// What the callback needs of the login it completes, sealed with
+/// AES-256-GCM rather than kept in memory so that any instance can open it
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    /// Seconds since the epoch
+    created_at: u64,
+}
+
+/// OpenID Connect authorization-code flow with PKCE
+pub struct OidcServiceHTTP {
+    http_connection: HttpConnection,
+    settings: OidcSettings,
+    discovery: OnceCell<OidcDiscoveryApiModel>,
+    jwks: RwLock<Option<JwkSet>>,
+    state_key: [u8; 32],
+}
+
+impl OidcServiceHTTP {
+    pub fn new(http_connection: HttpConnection, settings: OidcSettings) -> Self {
+        let state_key = match &settings.state_key {
+            Some(secret) => Sha256::digest(secret.as_bytes()).into(),
+            None => {
+                log::warn!(
+                    "no OIDC state key set, logins pending on a restart or on another instance will fail"
+                );
+                let mut key = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+
+        OidcServiceHTTP {
+            http_connection,
+            settings,
+            discovery: OnceCell::new(),
+            jwks: RwLock::new(None),
+            state_key,
+        }
+    }
+
+    fn seal(&self, login: &PendingLogin) -> Result<String, SsoError> {
+        let plaintext = serde_json::to_vec(login).map_err(|e| SsoError::Provider(e.to_string()))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut tag = [0u8; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.state_key,
+            Some(&nonce),
+            PENDING_LOGIN_AAD,
+            &plaintext,
+            &mut tag,
+        )
+        .map_err(|e| SsoError::Provider(e.to_string()))?;
+
+        Ok(URL_SAFE_NO_PAD.encode([&nonce[..], &ciphertext, &tag].concat()))
+    }
+
+    /// The pending login, if sealed by us, for this state and not expired
+    fn open(&self, sealed: &str, state: &str) -> Result<PendingLogin, SsoError> {
+        let sealed = URL_SAFE_NO_PAD
+            .decode(sealed)
+            .map_err(|_| SsoError::InvalidState)?;
+        if sealed.len() < NONCE_LEN + TAG_LEN {
+            return Err(SsoError::InvalidState);
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+        let plaintext = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.state_key,
+            Some(nonce),
+            PENDING_LOGIN_AAD,
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| SsoError::InvalidState)?;
+        let login: PendingLogin =
+            serde_json::from_slice(&plaintext).map_err(|_| SsoError::InvalidState)?;
+
+        let same_state = login.state.len() == state.len()
+            && memcmp::eq(login.state.as_bytes(), state.as_bytes());
+        let age = unix_now().saturating_sub(login.created_at);
+        if !same_state || age >= SSO_PENDING_LOGIN_TTL.as_secs() {
+            return Err(SsoError::InvalidState);
+        }
+
+        Ok(login)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, SsoError> {
+        let request = self.http_connection.get(url);
+        self.http_connection
+            .send(request)
+            .await
+            .map_err(|e| SsoError::Provider(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| SsoError::Provider(e.to_string()))?
+            .json::<T>()
+            .await
+            .map_err(|e| SsoError::Provider(e.to_string()))
+    }
+
+    async fn discovery(&self) -> Result<&OidcDiscoveryApiModel, SsoError> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let issuer = self.settings.issuer_url.trim_end_matches('/');
+                let discovery = self
+                    .get_json::<OidcDiscoveryApiModel>(&format!(
+                        "{}/.well-known/openid-configuration",
+                        issuer
+                    ))
+                    .await?;
+
+                if discovery.issuer.trim_end_matches('/') != issuer {
+                    return Err(SsoError::Provider(format!(
+                        "discovery document issued for {}",
+                        discovery.issuer
+                    )));
+                }
+                Ok(discovery)
+            })
+            .await
+    }
+
+    /// Find the signing key, refreshing the key set once in case the provider rotated keys
+    async fn decoding_key(&self, kid: Option<&str>) -> Result<DecodingKey, SsoError> {
+        let find = |jwks: &JwkSet| match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        };
+
+        if let Some(jwk) = self.jwks.read().await.as_ref().and_then(find) {
+            return DecodingKey::from_jwk(&jwk).map_err(|e| SsoError::InvalidToken(e.to_string()));
+        }
+
+        let discovery = self.discovery().await?;
+        let jwks = self.get_json::<JwkSet>(&discovery.jwks_uri).await?;
+        let jwk = find(&jwks);
+        *self.jwks.write().await = Some(jwks);
+
+        let jwk = jwk.ok_or_else(|| SsoError::InvalidToken("unknown signing key".into()))?;
+        DecodingKey::from_jwk(&jwk).map_err(|e| SsoError::InvalidToken(e.to_string()))
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<OidcTokenApiModel, SsoError> {
+        let discovery = self.discovery().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.settings.redirect_url),
+            ("client_id", &self.settings.client_id),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.settings.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let request = self
+            .http_connection
+            .post(&discovery.token_endpoint)
+            .form(&form);
+        let response = self
+            .http_connection
+            .send(request)
+            .await
+            .map_err(|e| SsoError::Provider(e.to_string()))?;
+
+        if response.status().is_client_error() {
+            return Err(SsoError::InvalidToken(format!(
+                "token exchange rejected with {}",
+                response.status()
+            )));
+        }
+
+        response
+            .error_for_status()
+            .map_err(|e| SsoError::Provider(e.to_string()))?
+            .json::<OidcTokenApiModel>()
+            .await
+            .map_err(|e| SsoError::Provider(e.to_string()))
+    }
+
+    async fn validate_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaimsModel, SsoError> {
+        let header = jsonwebtoken::decode_header(id_token)
+            .map_err(|e| SsoError::InvalidToken(e.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(SsoError::InvalidToken(format!(
+                "unsupported algorithm {:?}",
+                header.alg
+            )));
+        }
+
+        let key = self.decoding_key(header.kid.as_deref()).await?;
+        let discovery = self.discovery().await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&discovery.issuer]);
+        validation.set_audience(&[&self.settings.client_id]);
+
+        let claims = jsonwebtoken::decode::<IdTokenClaimsModel>(id_token, &key, &validation)
+            .map_err(|e| SsoError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(SsoError::InvalidToken("nonce mismatch".into()));
+        }
+
+        Ok(claims)
+    }
+
+    fn to_identity(&self, claims: IdTokenClaimsModel) -> SsoIdentity {
+        let mut path = self.settings.roles_claim.split('.');
+        let mut provider_roles = path.next().and_then(|claim| claims.extra.get(claim));
+        for key in path {
+            provider_roles = provider_roles.and_then(|value| value.get(key));
+        }
+
+        let provider_roles: Vec<String> = match provider_roles {
+            Some(serde_json::Value::Array(roles)) => roles
+                .iter()
+                .filter_map(|r| r.as_str().map(String::from))
+                .collect(),
+            Some(serde_json::Value::String(role)) => vec![role.clone()],
+            _ => vec![],
+        };
+
+        let roles = provider_roles
+            .iter()
+            .filter_map(|r| self.settings.role_map.get(r).cloned())
+            .collect();
+
+        SsoIdentity {
+            issuer: claims.iss,
+            subject: claims.sub,
+            email: claims.email,
+            name: claims.name.or(claims.preferred_username),
+            roles,
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[async_trait]
+impl SsoService for OidcServiceHTTP {
+    async fn begin_login(&self) -> Result<SsoRedirect, SsoError> {
+        let discovery = self.discovery().await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = url::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &self.settings.client_id),
+                ("redirect_uri", &self.settings.redirect_url),
+                ("scope", &self.settings.scopes.join(" ")),
+                ("state", &state),
+                ("nonce", &nonce),
+                ("code_challenge", &code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| SsoError::Provider(e.to_string()))?;
+
+        let pending = self.seal(&PendingLogin {
+            state: state.clone(),
+            nonce,
+            code_verifier,
+            created_at: unix_now(),
+        })?;
+
+        Ok(SsoRedirect {
+            url: url.to_string(),
+            state,
+            pending,
+        })
+    }
+
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        pending: &str,
+    ) -> Result<SsoIdentity, SsoError> {
+        // the provider redeems a code only once, so a replayed callback fails
+        // the exchange even with the pending login it was bound to
+        let pending = self.open(pending, state)?;
+
+        let tokens = self.exchange_code(code, &pending.code_verifier).await?;
+        let claims = self
+            .validate_id_token(&tokens.id_token, &pending.nonce)
+            .await?;
+
+        Ok(self.to_identity(claims))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::HttpSettings;
+
+    fn service(state_key: &str) -> OidcServiceHTTP {
+        OidcServiceHTTP::new(
+            HttpConnection::new(HttpSettings::default()),
+            OidcSettings {
+                issuer_url: String::from("http://idp.test"),
+                client_id: String::from("animal-facts"),
+                client_secret: None,
+                redirect_url: String::from("http://app.test/callback"),
+                scopes: vec![String::from("openid")],
+                roles_claim: String::from("roles"),
+                role_map: HashMap::new(),
+                state_key: Some(state_key.into()),
+            },
+        )
+    }
+
+    fn pending_login(created_at: u64) -> PendingLogin {
+        PendingLogin {
+            state: String::from("state"),
+            nonce: String::from("nonce"),
+            code_verifier: String::from("verifier"),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_should_open_pending_login_on_any_instance_sharing_the_key() {
+        // given a login sealed by one instance
+        let sealed = service("secret").seal(&pending_login(unix_now())).unwrap();
+
+        // when another instance with the same key opens it
+        let login = service("secret").open(&sealed, "state").unwrap();
+
+        // then it gets the pending login back
+        assert_eq!(login.nonce, "nonce");
+        assert_eq!(login.code_verifier, "verifier");
+    }
+
+    #[test]
+    fn test_should_reject_foreign_expired_or_tampered_pending_login() {
+        // given a sealed login
+        let oidc_service = service("secret");
+        let sealed = oidc_service.seal(&pending_login(unix_now())).unwrap();
+        let expired = oidc_service
+            .seal(&pending_login(unix_now() - SSO_PENDING_LOGIN_TTL.as_secs()))
+            .unwrap();
+        let mut tampered = URL_SAFE_NO_PAD.decode(&sealed).unwrap();
+        tampered[NONCE_LEN] ^= 1;
+
+        // when opening it for another state, with another key, too late
+        // or once tampered with
+        // then it is refused
+        assert!(oidc_service.open(&sealed, "other").is_err());
+        assert!(service("other").open(&sealed, "state").is_err());
+        assert!(oidc_service.open(&expired, "state").is_err());
+        assert!(oidc_service
+            .open(&URL_SAFE_NO_PAD.encode(tampered), "state")
+            .is_err());
+    }
+
+    #[test]
+    fn test_should_namespace_subject_and_give_only_mapped_roles() {
+        // given claims with provider roles
+        let claims = || IdTokenClaimsModel {
+            iss: String::from("http://idp.test"),
+            sub: String::from("jane"),
+            nonce: None,
+            email: None,
+            name: None,
+            preferred_username: None,
+            extra: HashMap::from([(
+                String::from("roles"),
+                serde_json::json!(["idp-admins", "idp-users"]),
+            )]),
+        };
+        let mut mapped = service("secret");
+        mapped.settings.role_map = HashMap::from([("idp-admins".into(), "admin".into())]);
+
+        // when mapping them to an identity, without and with a role map
+        let unmapped = service("secret").to_identity(claims());
+        let mapped = mapped.to_identity(claims());
+
+        // then the subject is namespaced by its provider
+        assert_eq!(unmapped.principal_name(), "oidc:http://idp.test:jane");
+        // and only the mapped roles are given
+        assert!(unmapped.roles.is_empty());
+        assert_eq!(mapped.roles, vec!["admin"]);
+    }
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, marker::PhantomData, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use tokio::sync::OnceCell;
+
+use crate::local_accounts::{LocalRoles, TokenStore};
+use app_core::services::{
+    AuthError, AuthService, AuthToken, PasswordHasher, Persistence, Principal, Transaction,
+    UserRepo,
+};
+
+/// Checks passwords against the users of the user store
+///
+/// Roles come from configuration, see `LocalRoles`, tokens are kept in
+/// memory, see `TokenStore`.
+pub struct PasswordAuthService<P, U> {
+    persistence: P,
+    password_hasher: Box<dyn PasswordHasher>,
+    roles: LocalRoles,
+    tokens: TokenStore,
+    /// Checked against for unknown users, so that they take as long to
+    /// refuse as a wrong password and don't tell who has an account;
+    /// hashed on the first login
+    unknown_user_hash: OnceCell<String>,
+    user_repository: PhantomData<U>,
+}
+
+impl<P, U> PasswordAuthService<P, U> {
+    pub fn new(
+        persistence: P,
+        password_hasher: Box<dyn PasswordHasher>,
+        admin_users: Vec<String>,
+        token_ttl: Duration,
+    ) -> Self {
+        PasswordAuthService {
+            persistence,
+            password_hasher,
+            roles: LocalRoles {
+                admin_users,
+                editor_users: vec![],
+                user_tenants: HashMap::new(),
+            },
+            tokens: TokenStore::new(token_ttl),
+            unknown_user_hash: OnceCell::new(),
+            user_repository: PhantomData,
+        }
+    }
+
+    pub fn with_editor_users(mut self, editor_users: Vec<String>) -> Self {
+        self.roles.editor_users = editor_users;
+        self
+    }
+
+    pub fn with_user_tenants(mut self, user_tenants: HashMap<String, String>) -> Self {
+        self.roles.user_tenants = user_tenants;
+        self
+    }
+}
+
+#[async_trait]
+impl<P, U> AuthService for PasswordAuthService<P, U>
+where
+    P: Persistence,
+    <P as Persistence>::Transaction: Transaction,
+    U: UserRepo<P> + Send + Sync,
+{
+    async fn login(
+        &self,
+        username: &str,
+        password: &str,
+        _client_ip: Option<IpAddr>,
+    ) -> Result<Principal, AuthError> {
+        if username.trim().is_empty() {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        let mut tx = self
+            .persistence
+            .get_transaction()
+            .await
+            .map_err(|e| AuthError::Backend(e.to_string()))?;
+        let user = U::get_user(&mut tx, username.into())
+            .await
+            .map_err(|e| AuthError::Backend(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| AuthError::Backend(e.to_string()))?;
+
+        let password_hash = match &user {
+            Some(user) => &user.password_hash,
+            None => {
+                self.unknown_user_hash
+                    .get_or_init(|| async {
+                        self.password_hasher.hash("").await.unwrap_or_default()
+                    })
+                    .await
+            }
+        };
+        let verified = self.password_hasher.verify(password, password_hash).await;
+        if user.is_none() || !verified {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        Ok(self.roles.principal(username))
+    }
+
+    async fn issue_token(&self, principal: &Principal) -> Result<AuthToken, AuthError> {
+        Ok(self.tokens.issue(principal))
+    }
+
+    async fn verify_token(&self, token: &str) -> Result<Principal, AuthError> {
+        self.tokens.verify(token)
+    }
+
+    async fn logout(&self, token: &str) -> Result<(), AuthError> {
+        self.tokens.revoke(token);
+        Ok(())
+    }
+
+    async fn revoke_tokens(&self, username: &str) -> Result<(), AuthError> {
+        self.tokens.revoke_user(username);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lockout_service::{LockoutAuthService, LockoutPolicy},
+        password_hasher::ScryptPasswordHasher,
+    };
+    use app_core::services::ADMIN_ROLE;
+    use app_domain::entities::UserEntity;
+    use service_memory::memory_service::{AuthAuditLogMemory, PersistenceMemory, UserRepoMemory};
+
+    type Service = PasswordAuthService<PersistenceMemory, UserRepoMemory>;
+
+    /// A service knowing jane, whose password is `secret`
+    async fn service(admin_users: Vec<String>) -> (Service, PersistenceMemory) {
+        let persistence = PersistenceMemory::new();
+        let password_hasher = ScryptPasswordHasher::with_cost(4);
+
+        let mut tx = persistence.get_transaction().await.unwrap();
+        UserRepoMemory::create_user(
+            &mut tx,
+            UserEntity::new(
+                "jane".into(),
+                "jane@example.com".into(),
+                password_hasher.hash("secret").await.unwrap(),
+            ),
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        let service = Service::new(
+            persistence.clone(),
+            Box::new(password_hasher),
+            admin_users,
+            Duration::from_secs(60),
+        );
+        (service, persistence)
+    }
+
+    #[actix_rt::test]
+    async fn test_should_login_with_the_stored_password_only() {
+        // given a known user
+        let (service, _) = service(vec!["jane".into()]).await;
+
+        // when logging in with the right password, a wrong one, or as a stranger
+        let principal = service.login("jane", "secret", None).await.unwrap();
+        let wrong = service.login("jane", "wrong", None).await;
+        let unknown = service.login("john", "secret", None).await;
+
+        // then only the right password opens, with the configured roles
+        assert!(principal.has_role(ADMIN_ROLE));
+        assert!(matches!(wrong, Err(AuthError::InvalidCredentials)));
+        assert!(matches!(unknown, Err(AuthError::InvalidCredentials)));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_count_wrong_passwords_towards_lockout() {
+        // given a known user behind brute-force protection
+        let (service, persistence) = service(vec![]).await;
+        let service = LockoutAuthService::new(
+            service,
+            LockoutPolicy {
+                max_failures_per_account: 2,
+                base_backoff: Duration::ZERO,
+                ..LockoutPolicy::default()
+            },
+            Box::new(AuthAuditLogMemory::new(persistence)),
+        );
+
+        // when guessing the password wrong as many times as allowed
+        for _ in 0..2 {
+            assert!(matches!(
+                service.login("jane", "wrong", None).await,
+                Err(AuthError::InvalidCredentials)
+            ));
+        }
+
+        // then even the right password is throttled
+        assert!(matches!(
+            service.login("jane", "secret", None).await,
+            Err(AuthError::Throttled { .. })
+        ));
+    }
+}
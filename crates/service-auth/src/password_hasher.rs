@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use openssl::{memcmp, pkcs5};
+use rand::RngCore;
+use tokio::task;
+
+use app_core::services::{AuthError, PasswordHasher};
+
+const SALT_LENGTH: usize = 16;
+const KEY_LENGTH: usize = 32;
+
+/// Hashes passwords with scrypt, memory-hard to slow down guessing
+///
+/// Hashes read `scrypt$<log2 N>$<r>$<p>$<salt>$<key>`, carrying their
+/// parameters, so raising the cost leaves existing hashes valid. Each hash
+/// runs on the blocking thread pool, not to hold up other requests.
+#[derive(Clone, Copy)]
+pub struct ScryptPasswordHasher {
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl Default for ScryptPasswordHasher {
+    /// The parameters recommended for interactive logins, 32 MiB per hash
+    fn default() -> Self {
+        ScryptPasswordHasher {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl ScryptPasswordHasher {
+    /// A CPU and memory cost of `2^log_n`, lower ones are for tests only
+    pub fn with_cost(log_n: u8) -> Self {
+        ScryptPasswordHasher {
+            log_n,
+            ..ScryptPasswordHasher::default()
+        }
+    }
+}
+
+fn derive(password: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<Vec<u8>, String> {
+    if log_n == 0 || log_n > 20 || r == 0 || p == 0 {
+        return Err(format!("unsupported scrypt cost {} {} {}", log_n, r, p));
+    }
+    let n = 1u64 << log_n;
+    // what the derivation needs, with room for openssl's own use
+    let maxmem = 128 * n * u64::from(r) * u64::from(p) + 1024 * 1024;
+    let mut key = vec![0u8; KEY_LENGTH];
+    pkcs5::scrypt(
+        password.as_bytes(),
+        salt,
+        n,
+        r.into(),
+        p.into(),
+        maxmem,
+        &mut key,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn hash(password: &str, log_n: u8, r: u32, p: u32) -> Result<String, AuthError> {
+    let mut salt = [0u8; SALT_LENGTH];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let key = derive(password, &salt, log_n, r, p).map_err(AuthError::Backend)?;
+
+    Ok(format!(
+        "scrypt${}${}${}${}${}",
+        log_n,
+        r,
+        p,
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(key)
+    ))
+}
+
+fn verify(password: &str, password_hash: &str) -> bool {
+    let parts: Vec<&str> = password_hash.split('$').collect();
+    let (log_n, r, p, salt, key) = match parts[..] {
+        ["scrypt", log_n, r, p, salt, key] => (log_n, r, p, salt, key),
+        _ => return false,
+    };
+    let parsed = (
+        log_n.parse(),
+        r.parse(),
+        p.parse(),
+        STANDARD_NO_PAD.decode(salt),
+        STANDARD_NO_PAD.decode(key),
+    );
+    let (log_n, r, p, salt, key) = match parsed {
+        (Ok(log_n), Ok(r), Ok(p), Ok(salt), Ok(key)) => (log_n, r, p, salt, key),
+        _ => return false,
+    };
+
+    match derive(password, &salt, log_n, r, p) {
+        Ok(derived) => derived.len() == key.len() && memcmp::eq(&derived, &key),
+        Err(_) => false,
+    }
+}
+
+#[async_trait]
+impl PasswordHasher for ScryptPasswordHasher {
+    async fn hash(&self, password: &str) -> Result<String, AuthError> {
+        let password = password.to_string();
+        let ScryptPasswordHasher { log_n, r, p } = *self;
+        task::spawn_blocking(move || hash(&password, log_n, r, p))
+            .await
+            .map_err(|e| AuthError::Backend(e.to_string()))?
+    }
+
+    async fn verify(&self, password: &str, password_hash: &str) -> bool {
+        let password = password.to_string();
+        let password_hash = password_hash.to_string();
+        task::spawn_blocking(move || verify(&password, &password_hash))
+            .await
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_should_verify_only_the_password_hashed() {
+        // given a password hashed
+        let hasher = ScryptPasswordHasher::with_cost(4);
+        let hash = hasher.hash("correct horse").await.unwrap();
+
+        // then only that password verifies
+        assert!(hasher.verify("correct horse", &hash).await);
+        assert!(!hasher.verify("wrong horse", &hash).await);
+        assert!(!hash.contains("correct horse"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_salt_every_hash() {
+        // given the same password hashed twice
+        let hasher = ScryptPasswordHasher::with_cost(4);
+        let first = hasher.hash("correct horse").await.unwrap();
+        let second = hasher.hash("correct horse").await.unwrap();
+
+        // then the hashes differ, both verifying
+        assert_ne!(first, second);
+        assert!(hasher.verify("correct horse", &second).await);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_verify_hashes_of_another_cost() {
+        // given a hash made at a lower cost
+        let hash = ScryptPasswordHasher::with_cost(4)
+            .hash("correct horse")
+            .await
+            .unwrap();
+
+        // then it still verifies once the cost is raised
+        let hasher = ScryptPasswordHasher::with_cost(5);
+        assert!(hasher.verify("correct horse", &hash).await);
+        assert!(!hasher.verify("correct horse", "plain").await);
+    }
+}
@@ -0,0 +1,11 @@
+CREATE TABLE "users" (username VARCHAR PRIMARY KEY,
+                      email VARCHAR NOT NULL UNIQUE,
+                      password_hash VARCHAR NOT NULL);
+
+
+-- only the hash of the token mailed is kept
+CREATE TABLE "password_resets" (token_hash VARCHAR PRIMARY KEY,
+                                username VARCHAR NOT NULL REFERENCES "users" (username) ON DELETE CASCADE,
+                                expires_at TIMESTAMPTZ NOT NULL);
+
+CREATE INDEX "password_resets_username_idx" ON "password_resets" (username);
@@ -0,0 +1,11 @@
+-- sessions are looked up by the SHA-256 of their id, a leaked table can't be replayed
+CREATE TABLE "sessions" (id_hash VARCHAR PRIMARY KEY,
+                                         username VARCHAR NOT NULL,
+                                         csrf_token VARCHAR NOT NULL,
+                                         created_at TIMESTAMPTZ NOT NULL,
+                                         expires_at TIMESTAMPTZ NOT NULL);
+
+
+CREATE INDEX "sessions_expires_at_idx" ON "sessions" (expires_at);
+
+CREATE INDEX "sessions_username_idx" ON "sessions" (username);
@@ -0,0 +1,8 @@
+CREATE TABLE "auth_events" (id BIGSERIAL PRIMARY KEY,
+                                               kind VARCHAR NOT NULL,
+                                               username VARCHAR NOT NULL,
+                                               client_ip VARCHAR,
+                                               occurred_at TIMESTAMPTZ NOT NULL);
+
+
+CREATE INDEX "auth_events_username_idx" ON "auth_events" (username, occurred_at);
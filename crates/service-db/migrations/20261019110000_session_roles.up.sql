@@ -0,0 +1 @@
+ALTER TABLE "sessions" ADD COLUMN roles VARCHAR[] NOT NULL DEFAULT '{}';
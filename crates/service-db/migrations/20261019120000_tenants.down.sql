@@ -0,0 +1,13 @@
+DROP POLICY "cat_facts_tenant_isolation" ON "cat_facts";
+DROP POLICY "dog_facts_tenant_isolation" ON "dog_facts";
+ALTER TABLE "cat_facts" NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE "cat_facts" DISABLE ROW LEVEL SECURITY;
+ALTER TABLE "dog_facts" NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE "dog_facts" DISABLE ROW LEVEL SECURITY;
+
+-- the role is shared by every database of the cluster, only this one's grants go
+REVOKE ALL ON "dog_facts", "cat_facts" FROM animal_fact_tenant;
+REVOKE ALL ON SEQUENCE "dog_facts_id_seq", "cat_facts_id_seq" FROM animal_fact_tenant;
+
+ALTER TABLE "cat_facts" DROP COLUMN tenant_id;
+ALTER TABLE "dog_facts" DROP COLUMN tenant_id;
@@ -0,0 +1,38 @@
+-- existing facts belong to the default tenant, new ones to the tenant of the transaction
+ALTER TABLE "dog_facts" ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT 'default';
+ALTER TABLE "cat_facts" ADD COLUMN tenant_id VARCHAR NOT NULL DEFAULT 'default';
+ALTER TABLE "dog_facts" ALTER COLUMN tenant_id SET DEFAULT COALESCE(NULLIF(current_setting('app.tenant_id', true), ''), 'default');
+ALTER TABLE "cat_facts" ALTER COLUMN tenant_id SET DEFAULT COALESCE(NULLIF(current_setting('app.tenant_id', true), ''), 'default');
+
+CREATE INDEX "dog_facts_tenant_id_idx" ON "dog_facts" (tenant_id);
+CREATE INDEX "cat_facts_tenant_id_idx" ON "cat_facts" (tenant_id);
+
+-- tenant transactions switch to this role, superusers would bypass the policies
+DO $$
+BEGIN
+    CREATE ROLE animal_fact_tenant NOLOGIN;
+EXCEPTION WHEN duplicate_object OR unique_violation THEN NULL;
+END
+$$;
+
+DO $$
+BEGIN
+    EXECUTE format('GRANT animal_fact_tenant TO %I', current_user);
+EXCEPTION WHEN duplicate_object OR unique_violation THEN NULL;
+END
+$$;
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON "dog_facts", "cat_facts" TO animal_fact_tenant;
+GRANT USAGE ON SEQUENCE "dog_facts_id_seq", "cat_facts_id_seq" TO animal_fact_tenant;
+
+ALTER TABLE "dog_facts" ENABLE ROW LEVEL SECURITY;
+ALTER TABLE "dog_facts" FORCE ROW LEVEL SECURITY;
+ALTER TABLE "cat_facts" ENABLE ROW LEVEL SECURITY;
+ALTER TABLE "cat_facts" FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY "dog_facts_tenant_isolation" ON "dog_facts"
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
+CREATE POLICY "cat_facts_tenant_isolation" ON "cat_facts"
+    USING (tenant_id = current_setting('app.tenant_id', true))
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
@@ -0,0 +1,5 @@
+DROP TABLE "sync_runs";
+
+DROP INDEX "cat_facts_upstream_index_idx";
+ALTER TABLE "cat_facts" DROP COLUMN upstream_changed;
+ALTER TABLE "cat_facts" DROP COLUMN upstream_index;
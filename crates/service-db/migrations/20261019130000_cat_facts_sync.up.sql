@@ -0,0 +1,19 @@
+-- position of the fact in the upstream listing, NULL for facts added locally
+ALTER TABLE "cat_facts" ADD COLUMN upstream_index INTEGER;
+ALTER TABLE "cat_facts" ADD COLUMN upstream_changed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE UNIQUE INDEX "cat_facts_upstream_index_idx" ON "cat_facts" (tenant_id, upstream_index);
+
+
+CREATE TABLE "sync_runs" (id BIGSERIAL PRIMARY KEY,
+                                           source VARCHAR NOT NULL,
+                                           tenant_id VARCHAR NOT NULL,
+                                           started_at TIMESTAMPTZ NOT NULL,
+                                           finished_at TIMESTAMPTZ,
+                                           inserted INTEGER NOT NULL,
+                                           unchanged INTEGER NOT NULL,
+                                           changed INTEGER NOT NULL,
+                                           error VARCHAR);
+
+
+CREATE INDEX "sync_runs_started_at_idx" ON "sync_runs" (started_at);
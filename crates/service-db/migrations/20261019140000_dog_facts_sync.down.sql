@@ -0,0 +1,3 @@
+DROP INDEX "dog_facts_upstream_index_idx";
+ALTER TABLE "dog_facts" DROP COLUMN upstream_changed;
+ALTER TABLE "dog_facts" DROP COLUMN upstream_index;
@@ -0,0 +1,5 @@
+-- position of the fact in the upstream listing, NULL for facts added locally
+ALTER TABLE "dog_facts" ADD COLUMN upstream_index INTEGER;
+ALTER TABLE "dog_facts" ADD COLUMN upstream_changed BOOLEAN NOT NULL DEFAULT false;
+
+CREATE UNIQUE INDEX "dog_facts_upstream_index_idx" ON "dog_facts" (tenant_id, upstream_index);
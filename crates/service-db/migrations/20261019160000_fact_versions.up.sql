@@ -0,0 +1,4 @@
+-- goes up by one with every update, for concurrent editors not to
+-- overwrite each other
+ALTER TABLE "dog_facts" ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE "cat_facts" ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
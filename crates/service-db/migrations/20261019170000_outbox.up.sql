@@ -0,0 +1,42 @@
+-- domain events, written in the transaction of the change they tell of and
+-- relayed to subscribers once committed
+CREATE TABLE "outbox" (id BIGSERIAL PRIMARY KEY,
+                       event_type VARCHAR NOT NULL CHECK (event_type IN ('fact_created', 'fact_updated')),
+                       species VARCHAR NOT NULL CHECK (species IN ('cat', 'dog')),
+                       tenant_id VARCHAR NOT NULL,
+                       fact_id INTEGER NOT NULL,
+                       text VARCHAR,
+                       version INTEGER,
+                       created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       -- pending, delivered, or dead once given up on
+                       status VARCHAR NOT NULL DEFAULT 'pending',
+                       -- purged once older than the retention of the relay
+                       delivered_at TIMESTAMPTZ,
+                       attempts INTEGER NOT NULL DEFAULT 0,
+                       -- subscribers having handled the event, not told again on retries
+                       delivered_to VARCHAR[] NOT NULL DEFAULT '{}',
+                       next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                       -- a relay is delivering the event until then
+                       claimed_until TIMESTAMPTZ,
+                       last_error VARCHAR);
+
+
+CREATE INDEX "outbox_pending_idx" ON "outbox" (species, tenant_id, fact_id, id) WHERE status = 'pending';
+CREATE INDEX "outbox_delivered_idx" ON "outbox" (delivered_at) WHERE status = 'delivered';
+
+-- tenants only add events, of their own facts, the relay reads them all
+GRANT INSERT ON "outbox" TO animal_fact_tenant;
+GRANT USAGE ON SEQUENCE "outbox_id_seq" TO animal_fact_tenant;
+
+ALTER TABLE "outbox" ENABLE ROW LEVEL SECURITY;
+ALTER TABLE "outbox" FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY "outbox_tenant_isolation" ON "outbox" FOR INSERT TO animal_fact_tenant
+    WITH CHECK (tenant_id = current_setting('app.tenant_id', true));
+
+-- the relay runs as the owner, which isn't exempt from the policies either
+DO $$
+BEGIN
+    EXECUTE format('CREATE POLICY "outbox_relay" ON "outbox" TO %I USING (true) WITH CHECK (true)', current_user);
+END
+$$;
@@ -0,0 +1 @@
+DROP INDEX "cat_facts_kept_idx";
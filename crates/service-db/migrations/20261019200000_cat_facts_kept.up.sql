@@ -0,0 +1,3 @@
+-- facts kept from upstream when falling back are stored once per tenant,
+-- synchronised ones being told apart by their upstream index
+CREATE UNIQUE INDEX "cat_facts_kept_idx" ON "cat_facts" (tenant_id, fact) WHERE upstream_index IS NULL;
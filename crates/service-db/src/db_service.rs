@@ -1,70 +1,519 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Transaction};
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    postgres::PgPoolOptions,
+    Pool, Postgres, Transaction,
+};
 
 use crate::{
-    mappers::{CatFactDbMapper, DogFactDbMapper},
-    models::{CatFact, DogFact},
+    mappers::{
+        CatFactDbMapper, DogFactDbMapper, OutboxEventDbMapper, PasswordResetDbMapper,
+        SessionDbMapper, SyncRunDbMapper, UserDbMapper,
+    },
+    models::{CatFact, DogFact, OutboxEvent, PasswordReset, Session, SyncRun, User},
+    replicas::{Replica, ReplicaSettings, Replicas},
 };
 use app_core::{
     mappers::service::ServiceMapper,
-    services::{self, CatRepo, DogRepo, Persistence, RepositoryError},
+    services::{
+        self, AuthAuditLog, CatRepo, DogRepo, IsolationLevel, Outbox, Persistence, RepositoryError,
+        SchemaMigrations, SessionRepo, SyncRunRepo, TransactionOptions, UserRepo,
+    },
+};
+use app_domain::{
+    entities::{
+        AuthEventEntity, CatFactEntity, DogFactEntity, MigrationEntity, OutboxEventEntity,
+        PasswordResetEntity, SessionEntity, SyncRunEntity, TenantEntity, UserEntity,
+    },
+    events::DomainEvent,
+};
+
+/// The `migrations/` directory, built in, locking is left to `migrate`
+static MIGRATOR: Migrator = Migrator {
+    locking: false,
+    ..sqlx::migrate!("./migrations")
 };
-use app_domain::entities::{CatFactEntity, DogFactEntity};
 
 #[derive(Clone)]
 pub struct PersistencePG {
     pool: Pool<Postgres>,
+    replicas: Arc<Replicas>,
 }
 
 impl PersistencePG {
     pub async fn new(db_name: &str) -> Result<Self, RepositoryError> {
         let re = Regex::new(r#"(^postgresql://[^@]+@[^/]+)/[^/]+"#).unwrap();
 
-        let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let database_url = dotenv::var("DATABASE_URL")
+            .map_err(|e| RepositoryError::new(format!("DATABASE_URL must be set: {}", e)))?;
         let database = re.replace_all(&database_url, |caps: &regex::Captures| {
             format!("{}/{}", &caps[1], db_name)
         });
 
         Ok(PersistencePG {
             pool: PgPoolOptions::new()
-                .max_connections((num_cpus::get_physical() * 4) as u32)
+                .max_connections(max_connections())
                 .connect(&database)
                 .await
-                .map_err(|e| RepositoryError(e.to_string()))?,
+                .map_err(db_error)?,
+            replicas: Arc::new(Replicas::none()),
         })
     }
+
+    /// Serve read transactions from replicas, taking turns
+    pub fn with_replicas(self, settings: ReplicaSettings) -> Result<Self, RepositoryError> {
+        Ok(PersistencePG {
+            replicas: Arc::new(Replicas::new(settings, max_connections())?),
+            ..self
+        })
+    }
+
+    /// Apply the migrations the database doesn't have yet, instances
+    /// starting together take turns
+    pub async fn migrate(&self) -> Result<(), RepositoryError> {
+        let mut conn = self.pool.acquire().await.map_err(db_error)?;
+
+        // held by the session, the first instance applies the migrations,
+        // the others find nothing left to apply once it lets go
+        sqlx::query("SELECT pg_advisory_lock(hashtext(current_database() || '.migrations'))")
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        let migrated = MIGRATOR
+            .run_direct(&mut *conn)
+            .await
+            .map_err(|e| RepositoryError::new(e.to_string()));
+        let unlocked =
+            sqlx::query("SELECT pg_advisory_unlock(hashtext(current_database() || '.migrations'))")
+                .execute(&mut *conn)
+                .await;
+        if let Err(e) = unlocked {
+            // closing the connection lets go of the lock too, it must not
+            // go back to the pool still holding it
+            drop(conn.detach());
+            return Err(RepositoryError::new(e.to_string()));
+        }
+
+        migrated
+    }
+
+    /// A read transaction of the first replica in turn that answers and
+    /// isn't lagging too far behind, else of the primary
+    async fn begin_read(&self) -> Result<TransactionPG, RepositoryError> {
+        let settings = &self.replicas.settings;
+        for replica in self.replicas.in_turn(Instant::now()) {
+            match begin_on_replica(replica, settings).await {
+                Ok(tx) => return Ok(tx),
+                Err(e) => {
+                    log::warn!("Leaving a read replica out for now: {}", e);
+                    replica.leave_out(Instant::now(), settings.retry_after);
+                }
+            }
+        }
+
+        self.get_transaction_with(READ_ONLY).await
+    }
+}
+
+const READ_ONLY: TransactionOptions = TransactionOptions {
+    isolation: IsolationLevel::ReadCommitted,
+    read_only: true,
+};
+
+/// Has to come first in the transaction, the defaults are left to the
+/// database configuration
+async fn set_options(
+    tx: &mut Transaction<'static, Postgres>,
+    options: TransactionOptions,
+) -> Result<(), RepositoryError> {
+    if options == TransactionOptions::default() {
+        return Ok(());
+    }
+
+    let isolation = match options.isolation {
+        IsolationLevel::ReadCommitted => "READ COMMITTED",
+        IsolationLevel::RepeatableRead => "REPEATABLE READ",
+        IsolationLevel::Serializable => "SERIALIZABLE",
+    };
+    let access = if options.read_only {
+        "READ ONLY"
+    } else {
+        "READ WRITE"
+    };
+    sqlx::query(&format!(
+        "SET TRANSACTION ISOLATION LEVEL {}, {}",
+        isolation, access
+    ))
+    .execute(&mut **tx)
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
+}
+
+/// Serialization failures and deadlocks are worth running the transaction
+/// again for
+fn db_error(e: sqlx::Error) -> RepositoryError {
+    let transient = e
+        .as_database_error()
+        .and_then(|db| db.code())
+        .is_some_and(|code| code == "40001" || code == "40P01");
+    if transient {
+        RepositoryError::transient(e.to_string())
+    } else {
+        RepositoryError::new(e.to_string())
+    }
+}
+
+fn max_connections() -> u32 {
+    (num_cpus::get_physical() * 4) as u32
+}
+
+async fn begin_on_replica(
+    replica: &Replica,
+    settings: &ReplicaSettings,
+) -> Result<TransactionPG, RepositoryError> {
+    let mut tx = replica.pool.begin().await.map_err(db_error)?;
+    set_options(&mut tx, READ_ONLY).await?;
+
+    // a replica that replayed all it received is as fresh as it gets, a
+    // primary has no lag at all
+    let lag = sqlx::query_scalar!(
+        r#"SELECT COALESCE(
+            CASE WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+            ELSE EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()) END,
+            0)::float8 AS "lag!""#
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    if lag > settings.max_lag.as_secs_f64() {
+        return Err(RepositoryError::new(format!(
+            "{:.1}s behind the primary",
+            lag
+        )));
+    }
+
+    Ok(TransactionPG(tx))
+}
+
+/// Only lasts until the end of the transaction, the pooled connection goes
+/// back without any tenant attached
+async fn attach_tenant(
+    tx: &mut TransactionPG,
+    tenant: &TenantEntity,
+) -> Result<(), RepositoryError> {
+    sqlx::query!(
+        "SELECT set_config('role', 'animal_fact_tenant', true) AS role, set_config('app.tenant_id', $1, true) AS tenant_id",
+        tenant.tenant_id
+    )
+    .fetch_one(&mut *tx.0)
+    .await
+    .map_err(db_error)?;
+
+    Ok(())
 }
 
 #[async_trait]
 impl Persistence for PersistencePG {
     type Transaction = TransactionPG;
     async fn get_transaction(&self) -> Result<TransactionPG, RepositoryError> {
-        let tx = self
-            .pool
-            .begin()
-            .await
-            .map_err(|e| RepositoryError(e.to_string()))?;
+        let tx = self.pool.begin().await.map_err(db_error)?;
 
         Ok(TransactionPG(tx))
     }
+
+    async fn get_tenant_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<TransactionPG, RepositoryError> {
+        let mut tx = self.get_transaction().await?;
+        attach_tenant(&mut tx, tenant).await?;
+        Ok(tx)
+    }
+
+    async fn get_transaction_with(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<TransactionPG, RepositoryError> {
+        let mut tx = self.get_transaction().await?;
+        set_options(&mut tx.0, options).await?;
+        Ok(tx)
+    }
+
+    async fn get_tenant_transaction_with(
+        &self,
+        tenant: &TenantEntity,
+        options: TransactionOptions,
+    ) -> Result<TransactionPG, RepositoryError> {
+        let mut tx = self.get_transaction_with(options).await?;
+        attach_tenant(&mut tx, tenant).await?;
+        Ok(tx)
+    }
+
+    async fn get_read_transaction(&self) -> Result<TransactionPG, RepositoryError> {
+        self.begin_read().await
+    }
+
+    async fn get_tenant_read_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<TransactionPG, RepositoryError> {
+        let mut tx = self.begin_read().await?;
+        attach_tenant(&mut tx, tenant).await?;
+        Ok(tx)
+    }
 }
 
 pub struct TransactionPG(pub Transaction<'static, Postgres>);
 
+impl TransactionPG {
+    /// Savepoint names can't be bound, they go in quoted
+    async fn execute_savepoint(
+        &mut self,
+        statement: &str,
+        name: &str,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query(&format!("{} \"{}\"", statement, name.replace('"', "\"\"")))
+            .execute(&mut *self.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+/// Authentication events kept in the `auth_events` table
+pub struct AuthAuditLogPG {
+    persistence: PersistencePG,
+}
+
+impl AuthAuditLogPG {
+    pub fn new(persistence: PersistencePG) -> Self {
+        AuthAuditLogPG { persistence }
+    }
+}
+
+#[async_trait]
+impl AuthAuditLog for AuthAuditLogPG {
+    async fn record(&self, event: AuthEventEntity) -> Result<(), RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        sqlx::query!(
+            "INSERT INTO auth_events (kind, username, client_ip, occurred_at) VALUES ($1, $2, $3, $4)",
+            event.kind.to_string(),
+            event.username,
+            event.client_ip,
+            event.occurred_at
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        services::Transaction::commit(tx).await
+    }
+}
+
+/// The migrations built in, and the ones recorded by `sqlx` as applied
+pub struct SchemaMigrationsPG {
+    persistence: PersistencePG,
+}
+
+impl SchemaMigrationsPG {
+    pub fn new(persistence: PersistencePG) -> Self {
+        SchemaMigrationsPG { persistence }
+    }
+}
+
+#[async_trait]
+impl SchemaMigrations for SchemaMigrationsPG {
+    async fn get_migrations(&self) -> Result<Vec<MigrationEntity>, RepositoryError> {
+        let mut conn = self.persistence.pool.acquire().await.map_err(db_error)?;
+        // `sqlx` creates its table on the first migration
+        let recorded = sqlx::query_scalar!(
+            r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "recorded!""#
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?;
+        let applied = if recorded {
+            conn.list_applied_migrations()
+                .await
+                .map_err(|e| RepositoryError::new(e.to_string()))?
+        } else {
+            vec![]
+        };
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| MigrationEntity {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.iter().any(|a| a.version == migration.version),
+            })
+            .collect())
+    }
+}
+
+/// Events kept in the `outbox` table by the transactions of use cases
+pub struct OutboxPG {
+    persistence: PersistencePG,
+}
+
+impl OutboxPG {
+    pub fn new(persistence: PersistencePG) -> Self {
+        OutboxPG { persistence }
+    }
+}
+
+#[async_trait]
+impl Outbox for OutboxPG {
+    async fn claim_events(
+        &self,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<OutboxEventEntity>, RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        // a row claimed concurrently no longer passes the claim check, so
+        // is left to the relay that got it first
+        let mut models = sqlx::query_as!(
+            OutboxEvent,
+            r#"UPDATE outbox SET claimed_until = now() + make_interval(secs => $2), attempts = attempts + 1
+            WHERE id IN (
+                SELECT id FROM (
+                    SELECT DISTINCT ON (species, tenant_id, fact_id) id, next_attempt_at, claimed_until
+                    FROM outbox WHERE status = 'pending'
+                    ORDER BY species, tenant_id, fact_id, id
+                ) AS heads
+                WHERE next_attempt_at <= now() AND (claimed_until IS NULL OR claimed_until <= now())
+                ORDER BY id LIMIT $1
+            )
+            AND (claimed_until IS NULL OR claimed_until <= now())
+            RETURNING id, event_type, species, tenant_id, fact_id, text, version, attempts, delivered_to"#,
+            limit,
+            lease.as_secs_f64()
+        )
+        .fetch_all(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+        services::Transaction::commit(tx).await?;
+
+        models.sort_by_key(|model| model.id);
+        models
+            .into_iter()
+            .map(OutboxEventDbMapper::to_entity)
+            .collect()
+    }
+
+    async fn mark_delivered(&self, event_id: i64) -> Result<(), RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        sqlx::query!(
+            "UPDATE outbox SET status = 'delivered', delivered_at = now(), claimed_until = NULL, last_error = NULL WHERE id = $1",
+            event_id
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        services::Transaction::commit(tx).await
+    }
+
+    async fn retry_event(
+        &self,
+        event: &OutboxEventEntity,
+        error: &str,
+        delay: Duration,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        sqlx::query!(
+            "UPDATE outbox SET delivered_to = $2, last_error = $3, next_attempt_at = now() + make_interval(secs => $4), claimed_until = NULL WHERE id = $1",
+            event.event_id,
+            &event.delivered_to[..],
+            error,
+            delay.as_secs_f64()
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        services::Transaction::commit(tx).await
+    }
+
+    async fn dead_letter_event(
+        &self,
+        event: &OutboxEventEntity,
+        error: &str,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        sqlx::query!(
+            "UPDATE outbox SET status = 'dead', delivered_to = $2, last_error = $3, claimed_until = NULL WHERE id = $1",
+            event.event_id,
+            &event.delivered_to[..],
+            error
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        services::Transaction::commit(tx).await
+    }
+
+    async fn purge_delivered(&self, retention: Duration) -> Result<u64, RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        let purged = sqlx::query!(
+            "DELETE FROM outbox WHERE status = 'delivered' AND delivered_at <= now() - make_interval(secs => $1)",
+            retention.as_secs_f64()
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+        services::Transaction::commit(tx).await?;
+        Ok(purged)
+    }
+}
+
 #[async_trait()]
 impl services::Transaction for TransactionPG {
     async fn commit(self) -> Result<(), RepositoryError> {
-        self.0
-            .commit()
-            .await
-            .map_err(|e| RepositoryError(e.to_string()))
+        self.0.commit().await.map_err(db_error)
     }
     async fn rollback(self) -> Result<(), RepositoryError> {
-        self.0
-            .rollback()
-            .await
-            .map_err(|e| RepositoryError(e.to_string()))
+        self.0.rollback().await.map_err(db_error)
+    }
+    async fn savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("SAVEPOINT", name).await
+    }
+    async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("ROLLBACK TO SAVEPOINT", name).await
+    }
+    async fn release_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("RELEASE SAVEPOINT", name).await
+    }
+    async fn add_event(&mut self, event: &DomainEvent) -> Result<(), RepositoryError> {
+        let model = OutboxEventDbMapper::to_service(event);
+        sqlx::query!(
+            "INSERT INTO outbox (event_type, species, tenant_id, fact_id, text, version) VALUES ($1, $2, $3, $4, $5, $6)",
+            model.event_type,
+            model.species,
+            model.tenant_id,
+            model.fact_id,
+            model.text,
+            model.version
+        )
+        .execute(&mut *self.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
     }
 }
 
@@ -79,12 +528,12 @@ impl DogRepo<PersistencePG> for DogRepoPG {
     ) -> Result<Option<DogFactEntity>, RepositoryError> {
         let model = sqlx::query_as!(
             DogFact,
-            "SELECT * FROM dog_facts WHERE id = $1",
+            "SELECT id, fact, version FROM dog_facts WHERE id = $1",
             dog_fact_id
         )
         .fetch_optional(&mut *tx.0)
         .await
-        .map_err(|e| RepositoryError(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(model.map(DogFactDbMapper::to_entity))
     }
@@ -92,16 +541,85 @@ impl DogRepo<PersistencePG> for DogRepoPG {
     async fn get_all_dog_facts(
         tx: &mut TransactionPG,
     ) -> Result<Vec<DogFactEntity>, RepositoryError> {
-        let models = sqlx::query_as!(DogFact, "SELECT * FROM dog_facts")
+        let models = sqlx::query_as!(DogFact, "SELECT id, fact, version FROM dog_facts")
             .fetch_all(&mut *tx.0)
             .await
-            .map_err(|e| RepositoryError(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(models
             .into_iter()
             .map(DogFactDbMapper::to_entity)
             .collect::<Vec<DogFactEntity>>())
     }
+
+    async fn get_upstream_dog_fact(
+        tx: &mut TransactionPG,
+        upstream_index: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            DogFact,
+            "SELECT id, fact, version FROM dog_facts WHERE upstream_index = $1",
+            upstream_index
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(DogFactDbMapper::to_entity))
+    }
+
+    async fn insert_upstream_dog_fact(
+        tx: &mut TransactionPG,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<DogFactEntity, RepositoryError> {
+        let model = sqlx::query_as!(
+            DogFact,
+            "INSERT INTO dog_facts (fact, upstream_index) VALUES ($1, $2) RETURNING id, fact, version",
+            fact,
+            upstream_index
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(DogFactDbMapper::to_entity(model))
+    }
+
+    async fn flag_changed_dog_fact(
+        tx: &mut TransactionPG,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE dog_facts SET upstream_changed = true WHERE id = $1",
+            fact_id
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn update_dog_fact(
+        tx: &mut TransactionPG,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            DogFact,
+            "UPDATE dog_facts SET fact = $2, version = version + 1 WHERE id = $1 AND version = $3 RETURNING id, fact, version",
+            fact_id,
+            fact,
+            version
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(DogFactDbMapper::to_entity))
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -109,11 +627,30 @@ pub struct CatRepoPG {}
 
 #[async_trait()]
 impl CatRepo<PersistencePG> for CatRepoPG {
+    async fn get_cat_fact_by_id(
+        tx: &mut TransactionPG,
+        fact_id: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            "SELECT id, fact, version FROM cat_facts WHERE id = $1",
+            fact_id
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(CatFactDbMapper::to_entity))
+    }
+
     async fn get_random_cat_fact(tx: &mut TransactionPG) -> Result<CatFactEntity, RepositoryError> {
-        let model = sqlx::query_as!(CatFact, "SELECT * FROM cat_facts WHERE id = $1", 1)
-            .fetch_one(&mut *tx.0)
-            .await
-            .map_err(|e| RepositoryError(e.to_string()))?;
+        let model = sqlx::query_as!(
+            CatFact,
+            "SELECT id, fact, version FROM cat_facts ORDER BY id LIMIT 1"
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
 
         Ok(CatFactDbMapper::to_entity(model))
     }
@@ -121,14 +658,398 @@ impl CatRepo<PersistencePG> for CatRepoPG {
     async fn get_all_cat_facts(
         tx: &mut TransactionPG,
     ) -> Result<Vec<CatFactEntity>, RepositoryError> {
-        let models = sqlx::query_as!(CatFact, "SELECT * FROM cat_facts")
+        let models = sqlx::query_as!(CatFact, "SELECT id, fact, version FROM cat_facts")
             .fetch_all(&mut *tx.0)
             .await
-            .map_err(|e| RepositoryError(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(models
             .into_iter()
             .map(CatFactDbMapper::to_entity)
             .collect::<Vec<CatFactEntity>>())
     }
+
+    async fn insert_cat_fact(
+        tx: &mut TransactionPG,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            "INSERT INTO cat_facts (fact) VALUES ($1) RETURNING id, fact, version",
+            fact
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(CatFactDbMapper::to_entity(model))
+    }
+
+    async fn upsert_cat_fact(
+        tx: &mut TransactionPG,
+        fact: String,
+    ) -> Result<(CatFactEntity, bool), RepositoryError> {
+        let inserted = sqlx::query_as!(
+            CatFact,
+            "INSERT INTO cat_facts (fact) VALUES ($1) ON CONFLICT (tenant_id, fact) WHERE upstream_index IS NULL DO NOTHING RETURNING id, fact, version",
+            fact
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+        if let Some(model) = inserted {
+            return Ok((CatFactDbMapper::to_entity(model), true));
+        }
+
+        let model = sqlx::query_as!(
+            CatFact,
+            "SELECT id, fact, version FROM cat_facts WHERE fact = $1 AND upstream_index IS NULL",
+            fact
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok((CatFactDbMapper::to_entity(model), false))
+    }
+
+    async fn get_upstream_cat_fact(
+        tx: &mut TransactionPG,
+        upstream_index: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            "SELECT id, fact, version FROM cat_facts WHERE upstream_index = $1",
+            upstream_index
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(CatFactDbMapper::to_entity))
+    }
+
+    async fn insert_upstream_cat_fact(
+        tx: &mut TransactionPG,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            "INSERT INTO cat_facts (fact, upstream_index) VALUES ($1, $2) RETURNING id, fact, version",
+            fact,
+            upstream_index
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(CatFactDbMapper::to_entity(model))
+    }
+
+    async fn flag_changed_cat_fact(
+        tx: &mut TransactionPG,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE cat_facts SET upstream_changed = true WHERE id = $1",
+            fact_id
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn update_cat_fact(
+        tx: &mut TransactionPG,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            "UPDATE cat_facts SET fact = $2, version = version + 1 WHERE id = $1 AND version = $3 RETURNING id, fact, version",
+            fact_id,
+            fact,
+            version
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(CatFactDbMapper::to_entity))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SyncRunRepoPG {}
+
+#[async_trait()]
+impl SyncRunRepo<PersistencePG> for SyncRunRepoPG {
+    async fn create_sync_run(
+        tx: &mut TransactionPG,
+        run: SyncRunEntity,
+    ) -> Result<i64, RepositoryError> {
+        let model = SyncRunDbMapper::to_service(run);
+        let row = sqlx::query!(
+            "INSERT INTO sync_runs (source, tenant_id, started_at, inserted, unchanged, changed) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+            model.source,
+            model.tenant_id,
+            model.started_at,
+            model.inserted,
+            model.unchanged,
+            model.changed
+        )
+        .fetch_one(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(row.id)
+    }
+
+    async fn finish_sync_run(
+        tx: &mut TransactionPG,
+        run: SyncRunEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = SyncRunDbMapper::to_service(run);
+        sqlx::query!(
+            "UPDATE sync_runs SET finished_at = $2, inserted = $3, unchanged = $4, changed = $5, error = $6 WHERE id = $1",
+            model.id,
+            model.finished_at,
+            model.inserted,
+            model.unchanged,
+            model.changed,
+            model.error
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_sync_runs(
+        tx: &mut TransactionPG,
+        limit: i64,
+    ) -> Result<Vec<SyncRunEntity>, RepositoryError> {
+        let models = sqlx::query_as!(
+            SyncRun,
+            "SELECT id, source, tenant_id, started_at, finished_at, inserted, unchanged, changed, error FROM sync_runs ORDER BY started_at DESC, id DESC LIMIT $1",
+            limit
+        )
+        .fetch_all(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(SyncRunDbMapper::to_entity)
+            .collect::<Vec<SyncRunEntity>>())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SessionRepoPG {}
+
+#[async_trait()]
+impl SessionRepo<PersistencePG> for SessionRepoPG {
+    async fn create_session(
+        tx: &mut TransactionPG,
+        session: SessionEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = SessionDbMapper::to_service(session);
+        sqlx::query!(
+            "INSERT INTO sessions (id_hash, username, roles, csrf_token, created_at, expires_at) VALUES ($1, $2, $3, $4, $5, $6)",
+            model.id_hash,
+            model.username,
+            &model.roles[..],
+            model.csrf_token,
+            model.created_at,
+            model.expires_at
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_session(
+        tx: &mut TransactionPG,
+        id_hash: String,
+    ) -> Result<Option<SessionEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            Session,
+            "SELECT * FROM sessions WHERE id_hash = $1",
+            id_hash
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(SessionDbMapper::to_entity))
+    }
+
+    async fn renew_session(
+        tx: &mut TransactionPG,
+        id_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE sessions SET expires_at = $2 WHERE id_hash = $1",
+            id_hash,
+            expires_at
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_session(
+        tx: &mut TransactionPG,
+        id_hash: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE id_hash = $1", id_hash)
+            .execute(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_user_sessions(
+        tx: &mut TransactionPG,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE username = $1", username)
+            .execute(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_sessions(
+        tx: &mut TransactionPG,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE expires_at <= $1", now)
+            .execute(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct UserRepoPG {}
+
+#[async_trait()]
+impl UserRepo<PersistencePG> for UserRepoPG {
+    async fn create_user(tx: &mut TransactionPG, user: UserEntity) -> Result<(), RepositoryError> {
+        let model = UserDbMapper::to_service(user);
+        sqlx::query!(
+            "INSERT INTO users (username, email, password_hash) VALUES ($1, $2, $3)",
+            model.username,
+            model.email,
+            model.password_hash
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_user(
+        tx: &mut TransactionPG,
+        username: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        let model = sqlx::query_as!(User, "SELECT * FROM users WHERE username = $1", username)
+            .fetch_optional(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(model.map(UserDbMapper::to_entity))
+    }
+
+    async fn get_user_by_email(
+        tx: &mut TransactionPG,
+        email: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        let model = sqlx::query_as!(User, "SELECT * FROM users WHERE email = $1", email)
+            .fetch_optional(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(model.map(UserDbMapper::to_entity))
+    }
+
+    async fn update_password_hash(
+        tx: &mut TransactionPG,
+        username: String,
+        password_hash: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE users SET password_hash = $2 WHERE username = $1",
+            username,
+            password_hash
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn create_password_reset(
+        tx: &mut TransactionPG,
+        reset: PasswordResetEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = PasswordResetDbMapper::to_service(reset);
+        sqlx::query!(
+            "INSERT INTO password_resets (token_hash, username, expires_at) VALUES ($1, $2, $3)",
+            model.token_hash,
+            model.username,
+            model.expires_at
+        )
+        .execute(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn take_password_reset(
+        tx: &mut TransactionPG,
+        token_hash: String,
+    ) -> Result<Option<PasswordResetEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            PasswordReset,
+            "DELETE FROM password_resets WHERE token_hash = $1 RETURNING *",
+            token_hash
+        )
+        .fetch_optional(&mut *tx.0)
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(PasswordResetDbMapper::to_entity))
+    }
+
+    async fn delete_password_resets(
+        tx: &mut TransactionPG,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM password_resets WHERE username = $1", username)
+            .execute(&mut *tx.0)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
 }
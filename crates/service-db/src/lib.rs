@@ -1,3 +1,4 @@
 pub mod db_service;
 pub mod mappers;
 pub mod models;
+pub mod replicas;
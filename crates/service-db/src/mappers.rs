@@ -1,6 +1,12 @@
-use crate::models::{CatFact, DogFact};
-use app_core::mappers::service::ServiceMapper;
-use app_domain::entities::{CatFactEntity, DogFactEntity};
+use crate::models::{CatFact, DogFact, OutboxEvent, PasswordReset, Session, SyncRun, User};
+use app_core::{mappers::service::ServiceMapper, services::RepositoryError};
+use app_domain::{
+    entities::{
+        CatFactEntity, DogFactEntity, OutboxEventEntity, PasswordResetEntity, SessionEntity,
+        SyncRunEntity, UserEntity,
+    },
+    events::{DomainEvent, FactRef, Species},
+};
 
 pub struct DogFactDbMapper {}
 
@@ -9,6 +15,7 @@ impl ServiceMapper<DogFactEntity, DogFact> for DogFactDbMapper {
         DogFact {
             id: entity.fact_id,
             fact: entity.fact,
+            version: entity.version,
         }
     }
 
@@ -16,6 +23,7 @@ impl ServiceMapper<DogFactEntity, DogFact> for DogFactDbMapper {
         DogFactEntity {
             fact_id: model.id,
             fact: model.fact,
+            version: model.version,
         }
     }
 }
@@ -27,6 +35,7 @@ impl ServiceMapper<CatFactEntity, CatFact> for CatFactDbMapper {
         CatFact {
             id: entity.fact_id,
             fact: entity.fact_txt,
+            version: entity.version,
         }
     }
 
@@ -34,6 +43,159 @@ impl ServiceMapper<CatFactEntity, CatFact> for CatFactDbMapper {
         CatFactEntity {
             fact_id: model.id,
             fact_txt: model.fact,
+            version: model.version,
         }
     }
 }
+
+pub struct SessionDbMapper {}
+
+impl ServiceMapper<SessionEntity, Session> for SessionDbMapper {
+    fn to_service(entity: SessionEntity) -> Session {
+        Session {
+            id_hash: entity.session_id,
+            username: entity.username,
+            roles: entity.roles,
+            csrf_token: entity.csrf_token,
+            created_at: entity.created_at,
+            expires_at: entity.expires_at,
+        }
+    }
+
+    fn to_entity(model: Session) -> SessionEntity {
+        SessionEntity {
+            session_id: model.id_hash,
+            username: model.username,
+            roles: model.roles,
+            csrf_token: model.csrf_token,
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+        }
+    }
+}
+
+pub struct UserDbMapper {}
+
+impl ServiceMapper<UserEntity, User> for UserDbMapper {
+    fn to_service(entity: UserEntity) -> User {
+        User {
+            username: entity.username,
+            email: entity.email,
+            password_hash: entity.password_hash,
+        }
+    }
+
+    fn to_entity(model: User) -> UserEntity {
+        UserEntity {
+            username: model.username,
+            email: model.email,
+            password_hash: model.password_hash,
+        }
+    }
+}
+
+pub struct PasswordResetDbMapper {}
+
+impl ServiceMapper<PasswordResetEntity, PasswordReset> for PasswordResetDbMapper {
+    fn to_service(entity: PasswordResetEntity) -> PasswordReset {
+        PasswordReset {
+            token_hash: entity.token_hash,
+            username: entity.username,
+            expires_at: entity.expires_at,
+        }
+    }
+
+    fn to_entity(model: PasswordReset) -> PasswordResetEntity {
+        PasswordResetEntity {
+            token_hash: model.token_hash,
+            username: model.username,
+            expires_at: model.expires_at,
+        }
+    }
+}
+
+pub struct SyncRunDbMapper {}
+
+impl ServiceMapper<SyncRunEntity, SyncRun> for SyncRunDbMapper {
+    fn to_service(entity: SyncRunEntity) -> SyncRun {
+        SyncRun {
+            id: entity.run_id,
+            source: entity.source,
+            tenant_id: entity.tenant_id,
+            started_at: entity.started_at,
+            finished_at: entity.finished_at,
+            inserted: entity.inserted,
+            unchanged: entity.unchanged,
+            changed: entity.changed,
+            error: entity.error,
+        }
+    }
+
+    fn to_entity(model: SyncRun) -> SyncRunEntity {
+        SyncRunEntity {
+            run_id: model.id,
+            source: model.source,
+            tenant_id: model.tenant_id,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+            inserted: model.inserted,
+            unchanged: model.unchanged,
+            changed: model.changed,
+            error: model.error,
+        }
+    }
+}
+
+/// Events are kept flat, a column per field of any of them
+pub struct OutboxEventDbMapper {}
+
+impl OutboxEventDbMapper {
+    /// The row of a new event, its delivery yet to start
+    pub fn to_service(event: &DomainEvent) -> OutboxEvent {
+        let fact = event.fact();
+        let (text, version) = match event {
+            DomainEvent::FactCreated { text, .. } => (Some(text.clone()), None),
+            DomainEvent::FactUpdated { text, version, .. } => (Some(text.clone()), Some(*version)),
+        };
+        OutboxEvent {
+            id: 0,
+            event_type: event.name().to_string(),
+            species: match fact.species {
+                Species::Cat => "cat",
+                Species::Dog => "dog",
+            }
+            .to_string(),
+            tenant_id: fact.tenant_id.clone(),
+            fact_id: fact.fact_id,
+            text,
+            version,
+            attempts: 0,
+            delivered_to: vec![],
+        }
+    }
+
+    pub fn to_entity(model: OutboxEvent) -> Result<OutboxEventEntity, RepositoryError> {
+        let malformed = || RepositoryError::new(format!("Malformed outbox event {}", model.id));
+        let species = match model.species.as_str() {
+            "cat" => Species::Cat,
+            "dog" => Species::Dog,
+            _ => return Err(malformed()),
+        };
+        let fact = FactRef::new(species, model.tenant_id.clone(), model.fact_id);
+        let event = match (model.event_type.as_str(), model.text.clone(), model.version) {
+            ("fact_created", Some(text), _) => DomainEvent::FactCreated { fact, text },
+            ("fact_updated", Some(text), Some(version)) => DomainEvent::FactUpdated {
+                fact,
+                text,
+                version,
+            },
+            _ => return Err(malformed()),
+        };
+        Ok(OutboxEventEntity {
+            event_id: model.id,
+            event,
+            attempts: model.attempts,
+            delivered_to: model.delivered_to,
+        })
+    }
+}
@@ -1,9 +1,58 @@
+use chrono::{DateTime, Utc};
+
 pub struct DogFact {
     pub id: i32,
     pub fact: String,
+    pub version: i32,
 }
 
 pub struct CatFact {
     pub id: i32,
     pub fact: String,
+    pub version: i32,
+}
+
+pub struct Session {
+    pub id_hash: String,
+    pub username: String,
+    pub roles: Vec<String>,
+    pub csrf_token: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub struct User {
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+}
+
+pub struct PasswordReset {
+    pub token_hash: String,
+    pub username: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub struct SyncRun {
+    pub id: i64,
+    pub source: String,
+    pub tenant_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub inserted: i32,
+    pub unchanged: i32,
+    pub changed: i32,
+    pub error: Option<String>,
+}
+
+pub struct OutboxEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub species: String,
+    pub tenant_id: String,
+    pub fact_id: i32,
+    pub text: Option<String>,
+    pub version: Option<i32>,
+    pub attempts: i32,
+    pub delivered_to: Vec<String>,
 }
@@ -0,0 +1,113 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+
+use app_core::services::RepositoryError;
+
+#[derive(Debug, Clone)]
+pub struct ReplicaSettings {
+    /// Connection URLs of the read replicas, reads stay on the primary
+    /// without any
+    pub urls: Vec<String>,
+    /// Replicas further behind the primary are left out, reads go to the
+    /// primary once all of them are
+    pub max_lag: Duration,
+    /// How long a replica that failed or lagged is left out
+    pub retry_after: Duration,
+    /// How long to wait for a connection of a replica before moving on
+    pub connect_timeout: Duration,
+}
+
+impl Default for ReplicaSettings {
+    fn default() -> Self {
+        ReplicaSettings {
+            urls: vec![],
+            max_lag: Duration::from_secs(5),
+            retry_after: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+pub(crate) struct Replica {
+    pub pool: Pool<Postgres>,
+    left_out_until: Mutex<Option<Instant>>,
+}
+
+impl Replica {
+    fn is_available(&self, now: Instant) -> bool {
+        let left_out_until = self
+            .left_out_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        left_out_until.is_none_or(|until| now >= until)
+    }
+
+    pub fn leave_out(&self, now: Instant, retry_after: Duration) {
+        *self
+            .left_out_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(now + retry_after);
+    }
+}
+
+/// The replicas of the primary, taking turns serving reads
+pub(crate) struct Replicas {
+    replicas: Vec<Replica>,
+    next: AtomicUsize,
+    pub settings: ReplicaSettings,
+}
+
+impl Replicas {
+    /// Replicas are connected to on first use, one being down doesn't keep
+    /// the app from starting
+    pub fn new(settings: ReplicaSettings, max_connections: u32) -> Result<Self, RepositoryError> {
+        let replicas = settings
+            .urls
+            .iter()
+            .map(|url| {
+                let pool = PgPoolOptions::new()
+                    .max_connections(max_connections)
+                    .acquire_timeout(settings.connect_timeout)
+                    .connect_lazy(url)
+                    .map_err(|e| RepositoryError::new(e.to_string()))?;
+                Ok(Replica {
+                    pool,
+                    left_out_until: Mutex::new(None),
+                })
+            })
+            .collect::<Result<_, RepositoryError>>()?;
+
+        Ok(Replicas {
+            replicas,
+            next: AtomicUsize::new(0),
+            settings,
+        })
+    }
+
+    pub fn none() -> Self {
+        Replicas {
+            replicas: vec![],
+            next: AtomicUsize::new(0),
+            settings: ReplicaSettings::default(),
+        }
+    }
+
+    /// The replicas not left out, starting with the one whose turn it is
+    pub fn in_turn(&self, now: Instant) -> Vec<&Replica> {
+        if self.replicas.is_empty() {
+            return vec![];
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|i| &self.replicas[(start + i) % self.replicas.len()])
+            .filter(|replica| replica.is_available(now))
+            .collect()
+    }
+}
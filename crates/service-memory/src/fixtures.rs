@@ -0,0 +1,33 @@
+use std::{fs, io, path::Path};
+
+use app_core::services::RepositoryError;
+use serde::Deserialize;
+
+pub const CAT_FACTS_FILE: &str = "cat_facts.json";
+pub const DOG_FACTS_FILE: &str = "dog_facts.json";
+
+/// A fact as listed in a fixtures file, the same files the integration
+/// tests import into Postgres
+#[derive(Debug, Deserialize)]
+pub struct FactFixture {
+    pub id: i32,
+    pub fact: String,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+}
+
+fn default_tenant() -> String {
+    String::from("default")
+}
+
+/// The facts of a fixtures file, none when there is no such file
+pub fn read_facts(path: &Path) -> Result<Vec<FactFixture>, RepositoryError> {
+    let json = match fs::read(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(RepositoryError::new(format!("{}: {}", path.display(), e))),
+    };
+
+    serde_json::from_slice(&json)
+        .map_err(|e| RepositoryError::new(format!("{}: {}", path.display(), e)))
+}
@@ -0,0 +1,3 @@
+pub mod fixtures;
+pub mod memory_service;
+mod store;
@@ -0,0 +1,1016 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    fixtures::{read_facts, FactFixture, CAT_FACTS_FILE, DOG_FACTS_FILE},
+    store::{check_upstream_index, check_version, FactRow, Store, Table},
+};
+use app_core::services::{
+    self, AuthAuditLog, CatRepo, DogRepo, IsolationLevel, Persistence, RepositoryError,
+    SchemaMigrations, SessionRepo, SyncRunRepo, TransactionOptions, UserRepo,
+};
+use app_domain::entities::{
+    AuthEventEntity, CatFactEntity, DogFactEntity, MigrationEntity, PasswordResetEntity,
+    SessionEntity, SyncRunEntity, TenantEntity, UserEntity,
+};
+
+/// Everything kept in the process memory, for running without a database
+///
+/// Clones share the same data, which is gone once the process exits.
+#[derive(Clone, Default)]
+pub struct PersistenceMemory {
+    store: Arc<Mutex<Store>>,
+}
+
+impl PersistenceMemory {
+    pub fn new() -> Self {
+        PersistenceMemory::default()
+    }
+
+    /// Start with the facts of the `cat_facts.json` and `dog_facts.json`
+    /// files of a directory, either of them may be missing
+    pub fn from_fixtures(dir: impl AsRef<Path>) -> Result<Self, RepositoryError> {
+        let dir = dir.as_ref();
+        let persistence = PersistenceMemory::new();
+        {
+            let mut store = lock(&persistence.store)?;
+            store.last_cat_fact_id =
+                load_facts(&mut store.cat_facts, read_facts(&dir.join(CAT_FACTS_FILE))?);
+            store.last_dog_fact_id =
+                load_facts(&mut store.dog_facts, read_facts(&dir.join(DOG_FACTS_FILE))?);
+        }
+        Ok(persistence)
+    }
+
+    fn begin(
+        &self,
+        tenant: Option<String>,
+        options: TransactionOptions,
+    ) -> Result<TransactionMemory, RepositoryError> {
+        let store = lock(&self.store)?;
+        let read_only = options.read_only;
+        let serializable_from = match options.isolation {
+            IsolationLevel::Serializable => Some(store.commits),
+            IsolationLevel::ReadCommitted | IsolationLevel::RepeatableRead => None,
+        };
+        Ok(TransactionMemory {
+            store: self.store.clone(),
+            tenant,
+            serializable_from,
+            cat_facts: Table::snapshot(&store.cat_facts, read_only),
+            dog_facts: Table::snapshot(&store.dog_facts, read_only),
+            sessions: Table::snapshot(&store.sessions, read_only),
+            sync_runs: Table::snapshot(&store.sync_runs, read_only),
+            users: Table::snapshot(&store.users, read_only),
+            password_resets: Table::snapshot(&store.password_resets, read_only),
+            savepoints: vec![],
+        })
+    }
+}
+
+/// Returns the highest id, for new facts to come after
+fn load_facts(rows: &mut BTreeMap<i32, FactRow>, facts: Vec<FactFixture>) -> i32 {
+    for fact in facts {
+        let row = FactRow::new(fact.tenant_id, fact.fact, None);
+        rows.insert(fact.id, row);
+    }
+    rows.keys().next_back().copied().unwrap_or_default()
+}
+
+fn lock(store: &Mutex<Store>) -> Result<MutexGuard<'_, Store>, RepositoryError> {
+    store
+        .lock()
+        .map_err(|e| RepositoryError::new(e.to_string()))
+}
+
+#[async_trait]
+impl Persistence for PersistenceMemory {
+    type Transaction = TransactionMemory;
+    async fn get_transaction(&self) -> Result<TransactionMemory, RepositoryError> {
+        self.begin(None, TransactionOptions::default())
+    }
+
+    async fn get_tenant_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<TransactionMemory, RepositoryError> {
+        self.begin(
+            Some(tenant.tenant_id.clone()),
+            TransactionOptions::default(),
+        )
+    }
+
+    async fn get_transaction_with(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<TransactionMemory, RepositoryError> {
+        self.begin(None, options)
+    }
+
+    async fn get_tenant_transaction_with(
+        &self,
+        tenant: &TenantEntity,
+        options: TransactionOptions,
+    ) -> Result<TransactionMemory, RepositoryError> {
+        self.begin(Some(tenant.tenant_id.clone()), options)
+    }
+}
+
+/// A snapshot of the data taken when the transaction began, its writes
+/// only reach the other transactions once committed
+///
+/// Like the database, a tenant transaction only sees and writes the facts
+/// of its tenant, and one of no tenant none.
+///
+/// Reading a snapshot is as isolated as repeatable read, a serializable
+/// transaction also fails to commit its writes once another one committed
+/// some since it began. A read-only one fails to write.
+pub struct TransactionMemory {
+    store: Arc<Mutex<Store>>,
+    tenant: Option<String>,
+    /// Commits of the store when a serializable transaction began
+    serializable_from: Option<u64>,
+    cat_facts: Table<i32, FactRow>,
+    dog_facts: Table<i32, FactRow>,
+    sessions: Table<String, SessionEntity>,
+    sync_runs: Table<i64, SyncRunEntity>,
+    users: Table<String, UserEntity>,
+    password_resets: Table<String, PasswordResetEntity>,
+    /// Latest last, names may be taken again
+    savepoints: Vec<Savepoint>,
+}
+
+/// The tables of a transaction as they were at a savepoint
+struct Savepoint {
+    name: String,
+    cat_facts: Table<i32, FactRow>,
+    dog_facts: Table<i32, FactRow>,
+    sessions: Table<String, SessionEntity>,
+    sync_runs: Table<i64, SyncRunEntity>,
+    users: Table<String, UserEntity>,
+    password_resets: Table<String, PasswordResetEntity>,
+}
+
+impl TransactionMemory {
+    /// Position of the latest savepoint of that name
+    fn find_savepoint(&self, name: &str) -> Result<usize, RepositoryError> {
+        self.savepoints
+            .iter()
+            .rposition(|savepoint| savepoint.name == name)
+            .ok_or_else(|| RepositoryError::new(format!("savepoint {} does not exist", name)))
+    }
+
+    fn cat_facts(&mut self) -> Facts<'_> {
+        Facts {
+            rows: &mut self.cat_facts,
+            tenant: self.tenant.as_deref(),
+        }
+    }
+
+    fn dog_facts(&mut self) -> Facts<'_> {
+        Facts {
+            rows: &mut self.dog_facts,
+            tenant: self.tenant.as_deref(),
+        }
+    }
+
+    fn is_written(&self) -> bool {
+        self.cat_facts.is_written()
+            || self.dog_facts.is_written()
+            || self.sessions.is_written()
+            || self.sync_runs.is_written()
+            || self.users.is_written()
+            || self.password_resets.is_written()
+    }
+
+    fn next_cat_fact_id(&self) -> Result<i32, RepositoryError> {
+        let mut store = lock(&self.store)?;
+        store.last_cat_fact_id += 1;
+        Ok(store.last_cat_fact_id)
+    }
+
+    fn next_dog_fact_id(&self) -> Result<i32, RepositoryError> {
+        let mut store = lock(&self.store)?;
+        store.last_dog_fact_id += 1;
+        Ok(store.last_dog_fact_id)
+    }
+
+    fn next_sync_run_id(&self) -> Result<i64, RepositoryError> {
+        let mut store = lock(&self.store)?;
+        store.last_sync_run_id += 1;
+        Ok(store.last_sync_run_id)
+    }
+}
+
+/// The facts of one table a transaction may see
+struct Facts<'t> {
+    rows: &'t mut Table<i32, FactRow>,
+    tenant: Option<&'t str>,
+}
+
+impl<'t> Facts<'t> {
+    fn is_visible(&self, row: &FactRow) -> bool {
+        self.tenant == Some(row.tenant_id.as_str())
+    }
+
+    /// In id order
+    fn all(&self) -> Vec<(i32, FactRow)> {
+        self.rows
+            .rows()
+            .iter()
+            .filter(|(_, row)| self.is_visible(row))
+            .map(|(id, row)| (*id, row.clone()))
+            .collect()
+    }
+
+    fn get(&self, id: i32) -> Option<FactRow> {
+        self.rows
+            .get(&id)
+            .filter(|row| self.is_visible(row))
+            .cloned()
+    }
+
+    fn get_upstream(&self, upstream_index: i32) -> Option<(i32, FactRow)> {
+        self.rows
+            .rows()
+            .iter()
+            .find(|(_, row)| self.is_visible(row) && row.upstream_index == Some(upstream_index))
+            .map(|(id, row)| (*id, row.clone()))
+    }
+
+    fn insert(
+        &mut self,
+        id: i32,
+        fact: String,
+        upstream_index: Option<i32>,
+    ) -> Result<(), RepositoryError> {
+        let tenant_id = self
+            .tenant
+            .ok_or_else(|| RepositoryError::new("facts are only written for a tenant".into()))?
+            .to_string();
+        let row = FactRow::new(tenant_id, fact, upstream_index);
+        check_upstream_index(self.rows.rows(), id, &row)?;
+        self.rows.put(id, row)
+    }
+
+    fn flag_changed(&mut self, id: i32) -> Result<(), RepositoryError> {
+        let row = match self.rows.get(&id) {
+            Some(row) if self.is_visible(row) => row,
+            _ => return Ok(()),
+        };
+        let row = FactRow {
+            upstream_changed: true,
+            ..row.clone()
+        };
+        self.rows.put(id, row)
+    }
+
+    /// The fact at the next version, `None` when missing or at another one
+    fn update(
+        &mut self,
+        id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<FactRow>, RepositoryError> {
+        let row = match self.rows.get(&id) {
+            Some(row) if self.is_visible(row) && row.version == version => row,
+            _ => return Ok(None),
+        };
+        let row = FactRow {
+            fact,
+            version: version + 1,
+            ..row.clone()
+        };
+        self.rows.put(id, row.clone())?;
+        Ok(Some(row))
+    }
+}
+
+fn dog_fact(id: i32, row: FactRow) -> DogFactEntity {
+    DogFactEntity {
+        fact_id: id,
+        fact: row.fact,
+        version: row.version,
+    }
+}
+
+fn cat_fact(id: i32, row: FactRow) -> CatFactEntity {
+    CatFactEntity {
+        fact_txt: row.fact,
+        fact_id: id,
+        version: row.version,
+    }
+}
+
+/// The committed facts with the transaction's writes applied, provided
+/// they keep upstream positions unique and update facts as they were read
+fn commit_facts(
+    table: &Table<i32, FactRow>,
+    committed: &BTreeMap<i32, FactRow>,
+) -> Result<BTreeMap<i32, FactRow>, RepositoryError> {
+    let mut rows = committed.clone();
+    table.apply(&mut rows);
+    for (id, row) in table.changes() {
+        if let Some(row) = row {
+            check_upstream_index(&rows, *id, row)?;
+            check_version(table.original(id), committed.get(id), *id, row)?;
+        }
+    }
+    Ok(rows)
+}
+
+#[async_trait()]
+impl services::Transaction for TransactionMemory {
+    async fn commit(self) -> Result<(), RepositoryError> {
+        let mut store = lock(&self.store)?;
+        if !self.is_written() {
+            return Ok(());
+        }
+        if self
+            .serializable_from
+            .is_some_and(|commits| commits != store.commits)
+        {
+            return Err(RepositoryError::transient(
+                "could not serialize access due to concurrent writes".into(),
+            ));
+        }
+        // checked before anything is written, a failed commit writes nothing
+        let cat_facts = commit_facts(&self.cat_facts, &store.cat_facts)?;
+        let dog_facts = commit_facts(&self.dog_facts, &store.dog_facts)?;
+
+        store.cat_facts = cat_facts;
+        store.dog_facts = dog_facts;
+        self.sessions.apply(&mut store.sessions);
+        self.sync_runs.apply(&mut store.sync_runs);
+        self.users.apply(&mut store.users);
+        self.password_resets.apply(&mut store.password_resets);
+        store.commits += 1;
+        Ok(())
+    }
+    async fn rollback(self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+    async fn savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.savepoints.push(Savepoint {
+            name: name.to_string(),
+            cat_facts: self.cat_facts.clone(),
+            dog_facts: self.dog_facts.clone(),
+            sessions: self.sessions.clone(),
+            sync_runs: self.sync_runs.clone(),
+            users: self.users.clone(),
+            password_resets: self.password_resets.clone(),
+        });
+        Ok(())
+    }
+    async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        let position = self.find_savepoint(name)?;
+        // the later savepoints go, this one stays
+        self.savepoints.truncate(position + 1);
+        let savepoint = &self.savepoints[position];
+        self.cat_facts = savepoint.cat_facts.clone();
+        self.dog_facts = savepoint.dog_facts.clone();
+        self.sessions = savepoint.sessions.clone();
+        self.sync_runs = savepoint.sync_runs.clone();
+        self.users = savepoint.users.clone();
+        self.password_resets = savepoint.password_resets.clone();
+        Ok(())
+    }
+    async fn release_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        let position = self.find_savepoint(name)?;
+        self.savepoints.truncate(position);
+        Ok(())
+    }
+}
+
+/// Authentication events kept in memory, outside of any transaction
+pub struct AuthAuditLogMemory {
+    persistence: PersistenceMemory,
+}
+
+impl AuthAuditLogMemory {
+    pub fn new(persistence: PersistenceMemory) -> Self {
+        AuthAuditLogMemory { persistence }
+    }
+}
+
+#[async_trait]
+impl AuthAuditLog for AuthAuditLogMemory {
+    async fn record(&self, event: AuthEventEntity) -> Result<(), RepositoryError> {
+        lock(&self.persistence.store)?.auth_events.push(event);
+        Ok(())
+    }
+}
+
+/// There is no schema to migrate in memory
+pub struct SchemaMigrationsMemory {}
+
+#[async_trait]
+impl SchemaMigrations for SchemaMigrationsMemory {
+    async fn get_migrations(&self) -> Result<Vec<MigrationEntity>, RepositoryError> {
+        Ok(vec![])
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DogRepoMemory {}
+
+#[async_trait()]
+impl DogRepo<PersistenceMemory> for DogRepoMemory {
+    async fn get_dog_fact_by_id(
+        tx: &mut TransactionMemory,
+        dog_fact_id: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        Ok(tx
+            .dog_facts()
+            .get(dog_fact_id)
+            .map(|row| dog_fact(dog_fact_id, row)))
+    }
+
+    async fn get_all_dog_facts(
+        tx: &mut TransactionMemory,
+    ) -> Result<Vec<DogFactEntity>, RepositoryError> {
+        Ok(tx
+            .dog_facts()
+            .all()
+            .into_iter()
+            .map(|(id, row)| dog_fact(id, row))
+            .collect())
+    }
+
+    async fn get_upstream_dog_fact(
+        tx: &mut TransactionMemory,
+        upstream_index: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        Ok(tx
+            .dog_facts()
+            .get_upstream(upstream_index)
+            .map(|(id, row)| dog_fact(id, row)))
+    }
+
+    async fn insert_upstream_dog_fact(
+        tx: &mut TransactionMemory,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<DogFactEntity, RepositoryError> {
+        let id = tx.next_dog_fact_id()?;
+        tx.dog_facts()
+            .insert(id, fact.clone(), Some(upstream_index))?;
+        Ok(DogFactEntity::new(id, fact))
+    }
+
+    async fn flag_changed_dog_fact(
+        tx: &mut TransactionMemory,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        tx.dog_facts().flag_changed(fact_id)
+    }
+
+    async fn update_dog_fact(
+        tx: &mut TransactionMemory,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        Ok(tx
+            .dog_facts()
+            .update(fact_id, fact, version)?
+            .map(|row| dog_fact(fact_id, row)))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CatRepoMemory {}
+
+#[async_trait()]
+impl CatRepo<PersistenceMemory> for CatRepoMemory {
+    async fn get_cat_fact_by_id(
+        tx: &mut TransactionMemory,
+        fact_id: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        Ok(tx
+            .cat_facts()
+            .get(fact_id)
+            .map(|row| cat_fact(fact_id, row)))
+    }
+
+    async fn get_random_cat_fact(
+        tx: &mut TransactionMemory,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        tx.cat_facts()
+            .all()
+            .into_iter()
+            .next()
+            .map(|(id, row)| cat_fact(id, row))
+            .ok_or_else(|| RepositoryError::new(String::from("no cat facts found")))
+    }
+
+    async fn get_all_cat_facts(
+        tx: &mut TransactionMemory,
+    ) -> Result<Vec<CatFactEntity>, RepositoryError> {
+        Ok(tx
+            .cat_facts()
+            .all()
+            .into_iter()
+            .map(|(id, row)| cat_fact(id, row))
+            .collect())
+    }
+
+    async fn insert_cat_fact(
+        tx: &mut TransactionMemory,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let id = tx.next_cat_fact_id()?;
+        tx.cat_facts().insert(id, fact.clone(), None)?;
+        Ok(CatFactEntity::new(fact, id))
+    }
+
+    async fn upsert_cat_fact(
+        tx: &mut TransactionMemory,
+        fact: String,
+    ) -> Result<(CatFactEntity, bool), RepositoryError> {
+        let kept = tx
+            .cat_facts()
+            .all()
+            .into_iter()
+            .find(|(_, row)| row.upstream_index.is_none() && row.fact == fact);
+        if let Some((id, row)) = kept {
+            return Ok((cat_fact(id, row), false));
+        }
+
+        let id = tx.next_cat_fact_id()?;
+        tx.cat_facts().insert(id, fact.clone(), None)?;
+        Ok((CatFactEntity::new(fact, id), true))
+    }
+
+    async fn get_upstream_cat_fact(
+        tx: &mut TransactionMemory,
+        upstream_index: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        Ok(tx
+            .cat_facts()
+            .get_upstream(upstream_index)
+            .map(|(id, row)| cat_fact(id, row)))
+    }
+
+    async fn insert_upstream_cat_fact(
+        tx: &mut TransactionMemory,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let id = tx.next_cat_fact_id()?;
+        tx.cat_facts()
+            .insert(id, fact.clone(), Some(upstream_index))?;
+        Ok(CatFactEntity::new(fact, id))
+    }
+
+    async fn flag_changed_cat_fact(
+        tx: &mut TransactionMemory,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        tx.cat_facts().flag_changed(fact_id)
+    }
+
+    async fn update_cat_fact(
+        tx: &mut TransactionMemory,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        Ok(tx
+            .cat_facts()
+            .update(fact_id, fact, version)?
+            .map(|row| cat_fact(fact_id, row)))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SyncRunRepoMemory {}
+
+#[async_trait()]
+impl SyncRunRepo<PersistenceMemory> for SyncRunRepoMemory {
+    async fn create_sync_run(
+        tx: &mut TransactionMemory,
+        run: SyncRunEntity,
+    ) -> Result<i64, RepositoryError> {
+        let run_id = tx.next_sync_run_id()?;
+        tx.sync_runs.put(run_id, SyncRunEntity { run_id, ..run })?;
+        Ok(run_id)
+    }
+
+    async fn finish_sync_run(
+        tx: &mut TransactionMemory,
+        run: SyncRunEntity,
+    ) -> Result<(), RepositoryError> {
+        let started = match tx.sync_runs.get(&run.run_id) {
+            Some(started) => started.clone(),
+            None => return Ok(()),
+        };
+        tx.sync_runs.put(
+            run.run_id,
+            SyncRunEntity {
+                source: started.source,
+                tenant_id: started.tenant_id,
+                started_at: started.started_at,
+                ..run
+            },
+        )?;
+        Ok(())
+    }
+
+    async fn get_sync_runs(
+        tx: &mut TransactionMemory,
+        limit: i64,
+    ) -> Result<Vec<SyncRunEntity>, RepositoryError> {
+        let mut runs: Vec<SyncRunEntity> = tx.sync_runs.rows().values().cloned().collect();
+        runs.sort_by_key(|run| Reverse((run.started_at, run.run_id)));
+        runs.truncate(limit.max(0) as usize);
+        Ok(runs)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SessionRepoMemory {}
+
+#[async_trait()]
+impl SessionRepo<PersistenceMemory> for SessionRepoMemory {
+    async fn create_session(
+        tx: &mut TransactionMemory,
+        session: SessionEntity,
+    ) -> Result<(), RepositoryError> {
+        if tx.sessions.get(&session.session_id).is_some() {
+            return Err(RepositoryError::new(format!(
+                "duplicate session id {}",
+                session.session_id
+            )));
+        }
+        tx.sessions.put(session.session_id.clone(), session)?;
+        Ok(())
+    }
+
+    async fn get_session(
+        tx: &mut TransactionMemory,
+        id_hash: String,
+    ) -> Result<Option<SessionEntity>, RepositoryError> {
+        Ok(tx.sessions.get(&id_hash).cloned())
+    }
+
+    async fn renew_session(
+        tx: &mut TransactionMemory,
+        id_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        if let Some(session) = tx.sessions.get(&id_hash) {
+            let session = SessionEntity {
+                expires_at,
+                ..session.clone()
+            };
+            tx.sessions.put(id_hash, session)?;
+        }
+        Ok(())
+    }
+
+    async fn delete_session(
+        tx: &mut TransactionMemory,
+        id_hash: String,
+    ) -> Result<(), RepositoryError> {
+        tx.sessions.remove(&id_hash)?;
+        Ok(())
+    }
+
+    async fn delete_user_sessions(
+        tx: &mut TransactionMemory,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        let ended: Vec<String> = tx
+            .sessions
+            .rows()
+            .iter()
+            .filter(|(_, session)| session.username == username)
+            .map(|(id_hash, _)| id_hash.clone())
+            .collect();
+        for id_hash in ended {
+            tx.sessions.remove(&id_hash)?;
+        }
+        Ok(())
+    }
+
+    async fn delete_expired_sessions(
+        tx: &mut TransactionMemory,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let expired: Vec<String> = tx
+            .sessions
+            .rows()
+            .iter()
+            .filter(|(_, session)| session.expires_at <= now)
+            .map(|(id_hash, _)| id_hash.clone())
+            .collect();
+        for id_hash in expired {
+            tx.sessions.remove(&id_hash)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct UserRepoMemory {}
+
+#[async_trait()]
+impl UserRepo<PersistenceMemory> for UserRepoMemory {
+    async fn create_user(
+        tx: &mut TransactionMemory,
+        user: UserEntity,
+    ) -> Result<(), RepositoryError> {
+        let taken = tx
+            .users
+            .rows()
+            .values()
+            .any(|other| other.username == user.username || other.email == user.email);
+        if taken {
+            return Err(RepositoryError::new(format!(
+                "duplicate username {} or email",
+                user.username
+            )));
+        }
+        tx.users.put(user.username.clone(), user)?;
+        Ok(())
+    }
+
+    async fn get_user(
+        tx: &mut TransactionMemory,
+        username: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        Ok(tx.users.get(&username).cloned())
+    }
+
+    async fn get_user_by_email(
+        tx: &mut TransactionMemory,
+        email: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        Ok(tx
+            .users
+            .rows()
+            .values()
+            .find(|user| user.email == email)
+            .cloned())
+    }
+
+    async fn update_password_hash(
+        tx: &mut TransactionMemory,
+        username: String,
+        password_hash: String,
+    ) -> Result<(), RepositoryError> {
+        if let Some(user) = tx.users.get(&username) {
+            let user = UserEntity {
+                password_hash,
+                ..user.clone()
+            };
+            tx.users.put(username, user)?;
+        }
+        Ok(())
+    }
+
+    async fn create_password_reset(
+        tx: &mut TransactionMemory,
+        reset: PasswordResetEntity,
+    ) -> Result<(), RepositoryError> {
+        tx.password_resets.put(reset.token_hash.clone(), reset)?;
+        Ok(())
+    }
+
+    async fn take_password_reset(
+        tx: &mut TransactionMemory,
+        token_hash: String,
+    ) -> Result<Option<PasswordResetEntity>, RepositoryError> {
+        let reset = tx.password_resets.get(&token_hash).cloned();
+        tx.password_resets.remove(&token_hash)?;
+        Ok(reset)
+    }
+
+    async fn delete_password_resets(
+        tx: &mut TransactionMemory,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        let resets: Vec<String> = tx
+            .password_resets
+            .rows()
+            .iter()
+            .filter(|(_, reset)| reset.username == username)
+            .map(|(token_hash, _)| token_hash.clone())
+            .collect();
+        for token_hash in resets {
+            tx.password_resets.remove(&token_hash)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app_core::services::Transaction;
+
+    fn acme() -> TenantEntity {
+        TenantEntity::new(String::from("acme"))
+    }
+
+    fn default() -> TenantEntity {
+        TenantEntity::new(String::from("default"))
+    }
+
+    /// Those of acme
+    async fn cat_facts(persistence: &PersistenceMemory) -> Vec<String> {
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::get_all_cat_facts(&mut tx)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fact| fact.fact_txt)
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_should_only_show_writes_once_committed() {
+        // given a fact inserted by a transaction not committed yet
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats purr"))
+            .await
+            .unwrap();
+        let mut concurrent = persistence.get_tenant_transaction(&acme()).await.unwrap();
+
+        // then only the writing transaction sees it
+        assert_eq!(
+            CatRepoMemory::get_all_cat_facts(&mut tx)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(cat_facts(&persistence).await.is_empty());
+
+        // when committed
+        tx.commit().await.unwrap();
+
+        // then new transactions see it, and the one begun before still doesn't
+        assert_eq!(cat_facts(&persistence).await, vec!["Cats purr"]);
+        assert!(CatRepoMemory::get_all_cat_facts(&mut concurrent)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_discard_writes_rolled_back() {
+        // given facts inserted by transactions rolled back, or just dropped
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats purr"))
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats nap"))
+            .await
+            .unwrap();
+        drop(tx);
+
+        // then none of them is stored
+        assert!(cat_facts(&persistence).await.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_isolate_tenants() {
+        // given a fact of each tenant
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence
+            .get_tenant_transaction(&default())
+            .await
+            .unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Default cats"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Acme cats"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // when reading as the second tenant
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        let facts = CatRepoMemory::get_all_cat_facts(&mut tx).await.unwrap();
+
+        // then only its fact shows
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].fact_txt, "Acme cats");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_hide_facts_from_transactions_of_no_tenant() {
+        // given a fact of the default tenant
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence
+            .get_tenant_transaction(&default())
+            .await
+            .unwrap();
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Default cats"))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // when reading and writing facts outside of any tenant
+        let mut tx = persistence.get_transaction().await.unwrap();
+        let facts = CatRepoMemory::get_all_cat_facts(&mut tx).await.unwrap();
+        let inserted = CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats nap")).await;
+
+        // then none is seen and none written, as with row level security
+        assert!(facts.is_empty());
+        assert!(inserted.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_refuse_writes_of_read_only_transactions() {
+        // given a read-only transaction
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence
+            .get_tenant_transaction_with(&acme(), TransactionOptions::read_only())
+            .await
+            .unwrap();
+
+        // when writing in it
+        let inserted = CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats nap")).await;
+        let created = UserRepoMemory::create_user(
+            &mut tx,
+            UserEntity::new("jane".into(), "jane@example.com".into(), "hash".into()),
+        )
+        .await;
+
+        // then nothing is written
+        assert!(inserted.is_err());
+        assert!(created.is_err());
+        tx.commit().await.unwrap();
+        assert!(cat_facts(&persistence).await.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fail_serializable_commit_after_concurrent_writes() {
+        // given a serializable transaction, and another one committing meanwhile
+        let persistence = PersistenceMemory::new();
+        let mut tx = persistence
+            .get_tenant_transaction_with(&acme(), TransactionOptions::serializable())
+            .await
+            .unwrap();
+        let mut concurrent = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        CatRepoMemory::insert_cat_fact(&mut concurrent, String::from("Cats purr"))
+            .await
+            .unwrap();
+        concurrent.commit().await.unwrap();
+
+        // when the first one writes and commits
+        CatRepoMemory::insert_cat_fact(&mut tx, String::from("Cats nap"))
+            .await
+            .unwrap();
+        let result = tx.commit().await;
+
+        // then it fails to be run again, without writing anything
+        assert!(result.is_err_and(|e| e.transient));
+        assert_eq!(cat_facts(&persistence).await, vec!["Cats purr"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_reject_commit_breaking_upstream_index() {
+        // given two transactions synchronising the same upstream fact
+        let persistence = PersistenceMemory::new();
+        let mut first = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        let mut second = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        DogRepoMemory::insert_upstream_dog_fact(&mut first, 0, String::from("Dogs bark"))
+            .await
+            .unwrap();
+        DogRepoMemory::insert_upstream_dog_fact(&mut second, 0, String::from("Dogs bark"))
+            .await
+            .unwrap();
+
+        // when both commit
+        first.commit().await.unwrap();
+        let result = second.commit().await;
+
+        // then the second one fails, without writing anything
+        assert!(result.is_err());
+        let mut tx = persistence.get_tenant_transaction(&acme()).await.unwrap();
+        assert_eq!(
+            DogRepoMemory::get_all_dog_facts(&mut tx)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use app_core::services::RepositoryError;
+use app_domain::entities::{
+    AuthEventEntity, PasswordResetEntity, SessionEntity, SyncRunEntity, UserEntity,
+};
+
+/// A row of the `cat_facts` or `dog_facts` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FactRow {
+    pub tenant_id: String,
+    pub fact: String,
+    /// Position of the fact in the upstream listing, `None` for facts added locally
+    pub upstream_index: Option<i32>,
+    pub upstream_changed: bool,
+    pub version: i32,
+}
+
+impl FactRow {
+    pub fn new(tenant_id: String, fact: String, upstream_index: Option<i32>) -> Self {
+        FactRow {
+            tenant_id,
+            fact,
+            upstream_index,
+            upstream_changed: false,
+            version: 1,
+        }
+    }
+}
+
+/// Committed rows, as every new transaction first sees them
+#[derive(Default)]
+pub(crate) struct Store {
+    pub cat_facts: BTreeMap<i32, FactRow>,
+    pub dog_facts: BTreeMap<i32, FactRow>,
+    pub sessions: BTreeMap<String, SessionEntity>,
+    pub sync_runs: BTreeMap<i64, SyncRunEntity>,
+    pub users: BTreeMap<String, UserEntity>,
+    /// By token hash
+    pub password_resets: BTreeMap<String, PasswordResetEntity>,
+    pub auth_events: Vec<AuthEventEntity>,
+    /// Last ids handed out, like sequences they never go back on rollback
+    pub last_cat_fact_id: i32,
+    pub last_dog_fact_id: i32,
+    pub last_sync_run_id: i64,
+    /// Commits that wrote anything, for a serializable transaction to tell
+    /// whether another one wrote since it began
+    pub commits: u64,
+}
+
+/// The rows of a table as seen by one transaction: a snapshot of the
+/// committed ones, with the transaction's own writes applied
+#[derive(Clone)]
+pub(crate) struct Table<K, V> {
+    rows: BTreeMap<K, V>,
+    /// Writing fails, as in a read-only transaction
+    read_only: bool,
+    written: BTreeSet<K>,
+    /// The rows as they were before the transaction first wrote them
+    originals: BTreeMap<K, Option<V>>,
+}
+
+impl<K: Ord + Clone, V: Clone> Table<K, V> {
+    pub fn snapshot(committed: &BTreeMap<K, V>, read_only: bool) -> Self {
+        Table {
+            rows: committed.clone(),
+            read_only,
+            written: BTreeSet::new(),
+            originals: BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.rows.get(key)
+    }
+
+    pub fn rows(&self) -> &BTreeMap<K, V> {
+        &self.rows
+    }
+
+    pub fn put(&mut self, key: K, row: V) -> Result<(), RepositoryError> {
+        self.check_writable()?;
+        self.keep_original(&key);
+        self.written.insert(key.clone());
+        self.rows.insert(key, row);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: &K) -> Result<(), RepositoryError> {
+        self.check_writable()?;
+        self.keep_original(key);
+        if self.rows.remove(key).is_some() {
+            self.written.insert(key.clone());
+        }
+        Ok(())
+    }
+
+    fn check_writable(&self) -> Result<(), RepositoryError> {
+        if self.read_only {
+            return Err(RepositoryError::new(
+                "cannot write in a read-only transaction".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn keep_original(&mut self, key: &K) {
+        if !self.originals.contains_key(key) {
+            let original = self.rows.get(key).cloned();
+            self.originals.insert(key.clone(), original);
+        }
+    }
+
+    /// The row as the transaction saw it before writing it
+    pub fn original(&self, key: &K) -> Option<&V> {
+        match self.originals.get(key) {
+            Some(original) => original.as_ref(),
+            None => self.rows.get(key),
+        }
+    }
+
+    pub fn is_written(&self) -> bool {
+        !self.written.is_empty()
+    }
+
+    /// The rows written by the transaction, `None` for removed ones
+    pub fn changes(&self) -> impl Iterator<Item = (&K, Option<&V>)> {
+        self.written.iter().map(|key| (key, self.rows.get(key)))
+    }
+
+    /// Write the transaction's changes over the committed rows, leaving
+    /// alone those changed by other transactions in the meantime
+    pub fn apply(&self, committed: &mut BTreeMap<K, V>) {
+        for (key, row) in self.changes() {
+            match row {
+                Some(row) => committed.insert(key.clone(), row.clone()),
+                None => committed.remove(key),
+            };
+        }
+    }
+}
+
+/// Facts are unique per tenant and upstream position, as the
+/// `(tenant_id, upstream_index)` index enforces in the database
+pub(crate) fn check_upstream_index(
+    rows: &BTreeMap<i32, FactRow>,
+    id: i32,
+    row: &FactRow,
+) -> Result<(), RepositoryError> {
+    let index = match row.upstream_index {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+    let taken = rows.iter().any(|(other_id, other)| {
+        *other_id != id && other.tenant_id == row.tenant_id && other.upstream_index == Some(index)
+    });
+    if taken {
+        return Err(RepositoryError::new(format!(
+            "duplicate upstream index {} for tenant {}",
+            index, row.tenant_id
+        )));
+    }
+    Ok(())
+}
+
+/// A fact updated by the transaction must still be at the version it was
+/// updated from, as the conditional update ensures in the database
+pub(crate) fn check_version(
+    original: Option<&FactRow>,
+    committed: Option<&FactRow>,
+    id: i32,
+    row: &FactRow,
+) -> Result<(), RepositoryError> {
+    let (original, committed) = match (original, committed) {
+        (Some(original), Some(committed)) => (original, committed),
+        _ => return Ok(()),
+    };
+    if row.version != original.version && committed.version != original.version {
+        return Err(RepositoryError::transient(format!(
+            "fact {} was updated concurrently",
+            id
+        )));
+    }
+    Ok(())
+}
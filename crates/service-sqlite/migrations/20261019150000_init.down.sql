@@ -0,0 +1,7 @@
+DROP TABLE "password_resets";
+DROP TABLE "users";
+DROP TABLE "sync_runs";
+DROP TABLE "auth_events";
+DROP TABLE "sessions";
+DROP TABLE "cat_facts";
+DROP TABLE "dog_facts";
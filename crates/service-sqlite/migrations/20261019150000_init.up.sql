@@ -0,0 +1,68 @@
+-- the schema of the Postgres migrations so far, tenants being enforced by
+-- the repositories as SQLite has no row level security
+CREATE TABLE "dog_facts" (id INTEGER PRIMARY KEY AUTOINCREMENT,
+                          fact TEXT NOT NULL,
+                          tenant_id TEXT NOT NULL DEFAULT 'default',
+                          upstream_index INTEGER,
+                          upstream_changed BOOLEAN NOT NULL DEFAULT false);
+
+CREATE INDEX "dog_facts_tenant_id_idx" ON "dog_facts" (tenant_id);
+CREATE UNIQUE INDEX "dog_facts_upstream_index_idx" ON "dog_facts" (tenant_id, upstream_index);
+
+
+CREATE TABLE "cat_facts" (id INTEGER PRIMARY KEY AUTOINCREMENT,
+                          fact TEXT NOT NULL,
+                          tenant_id TEXT NOT NULL DEFAULT 'default',
+                          upstream_index INTEGER,
+                          upstream_changed BOOLEAN NOT NULL DEFAULT false);
+
+CREATE INDEX "cat_facts_tenant_id_idx" ON "cat_facts" (tenant_id);
+CREATE UNIQUE INDEX "cat_facts_upstream_index_idx" ON "cat_facts" (tenant_id, upstream_index);
+
+
+-- roles are a JSON array
+-- sessions are looked up by the SHA-256 of their id, a leaked table can't be replayed
+CREATE TABLE "sessions" (id_hash TEXT PRIMARY KEY,
+                         username TEXT NOT NULL,
+                         roles TEXT NOT NULL DEFAULT '[]',
+                         csrf_token TEXT NOT NULL,
+                         created_at DATETIME NOT NULL,
+                         expires_at DATETIME NOT NULL);
+
+CREATE INDEX "sessions_expires_at_idx" ON "sessions" (expires_at);
+CREATE INDEX "sessions_username_idx" ON "sessions" (username);
+
+
+CREATE TABLE "auth_events" (id INTEGER PRIMARY KEY AUTOINCREMENT,
+                            kind TEXT NOT NULL,
+                            username TEXT NOT NULL,
+                            client_ip TEXT,
+                            occurred_at DATETIME NOT NULL);
+
+CREATE INDEX "auth_events_username_idx" ON "auth_events" (username, occurred_at);
+
+
+CREATE TABLE "sync_runs" (id INTEGER PRIMARY KEY AUTOINCREMENT,
+                          source TEXT NOT NULL,
+                          tenant_id TEXT NOT NULL,
+                          started_at DATETIME NOT NULL,
+                          finished_at DATETIME,
+                          inserted INTEGER NOT NULL,
+                          unchanged INTEGER NOT NULL,
+                          changed INTEGER NOT NULL,
+                          error TEXT);
+
+CREATE INDEX "sync_runs_started_at_idx" ON "sync_runs" (started_at);
+
+
+CREATE TABLE "users" (username TEXT PRIMARY KEY,
+                      email TEXT NOT NULL UNIQUE,
+                      password_hash TEXT NOT NULL);
+
+
+-- only the hash of the token mailed is kept
+CREATE TABLE "password_resets" (token_hash TEXT PRIMARY KEY,
+                                username TEXT NOT NULL REFERENCES "users" (username) ON DELETE CASCADE,
+                                expires_at DATETIME NOT NULL);
+
+CREATE INDEX "password_resets_username_idx" ON "password_resets" (username);
@@ -0,0 +1,2 @@
+ALTER TABLE "cat_facts" DROP COLUMN version;
+ALTER TABLE "dog_facts" DROP COLUMN version;
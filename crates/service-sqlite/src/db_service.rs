@@ -0,0 +1,864 @@
+use std::{
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    Pool, Sqlite, SqliteConnection, Transaction,
+};
+
+use crate::{
+    mappers::{
+        CatFactDbMapper, DogFactDbMapper, PasswordResetDbMapper, SessionDbMapper, SyncRunDbMapper,
+        UserDbMapper,
+    },
+    models::{CatFact, DogFact, PasswordReset, Session, SyncRun, User},
+};
+use app_core::{
+    mappers::service::ServiceMapper,
+    services::{
+        self, AuthAuditLog, CatRepo, DogRepo, Persistence, RepositoryError, SchemaMigrations,
+        SessionRepo, SyncRunRepo, TransactionOptions, UserRepo,
+    },
+};
+use app_domain::entities::{
+    AuthEventEntity, CatFactEntity, DogFactEntity, MigrationEntity, PasswordResetEntity,
+    SessionEntity, SyncRunEntity, TenantEntity, UserEntity,
+};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Clone)]
+pub struct PersistenceSqlite {
+    pool: Pool<Sqlite>,
+}
+
+impl PersistenceSqlite {
+    /// Open a `sqlite:` database, creating its file when missing
+    pub async fn new(database_url: &str) -> Result<Self, RepositoryError> {
+        let options = SqliteConnectOptions::from_str(database_url)
+            .map_err(db_error)?
+            .create_if_missing(true)
+            // readers don't block the writer, writers queue for a while
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+
+        Ok(PersistenceSqlite {
+            pool: SqlitePoolOptions::new()
+                // a read-only transaction leaves its connection read-only
+                .after_release(|conn, _| {
+                    Box::pin(async move {
+                        sqlx::query("PRAGMA query_only = OFF").execute(conn).await?;
+                        Ok(true)
+                    })
+                })
+                .connect_with(options)
+                .await
+                .map_err(db_error)?,
+        })
+    }
+
+    /// Bring the schema up to date, there is no separate migration step
+    /// for a single file database
+    pub async fn migrate(&self) -> Result<(), RepositoryError> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| RepositoryError::new(e.to_string()))
+    }
+
+    /// Every isolation level is kept, SQLite transactions are serializable
+    async fn begin(
+        &self,
+        tenant: Option<String>,
+        options: TransactionOptions,
+    ) -> Result<TransactionSqlite, RepositoryError> {
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        if options.read_only {
+            sqlx::query("PRAGMA query_only = ON")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(TransactionSqlite {
+            tx: Mutex::new(tx),
+            tenant,
+        })
+    }
+}
+
+/// The database stayed locked by another writer past the busy timeout, or
+/// a reader's snapshot went stale before it could write
+fn db_error(e: sqlx::Error) -> RepositoryError {
+    let transient = e
+        .as_database_error()
+        .and_then(|db| db.code())
+        .is_some_and(|code| code == "5" || code == "6" || code == "517");
+    if transient {
+        RepositoryError::transient(e.to_string())
+    } else {
+        RepositoryError::new(e.to_string())
+    }
+}
+
+#[async_trait]
+impl Persistence for PersistenceSqlite {
+    type Transaction = TransactionSqlite;
+    async fn get_transaction(&self) -> Result<TransactionSqlite, RepositoryError> {
+        self.begin(None, TransactionOptions::default()).await
+    }
+
+    async fn get_tenant_transaction(
+        &self,
+        tenant: &TenantEntity,
+    ) -> Result<TransactionSqlite, RepositoryError> {
+        self.begin(
+            Some(tenant.tenant_id.clone()),
+            TransactionOptions::default(),
+        )
+        .await
+    }
+
+    async fn get_transaction_with(
+        &self,
+        options: TransactionOptions,
+    ) -> Result<TransactionSqlite, RepositoryError> {
+        self.begin(None, options).await
+    }
+
+    async fn get_tenant_transaction_with(
+        &self,
+        tenant: &TenantEntity,
+        options: TransactionOptions,
+    ) -> Result<TransactionSqlite, RepositoryError> {
+        self.begin(Some(tenant.tenant_id.clone()), options).await
+    }
+}
+
+/// SQLite has no row level security, a tenant transaction carries its
+/// tenant for every query on facts to filter on
+///
+/// Like the database, a transaction of no tenant sees no facts and can't
+/// write any.
+pub struct TransactionSqlite {
+    /// Only ever reached through `&mut`, the mutex is there for the
+    /// connection to be shared between threads, which SQLite's can't
+    tx: Mutex<Transaction<'static, Sqlite>>,
+    /// No fact is seen when unset, and new ones break `NOT NULL`
+    tenant: Option<String>,
+}
+
+impl TransactionSqlite {
+    /// Savepoint names can't be bound, they go in quoted
+    async fn execute_savepoint(
+        &mut self,
+        statement: &str,
+        name: &str,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query(&format!("{} \"{}\"", statement, name.replace('"', "\"\"")))
+            .execute(connection(&mut self.tx))
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+fn connection<'t>(tx: &'t mut Mutex<Transaction<'static, Sqlite>>) -> &'t mut SqliteConnection {
+    tx.get_mut().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Authentication events kept in the `auth_events` table
+pub struct AuthAuditLogSqlite {
+    persistence: PersistenceSqlite,
+}
+
+impl AuthAuditLogSqlite {
+    pub fn new(persistence: PersistenceSqlite) -> Self {
+        AuthAuditLogSqlite { persistence }
+    }
+}
+
+#[async_trait]
+impl AuthAuditLog for AuthAuditLogSqlite {
+    async fn record(&self, event: AuthEventEntity) -> Result<(), RepositoryError> {
+        let mut tx = self.persistence.get_transaction().await?;
+        let kind = event.kind.to_string();
+        sqlx::query!(
+            "INSERT INTO auth_events (kind, username, client_ip, occurred_at) VALUES (?1, ?2, ?3, ?4)",
+            kind,
+            event.username,
+            event.client_ip,
+            event.occurred_at
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        services::Transaction::commit(tx).await
+    }
+}
+
+/// The migrations built in, and the ones recorded by `sqlx` as applied
+pub struct SchemaMigrationsSqlite {
+    persistence: PersistenceSqlite,
+}
+
+impl SchemaMigrationsSqlite {
+    pub fn new(persistence: PersistenceSqlite) -> Self {
+        SchemaMigrationsSqlite { persistence }
+    }
+}
+
+#[async_trait]
+impl SchemaMigrations for SchemaMigrationsSqlite {
+    async fn get_migrations(&self) -> Result<Vec<MigrationEntity>, RepositoryError> {
+        let mut conn = self.persistence.pool.acquire().await.map_err(db_error)?;
+        let applied = conn
+            .list_applied_migrations()
+            .await
+            .map_err(|e| RepositoryError::new(e.to_string()))?;
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .map(|migration| MigrationEntity {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.iter().any(|a| a.version == migration.version),
+            })
+            .collect())
+    }
+}
+
+#[async_trait()]
+impl services::Transaction for TransactionSqlite {
+    async fn commit(self) -> Result<(), RepositoryError> {
+        self.tx
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .commit()
+            .await
+            .map_err(db_error)
+    }
+    async fn rollback(self) -> Result<(), RepositoryError> {
+        self.tx
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .rollback()
+            .await
+            .map_err(db_error)
+    }
+    async fn savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("SAVEPOINT", name).await
+    }
+    async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("ROLLBACK TO SAVEPOINT", name).await
+    }
+    async fn release_savepoint(&mut self, name: &str) -> Result<(), RepositoryError> {
+        self.execute_savepoint("RELEASE SAVEPOINT", name).await
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DogRepoSqlite {}
+
+#[async_trait()]
+impl DogRepo<PersistenceSqlite> for DogRepoSqlite {
+    async fn get_dog_fact_by_id(
+        tx: &mut TransactionSqlite,
+        dog_fact_id: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            DogFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM dog_facts WHERE id = ?1 AND tenant_id = ?2"#,
+            dog_fact_id,
+            tx.tenant
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(DogFactDbMapper::to_entity))
+    }
+
+    async fn get_all_dog_facts(
+        tx: &mut TransactionSqlite,
+    ) -> Result<Vec<DogFactEntity>, RepositoryError> {
+        let models = sqlx::query_as!(
+            DogFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM dog_facts WHERE tenant_id = ?1 ORDER BY id"#,
+            tx.tenant
+        )
+        .fetch_all(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(DogFactDbMapper::to_entity)
+            .collect::<Vec<DogFactEntity>>())
+    }
+
+    async fn get_upstream_dog_fact(
+        tx: &mut TransactionSqlite,
+        upstream_index: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            DogFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM dog_facts WHERE upstream_index = ?1 AND tenant_id = ?2"#,
+            upstream_index,
+            tx.tenant
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(DogFactDbMapper::to_entity))
+    }
+
+    async fn insert_upstream_dog_fact(
+        tx: &mut TransactionSqlite,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<DogFactEntity, RepositoryError> {
+        let id = sqlx::query!(
+            "INSERT INTO dog_facts (fact, upstream_index, tenant_id) VALUES (?1, ?2, ?3)",
+            fact,
+            upstream_index,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+
+        Ok(DogFactDbMapper::to_entity(DogFact {
+            id: id as i32,
+            fact,
+            version: 1,
+        }))
+    }
+
+    async fn flag_changed_dog_fact(
+        tx: &mut TransactionSqlite,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE dog_facts SET upstream_changed = true WHERE id = ?1 AND tenant_id = ?2",
+            fact_id,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn update_dog_fact(
+        tx: &mut TransactionSqlite,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<DogFactEntity>, RepositoryError> {
+        let updated = sqlx::query!(
+            "UPDATE dog_facts SET fact = ?1, version = version + 1 WHERE id = ?2 AND version = ?3 AND tenant_id = ?4",
+            fact,
+            fact_id,
+            version,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(None);
+        }
+        DogRepoSqlite::get_dog_fact_by_id(tx, fact_id).await
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CatRepoSqlite {}
+
+#[async_trait()]
+impl CatRepo<PersistenceSqlite> for CatRepoSqlite {
+    async fn get_cat_fact_by_id(
+        tx: &mut TransactionSqlite,
+        fact_id: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM cat_facts WHERE id = ?1 AND tenant_id = ?2"#,
+            fact_id,
+            tx.tenant
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(CatFactDbMapper::to_entity))
+    }
+
+    async fn get_random_cat_fact(
+        tx: &mut TransactionSqlite,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM cat_facts WHERE tenant_id = ?1 ORDER BY id LIMIT 1"#,
+            tx.tenant
+        )
+        .fetch_one(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(CatFactDbMapper::to_entity(model))
+    }
+
+    async fn get_all_cat_facts(
+        tx: &mut TransactionSqlite,
+    ) -> Result<Vec<CatFactEntity>, RepositoryError> {
+        let models = sqlx::query_as!(
+            CatFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM cat_facts WHERE tenant_id = ?1 ORDER BY id"#,
+            tx.tenant
+        )
+        .fetch_all(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(CatFactDbMapper::to_entity)
+            .collect::<Vec<CatFactEntity>>())
+    }
+
+    async fn insert_cat_fact(
+        tx: &mut TransactionSqlite,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let id = sqlx::query!(
+            "INSERT INTO cat_facts (fact, tenant_id) VALUES (?1, ?2)",
+            fact,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+
+        Ok(CatFactDbMapper::to_entity(CatFact {
+            id: id as i32,
+            fact,
+            version: 1,
+        }))
+    }
+
+    async fn upsert_cat_fact(
+        tx: &mut TransactionSqlite,
+        fact: String,
+    ) -> Result<(CatFactEntity, bool), RepositoryError> {
+        let inserted = sqlx::query!(
+            "INSERT INTO cat_facts (fact, tenant_id) VALUES (?1, ?2) ON CONFLICT (tenant_id, fact) WHERE upstream_index IS NULL DO NOTHING",
+            fact,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+        if inserted.rows_affected() > 0 {
+            return Ok((
+                CatFactDbMapper::to_entity(CatFact {
+                    id: inserted.last_insert_rowid() as i32,
+                    fact,
+                    version: 1,
+                }),
+                true,
+            ));
+        }
+
+        let model = sqlx::query_as!(
+            CatFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM cat_facts WHERE fact = ?1 AND tenant_id = ?2 AND upstream_index IS NULL"#,
+            fact,
+            tx.tenant
+        )
+        .fetch_one(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok((CatFactDbMapper::to_entity(model), false))
+    }
+
+    async fn get_upstream_cat_fact(
+        tx: &mut TransactionSqlite,
+        upstream_index: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            CatFact,
+            r#"SELECT id AS "id!: i32", fact, version AS "version: i32" FROM cat_facts WHERE upstream_index = ?1 AND tenant_id = ?2"#,
+            upstream_index,
+            tx.tenant
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(CatFactDbMapper::to_entity))
+    }
+
+    async fn insert_upstream_cat_fact(
+        tx: &mut TransactionSqlite,
+        upstream_index: i32,
+        fact: String,
+    ) -> Result<CatFactEntity, RepositoryError> {
+        let id = sqlx::query!(
+            "INSERT INTO cat_facts (fact, upstream_index, tenant_id) VALUES (?1, ?2, ?3)",
+            fact,
+            upstream_index,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+
+        Ok(CatFactDbMapper::to_entity(CatFact {
+            id: id as i32,
+            fact,
+            version: 1,
+        }))
+    }
+
+    async fn flag_changed_cat_fact(
+        tx: &mut TransactionSqlite,
+        fact_id: i32,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE cat_facts SET upstream_changed = true WHERE id = ?1 AND tenant_id = ?2",
+            fact_id,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn update_cat_fact(
+        tx: &mut TransactionSqlite,
+        fact_id: i32,
+        fact: String,
+        version: i32,
+    ) -> Result<Option<CatFactEntity>, RepositoryError> {
+        let updated = sqlx::query!(
+            "UPDATE cat_facts SET fact = ?1, version = version + 1 WHERE id = ?2 AND version = ?3 AND tenant_id = ?4",
+            fact,
+            fact_id,
+            version,
+            tx.tenant
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+        if updated == 0 {
+            return Ok(None);
+        }
+        CatRepoSqlite::get_cat_fact_by_id(tx, fact_id).await
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SyncRunRepoSqlite {}
+
+#[async_trait()]
+impl SyncRunRepo<PersistenceSqlite> for SyncRunRepoSqlite {
+    async fn create_sync_run(
+        tx: &mut TransactionSqlite,
+        run: SyncRunEntity,
+    ) -> Result<i64, RepositoryError> {
+        let model = SyncRunDbMapper::to_service(run);
+        let id = sqlx::query!(
+            "INSERT INTO sync_runs (source, tenant_id, started_at, inserted, unchanged, changed) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            model.source,
+            model.tenant_id,
+            model.started_at,
+            model.inserted,
+            model.unchanged,
+            model.changed
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn finish_sync_run(
+        tx: &mut TransactionSqlite,
+        run: SyncRunEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = SyncRunDbMapper::to_service(run);
+        sqlx::query!(
+            "UPDATE sync_runs SET finished_at = ?2, inserted = ?3, unchanged = ?4, changed = ?5, error = ?6 WHERE id = ?1",
+            model.id,
+            model.finished_at,
+            model.inserted,
+            model.unchanged,
+            model.changed,
+            model.error
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_sync_runs(
+        tx: &mut TransactionSqlite,
+        limit: i64,
+    ) -> Result<Vec<SyncRunEntity>, RepositoryError> {
+        let models = sqlx::query_as!(
+            SyncRun,
+            r#"SELECT id AS "id!", source, tenant_id, started_at AS "started_at: DateTime<Utc>", finished_at AS "finished_at: DateTime<Utc>", inserted AS "inserted: i32", unchanged AS "unchanged: i32", changed AS "changed: i32", error FROM sync_runs ORDER BY started_at DESC, id DESC LIMIT ?1"#,
+            limit
+        )
+        .fetch_all(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(models
+            .into_iter()
+            .map(SyncRunDbMapper::to_entity)
+            .collect::<Vec<SyncRunEntity>>())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SessionRepoSqlite {}
+
+#[async_trait()]
+impl SessionRepo<PersistenceSqlite> for SessionRepoSqlite {
+    async fn create_session(
+        tx: &mut TransactionSqlite,
+        session: SessionEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = SessionDbMapper::to_service(session);
+        sqlx::query!(
+            "INSERT INTO sessions (id_hash, username, roles, csrf_token, created_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            model.id_hash,
+            model.username,
+            model.roles,
+            model.csrf_token,
+            model.created_at,
+            model.expires_at
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_session(
+        tx: &mut TransactionSqlite,
+        id_hash: String,
+    ) -> Result<Option<SessionEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            Session,
+            r#"SELECT id_hash AS "id_hash!", username, roles, csrf_token, created_at AS "created_at: DateTime<Utc>", expires_at AS "expires_at: DateTime<Utc>" FROM sessions WHERE id_hash = ?1"#,
+            id_hash
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(SessionDbMapper::to_entity))
+    }
+
+    async fn renew_session(
+        tx: &mut TransactionSqlite,
+        id_hash: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE sessions SET expires_at = ?2 WHERE id_hash = ?1",
+            id_hash,
+            expires_at
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_session(
+        tx: &mut TransactionSqlite,
+        id_hash: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE id_hash = ?1", id_hash)
+            .execute(connection(&mut tx.tx))
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_user_sessions(
+        tx: &mut TransactionSqlite,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE username = ?1", username)
+            .execute(connection(&mut tx.tx))
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_sessions(
+        tx: &mut TransactionSqlite,
+        now: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM sessions WHERE expires_at <= ?1", now)
+            .execute(connection(&mut tx.tx))
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct UserRepoSqlite {}
+
+#[async_trait()]
+impl UserRepo<PersistenceSqlite> for UserRepoSqlite {
+    async fn create_user(
+        tx: &mut TransactionSqlite,
+        user: UserEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = UserDbMapper::to_service(user);
+        sqlx::query!(
+            "INSERT INTO users (username, email, password_hash) VALUES (?1, ?2, ?3)",
+            model.username,
+            model.email,
+            model.password_hash
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn get_user(
+        tx: &mut TransactionSqlite,
+        username: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            User,
+            r#"SELECT username AS "username!", email, password_hash FROM users WHERE username = ?1"#,
+            username
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(UserDbMapper::to_entity))
+    }
+
+    async fn get_user_by_email(
+        tx: &mut TransactionSqlite,
+        email: String,
+    ) -> Result<Option<UserEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            User,
+            r#"SELECT username AS "username!", email, password_hash FROM users WHERE email = ?1"#,
+            email
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(UserDbMapper::to_entity))
+    }
+
+    async fn update_password_hash(
+        tx: &mut TransactionSqlite,
+        username: String,
+        password_hash: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE users SET password_hash = ?2 WHERE username = ?1",
+            username,
+            password_hash
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn create_password_reset(
+        tx: &mut TransactionSqlite,
+        reset: PasswordResetEntity,
+    ) -> Result<(), RepositoryError> {
+        let model = PasswordResetDbMapper::to_service(reset);
+        sqlx::query!(
+            "INSERT INTO password_resets (token_hash, username, expires_at) VALUES (?1, ?2, ?3)",
+            model.token_hash,
+            model.username,
+            model.expires_at
+        )
+        .execute(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    async fn take_password_reset(
+        tx: &mut TransactionSqlite,
+        token_hash: String,
+    ) -> Result<Option<PasswordResetEntity>, RepositoryError> {
+        let model = sqlx::query_as!(
+            PasswordReset,
+            r#"DELETE FROM password_resets WHERE token_hash = ?1 RETURNING token_hash AS "token_hash!", username AS "username!", expires_at AS "expires_at!: DateTime<Utc>""#,
+            token_hash
+        )
+        .fetch_optional(connection(&mut tx.tx))
+        .await
+        .map_err(db_error)?;
+
+        Ok(model.map(PasswordResetDbMapper::to_entity))
+    }
+
+    async fn delete_password_resets(
+        tx: &mut TransactionSqlite,
+        username: String,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM password_resets WHERE username = ?1", username)
+            .execute(connection(&mut tx.tx))
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
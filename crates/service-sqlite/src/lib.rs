@@ -0,0 +1,3 @@
+pub mod db_service;
+pub mod mappers;
+pub mod models;
@@ -0,0 +1,143 @@
+use crate::models::{CatFact, DogFact, PasswordReset, Session, SyncRun, User};
+use app_core::mappers::service::ServiceMapper;
+use app_domain::entities::{
+    CatFactEntity, DogFactEntity, PasswordResetEntity, SessionEntity, SyncRunEntity, UserEntity,
+};
+
+pub struct DogFactDbMapper {}
+
+impl ServiceMapper<DogFactEntity, DogFact> for DogFactDbMapper {
+    fn to_service(entity: DogFactEntity) -> DogFact {
+        DogFact {
+            id: entity.fact_id,
+            fact: entity.fact,
+            version: entity.version,
+        }
+    }
+
+    fn to_entity(model: DogFact) -> DogFactEntity {
+        DogFactEntity {
+            fact_id: model.id,
+            fact: model.fact,
+            version: model.version,
+        }
+    }
+}
+
+pub struct CatFactDbMapper {}
+
+impl ServiceMapper<CatFactEntity, CatFact> for CatFactDbMapper {
+    fn to_service(entity: CatFactEntity) -> CatFact {
+        CatFact {
+            id: entity.fact_id,
+            fact: entity.fact_txt,
+            version: entity.version,
+        }
+    }
+
+    fn to_entity(model: CatFact) -> CatFactEntity {
+        CatFactEntity {
+            fact_id: model.id,
+            fact_txt: model.fact,
+            version: model.version,
+        }
+    }
+}
+
+pub struct SessionDbMapper {}
+
+impl ServiceMapper<SessionEntity, Session> for SessionDbMapper {
+    fn to_service(entity: SessionEntity) -> Session {
+        Session {
+            id_hash: entity.session_id,
+            username: entity.username,
+            roles: serde_json::Value::from(entity.roles).to_string(),
+            csrf_token: entity.csrf_token,
+            created_at: entity.created_at,
+            expires_at: entity.expires_at,
+        }
+    }
+
+    fn to_entity(model: Session) -> SessionEntity {
+        SessionEntity {
+            session_id: model.id_hash,
+            username: model.username,
+            roles: serde_json::from_str(&model.roles).unwrap_or_default(),
+            csrf_token: model.csrf_token,
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+        }
+    }
+}
+
+pub struct UserDbMapper {}
+
+impl ServiceMapper<UserEntity, User> for UserDbMapper {
+    fn to_service(entity: UserEntity) -> User {
+        User {
+            username: entity.username,
+            email: entity.email,
+            password_hash: entity.password_hash,
+        }
+    }
+
+    fn to_entity(model: User) -> UserEntity {
+        UserEntity {
+            username: model.username,
+            email: model.email,
+            password_hash: model.password_hash,
+        }
+    }
+}
+
+pub struct PasswordResetDbMapper {}
+
+impl ServiceMapper<PasswordResetEntity, PasswordReset> for PasswordResetDbMapper {
+    fn to_service(entity: PasswordResetEntity) -> PasswordReset {
+        PasswordReset {
+            token_hash: entity.token_hash,
+            username: entity.username,
+            expires_at: entity.expires_at,
+        }
+    }
+
+    fn to_entity(model: PasswordReset) -> PasswordResetEntity {
+        PasswordResetEntity {
+            token_hash: model.token_hash,
+            username: model.username,
+            expires_at: model.expires_at,
+        }
+    }
+}
+
+pub struct SyncRunDbMapper {}
+
+impl ServiceMapper<SyncRunEntity, SyncRun> for SyncRunDbMapper {
+    fn to_service(entity: SyncRunEntity) -> SyncRun {
+        SyncRun {
+            id: entity.run_id,
+            source: entity.source,
+            tenant_id: entity.tenant_id,
+            started_at: entity.started_at,
+            finished_at: entity.finished_at,
+            inserted: entity.inserted,
+            unchanged: entity.unchanged,
+            changed: entity.changed,
+            error: entity.error,
+        }
+    }
+
+    fn to_entity(model: SyncRun) -> SyncRunEntity {
+        SyncRunEntity {
+            run_id: model.id,
+            source: model.source,
+            tenant_id: model.tenant_id,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+            inserted: model.inserted,
+            unchanged: model.unchanged,
+            changed: model.changed,
+            error: model.error,
+        }
+    }
+}